@@ -353,6 +353,78 @@ pub fn ipc_trait(_args: TokenStream, ipc_trait: TokenStream) -> syn::Result<Toke
 
         client_fns.push(client_fn);
 
+        // the async client variant sends the same request through a user buffer, and defers reading the outputs
+        // until the pending request is signaled. Every elided lifetime is tied to the pending request, since the
+        // kernel/server may still access the in/out buffers until the reply arrives
+        let request_lifetime: syn::Lifetime = syn::parse2(quote! {'req})?;
+        let mut client_async_fn = fn_item.clone();
+        client_async_fn.attrs = vec![];
+        let async_doc = format!(
+            " Asynchronous variant of [`{0}`][`Self::{0}`], returning a pending request which can be waited on and yields the response once it's signaled",
+            fn_name
+        );
+        client_async_fn
+            .attrs
+            .push(syn::parse_quote! { #[doc = #async_doc] });
+        client_async_fn.sig.ident = format_ident!("{}_async", fn_name);
+        client_async_fn
+            .sig
+            .generics
+            .params
+            .insert(0, syn::parse2(quote! {'req})?);
+        for fn_arg in client_async_fn.sig.inputs.iter_mut() {
+            match fn_arg {
+                FnArg::Receiver(receiver) => {
+                    if let Some((_, lifetime)) = receiver.reference.as_mut() {
+                        *lifetime = Some(request_lifetime.clone());
+                    }
+                    set_elided_lifetimes(&mut receiver.ty, &request_lifetime);
+                }
+                FnArg::Typed(arg_pat) => set_elided_lifetimes(&mut arg_pat.ty, &request_lifetime),
+            }
+        }
+
+        let async_out_type: Type = match &fn_item.sig.output {
+            ReturnType::Default => syn::parse2(quote! {()})?,
+            ReturnType::Type(_, ty) => *ty.clone(),
+        };
+        client_async_fn.sig.output = syn::parse2(quote! {
+            -> ::nx::result::Result<::nx::ipc::client::PendingRequest<'req, #async_out_type>>
+        })?;
+
+        let (response_walker, response_ctx) = match out_param_names.is_empty() {
+            true => (format_ident!("_walker"), format_ident!("_ctx")),
+            false => (format_ident!("walker"), format_ident!("ctx")),
+        };
+        client_async_fn.default = Some(syn::parse2(quote! {
+                {
+                    let mut ctx = ::nx::ipc::CommandContext::new_client(self.get_session().object_info);
+
+                    let mut walker = ::nx::ipc::DataWalker::new(core::ptr::null_mut());
+
+                    #(::nx::ipc::client::RequestCommandParameter::before_request_write(&#client_in_param_names, &mut walker, &mut ctx)?;)*
+
+                    ctx.in_params.data_size = walker.get_offset() as u32;
+
+                    match self.get_session().object_info.protocol {
+                        ::nx::ipc::CommandProtocol::Cmif => ::nx::ipc::cmif::client::write_request_command_on_msg_buffer(&mut ctx, Some(#ipc_rid), ::nx::ipc::cmif::DomainCommandType::SendMessage),
+                        ::nx::ipc::CommandProtocol::Tipc => ::nx::ipc::tipc::client::write_request_command_on_msg_buffer(&mut ctx, #ipc_rid)
+                    };
+
+                    walker.reset_with(ctx.in_params.data_offset);
+                    #( ::nx::ipc::client::RequestCommandParameter::before_send_sync_request(&#client_in_param_names, &mut walker, &mut ctx)?; )*
+
+                    ::nx::ipc::client::PendingRequest::<#async_out_type>::send(ctx, |#response_walker: &mut ::nx::ipc::DataWalker, #response_ctx: &mut ::nx::ipc::CommandContext| {
+                        #( let #out_param_names = <#out_param_types as ::nx::ipc::client::ResponseCommandParameter<_>>::after_response_read(#response_walker, #response_ctx)?; )*
+
+                        Ok(( #(#out_param_names as _),* ))
+                    })
+                }
+        })?);
+        client_async_fn.semi_token = None;
+
+        client_fns.push(client_async_fn);
+
         // fix the return type for the server function types if
         let mut server_fn = fn_item.clone();
         server_fn.attrs = vec![];
@@ -519,3 +591,45 @@ pub fn ipc_trait(_args: TokenStream, ipc_trait: TokenStream) -> syn::Result<Toke
 fn stringify_error(span: proc_macro2::Span, msg: impl std::fmt::Display) -> syn::Error {
     syn::Error::new(span, msg)
 }
+
+/// Replaces every elided (`'_` or omitted reference) lifetime in a type with the given one
+fn set_elided_lifetimes(ty: &mut Type, lifetime: &syn::Lifetime) {
+    match ty {
+        Type::Reference(ty_ref) => {
+            match ty_ref.lifetime.as_ref() {
+                None => ty_ref.lifetime = Some(lifetime.clone()),
+                Some(ty_lifetime) if ty_lifetime.ident == "_" => {
+                    ty_ref.lifetime = Some(lifetime.clone())
+                }
+                _ => {}
+            }
+            set_elided_lifetimes(&mut ty_ref.elem, lifetime);
+        }
+        Type::Path(ty_path) => {
+            for segment in ty_path.path.segments.iter_mut() {
+                if let syn::PathArguments::AngleBracketed(generic_args) = &mut segment.arguments {
+                    for generic_arg in generic_args.args.iter_mut() {
+                        match generic_arg {
+                            GenericArgument::Lifetime(arg_lifetime)
+                                if arg_lifetime.ident == "_" =>
+                            {
+                                *arg_lifetime = lifetime.clone()
+                            }
+                            GenericArgument::Type(arg_ty) => set_elided_lifetimes(arg_ty, lifetime),
+                            _ => {}
+                        }
+                    }
+                }
+            }
+        }
+        Type::Tuple(ty_tuple) => ty_tuple
+            .elems
+            .iter_mut()
+            .for_each(|elem| set_elided_lifetimes(elem, lifetime)),
+        Type::Array(ty_array) => set_elided_lifetimes(&mut ty_array.elem, lifetime),
+        Type::Slice(ty_slice) => set_elided_lifetimes(&mut ty_slice.elem, lifetime),
+        Type::Paren(ty_paren) => set_elided_lifetimes(&mut ty_paren.elem, lifetime),
+        Type::Group(ty_group) => set_elided_lifetimes(&mut ty_group.elem, lifetime),
+        _ => {}
+    }
+}
@@ -6,6 +6,8 @@ use crate::result::*;
 use crate::service;
 use crate::svc;
 use crate::sync::{ReadGuard, RwLock};
+use crate::thread;
+use crate::time::Duration;
 use crate::version::{Version, get_version};
 
 use core::sync::atomic::AtomicU64;
@@ -146,7 +148,7 @@ pub fn initialize() -> Result<()> {
             Ok(p) => break Ok(p),
             Err(rc) if rc.get_value() == 0x19280 => {
                 // behaviour from libnx, though we don't check for a global timeout
-                let _ = svc::sleep_thread(100000000);
+                let _ = thread::sleep(Duration::from_millis(100));
                 continue;
             }
             Err(rc) => break Err(rc),
@@ -1,6 +1,6 @@
 //! ARM support and utils
 
-use core::arch::asm;
+use crate::macros::util::naked_asm;
 
 /// Represents a CPU register value (`W`, `X` or `R` value depending on the context/arch).
 #[derive(Copy, Clone, PartialEq, Eq, Debug, Default)]
@@ -169,8 +169,8 @@ pub fn cache_flush(address: *mut u8, size: usize) {
     // than compiler optimised.
     #[unsafe(naked)]
     unsafe extern "C" fn __nx_arm_cache_flush(address: *mut u8, size: usize) {
-        core::arch::naked_asm!(
-            crate::macros::util::maybe_cfi!(".cfi_startproc"),
+        naked_asm!(
+            maybe_cfi!(".cfi_startproc"),
             "add x1, x1, x0",
             "mrs x8, CTR_EL0",
             "lsr x8, x8, #16",
@@ -191,7 +191,7 @@ pub fn cache_flush(address: *mut u8, size: usize) {
             "dsb sy",
             "strb wzr, [x0, #0x104]", // Unset flag at TLR[0x104] for kernel
             "ret",
-            crate::macros::util::maybe_cfi!(".cfi_endproc")
+            maybe_cfi!(".cfi_endproc")
         );
     }
 
@@ -231,33 +231,37 @@ pub fn cache_flush2(address: *mut u8, size: usize) {
 /// Gets the system tick.
 #[inline(always)]
 pub fn get_system_tick() -> u64 {
-    let system_tick: u64;
-    unsafe {
-        asm!(
-            "mrs {}, cntpct_el0",
-            out(reg) system_tick
-        );
+    #[unsafe(naked)]
+    unsafe extern "C" fn __nx_arm_get_system_tick() -> u64 {
+        naked_asm!(
+            maybe_cfi!(".cfi_startproc"),
+            "mrs x0, cntpct_el0",
+            "ret",
+            maybe_cfi!(".cfi_endproc")
+        )
     }
-    system_tick
+    unsafe { __nx_arm_get_system_tick() }
 }
 
 /// Gets the system tick time as nanoseconds.
 #[inline(always)]
 pub fn get_system_tick_as_nanos() -> u64 {
-    get_system_tick() / (get_system_tick_frequency() / 1_000_000_000u64)
+    ticks_to_nanoseconds(get_system_tick())
 }
 
 /// Gets the system tick frequency.
 #[inline(always)]
 pub fn get_system_tick_frequency() -> u64 {
-    let system_tick_freq: u64;
-    unsafe {
-        asm!(
-            "mrs {}, cntfrq_el0",
-            out(reg) system_tick_freq
-        );
+    #[unsafe(naked)]
+    unsafe extern "C" fn __nx_arm_get_system_tick_frequency() -> u64 {
+        naked_asm!(
+            maybe_cfi!(".cfi_startproc"),
+            "mrs x0, cntfrq_el0",
+            "ret",
+            maybe_cfi!(".cfi_endproc")
+        )
     }
-    system_tick_freq
+    unsafe { __nx_arm_get_system_tick_frequency() }
 }
 
 /// Converts ticks to nanoseconds.
@@ -267,7 +271,7 @@ pub fn get_system_tick_frequency() -> u64 {
 /// * `ticks`: Ticks to convert.
 #[inline]
 pub const fn ticks_to_nanoseconds(ticks: u64) -> u64 {
-    (ticks * 625) / 12
+    ((ticks as u128 * 625) / 12) as u64
 }
 
 /// Converts nanoseconds to ticks.
@@ -277,5 +281,5 @@ pub const fn ticks_to_nanoseconds(ticks: u64) -> u64 {
 /// * `ns`: Nanoseconds to convert.
 #[inline]
 pub const fn nanoseconds_to_ticks(ns: u64) -> u64 {
-    (ns * 12) / 625
+    ((ns as u128 * 12) / 625) as u64
 }
@@ -0,0 +1,23 @@
+//! Audio support
+//!
+//! Audio is streamed as interleaved PCM16 samples (`i16`s, one per channel for each frame)
+//!
+//! Audio files (WAV, DSP-ADPCM and Ogg Vorbis) can be decoded via the [`decode`] module
+
+pub mod rc;
+
+pub mod queue;
+
+pub mod output;
+pub use output::*;
+
+pub mod input;
+pub use input::Input;
+
+pub mod mixer;
+pub use mixer::{Sound, VoiceParams};
+
+pub mod renderer;
+pub use renderer::*;
+
+pub mod decode;
@@ -0,0 +1,381 @@
+//! Audio file decoding
+//!
+//! Decoders convert encoded audio files into interleaved PCM16 frames. The supported formats are:
+//!
+//! - WAV (PCM8/16/24/32 and float32 samples), see [`wav`]
+//!
+//! - Nintendo DSP-ADPCM (standard `.dsp` files), see [`adpcm`]
+//!
+//! - Ogg Vorbis, see [`vorbis`]
+//!
+//! Decoders read their data from a [`Source`] (a byte slice or a file) as they decode, thus long tracks can be streamed by decoding a few frames at a time, or they can be fully decoded into a [`Sound`] via [`decode_all`]
+//!
+//! This has no dependencies on the audio services, thus it can be used (and tested) outside of the console
+
+use super::mixer::Sound;
+use super::rc;
+use crate::mem::alloc::rc as alloc_rc;
+use crate::result::*;
+use alloc::boxed::Box;
+use alloc::vec::Vec;
+
+#[cfg(feature = "fs")]
+use crate::fs;
+
+#[cfg(test)]
+macro_rules! audio_fixture {
+    ($name:literal) => {
+        include_bytes!(concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/tests/fixtures/audio/",
+            $name
+        ))
+    };
+}
+
+pub mod wav;
+
+pub mod adpcm;
+
+pub mod ogg;
+
+pub mod vorbis;
+
+/// Represents a seekable source of encoded data
+pub trait Source {
+    /// Reads data at the current position, advancing it, and returns the read size (`0` at the end of the source)
+    ///
+    /// # Arguments
+    ///
+    /// * `out_buf`: The buffer to read into
+    fn read(&mut self, out_buf: &mut [u8]) -> Result<usize>;
+
+    /// Seeks to the given absolute position
+    ///
+    /// # Arguments
+    ///
+    /// * `offset`: The position
+    fn seek(&mut self, offset: usize) -> Result<()>;
+
+    /// Gets the total size of the source
+    fn get_size(&mut self) -> Result<usize>;
+
+    /// Reads data at the current position until the given buffer is filled, failing if the source ends before
+    ///
+    /// # Arguments
+    ///
+    /// * `out_buf`: The buffer to read into
+    fn read_exact(&mut self, out_buf: &mut [u8]) -> Result<()> {
+        let mut offset = 0;
+        while offset < out_buf.len() {
+            let read_size = self.read(&mut out_buf[offset..])?;
+            result_return_if!(read_size == 0, rc::ResultInvalidFormat);
+            offset += read_size;
+        }
+        Ok(())
+    }
+}
+
+impl<S: Source + ?Sized> Source for Box<S> {
+    fn read(&mut self, out_buf: &mut [u8]) -> Result<usize> {
+        (**self).read(out_buf)
+    }
+
+    fn seek(&mut self, offset: usize) -> Result<()> {
+        (**self).seek(offset)
+    }
+
+    fn get_size(&mut self) -> Result<usize> {
+        (**self).get_size()
+    }
+}
+
+/// Represents a [`Source`] over a byte slice
+pub struct SliceSource<'a> {
+    data: &'a [u8],
+    offset: usize,
+}
+
+impl<'a> SliceSource<'a> {
+    /// Creates a new [`SliceSource`]
+    ///
+    /// # Arguments
+    ///
+    /// * `data`: The encoded data
+    #[inline]
+    pub const fn new(data: &'a [u8]) -> Self {
+        Self { data, offset: 0 }
+    }
+}
+
+impl Source for SliceSource<'_> {
+    fn read(&mut self, out_buf: &mut [u8]) -> Result<usize> {
+        let remaining = self.data.get(self.offset..).unwrap_or(&[]);
+        let read_size = remaining.len().min(out_buf.len());
+        out_buf[..read_size].copy_from_slice(&remaining[..read_size]);
+        self.offset += read_size;
+        Ok(read_size)
+    }
+
+    fn seek(&mut self, offset: usize) -> Result<()> {
+        self.offset = offset;
+        Ok(())
+    }
+
+    fn get_size(&mut self) -> Result<usize> {
+        Ok(self.data.len())
+    }
+}
+
+#[cfg(feature = "fs")]
+impl Source for fs::FileAccessor {
+    fn read(&mut self, out_buf: &mut [u8]) -> Result<usize> {
+        self.read_array(out_buf)
+    }
+
+    fn seek(&mut self, offset: usize) -> Result<()> {
+        fs::FileAccessor::seek(self, fs::SeekFrom::Start(offset))
+    }
+
+    fn get_size(&mut self) -> Result<usize> {
+        fs::FileAccessor::get_size(self)
+    }
+}
+
+/// Represents a streaming decoder
+pub trait Decoder {
+    /// Gets the channel count
+    fn get_channel_count(&self) -> u16;
+
+    /// Gets the sample rate
+    fn get_sample_rate(&self) -> u32;
+
+    /// Gets the total frame count, if it's known without decoding the whole file
+    fn get_frame_count(&self) -> Option<usize>;
+
+    /// Decodes the next frames into the given interleaved samples, returning the decoded frame count (`0` when the end is reached)
+    ///
+    /// # Arguments
+    ///
+    /// * `out_samples`: The interleaved samples to decode into, whose length must be a multiple of the channel count
+    fn decode(&mut self, out_samples: &mut [i16]) -> Result<usize>;
+
+    /// Goes back to the start, so that decoding starts again from the first frame
+    fn rewind(&mut self) -> Result<()>;
+}
+
+/// Represents the supported encoded formats
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum Format {
+    Wav,
+    DspAdpcm,
+    OggVorbis,
+}
+
+/// Detects the format of an encoded file from its first bytes
+///
+/// DSP-ADPCM files have no magic, thus any (long enough) data which isn't WAV or Ogg is assumed to be DSP-ADPCM
+///
+/// # Arguments
+///
+/// * `header`: The first bytes of the file (at least [`adpcm::HEADER_SIZE`] bytes are needed to detect DSP-ADPCM files)
+pub fn detect_format(header: &[u8]) -> Option<Format> {
+    if header.starts_with(b"RIFF") && (header.get(8..12) == Some(b"WAVE")) {
+        Some(Format::Wav)
+    } else if header.starts_with(b"OggS") {
+        Some(Format::OggVorbis)
+    } else if header.len() >= adpcm::HEADER_SIZE {
+        Some(Format::DspAdpcm)
+    } else {
+        None
+    }
+}
+
+/// Opens a decoder for the given source, detecting its format
+///
+/// # Arguments
+///
+/// * `source`: The source to decode
+pub fn open<S: Source + 'static>(mut source: S) -> Result<Box<dyn Decoder>> {
+    let mut header = [0u8; adpcm::HEADER_SIZE];
+    let header_size = header.len().min(source.get_size()?);
+    source.seek(0)?;
+    source.read_exact(&mut header[..header_size])?;
+    source.seek(0)?;
+
+    match detect_format(&header[..header_size]) {
+        Some(Format::Wav) => Ok(Box::new(wav::WavDecoder::new(source)?)),
+        Some(Format::DspAdpcm) => Ok(Box::new(adpcm::DspAdpcmDecoder::new(source)?)),
+        Some(Format::OggVorbis) => Ok(Box::new(vorbis::VorbisDecoder::new(source)?)),
+        None => rc::ResultInvalidFormat::make_err(),
+    }
+}
+
+/// Decodes all the (remaining) frames of a decoder into a [`Sound`]
+///
+/// # Arguments
+///
+/// * `decoder`: The decoder
+pub fn decode_all(decoder: &mut dyn Decoder) -> Result<Sound> {
+    const CHUNK_FRAME_COUNT: usize = 4096;
+
+    let channel_count = decoder.get_channel_count() as usize;
+    // The frame count comes from the file headers, thus it's only trusted as long as the memory can be reserved
+    let sample_count = decoder
+        .get_frame_count()
+        .unwrap_or(0)
+        .checked_mul(channel_count)
+        .ok_or(rc::ResultInvalidFormat::make())?;
+    let mut samples: Vec<i16> = try_vec_with_capacity(sample_count)?;
+    let mut chunk = vec![0i16; CHUNK_FRAME_COUNT * channel_count];
+    loop {
+        let frame_count = decoder.decode(&mut chunk)?;
+        if frame_count == 0 {
+            break;
+        }
+        samples.extend_from_slice(&chunk[..frame_count * channel_count]);
+    }
+
+    Sound::new(
+        samples,
+        decoder.get_channel_count(),
+        decoder.get_sample_rate(),
+    )
+}
+
+/// Decodes a whole encoded file into a [`Sound`], detecting its format
+///
+/// # Arguments
+///
+/// * `data`: The encoded data
+pub fn decode_slice(data: &[u8]) -> Result<Sound> {
+    // Decoders are boxed by `open`, which requires owned sources
+    let mut header = [0u8; adpcm::HEADER_SIZE];
+    let header_size = header.len().min(data.len());
+    header[..header_size].copy_from_slice(&data[..header_size]);
+
+    let source = SliceSource::new(data);
+    match detect_format(&header[..header_size]) {
+        Some(Format::Wav) => decode_all(&mut wav::WavDecoder::new(source)?),
+        Some(Format::DspAdpcm) => decode_all(&mut adpcm::DspAdpcmDecoder::new(source)?),
+        Some(Format::OggVorbis) => decode_all(&mut vorbis::VorbisDecoder::new(source)?),
+        None => rc::ResultInvalidFormat::make_err(),
+    }
+}
+
+// Allocates a vector whose capacity comes from (possibly corrupt) file data, failing instead of aborting if there isn't enough memory
+pub(crate) fn try_vec_with_capacity<T>(capacity: usize) -> Result<Vec<T>> {
+    let mut vec = Vec::new();
+    vec.try_reserve_exact(capacity)
+        .map_err(|_| alloc_rc::ResultOutOfMemory::make())?;
+    Ok(vec)
+}
+
+#[inline]
+pub(crate) fn check_out_samples(out_samples: &[i16], channel_count: u16) -> Result<usize> {
+    result_return_unless!(
+        out_samples.len().is_multiple_of(channel_count as usize),
+        rc::ResultInvalidSampleCount
+    );
+    Ok(out_samples.len() / channel_count as usize)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FIXTURES: [&[u8]; 6] = [
+        audio_fixture!("pcm16_stereo.wav"),
+        audio_fixture!("pcm8_mono.wav"),
+        audio_fixture!("pcm24_mono.wav"),
+        audio_fixture!("float32_stereo.wav"),
+        audio_fixture!("adpcm.dsp"),
+        audio_fixture!("vorbis_stereo.ogg"),
+    ];
+
+    #[test]
+    fn format_detection() {
+        let formats: Vec<_> = FIXTURES.iter().map(|data| detect_format(data)).collect();
+        assert_eq!(
+            formats,
+            [
+                Some(Format::Wav),
+                Some(Format::Wav),
+                Some(Format::Wav),
+                Some(Format::Wav),
+                Some(Format::DspAdpcm),
+                Some(Format::OggVorbis)
+            ]
+        );
+        // DSP-ADPCM files can only be told apart by their size
+        assert_eq!(detect_format(&[0; adpcm::HEADER_SIZE - 1]), None);
+        assert_eq!(detect_format(b"RIFF\0\0\0\0AVI "), None);
+    }
+
+    #[test]
+    fn decode_fixtures() {
+        let expected = [
+            (2, 8000, 500),
+            (1, 8000, 500),
+            (1, 22050, 500),
+            (2, 48000, 500),
+            (1, 32000, 163),
+            (2, 8000, 6555),
+        ];
+        for (data, (channel_count, sample_rate, frame_count)) in FIXTURES.into_iter().zip(expected)
+        {
+            let sound = decode_slice(data).unwrap();
+            assert_eq!(sound.get_channel_count(), channel_count);
+            assert_eq!(sound.get_sample_rate(), sample_rate);
+            assert_eq!(sound.get_frame_count(), frame_count);
+
+            // Opened decoders give the same result
+            let mut decoder = open(SliceSource::new(data)).unwrap();
+            assert_eq!(decoder.get_frame_count(), Some(frame_count));
+            assert_eq!(
+                decode_all(decoder.as_mut()).unwrap().get_samples(),
+                sound.get_samples()
+            );
+        }
+    }
+
+    #[test]
+    fn truncated_fixtures() {
+        for data in FIXTURES {
+            let sound = decode_slice(data).unwrap();
+            for size in (0..data.len()).step_by(7) {
+                // Truncated files either fail or decode fewer frames, but they never panic
+                if let Ok(truncated_sound) = decode_slice(&data[..size]) {
+                    assert!(truncated_sound.get_frame_count() <= sound.get_frame_count());
+                }
+            }
+        }
+        assert!(decode_slice(&[]).is_err());
+    }
+
+    #[test]
+    fn corrupt_fixtures() {
+        for data in FIXTURES {
+            for offset in (0..data.len()).step_by(5) {
+                let mut corrupt_data = data.to_vec();
+                corrupt_data[offset] ^= 0xA5;
+                if let Ok(corrupt_sound) = decode_slice(&corrupt_data) {
+                    // Sizes from the headers never make it decode more than the file can hold (at most 14 samples every 8 bytes, with DSP-ADPCM)
+                    assert!(corrupt_sound.get_samples().len() <= corrupt_data.len() * 2);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn sample_count_alignment() {
+        let data = FIXTURES[0];
+        let mut decoder = wav::WavDecoder::new(SliceSource::new(data)).unwrap();
+        assert_eq!(
+            decoder.decode(&mut [0; 3]),
+            Err(rc::ResultInvalidSampleCount::make())
+        );
+        assert_eq!(decoder.decode(&mut [0; 4]), Ok(2));
+        assert_eq!(decoder.decode(&mut []), Ok(0));
+    }
+}
@@ -0,0 +1,388 @@
+//! Nintendo DSP-ADPCM decoding
+//!
+//! Supports standard mono `.dsp` files (a big-endian header followed by the ADPCM frames). Multi-channel sounds are usually stored as separate `.dsp` files, one per channel, which can be decoded separately
+
+use super::{Decoder, Source, check_out_samples};
+use crate::audio::rc;
+use crate::result::*;
+use alloc::vec::Vec;
+
+/// The size of the header of `.dsp` files
+pub const HEADER_SIZE: usize = 0x60;
+
+/// The size of an ADPCM frame
+pub const FRAME_SIZE: usize = 8;
+
+/// The amount of samples encoded in an ADPCM frame
+pub const FRAME_SAMPLE_COUNT: usize = 14;
+
+/// Represents the ADPCM decoding state: the coefficients and the sample history
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Default)]
+pub struct AdpcmContext {
+    /// The 8 coefficient pairs
+    pub coefficients: [i16; 16],
+    /// The last decoded sample
+    pub history_1: i16,
+    /// The second-to-last decoded sample
+    pub history_2: i16,
+}
+
+impl AdpcmContext {
+    /// Decodes an ADPCM frame, returning the decoded sample count
+    ///
+    /// # Arguments
+    ///
+    /// * `frame`: The frame, a header byte followed by 7 bytes of samples (two per byte, high nibble first)
+    /// * `out_samples`: The samples to decode into, of which up to [`FRAME_SAMPLE_COUNT`] are decoded
+    pub fn decode_frame(&mut self, frame: &[u8; FRAME_SIZE], out_samples: &mut [i16]) -> usize {
+        let predictor = ((frame[0] >> 4) & 0x7) as usize;
+        let scale = 1i32 << (frame[0] & 0xF);
+        let coefficient_1 = self.coefficients[predictor * 2] as i32;
+        let coefficient_2 = self.coefficients[predictor * 2 + 1] as i32;
+
+        let sample_count = out_samples.len().min(FRAME_SAMPLE_COUNT);
+        for (i, out_sample) in out_samples[..sample_count].iter_mut().enumerate() {
+            let byte = frame[1 + i / 2];
+            let nibble = match i % 2 {
+                0 => byte >> 4,
+                _ => byte & 0xF,
+            };
+            // Sign-extend the 4-bit value
+            let nibble = ((nibble << 4) as i8 >> 4) as i32;
+
+            let sample = (((nibble * scale) << 11)
+                + 1024
+                + coefficient_1 * self.history_1 as i32
+                + coefficient_2 * self.history_2 as i32)
+                >> 11;
+            let sample = sample.clamp(i16::MIN as i32, i16::MAX as i32) as i16;
+
+            self.history_2 = self.history_1;
+            self.history_1 = sample;
+            *out_sample = sample;
+        }
+        sample_count
+    }
+}
+
+/// Represents the header of a `.dsp` file
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct DspHeader {
+    pub sample_count: u32,
+    pub nibble_count: u32,
+    pub sample_rate: u32,
+    pub looping: bool,
+    /// The loop start, in nibbles
+    pub loop_start: u32,
+    /// The loop end, in nibbles
+    pub loop_end: u32,
+    /// The initial decoding state
+    pub context: AdpcmContext,
+}
+
+impl DspHeader {
+    /// Parses a `.dsp` header
+    ///
+    /// # Arguments
+    ///
+    /// * `header`: The header data
+    pub fn parse(header: &[u8; HEADER_SIZE]) -> Result<Self> {
+        let read_u16 = |offset: usize| u16::from_be_bytes([header[offset], header[offset + 1]]);
+        let read_u32 = |offset: usize| {
+            u32::from_be_bytes([
+                header[offset],
+                header[offset + 1],
+                header[offset + 2],
+                header[offset + 3],
+            ])
+        };
+
+        let sample_count = read_u32(0x0);
+        let nibble_count = read_u32(0x4);
+        let sample_rate = read_u32(0x8);
+        let looping = read_u16(0xC) != 0;
+        let format = read_u16(0xE);
+        // Only ADPCM (format 0) is used in practice
+        result_return_unless!(format == 0, rc::ResultUnsupportedFormat);
+        result_return_if!(sample_rate == 0, rc::ResultInvalidFormat);
+        // Each frame has 2 header nibbles and 14 sample nibbles
+        result_return_if!(
+            (sample_count as u64) > (nibble_count as u64).div_ceil(16) * 14,
+            rc::ResultInvalidFormat
+        );
+
+        let mut coefficients = [0i16; 16];
+        for (i, coefficient) in coefficients.iter_mut().enumerate() {
+            *coefficient = read_u16(0x1C + i * 2) as i16;
+        }
+
+        Ok(Self {
+            sample_count,
+            nibble_count,
+            sample_rate,
+            looping,
+            loop_start: read_u32(0x10),
+            loop_end: read_u32(0x14),
+            context: AdpcmContext {
+                coefficients,
+                history_1: read_u16(0x40) as i16,
+                history_2: read_u16(0x42) as i16,
+            },
+        })
+    }
+
+    /// Gets the size of the ADPCM frame data
+    #[inline]
+    pub const fn get_data_size(&self) -> usize {
+        (self.nibble_count as usize).div_ceil(2)
+    }
+}
+
+fn take_frame_samples(
+    frame_samples: &[i16; FRAME_SAMPLE_COUNT],
+    frame_sample_offset: &mut usize,
+    out_samples: &mut [i16],
+) -> usize {
+    let frame_samples = &frame_samples[*frame_sample_offset..];
+    let sample_count = frame_samples.len().min(out_samples.len());
+    out_samples[..sample_count].copy_from_slice(&frame_samples[..sample_count]);
+    *frame_sample_offset += sample_count;
+    sample_count
+}
+
+/// Represents a streaming DSP-ADPCM decoder
+pub struct DspAdpcmDecoder<S: Source> {
+    source: S,
+    header: DspHeader,
+    context: AdpcmContext,
+    sample_offset: usize,
+    frame_offset: usize,
+    frame_samples: [i16; FRAME_SAMPLE_COUNT],
+    frame_sample_offset: usize,
+    read_buf: Vec<u8>,
+}
+
+impl<S: Source> DspAdpcmDecoder<S> {
+    /// Creates a new [`DspAdpcmDecoder`]
+    ///
+    /// # Arguments
+    ///
+    /// * `source`: The source to decode
+    pub fn new(mut source: S) -> Result<Self> {
+        let mut header_data = [0u8; HEADER_SIZE];
+        source.seek(0)?;
+        source.read_exact(&mut header_data)?;
+        let header = DspHeader::parse(&header_data)?;
+        result_return_if!(
+            HEADER_SIZE + header.get_data_size() > source.get_size()?,
+            rc::ResultInvalidFormat
+        );
+
+        Ok(Self {
+            source,
+            header,
+            context: header.context,
+            sample_offset: 0,
+            frame_offset: 0,
+            frame_samples: [0; FRAME_SAMPLE_COUNT],
+            frame_sample_offset: FRAME_SAMPLE_COUNT,
+            read_buf: Vec::new(),
+        })
+    }
+
+    /// Gets the file header
+    #[inline]
+    pub fn get_header(&self) -> &DspHeader {
+        &self.header
+    }
+}
+
+impl<S: Source> Decoder for DspAdpcmDecoder<S> {
+    fn get_channel_count(&self) -> u16 {
+        1
+    }
+
+    fn get_sample_rate(&self) -> u32 {
+        self.header.sample_rate
+    }
+
+    fn get_frame_count(&self) -> Option<usize> {
+        Some(self.header.sample_count as usize)
+    }
+
+    fn decode(&mut self, out_samples: &mut [i16]) -> Result<usize> {
+        let max_sample_count = check_out_samples(out_samples, 1)?;
+        let sample_count =
+            max_sample_count.min(self.header.sample_count as usize - self.sample_offset);
+        let out_samples = &mut out_samples[..sample_count];
+
+        // Samples left from the last frame, if it wasn't fully returned
+        let mut decoded_count = take_frame_samples(
+            &self.frame_samples,
+            &mut self.frame_sample_offset,
+            out_samples,
+        );
+
+        let frame_count = (sample_count - decoded_count).div_ceil(FRAME_SAMPLE_COUNT);
+        if frame_count > 0 {
+            let data_size = (frame_count * FRAME_SIZE).min(
+                self.header
+                    .get_data_size()
+                    .saturating_sub(self.frame_offset * FRAME_SIZE),
+            );
+            self.read_buf.clear();
+            self.read_buf.resize(frame_count * FRAME_SIZE, 0);
+            // The last frame may be truncated
+            self.source.read_exact(&mut self.read_buf[..data_size])?;
+
+            for frame in self.read_buf.chunks_exact(FRAME_SIZE) {
+                let frame: &[u8; FRAME_SIZE] = frame.try_into().unwrap();
+                self.context.decode_frame(frame, &mut self.frame_samples);
+                self.frame_sample_offset = 0;
+                decoded_count += take_frame_samples(
+                    &self.frame_samples,
+                    &mut self.frame_sample_offset,
+                    &mut out_samples[decoded_count..],
+                );
+            }
+            self.frame_offset += frame_count;
+        }
+
+        self.sample_offset += decoded_count;
+        Ok(decoded_count)
+    }
+
+    fn rewind(&mut self) -> Result<()> {
+        self.source.seek(HEADER_SIZE)?;
+        self.context = self.header.context;
+        self.sample_offset = 0;
+        self.frame_offset = 0;
+        self.frame_sample_offset = FRAME_SAMPLE_COUNT;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::audio::decode::{SliceSource, wav};
+
+    const DSP: &[u8] = audio_fixture!("adpcm.dsp");
+    // The reference decoding of the DSP file
+    const DSP_DECODED: &[u8] = audio_fixture!("adpcm.wav");
+
+    fn decode(data: &[u8], chunk_sample_count: usize) -> Result<Vec<i16>> {
+        let mut decoder = DspAdpcmDecoder::new(SliceSource::new(data))?;
+        let mut samples = Vec::new();
+        let mut chunk = vec![0; chunk_sample_count];
+        loop {
+            let sample_count = decoder.decode(&mut chunk)?;
+            if sample_count == 0 {
+                return Ok(samples);
+            }
+            samples.extend_from_slice(&chunk[..sample_count]);
+        }
+    }
+
+    fn reference_samples() -> Vec<i16> {
+        let mut decoder = wav::WavDecoder::new(SliceSource::new(DSP_DECODED)).unwrap();
+        let mut samples = vec![0; decoder.get_info().get_frame_count()];
+        assert_eq!(decoder.decode(&mut samples), Ok(samples.len()));
+        samples
+    }
+
+    #[test]
+    fn header() {
+        let header = DspHeader::parse(DSP[..HEADER_SIZE].try_into().unwrap()).unwrap();
+        assert_eq!(header.sample_count, 163);
+        assert_eq!(header.nibble_count, 192);
+        assert_eq!(header.sample_rate, 32000);
+        assert!(header.looping);
+        assert_eq!(header.loop_start, 2);
+        assert_eq!(header.loop_end, 191);
+        assert_eq!(header.context.coefficients[..2], [2048, 0]);
+        assert_eq!(header.context.history_1, 100);
+        assert_eq!(header.context.history_2, -50);
+        assert_eq!(header.get_data_size(), 12 * FRAME_SIZE);
+    }
+
+    #[test]
+    fn frame_decoding() {
+        let mut context = AdpcmContext {
+            coefficients: [0; 16],
+            history_1: 0,
+            history_2: 0,
+        };
+        // Coefficient pair 1 doubles the last sample, scale 2
+        context.coefficients[2] = 4096;
+        let frame = [0x11, 0x10, 0xF0, 0, 0, 0, 0, 0x7F];
+        let mut samples = [0; FRAME_SAMPLE_COUNT];
+        assert_eq!(
+            context.decode_frame(&frame, &mut samples),
+            FRAME_SAMPLE_COUNT
+        );
+        assert_eq!(&samples[..5], &[2, 4, 6, 12, 24]);
+        assert_eq!(&samples[12..], &[6158, 12314]);
+        assert_eq!((context.history_1, context.history_2), (12314, 6158));
+
+        // Partial frames, where samples are clamped
+        let mut samples = [0; 3];
+        assert_eq!(context.decode_frame(&[0x10; FRAME_SIZE], &mut samples), 3);
+        assert_eq!(&samples, &[24629, i16::MAX, i16::MAX]);
+    }
+
+    #[test]
+    fn reference_decoding() {
+        let expected = reference_samples();
+        assert_eq!(expected.len(), 163);
+        for chunk_sample_count in [1, 5, 14, 15, 28, 200] {
+            assert_eq!(decode(DSP, chunk_sample_count).unwrap(), expected);
+        }
+    }
+
+    #[test]
+    fn rewind() {
+        let expected = reference_samples();
+        let mut decoder = DspAdpcmDecoder::new(SliceSource::new(DSP)).unwrap();
+        let mut samples = [0; 20];
+        assert_eq!(decoder.decode(&mut samples), Ok(20));
+        decoder.rewind().unwrap();
+        assert_eq!(decoder.decode(&mut samples), Ok(20));
+        assert_eq!(&samples, &expected[..20]);
+    }
+
+    #[test]
+    fn truncated_and_corrupt() {
+        let open = |data: &[u8]| DspAdpcmDecoder::new(SliceSource::new(data)).map(|_| ());
+        assert!(open(&DSP[..DSP.len() - 1]).is_err());
+        assert!(open(&DSP[..HEADER_SIZE - 1]).is_err());
+
+        let patched = |offset: usize, value: &[u8]| {
+            let mut data = DSP.to_vec();
+            data[offset..offset + value.len()].copy_from_slice(value);
+            data
+        };
+        // More samples than the nibbles can hold
+        assert_eq!(
+            open(&patched(0, &169u32.to_be_bytes())),
+            rc::ResultInvalidFormat::make_err()
+        );
+        // More nibbles than the file holds
+        assert_eq!(
+            open(&patched(4, &u32::MAX.to_be_bytes())),
+            rc::ResultInvalidFormat::make_err()
+        );
+        assert_eq!(
+            open(&patched(8, &0u32.to_be_bytes())),
+            rc::ResultInvalidFormat::make_err()
+        );
+        assert_eq!(
+            open(&patched(0xE, &1u16.to_be_bytes())),
+            rc::ResultUnsupportedFormat::make_err()
+        );
+
+        // Fewer samples than the frames hold are fine
+        let data = patched(0, &100u32.to_be_bytes());
+        assert_eq!(decode(&data, 64).unwrap(), &reference_samples()[..100]);
+    }
+}
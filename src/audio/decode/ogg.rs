@@ -0,0 +1,281 @@
+//! Ogg container parsing
+//!
+//! Only the packets of the first logical bitstream are read (chained or multiplexed streams are not supported, which is fine for regular audio files)
+
+use super::Source;
+use crate::audio::rc;
+use crate::result::*;
+use alloc::vec::Vec;
+
+/// The size of the fixed part of a page header
+pub const PAGE_HEADER_SIZE: usize = 27;
+
+const CRC_TABLE: [u32; 0x100] = {
+    let mut table = [0u32; 0x100];
+    let mut i = 0;
+    while i < table.len() {
+        let mut crc = (i as u32) << 24;
+        let mut j = 0;
+        while j < 8 {
+            crc = match crc & 0x80000000 {
+                0 => crc << 1,
+                _ => (crc << 1) ^ 0x04C11DB7,
+            };
+            j += 1;
+        }
+        table[i] = crc;
+        i += 1;
+    }
+    table
+};
+
+/// Computes the Ogg CRC of the given data
+///
+/// # Arguments
+///
+/// * `crc`: The initial CRC (`0` at the start of a page)
+/// * `data`: The data
+pub fn update_crc(crc: u32, data: &[u8]) -> u32 {
+    data.iter().fold(crc, |crc, &byte| {
+        (crc << 8) ^ CRC_TABLE[(((crc >> 24) as u8) ^ byte) as usize]
+    })
+}
+
+define_bit_set! {
+    /// Represents the flags of a page header
+    PageFlags (u8) {
+        /// The page starts with the continuation of the last packet of the previous page
+        Continued = bit!(0),
+        /// The page is the first one of the logical bitstream
+        BeginningOfStream = bit!(1),
+        /// The page is the last one of the logical bitstream
+        EndOfStream = bit!(2)
+    }
+}
+
+/// Represents a page header
+#[derive(Clone, Debug)]
+pub struct PageHeader {
+    pub flags: PageFlags,
+    /// The granule position after the last packet finished in the page (`u64::MAX` if no packet finishes in it)
+    pub granule_position: u64,
+    pub serial_number: u32,
+    pub sequence_number: u32,
+    pub crc: u32,
+    /// The size of each segment of the page (packets are split in segments of 255 bytes, ending with a shorter one)
+    pub segment_sizes: Vec<u8>,
+}
+
+impl PageHeader {
+    /// Parses the fixed part of a page header (the segment sizes are left empty)
+    ///
+    /// # Arguments
+    ///
+    /// * `header`: The header data
+    pub fn parse(header: &[u8; PAGE_HEADER_SIZE]) -> Result<Self> {
+        result_return_unless!(&header[..4] == b"OggS", rc::ResultInvalidFormat);
+        // Only version 0 exists
+        result_return_unless!(header[4] == 0, rc::ResultUnsupportedFormat);
+
+        let read_u32 = |offset: usize| {
+            u32::from_le_bytes([
+                header[offset],
+                header[offset + 1],
+                header[offset + 2],
+                header[offset + 3],
+            ])
+        };
+        Ok(Self {
+            flags: PageFlags::from(header[5]),
+            granule_position: read_u32(6) as u64 | ((read_u32(10) as u64) << 32),
+            serial_number: read_u32(14),
+            sequence_number: read_u32(18),
+            crc: read_u32(22),
+            segment_sizes: Vec::with_capacity(header[26] as usize),
+        })
+    }
+}
+
+/// Represents a packet read from an Ogg stream
+#[derive(Clone, Debug)]
+pub struct Packet {
+    pub data: Vec<u8>,
+    /// The granule position of the page, if this is the last packet finished in it
+    pub granule_position: Option<u64>,
+    /// Whether this is the last packet of the stream
+    pub end_of_stream: bool,
+}
+
+/// Represents a packet reader for the first logical bitstream of an Ogg file
+pub struct PacketReader<S: Source> {
+    source: S,
+    offset: usize,
+    serial_number: Option<u32>,
+    page: Option<PageHeader>,
+    page_data: Vec<u8>,
+    segment_index: usize,
+    segment_offset: usize,
+    packet_data: Vec<u8>,
+}
+
+impl<S: Source> PacketReader<S> {
+    /// Creates a new [`PacketReader`]
+    ///
+    /// # Arguments
+    ///
+    /// * `source`: The source to read from
+    pub fn new(source: S) -> Self {
+        Self {
+            source,
+            offset: 0,
+            serial_number: None,
+            page: None,
+            page_data: Vec::new(),
+            segment_index: 0,
+            segment_offset: 0,
+            packet_data: Vec::new(),
+        }
+    }
+
+    /// Goes back to the start of the file
+    pub fn rewind(&mut self) {
+        self.offset = 0;
+        self.serial_number = None;
+        self.page = None;
+        self.segment_index = 0;
+        self.segment_offset = 0;
+        self.packet_data.clear();
+    }
+
+    /// Gets the underlying source
+    #[inline]
+    pub fn get_source(&mut self) -> &mut S {
+        &mut self.source
+    }
+
+    fn read_page(&mut self) -> Result<Option<PageHeader>> {
+        let mut header_data = [0u8; PAGE_HEADER_SIZE];
+        self.source.seek(self.offset)?;
+        if self.source.read(&mut header_data[..1])? == 0 {
+            // The end of the file was reached
+            return Ok(None);
+        }
+        self.source.read_exact(&mut header_data[1..])?;
+        let mut header = PageHeader::parse(&header_data)?;
+
+        header
+            .segment_sizes
+            .resize(header_data[PAGE_HEADER_SIZE - 1] as usize, 0);
+        self.source.read_exact(&mut header.segment_sizes)?;
+        let data_size = header.segment_sizes.iter().map(|&size| size as usize).sum();
+        self.page_data.resize(data_size, 0);
+        self.source.read_exact(&mut self.page_data)?;
+
+        // The CRC is computed with the CRC field zeroed
+        header_data[22..26].fill(0);
+        let mut crc = update_crc(0, &header_data);
+        crc = update_crc(crc, &header.segment_sizes);
+        crc = update_crc(crc, &self.page_data);
+        result_return_unless!(crc == header.crc, rc::ResultInvalidFormat);
+
+        self.offset += PAGE_HEADER_SIZE + header.segment_sizes.len() + data_size;
+        Ok(Some(header))
+    }
+
+    fn next_page(&mut self) -> Result<bool> {
+        loop {
+            let header = match self.read_page()? {
+                Some(header) => header,
+                None => return Ok(false),
+            };
+
+            // Pages of other logical bitstreams are skipped
+            let serial_number = *self.serial_number.get_or_insert(header.serial_number);
+            if header.serial_number != serial_number {
+                continue;
+            }
+
+            if !header.flags.contains(PageFlags::Continued()) {
+                // Any unfinished packet was lost (or the stream is malformed)
+                self.packet_data.clear();
+            }
+            self.page = Some(header);
+            self.segment_index = 0;
+            self.segment_offset = 0;
+            return Ok(true);
+        }
+    }
+
+    /// Reads the next packet, returning `None` at the end of the stream
+    pub fn read_packet(&mut self) -> Result<Option<Packet>> {
+        loop {
+            if let Some(page) = &self.page {
+                while self.segment_index < page.segment_sizes.len() {
+                    let segment_size = page.segment_sizes[self.segment_index] as usize;
+                    self.packet_data.extend_from_slice(
+                        &self.page_data[self.segment_offset..self.segment_offset + segment_size],
+                    );
+                    self.segment_index += 1;
+                    self.segment_offset += segment_size;
+
+                    // A segment shorter than 255 bytes finishes the packet
+                    if segment_size < 0xFF {
+                        let is_last = page.segment_sizes[self.segment_index..]
+                            .iter()
+                            .all(|&size| size == 0xFF);
+                        let granule_position = match page.granule_position {
+                            u64::MAX => None,
+                            granule_position if is_last => Some(granule_position),
+                            _ => None,
+                        };
+                        let end_of_stream =
+                            is_last && page.flags.contains(PageFlags::EndOfStream());
+                        if end_of_stream {
+                            // Nothing else is read afterwards
+                            self.offset = usize::MAX;
+                        }
+
+                        return Ok(Some(Packet {
+                            data: core::mem::take(&mut self.packet_data),
+                            granule_position,
+                            end_of_stream,
+                        }));
+                    }
+                }
+            }
+
+            if (self.offset == usize::MAX) || !self.next_page()? {
+                return Ok(None);
+            }
+        }
+    }
+
+    /// Finds the granule position of the last page of the stream, without changing the read position
+    ///
+    /// Only the last part of the file is scanned, thus this may fail to find it with unusually big pages
+    pub fn find_last_granule_position(&mut self) -> Result<Option<u64>> {
+        // Pages are at most ~64KB long
+        const SCAN_SIZE: usize = 0x10000 + PAGE_HEADER_SIZE;
+
+        let serial_number = match self.serial_number {
+            Some(serial_number) => serial_number,
+            None => return Ok(None),
+        };
+        let size = self.source.get_size()?;
+        let scan_offset = size.saturating_sub(SCAN_SIZE);
+        let mut data = vec![0u8; size - scan_offset];
+        self.source.seek(scan_offset)?;
+        self.source.read_exact(&mut data)?;
+
+        let mut granule_position = None;
+        for header_data in data.windows(PAGE_HEADER_SIZE) {
+            if let Ok(header) = PageHeader::parse(header_data.try_into().unwrap())
+                && (header.serial_number == serial_number)
+                && (header.granule_position != u64::MAX)
+            {
+                granule_position = Some(header.granule_position);
+            }
+        }
+        Ok(granule_position)
+    }
+}
@@ -0,0 +1,1747 @@
+//! Ogg Vorbis decoding
+//!
+//! This is a from-scratch implementation of the Vorbis I specification. Streams using the (obsolete) floor type 0 are not supported, since no encoder has produced them in a long time
+
+use super::ogg::{Packet, PacketReader};
+use super::{Decoder, Source, check_out_samples, try_vec_with_capacity};
+use crate::audio::rc;
+use crate::result::*;
+use alloc::vec::Vec;
+use core::f32::consts::PI;
+// Tests link std, whose float methods would shadow these ones
+#[cfg(not(test))]
+use num_traits::float::Float;
+
+const VORBIS_MAGIC: &[u8; 6] = b"vorbis";
+
+const PACKET_TYPE_IDENTIFICATION: u8 = 1;
+const PACKET_TYPE_COMMENT: u8 = 3;
+const PACKET_TYPE_SETUP: u8 = 5;
+
+const CODEBOOK_SYNC_PATTERN: u32 = 0x564342;
+
+// The amount of bits needed to store the given value
+#[inline]
+const fn ilog(value: u32) -> u32 {
+    u32::BITS - value.leading_zeros()
+}
+
+// Unpacks the custom float format used in codebooks
+fn float32_unpack(value: u32) -> f32 {
+    let mantissa = (value & 0x1FFFFF) as f64;
+    let mantissa = match value & 0x80000000 {
+        0 => mantissa,
+        _ => -mantissa,
+    };
+    let exponent = ((value & 0x7FE00000) >> 21) as i64 - 788;
+    // The exponent is always within the range of normal doubles, so the power of 2 can be built directly
+    (mantissa * f64::from_bits(((exponent + 1023) as u64) << 52)) as f32
+}
+
+// Gets the greatest integer whose `dimensions`-th power is less than or equal to `entry_count`
+fn lookup1_values(entry_count: u32, dimensions: u32) -> u32 {
+    let fits = |value: u32| {
+        (value as u64)
+            .checked_pow(dimensions)
+            .is_some_and(|power| power <= entry_count as u64)
+    };
+
+    let mut value = (entry_count as f32).powf(1.0 / dimensions as f32) as u32;
+    while fits(value + 1) {
+        value += 1;
+    }
+    while (value > 0) && !fits(value) {
+        value -= 1;
+    }
+    value
+}
+
+/// Represents a bit reader over a Vorbis packet
+///
+/// Reads past the end of the packet return `None`, which is an expected condition in audio packets
+pub struct BitReader<'a> {
+    data: &'a [u8],
+    bit_offset: usize,
+}
+
+impl<'a> BitReader<'a> {
+    /// Creates a new [`BitReader`]
+    ///
+    /// # Arguments
+    ///
+    /// * `data`: The packet data
+    #[inline]
+    pub const fn new(data: &'a [u8]) -> Self {
+        Self {
+            data,
+            bit_offset: 0,
+        }
+    }
+
+    /// Reads an unsigned value of up to 32 bits (least significant bits first)
+    ///
+    /// # Arguments
+    ///
+    /// * `bit_count`: The amount of bits to read
+    pub fn read(&mut self, bit_count: u32) -> Option<u32> {
+        let bit_count = bit_count as usize;
+        if (self.bit_offset + bit_count) > (self.data.len() * 8) {
+            // Any further reads also fail
+            self.bit_offset = self.data.len() * 8;
+            return None;
+        }
+
+        let mut value = 0u64;
+        let mut read_count = 0;
+        while read_count < bit_count {
+            let byte = self.data[self.bit_offset / 8] as u64;
+            let bit = self.bit_offset % 8;
+            let take_count = (8 - bit).min(bit_count - read_count);
+            value |= ((byte >> bit) & ((1 << take_count) - 1)) << read_count;
+            read_count += take_count;
+            self.bit_offset += take_count;
+        }
+        Some(value as u32)
+    }
+
+    /// Reads a single bit as a flag
+    #[inline]
+    pub fn read_flag(&mut self) -> Option<bool> {
+        self.read(1).map(|bit| bit != 0)
+    }
+
+    /// Gets the amount of bits left to read
+    #[inline]
+    pub fn get_remaining_bits(&self) -> usize {
+        self.data.len() * 8 - self.bit_offset
+    }
+}
+
+// Header parsing runs out of data only with malformed headers
+trait HeaderRead<T> {
+    fn or_invalid(self) -> Result<T>;
+}
+
+impl<T> HeaderRead<T> for Option<T> {
+    #[inline]
+    fn or_invalid(self) -> Result<T> {
+        self.ok_or(rc::ResultInvalidFormat::make())
+    }
+}
+
+// Allocates the storage for header entries of at least `entry_bits` bits each, which must all fit in the rest of the packet
+fn alloc_entries<T>(reader: &BitReader, count: usize, entry_bits: usize) -> Result<Vec<T>> {
+    let total_bits = count
+        .checked_mul(entry_bits)
+        .ok_or(rc::ResultInvalidFormat::make())?;
+    result_return_if!(
+        total_bits > reader.get_remaining_bits(),
+        rc::ResultInvalidFormat
+    );
+    try_vec_with_capacity(count)
+}
+
+// Tree nodes contain two children: positive values are node indices, negative ones are entries (`-(entry + 1)`), and zero means no child
+const NO_CHILD: i32 = 0;
+
+struct Codebook {
+    dimensions: usize,
+    tree: Vec<[i32; 2]>,
+    // Entry and codeword length of books with a single used entry
+    single_entry: Option<(u32, u32)>,
+    lookup: Option<Vec<f32>>,
+}
+
+impl Codebook {
+    fn build_tree(lengths: &[u8]) -> Result<Vec<[i32; 2]>> {
+        // Codewords are assigned in entry order, always taking the lowest available one of each length
+        let mut markers = [0u32; 33];
+        let mut tree: Vec<[i32; 2]> = vec![[NO_CHILD; 2]];
+        for (entry, &length) in lengths.iter().enumerate() {
+            let length = length as usize;
+            if length == 0 {
+                continue;
+            }
+
+            let codeword = markers[length];
+            result_return_if!(
+                (length < 32) && ((codeword >> length) != 0),
+                rc::ResultInvalidFormat
+            );
+
+            // Claim the codeword, and move the markers of the shorter lengths past its branch
+            for j in (1..=length).rev() {
+                if (markers[j] & 1) != 0 {
+                    markers[j] = match j {
+                        1 => markers[1] + 1,
+                        _ => markers[j - 1] << 1,
+                    };
+                    break;
+                }
+                markers[j] += 1;
+            }
+            // The markers of the longer lengths which were hanging from the claimed codeword move to the new branch
+            let mut branch = codeword;
+            for j in (length + 1)..markers.len() {
+                if (markers[j] >> 1) != branch {
+                    break;
+                }
+                branch = markers[j];
+                markers[j] = markers[j - 1] << 1;
+            }
+
+            // Codewords are read from their most significant bit
+            let mut node = 0;
+            for bit_index in (0..length).rev() {
+                let bit = ((codeword >> bit_index) & 1) as usize;
+                if bit_index == 0 {
+                    result_return_unless!(tree[node][bit] == NO_CHILD, rc::ResultInvalidFormat);
+                    tree[node][bit] = -(entry as i32 + 1);
+                } else {
+                    if tree[node][bit] == NO_CHILD {
+                        tree.push([NO_CHILD; 2]);
+                        tree[node][bit] = (tree.len() - 1) as i32;
+                    }
+                    result_return_if!(tree[node][bit] < 0, rc::ResultInvalidFormat);
+                    node = tree[node][bit] as usize;
+                }
+            }
+        }
+        Ok(tree)
+    }
+
+    fn read(reader: &mut BitReader) -> Result<Self> {
+        result_return_unless!(
+            reader.read(24).or_invalid()? == CODEBOOK_SYNC_PATTERN,
+            rc::ResultInvalidFormat
+        );
+        let dimensions = reader.read(16).or_invalid()?;
+        let entry_count = reader.read(24).or_invalid()?;
+        result_return_if!(dimensions == 0, rc::ResultInvalidFormat);
+
+        let ordered = reader.read_flag().or_invalid()?;
+        let sparse = !ordered && reader.read_flag().or_invalid()?;
+        let mut lengths = match (ordered, sparse) {
+            // Ordered lengths are stored as run lengths, thus their count isn't bound by the packet size
+            (true, _) => try_vec_with_capacity(entry_count as usize)?,
+            // Each entry has at least the used flag or the length
+            (false, true) => alloc_entries(reader, entry_count as usize, 1)?,
+            (false, false) => alloc_entries(reader, entry_count as usize, 5)?,
+        };
+        lengths.resize(entry_count as usize, 0u8);
+        if ordered {
+            let mut entry = 0;
+            let mut length = reader.read(5).or_invalid()? + 1;
+            while entry < entry_count {
+                let count = reader.read(ilog(entry_count - entry)).or_invalid()?;
+                result_return_if!(
+                    (entry + count > entry_count) || (length > 32),
+                    rc::ResultInvalidFormat
+                );
+                lengths[entry as usize..(entry + count) as usize].fill(length as u8);
+                entry += count;
+                length += 1;
+            }
+        } else {
+            for length in lengths.iter_mut() {
+                let used = match sparse {
+                    true => reader.read_flag().or_invalid()?,
+                    false => true,
+                };
+                if used {
+                    *length = (reader.read(5).or_invalid()? + 1) as u8;
+                }
+            }
+        }
+
+        let mut used_entries = lengths
+            .iter()
+            .enumerate()
+            .filter(|(_, length)| **length > 0);
+        let single_entry = match (used_entries.next(), used_entries.next()) {
+            // A single used entry always gets decoded regardless of the bits, since its codeword doesn't form a full tree
+            (Some((entry, &length)), None) => Some((entry as u32, length as u32)),
+            _ => None,
+        };
+        let tree = match single_entry {
+            Some(_) => Vec::new(),
+            None => Self::build_tree(&lengths)?,
+        };
+
+        let lookup_type = reader.read(4).or_invalid()?;
+        let lookup = match lookup_type {
+            0 => None,
+            1 | 2 => {
+                let minimum_value = float32_unpack(reader.read(32).or_invalid()?);
+                let delta_value = float32_unpack(reader.read(32).or_invalid()?);
+                let value_bits = reader.read(4).or_invalid()? + 1;
+                let sequence_p = reader.read_flag().or_invalid()?;
+                let lookup_value_count = match lookup_type {
+                    1 => lookup1_values(entry_count, dimensions),
+                    _ => entry_count
+                        .checked_mul(dimensions)
+                        .ok_or(rc::ResultInvalidFormat::make())?,
+                };
+                let mut multiplicands =
+                    alloc_entries(reader, lookup_value_count as usize, value_bits as usize)?;
+                for _ in 0..lookup_value_count {
+                    multiplicands.push(reader.read(value_bits).or_invalid()?);
+                }
+                result_return_if!(multiplicands.is_empty(), rc::ResultInvalidFormat);
+
+                // All the vectors are unpacked beforehand
+                let dimensions = dimensions as usize;
+                let lookup_size = (entry_count as usize)
+                    .checked_mul(dimensions)
+                    .ok_or(rc::ResultInvalidFormat::make())?;
+                let mut lookup = try_vec_with_capacity(lookup_size)?;
+                for entry in 0..entry_count as usize {
+                    let mut last = 0.0;
+                    let mut index_divisor = 1usize;
+                    for i in 0..dimensions {
+                        let multiplicand_offset = match lookup_type {
+                            1 => (entry / index_divisor) % multiplicands.len(),
+                            _ => entry * dimensions + i,
+                        };
+                        let value = multiplicands[multiplicand_offset] as f32 * delta_value
+                            + minimum_value
+                            + last;
+                        lookup.push(value);
+                        if sequence_p {
+                            last = value;
+                        }
+                        index_divisor = index_divisor.wrapping_mul(multiplicands.len());
+                    }
+                }
+                Some(lookup)
+            }
+            _ => return rc::ResultInvalidFormat::make_err(),
+        };
+
+        Ok(Self {
+            dimensions: dimensions as usize,
+            tree,
+            single_entry,
+            lookup,
+        })
+    }
+
+    fn decode_entry(&self, reader: &mut BitReader) -> Option<u32> {
+        if let Some((entry, length)) = self.single_entry {
+            reader.read(length)?;
+            return Some(entry);
+        }
+
+        let mut node = 0;
+        loop {
+            let child = self.tree[node][reader.read(1)? as usize];
+            match child {
+                NO_CHILD => return None,
+                child if child < 0 => return Some((-child - 1) as u32),
+                child => node = child as usize,
+            }
+        }
+    }
+
+    fn decode_vector(&self, reader: &mut BitReader) -> Option<&[f32]> {
+        let entry = self.decode_entry(reader)? as usize;
+        let lookup = self.lookup.as_ref()?;
+        lookup.get(entry * self.dimensions..(entry + 1) * self.dimensions)
+    }
+}
+
+struct FloorClass {
+    dimensions: usize,
+    subclass_bits: u32,
+    masterbook: usize,
+    // `None` for unused subclasses
+    subclass_books: Vec<Option<usize>>,
+}
+
+struct Floor1 {
+    partition_classes: Vec<usize>,
+    classes: Vec<FloorClass>,
+    multiplier: i32,
+    x_list: Vec<u32>,
+    // Indices of the X list sorted by their X value
+    sorted_indices: Vec<usize>,
+    // The low and high neighbors of each X list entry (unused for the first two)
+    neighbors: Vec<(usize, usize)>,
+}
+
+impl Floor1 {
+    fn read(reader: &mut BitReader, codebook_count: usize) -> Result<Self> {
+        let partition_count = reader.read(5).or_invalid()? as usize;
+        let mut partition_classes = alloc_entries(reader, partition_count, 4)?;
+        for _ in 0..partition_count {
+            partition_classes.push(reader.read(4).or_invalid()? as usize);
+        }
+
+        let class_count = partition_classes.iter().max().map_or(0, |&class| class + 1);
+        let mut classes = alloc_entries(reader, class_count, 5)?;
+        for _ in 0..class_count {
+            let dimensions = reader.read(3).or_invalid()? as usize + 1;
+            let subclass_bits = reader.read(2).or_invalid()?;
+            let masterbook = match subclass_bits {
+                0 => 0,
+                _ => reader.read(8).or_invalid()? as usize,
+            };
+            result_return_unless!(masterbook < codebook_count, rc::ResultInvalidFormat);
+
+            let mut subclass_books = alloc_entries(reader, 1 << subclass_bits, 8)?;
+            for _ in 0..(1 << subclass_bits) {
+                let book = match reader.read(8).or_invalid()? {
+                    0 => None,
+                    book => Some(book as usize - 1),
+                };
+                result_return_if!(
+                    book.is_some_and(|book| book >= codebook_count),
+                    rc::ResultInvalidFormat
+                );
+                subclass_books.push(book);
+            }
+
+            classes.push(FloorClass {
+                dimensions,
+                subclass_bits,
+                masterbook,
+                subclass_books,
+            });
+        }
+
+        let multiplier = reader.read(2).or_invalid()? as i32 + 1;
+        let range_bits = reader.read(4).or_invalid()?;
+        let mut x_list = vec![0, 1 << range_bits];
+        for &class in partition_classes.iter() {
+            for _ in 0..classes[class].dimensions {
+                x_list.push(reader.read(range_bits).or_invalid()?);
+            }
+        }
+        // The spec limits the amount of points to 65, and they must all be different
+        result_return_if!(x_list.len() > 65, rc::ResultInvalidFormat);
+
+        let mut sorted_indices: Vec<usize> = (0..x_list.len()).collect();
+        sorted_indices.sort_by_key(|&i| x_list[i]);
+        result_return_if!(
+            sorted_indices
+                .windows(2)
+                .any(|pair| x_list[pair[0]] == x_list[pair[1]]),
+            rc::ResultInvalidFormat
+        );
+
+        let neighbors = (0..x_list.len())
+            .map(|i| {
+                let mut low = 0;
+                let mut high = 1;
+                for j in 0..i {
+                    if (x_list[j] < x_list[i]) && (x_list[j] > x_list[low]) {
+                        low = j;
+                    }
+                    if (x_list[j] > x_list[i]) && (x_list[j] < x_list[high]) {
+                        high = j;
+                    }
+                }
+                (low, high)
+            })
+            .collect();
+
+        Ok(Self {
+            partition_classes,
+            classes,
+            multiplier,
+            x_list,
+            sorted_indices,
+            neighbors,
+        })
+    }
+
+    #[inline]
+    fn get_range(&self) -> i32 {
+        [256, 128, 86, 64][self.multiplier as usize - 1]
+    }
+
+    // Decodes the Y values of the floor, returning `None` if the floor is unused in this packet
+    fn decode(
+        &self,
+        reader: &mut BitReader,
+        codebooks: &[Codebook],
+        y_list: &mut Vec<i32>,
+    ) -> Option<()> {
+        if !reader.read_flag()? {
+            return None;
+        }
+
+        let range_bits = ilog(self.get_range() as u32 - 1);
+        y_list.clear();
+        y_list.push(reader.read(range_bits)? as i32);
+        y_list.push(reader.read(range_bits)? as i32);
+        for &class in self.partition_classes.iter() {
+            let class = &self.classes[class];
+            let mut class_value = match class.subclass_bits {
+                0 => 0,
+                _ => codebooks[class.masterbook].decode_entry(reader)?,
+            };
+            let subclass_mask = (1 << class.subclass_bits) - 1;
+            for _ in 0..class.dimensions {
+                let y = match class.subclass_books[(class_value & subclass_mask) as usize] {
+                    Some(book) => codebooks[book].decode_entry(reader)? as i32,
+                    None => 0,
+                };
+                y_list.push(y);
+                class_value >>= class.subclass_bits;
+            }
+        }
+        Some(())
+    }
+
+    // Computes the floor curve from the decoded Y values, as indices into the inverse dB table
+    fn synthesize(
+        &self,
+        y_list: &[i32],
+        step2_flags: &mut Vec<bool>,
+        final_y_list: &mut Vec<i32>,
+        out_curve: &mut [f32],
+        inverse_db_table: &[f32; 0x100],
+    ) {
+        let range = self.get_range();
+        step2_flags.clear();
+        step2_flags.resize(self.x_list.len(), false);
+        final_y_list.clear();
+        final_y_list.resize(self.x_list.len(), 0);
+
+        step2_flags[0] = true;
+        step2_flags[1] = true;
+        final_y_list[0] = y_list[0];
+        final_y_list[1] = y_list[1];
+        for i in 2..self.x_list.len() {
+            let (low, high) = self.neighbors[i];
+            let predicted = render_point(
+                self.x_list[low] as i32,
+                final_y_list[low],
+                self.x_list[high] as i32,
+                final_y_list[high],
+                self.x_list[i] as i32,
+            );
+            let value = y_list[i];
+            let high_room = range - predicted;
+            let low_room = predicted;
+            let room = high_room.min(low_room) * 2;
+            if value != 0 {
+                step2_flags[low] = true;
+                step2_flags[high] = true;
+                step2_flags[i] = true;
+                final_y_list[i] = match value >= room {
+                    true if high_room > low_room => value - low_room + predicted,
+                    true => predicted - value + high_room - 1,
+                    false if (value % 2) == 1 => predicted - (value + 1) / 2,
+                    false => predicted + value / 2,
+                };
+            } else {
+                final_y_list[i] = predicted;
+            }
+        }
+
+        let n = out_curve.len() as i32;
+        let mut low_x = 0;
+        let mut low_y = final_y_list[self.sorted_indices[0]] * self.multiplier;
+        let mut high_x = 0;
+        let mut high_y = 0;
+        for &i in self.sorted_indices[1..].iter() {
+            if step2_flags[i] {
+                high_x = self.x_list[i] as i32;
+                high_y = final_y_list[i] * self.multiplier;
+                render_line(low_x, low_y, high_x, high_y, out_curve, inverse_db_table);
+                low_x = high_x;
+                low_y = high_y;
+            }
+        }
+        if high_x < n {
+            render_line(high_x, high_y, n, high_y, out_curve, inverse_db_table);
+        }
+    }
+}
+
+fn render_point(x0: i32, y0: i32, x1: i32, y1: i32, x: i32) -> i32 {
+    let dy = y1 - y0;
+    let adx = x1 - x0;
+    let offset = dy.abs() * (x - x0) / adx;
+    match dy < 0 {
+        true => y0 - offset,
+        false => y0 + offset,
+    }
+}
+
+fn render_line(
+    x0: i32,
+    y0: i32,
+    x1: i32,
+    y1: i32,
+    out_curve: &mut [f32],
+    inverse_db_table: &[f32; 0x100],
+) {
+    let n = out_curve.len() as i32;
+    let dy = y1 - y0;
+    let adx = x1 - x0;
+    if adx <= 0 {
+        return;
+    }
+    let base = dy / adx;
+    let step_y = match dy < 0 {
+        true => base - 1,
+        false => base + 1,
+    };
+    let ady = dy.abs() - base.abs() * adx;
+
+    let mut put = |x: i32, y: i32| {
+        if x < n {
+            out_curve[x as usize] = inverse_db_table[y.clamp(0, 0xFF) as usize];
+        }
+    };
+
+    let mut y = y0;
+    let mut error = 0;
+    put(x0, y);
+    for x in (x0 + 1)..x1.min(n) {
+        error += ady;
+        if error >= adx {
+            error -= adx;
+            y += step_y;
+        } else {
+            y += base;
+        }
+        put(x, y);
+    }
+}
+
+struct Residue {
+    residue_type: u32,
+    begin: usize,
+    end: usize,
+    partition_size: usize,
+    classification_count: usize,
+    classbook: usize,
+    // The book of each classification for each of the 8 passes
+    books: Vec<[Option<usize>; 8]>,
+}
+
+impl Residue {
+    fn read(reader: &mut BitReader, residue_type: u32, codebooks: &[Codebook]) -> Result<Self> {
+        let begin = reader.read(24).or_invalid()? as usize;
+        let end = reader.read(24).or_invalid()? as usize;
+        let partition_size = reader.read(24).or_invalid()? as usize + 1;
+        let classification_count = reader.read(6).or_invalid()? as usize + 1;
+        let classbook = reader.read(8).or_invalid()? as usize;
+        result_return_unless!(classbook < codebooks.len(), rc::ResultInvalidFormat);
+
+        let mut cascades = alloc_entries(reader, classification_count, 4)?;
+        for _ in 0..classification_count {
+            let low_bits = reader.read(3).or_invalid()?;
+            let high_bits = match reader.read_flag().or_invalid()? {
+                true => reader.read(5).or_invalid()?,
+                false => 0,
+            };
+            cascades.push((high_bits << 3) | low_bits);
+        }
+
+        let mut books = try_vec_with_capacity(classification_count)?;
+        for cascade in cascades {
+            let mut class_books = [None; 8];
+            for (pass, book) in class_books.iter_mut().enumerate() {
+                if (cascade & (1 << pass)) != 0 {
+                    let book_index = reader.read(8).or_invalid()? as usize;
+                    // Residue books must be VQ ones
+                    result_return_unless!(
+                        codebooks
+                            .get(book_index)
+                            .is_some_and(|book| book.lookup.is_some()),
+                        rc::ResultInvalidFormat
+                    );
+                    *book = Some(book_index);
+                }
+            }
+            books.push(class_books);
+        }
+
+        Ok(Self {
+            residue_type,
+            begin,
+            end,
+            partition_size,
+            classification_count,
+            classbook,
+            books,
+        })
+    }
+
+    fn decode_partition(
+        &self,
+        reader: &mut BitReader,
+        codebook: &Codebook,
+        out_vector: &mut [f32],
+    ) -> Option<()> {
+        let dimensions = codebook.dimensions;
+        match self.residue_type {
+            0 => {
+                // Vector values are interleaved through the partition
+                let step = self.partition_size / dimensions;
+                for i in 0..step {
+                    let entry = codebook.decode_vector(reader)?;
+                    for (j, value) in entry.iter().enumerate() {
+                        out_vector[i + j * step] += value;
+                    }
+                }
+            }
+            _ => {
+                let mut i = 0;
+                while i < self.partition_size {
+                    let entry = codebook.decode_vector(reader)?;
+                    for value in entry.iter() {
+                        if i < self.partition_size {
+                            out_vector[i] += value;
+                        }
+                        i += 1;
+                    }
+                }
+            }
+        }
+        Some(())
+    }
+
+    // Decodes the residue vectors, which stops at the end of the packet (leaving the rest of them as zeros)
+    fn decode_vectors(
+        &self,
+        reader: &mut BitReader,
+        codebooks: &[Codebook],
+        vectors: &mut [&mut [f32]],
+        do_not_decode: &[bool],
+        classifications: &mut Vec<usize>,
+    ) -> Option<()> {
+        let vector_size = vectors.first()?.len();
+        let begin = self.begin.min(vector_size);
+        let end = self.end.min(vector_size);
+        let partition_count = (end - begin) / self.partition_size;
+        if partition_count == 0 {
+            return Some(());
+        }
+
+        let classbook = &codebooks[self.classbook];
+        let classwords_per_codeword = classbook.dimensions;
+        let channel_count = vectors.len();
+        classifications.clear();
+        classifications.resize(
+            channel_count * (partition_count + classwords_per_codeword),
+            0,
+        );
+
+        for pass in 0..8 {
+            let mut partition = 0;
+            while partition < partition_count {
+                if pass == 0 {
+                    for channel in 0..channel_count {
+                        if do_not_decode[channel] {
+                            continue;
+                        }
+                        let mut value = classbook.decode_entry(reader)? as usize;
+                        let channel_classifications = &mut classifications
+                            [channel * (partition_count + classwords_per_codeword)..];
+                        for i in (0..classwords_per_codeword).rev() {
+                            channel_classifications[partition + i] =
+                                value % self.classification_count;
+                            value /= self.classification_count;
+                        }
+                    }
+                }
+
+                for _ in 0..classwords_per_codeword {
+                    if partition >= partition_count {
+                        break;
+                    }
+                    for channel in 0..channel_count {
+                        if do_not_decode[channel] {
+                            continue;
+                        }
+                        let classification = classifications
+                            [channel * (partition_count + classwords_per_codeword) + partition];
+                        if let Some(book) = self.books[classification][pass] {
+                            let offset = begin + partition * self.partition_size;
+                            self.decode_partition(
+                                reader,
+                                &codebooks[book],
+                                &mut vectors[channel][offset..offset + self.partition_size],
+                            )?;
+                        }
+                    }
+                    partition += 1;
+                }
+            }
+        }
+        Some(())
+    }
+}
+
+struct Mapping {
+    // Magnitude and angle channels
+    coupling_steps: Vec<(usize, usize)>,
+    // The submap of each channel
+    channel_submaps: Vec<usize>,
+    // Floor and residue of each submap
+    submaps: Vec<(usize, usize)>,
+}
+
+impl Mapping {
+    fn read(
+        reader: &mut BitReader,
+        channel_count: usize,
+        floor_count: usize,
+        residue_count: usize,
+    ) -> Result<Self> {
+        // Only mapping type 0 exists
+        result_return_unless!(reader.read(16).or_invalid()? == 0, rc::ResultInvalidFormat);
+
+        let submap_count = match reader.read_flag().or_invalid()? {
+            true => reader.read(4).or_invalid()? as usize + 1,
+            false => 1,
+        };
+
+        let mut coupling_steps = Vec::new();
+        if reader.read_flag().or_invalid()? {
+            let step_count = reader.read(8).or_invalid()? as usize + 1;
+            let channel_bits = ilog(channel_count as u32 - 1);
+            for _ in 0..step_count {
+                let magnitude = reader.read(channel_bits).or_invalid()? as usize;
+                let angle = reader.read(channel_bits).or_invalid()? as usize;
+                result_return_if!(
+                    (magnitude == angle)
+                        || (magnitude >= channel_count)
+                        || (angle >= channel_count),
+                    rc::ResultInvalidFormat
+                );
+                coupling_steps.push((magnitude, angle));
+            }
+        }
+
+        result_return_unless!(reader.read(2).or_invalid()? == 0, rc::ResultInvalidFormat);
+
+        let mut channel_submaps = vec![0; channel_count];
+        if submap_count > 1 {
+            for submap in channel_submaps.iter_mut() {
+                *submap = reader.read(4).or_invalid()? as usize;
+                result_return_unless!(*submap < submap_count, rc::ResultInvalidFormat);
+            }
+        }
+
+        let mut submaps = alloc_entries(reader, submap_count, 24)?;
+        for _ in 0..submap_count {
+            // Unused time configuration
+            reader.read(8).or_invalid()?;
+            let floor = reader.read(8).or_invalid()? as usize;
+            let residue = reader.read(8).or_invalid()? as usize;
+            result_return_unless!(
+                (floor < floor_count) && (residue < residue_count),
+                rc::ResultInvalidFormat
+            );
+            submaps.push((floor, residue));
+        }
+
+        Ok(Self {
+            coupling_steps,
+            channel_submaps,
+            submaps,
+        })
+    }
+}
+
+struct Mode {
+    long_block: bool,
+    mapping: usize,
+}
+
+struct Setup {
+    codebooks: Vec<Codebook>,
+    floors: Vec<Floor1>,
+    residues: Vec<Residue>,
+    mappings: Vec<Mapping>,
+    modes: Vec<Mode>,
+}
+
+impl Setup {
+    fn read(data: &[u8], channel_count: usize) -> Result<Self> {
+        result_return_unless!(
+            (data.len() > 7) && (data[0] == PACKET_TYPE_SETUP) && (&data[1..7] == VORBIS_MAGIC),
+            rc::ResultInvalidFormat
+        );
+        let mut reader = BitReader::new(&data[7..]);
+
+        // Counts are checked against the minimum size of each entry before allocating
+        let codebook_count = reader.read(8).or_invalid()? as usize + 1;
+        let mut codebooks = alloc_entries(&reader, codebook_count, 69)?;
+        for _ in 0..codebook_count {
+            codebooks.push(Codebook::read(&mut reader)?);
+        }
+
+        // Unused time domain transforms, which must be zero
+        let time_count = reader.read(6).or_invalid()? + 1;
+        for _ in 0..time_count {
+            result_return_unless!(reader.read(16).or_invalid()? == 0, rc::ResultInvalidFormat);
+        }
+
+        let floor_count = reader.read(6).or_invalid()? as usize + 1;
+        let mut floors = alloc_entries(&reader, floor_count, 27)?;
+        for _ in 0..floor_count {
+            match reader.read(16).or_invalid()? {
+                1 => floors.push(Floor1::read(&mut reader, codebook_count)?),
+                0 => return rc::ResultUnsupportedFormat::make_err(),
+                _ => return rc::ResultInvalidFormat::make_err(),
+            }
+        }
+
+        let residue_count = reader.read(6).or_invalid()? as usize + 1;
+        let mut residues = alloc_entries(&reader, residue_count, 102)?;
+        for _ in 0..residue_count {
+            let residue_type = reader.read(16).or_invalid()?;
+            result_return_unless!(residue_type <= 2, rc::ResultInvalidFormat);
+            residues.push(Residue::read(&mut reader, residue_type, &codebooks)?);
+        }
+
+        let mapping_count = reader.read(6).or_invalid()? as usize + 1;
+        let mut mappings = alloc_entries(&reader, mapping_count, 44)?;
+        for _ in 0..mapping_count {
+            mappings.push(Mapping::read(
+                &mut reader,
+                channel_count,
+                floor_count,
+                residue_count,
+            )?);
+        }
+
+        let mode_count = reader.read(6).or_invalid()? as usize + 1;
+        let mut modes = alloc_entries(&reader, mode_count, 41)?;
+        for _ in 0..mode_count {
+            let long_block = reader.read_flag().or_invalid()?;
+            let window_type = reader.read(16).or_invalid()?;
+            let transform_type = reader.read(16).or_invalid()?;
+            let mapping = reader.read(8).or_invalid()? as usize;
+            result_return_unless!(
+                (window_type == 0) && (transform_type == 0) && (mapping < mapping_count),
+                rc::ResultInvalidFormat
+            );
+            modes.push(Mode {
+                long_block,
+                mapping,
+            });
+        }
+
+        result_return_unless!(reader.read_flag().or_invalid()?, rc::ResultInvalidFormat);
+
+        Ok(Self {
+            codebooks,
+            floors,
+            residues,
+            mappings,
+            modes,
+        })
+    }
+}
+
+/// Represents the information from the identification header of a Vorbis stream
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct VorbisInfo {
+    pub channel_count: u16,
+    pub sample_rate: u32,
+    pub bitrate_maximum: i32,
+    pub bitrate_nominal: i32,
+    pub bitrate_minimum: i32,
+    /// The short block size
+    pub block_size_0: usize,
+    /// The long block size
+    pub block_size_1: usize,
+}
+
+impl VorbisInfo {
+    /// Parses an identification header packet
+    ///
+    /// # Arguments
+    ///
+    /// * `data`: The packet data
+    pub fn parse(data: &[u8]) -> Result<Self> {
+        result_return_unless!(
+            (data.len() >= 30)
+                && (data[0] == PACKET_TYPE_IDENTIFICATION)
+                && (&data[1..7] == VORBIS_MAGIC),
+            rc::ResultInvalidFormat
+        );
+        let read_u32 = |offset: usize| {
+            u32::from_le_bytes([
+                data[offset],
+                data[offset + 1],
+                data[offset + 2],
+                data[offset + 3],
+            ])
+        };
+
+        let version = read_u32(7);
+        result_return_unless!(version == 0, rc::ResultUnsupportedFormat);
+
+        let channel_count = data[11] as u16;
+        let sample_rate = read_u32(12);
+        let block_size_0 = 1usize << (data[28] & 0xF);
+        let block_size_1 = 1usize << (data[28] >> 4);
+        result_return_if!(channel_count == 0, rc::ResultInvalidChannelCount);
+        result_return_if!(sample_rate == 0, rc::ResultInvalidFormat);
+        result_return_unless!(
+            (64..=8192).contains(&block_size_0)
+                && (64..=8192).contains(&block_size_1)
+                && (block_size_0 <= block_size_1),
+            rc::ResultInvalidFormat
+        );
+        // Framing bit
+        result_return_unless!((data[29] & 1) != 0, rc::ResultInvalidFormat);
+
+        Ok(Self {
+            channel_count,
+            sample_rate,
+            bitrate_maximum: read_u32(16) as i32,
+            bitrate_nominal: read_u32(20) as i32,
+            bitrate_minimum: read_u32(24) as i32,
+            block_size_0,
+            block_size_1,
+        })
+    }
+}
+
+#[derive(Copy, Clone, Default)]
+struct Complex {
+    re: f32,
+    im: f32,
+}
+
+impl Complex {
+    #[inline]
+    fn from_angle(angle: f32) -> Self {
+        Self {
+            re: angle.cos(),
+            im: angle.sin(),
+        }
+    }
+
+    #[inline]
+    fn mul(self, other: Self) -> Self {
+        Self {
+            re: self.re * other.re - self.im * other.im,
+            im: self.re * other.im + self.im * other.re,
+        }
+    }
+}
+
+/// Represents an inverse MDCT of a fixed size, computed via a complex FFT of a quarter of its size
+pub struct Imdct {
+    size: usize,
+    pre_twiddles: Vec<Complex>,
+    post_twiddles: Vec<Complex>,
+    fft_twiddles: Vec<Complex>,
+    bit_reverse: Vec<usize>,
+    buffer: Vec<Complex>,
+    dct: Vec<f32>,
+}
+
+impl Imdct {
+    /// Creates a new [`Imdct`]
+    ///
+    /// # Arguments
+    ///
+    /// * `size`: The output size, which must be a power of two of at least `16`
+    pub fn new(size: usize) -> Self {
+        let half_size = size / 2;
+        let fft_size = size / 4;
+        let pre_twiddles = (0..fft_size)
+            .map(|k| Complex::from_angle(-PI * k as f32 / half_size as f32))
+            .collect();
+        let post_twiddles = (0..fft_size)
+            .map(|k| Complex::from_angle(-PI * (k as f32 + 0.25) / half_size as f32))
+            .collect();
+        let fft_twiddles = (0..fft_size / 2)
+            .map(|k| Complex::from_angle(-2.0 * PI * k as f32 / fft_size as f32))
+            .collect();
+        let fft_bits = fft_size.trailing_zeros();
+        let bit_reverse = (0..fft_size)
+            .map(|i| i.reverse_bits() >> (usize::BITS - fft_bits))
+            .collect();
+
+        Self {
+            size,
+            pre_twiddles,
+            post_twiddles,
+            fft_twiddles,
+            bit_reverse,
+            buffer: vec![Complex::default(); fft_size],
+            dct: vec![0.0; half_size],
+        }
+    }
+
+    /// Gets the output size
+    #[inline]
+    pub fn get_size(&self) -> usize {
+        self.size
+    }
+
+    fn fft(&mut self) {
+        let fft_size = self.buffer.len();
+        for i in 0..fft_size {
+            let j = self.bit_reverse[i];
+            if i < j {
+                self.buffer.swap(i, j);
+            }
+        }
+
+        let mut length = 2;
+        while length <= fft_size {
+            let twiddle_step = fft_size / length;
+            for start in (0..fft_size).step_by(length) {
+                for k in 0..length / 2 {
+                    let twiddle = self.fft_twiddles[k * twiddle_step];
+                    let even = self.buffer[start + k];
+                    let odd = self.buffer[start + k + length / 2].mul(twiddle);
+                    self.buffer[start + k] = Complex {
+                        re: even.re + odd.re,
+                        im: even.im + odd.im,
+                    };
+                    self.buffer[start + k + length / 2] = Complex {
+                        re: even.re - odd.re,
+                        im: even.im - odd.im,
+                    };
+                }
+            }
+            length *= 2;
+        }
+    }
+
+    /// Computes the inverse MDCT (without any scaling, as Vorbis expects)
+    ///
+    /// # Arguments
+    ///
+    /// * `input`: The spectral coefficients, half of the output size
+    /// * `output`: The output samples
+    pub fn compute(&mut self, input: &[f32], output: &mut [f32]) {
+        let half_size = self.size / 2;
+        let quarter_size = self.size / 4;
+
+        // DCT-IV of the input, via a complex FFT
+        for k in 0..quarter_size {
+            let value = Complex {
+                re: input[2 * k],
+                im: input[half_size - 1 - 2 * k],
+            };
+            self.buffer[k] = value.mul(self.pre_twiddles[k]);
+        }
+        self.fft();
+        for k in 0..quarter_size {
+            let value = self.buffer[k].mul(self.post_twiddles[k]);
+            self.dct[2 * k] = value.re;
+            self.dct[half_size - 1 - 2 * k] = -value.im;
+        }
+
+        // The IMDCT output is made of the (mirrored) DCT-IV output
+        let (first_quarter, rest) = output[..self.size].split_at_mut(quarter_size);
+        let (middle_half, last_quarter) = rest.split_at_mut(half_size);
+        first_quarter.copy_from_slice(&self.dct[quarter_size..]);
+        for (value, dct_value) in middle_half.iter_mut().zip(self.dct.iter().rev()) {
+            *value = -dct_value;
+        }
+        for (value, dct_value) in last_quarter.iter_mut().zip(self.dct.iter()) {
+            *value = -dct_value;
+        }
+    }
+}
+
+// The rising slope of a window overlap of the given size
+fn make_window_slope(overlap_size: usize) -> Vec<f32> {
+    let half_size = overlap_size / 2;
+    (0..half_size)
+        .map(|i| {
+            let x = ((i as f32 + 0.5) / half_size as f32 * PI / 2.0).sin();
+            (PI / 2.0 * x * x).sin()
+        })
+        .collect()
+}
+
+fn make_inverse_db_table() -> [f32; 0x100] {
+    // The table values go geometrically from ~-140dB to 0dB
+    const MINIMUM_VALUE: f64 = 1.0649863e-07;
+    let mut table = [0.0; 0x100];
+    for (i, value) in table.iter_mut().enumerate() {
+        *value = MINIMUM_VALUE.powf((0xFF - i) as f64 / 0xFF as f64) as f32;
+    }
+    table
+}
+
+struct DecodeState {
+    imdcts: [Imdct; 2],
+    window_slopes: [Vec<f32>; 2],
+    inverse_db_table: [f32; 0x100],
+    spectra: Vec<Vec<f32>>,
+    floor_curves: Vec<Vec<f32>>,
+    floor_used: Vec<bool>,
+    y_list: Vec<i32>,
+    final_y_list: Vec<i32>,
+    step2_flags: Vec<bool>,
+    classifications: Vec<usize>,
+    residue_buffer: Vec<f32>,
+    block: Vec<f32>,
+    // The windowed right half of the previous block of each channel
+    previous_halves: Vec<Vec<f32>>,
+    previous_block_size: Option<usize>,
+}
+
+impl DecodeState {
+    fn new(info: &VorbisInfo) -> Self {
+        let channel_count = info.channel_count as usize;
+        let max_half_size = info.block_size_1 / 2;
+        Self {
+            imdcts: [Imdct::new(info.block_size_0), Imdct::new(info.block_size_1)],
+            window_slopes: [
+                make_window_slope(info.block_size_0),
+                make_window_slope(info.block_size_1),
+            ],
+            inverse_db_table: make_inverse_db_table(),
+            spectra: vec![vec![0.0; max_half_size]; channel_count],
+            floor_curves: vec![vec![0.0; max_half_size]; channel_count],
+            floor_used: vec![false; channel_count],
+            y_list: Vec::new(),
+            final_y_list: Vec::new(),
+            step2_flags: Vec::new(),
+            classifications: Vec::new(),
+            residue_buffer: Vec::new(),
+            block: vec![0.0; info.block_size_1],
+            previous_halves: vec![Vec::with_capacity(max_half_size); channel_count],
+            previous_block_size: None,
+        }
+    }
+
+    fn reset(&mut self) {
+        self.previous_block_size = None;
+    }
+}
+
+/// Represents a streaming Ogg Vorbis decoder
+pub struct VorbisDecoder<S: Source> {
+    reader: PacketReader<S>,
+    info: VorbisInfo,
+    setup: Setup,
+    state: DecodeState,
+    frame_count: Option<usize>,
+    decoded_frame_count: u64,
+    pending_samples: Vec<i16>,
+    pending_offset: usize,
+    finished: bool,
+}
+
+impl<S: Source> VorbisDecoder<S> {
+    /// Creates a new [`VorbisDecoder`]
+    ///
+    /// # Arguments
+    ///
+    /// * `source`: The source to decode
+    pub fn new(source: S) -> Result<Self> {
+        let mut reader = PacketReader::new(source);
+        let (info, setup) = Self::read_headers(&mut reader)?;
+        // Each audio packet takes at least a byte and decodes into at most half a long block, thus bigger granule positions can't be trusted
+        let max_frame_count = reader
+            .get_source()
+            .get_size()?
+            .saturating_mul(info.block_size_1 / 2);
+        let frame_count = reader
+            .find_last_granule_position()?
+            .and_then(|granule_position| usize::try_from(granule_position).ok())
+            .filter(|&frame_count| frame_count <= max_frame_count);
+        let state = DecodeState::new(&info);
+
+        Ok(Self {
+            reader,
+            info,
+            setup,
+            state,
+            frame_count,
+            decoded_frame_count: 0,
+            pending_samples: Vec::new(),
+            pending_offset: 0,
+            finished: false,
+        })
+    }
+
+    fn read_header_packet(reader: &mut PacketReader<S>) -> Result<Packet> {
+        reader.read_packet()?.ok_or(rc::ResultInvalidFormat::make())
+    }
+
+    fn read_headers(reader: &mut PacketReader<S>) -> Result<(VorbisInfo, Setup)> {
+        let info = VorbisInfo::parse(&Self::read_header_packet(reader)?.data)?;
+
+        // Comments are not needed for decoding
+        let comment = Self::read_header_packet(reader)?;
+        result_return_unless!(
+            (comment.data.len() > 7)
+                && (comment.data[0] == PACKET_TYPE_COMMENT)
+                && (&comment.data[1..7] == VORBIS_MAGIC),
+            rc::ResultInvalidFormat
+        );
+
+        let setup = Setup::read(
+            &Self::read_header_packet(reader)?.data,
+            info.channel_count as usize,
+        )?;
+        Ok((info, setup))
+    }
+
+    /// Gets the stream information
+    #[inline]
+    pub fn get_info(&self) -> &VorbisInfo {
+        &self.info
+    }
+
+    // Decodes an audio packet into the pending samples, returning the amount of decoded frames (packets with errors are skipped, as the spec recommends)
+    fn decode_packet(&mut self, data: &[u8]) -> usize {
+        let mut reader = BitReader::new(data);
+        let channel_count = self.info.channel_count as usize;
+        let setup = &self.setup;
+        let state = &mut self.state;
+
+        // Not an audio packet
+        if reader.read_flag() != Some(false) {
+            return 0;
+        }
+        let mode = match reader
+            .read(ilog(setup.modes.len() as u32 - 1))
+            .and_then(|mode| setup.modes.get(mode as usize))
+        {
+            Some(mode) => mode,
+            None => return 0,
+        };
+        let mapping = &setup.mappings[mode.mapping];
+
+        let block_size = match mode.long_block {
+            true => self.info.block_size_1,
+            false => self.info.block_size_0,
+        };
+        let half_size = block_size / 2;
+        let (previous_long, next_long) = match mode.long_block {
+            true => match (reader.read_flag(), reader.read_flag()) {
+                (Some(previous_long), Some(next_long)) => (previous_long, next_long),
+                _ => return 0,
+            },
+            false => (false, false),
+        };
+
+        // Floors
+        for channel in 0..channel_count {
+            let (floor, _) = mapping.submaps[mapping.channel_submaps[channel]];
+            let floor = &setup.floors[floor];
+            let floor_curve = &mut state.floor_curves[channel][..half_size];
+            state.floor_used[channel] = floor
+                .decode(&mut reader, &setup.codebooks, &mut state.y_list)
+                .is_some();
+            if state.floor_used[channel] {
+                floor.synthesize(
+                    &state.y_list,
+                    &mut state.step2_flags,
+                    &mut state.final_y_list,
+                    floor_curve,
+                    &state.inverse_db_table,
+                );
+            }
+        }
+
+        // Channels coupled with used channels need their residues too
+        let mut do_not_decode: Vec<bool> = state.floor_used.iter().map(|&used| !used).collect();
+        for &(magnitude, angle) in mapping.coupling_steps.iter() {
+            if !do_not_decode[magnitude] || !do_not_decode[angle] {
+                do_not_decode[magnitude] = false;
+                do_not_decode[angle] = false;
+            }
+        }
+
+        // Residues
+        for spectrum in state.spectra.iter_mut() {
+            spectrum[..half_size].fill(0.0);
+        }
+        for (submap, &(_, residue)) in mapping.submaps.iter().enumerate() {
+            let residue = &setup.residues[residue];
+            let channels: Vec<usize> = (0..channel_count)
+                .filter(|&channel| mapping.channel_submaps[channel] == submap)
+                .collect();
+            let submap_do_not_decode: Vec<bool> = channels
+                .iter()
+                .map(|&channel| do_not_decode[channel])
+                .collect();
+
+            if residue.residue_type == 2 {
+                // All the channels are interleaved into a single vector
+                if submap_do_not_decode.iter().all(|&skip| skip) {
+                    continue;
+                }
+                state.residue_buffer.clear();
+                state.residue_buffer.resize(half_size * channels.len(), 0.0);
+                let _ = residue.decode_vectors(
+                    &mut reader,
+                    &setup.codebooks,
+                    &mut [&mut state.residue_buffer[..]],
+                    &[false],
+                    &mut state.classifications,
+                );
+                for (i, frame) in state
+                    .residue_buffer
+                    .chunks_exact(channels.len())
+                    .enumerate()
+                {
+                    for (&channel, &value) in channels.iter().zip(frame.iter()) {
+                        state.spectra[channel][i] = value;
+                    }
+                }
+            } else {
+                let mut vectors: Vec<&mut [f32]> = state
+                    .spectra
+                    .iter_mut()
+                    .enumerate()
+                    .filter(|(channel, _)| mapping.channel_submaps[*channel] == submap)
+                    .map(|(_, spectrum)| &mut spectrum[..half_size])
+                    .collect();
+                let _ = residue.decode_vectors(
+                    &mut reader,
+                    &setup.codebooks,
+                    &mut vectors,
+                    &submap_do_not_decode,
+                    &mut state.classifications,
+                );
+            }
+        }
+
+        // Inverse coupling
+        for &(magnitude, angle) in mapping.coupling_steps.iter().rev() {
+            for i in 0..half_size {
+                let magnitude_value = state.spectra[magnitude][i];
+                let angle_value = state.spectra[angle][i];
+                let (new_magnitude, new_angle) = match (magnitude_value > 0.0, angle_value > 0.0) {
+                    (true, true) => (magnitude_value, magnitude_value - angle_value),
+                    (true, false) => (magnitude_value + angle_value, magnitude_value),
+                    (false, true) => (magnitude_value, magnitude_value + angle_value),
+                    (false, false) => (magnitude_value - angle_value, magnitude_value),
+                };
+                state.spectra[magnitude][i] = new_magnitude;
+                state.spectra[angle][i] = new_angle;
+            }
+        }
+
+        // Window shape, depending on the neighbor block sizes
+        let short_size = self.info.block_size_0;
+        let (left_start, left_slope) = match mode.long_block && !previous_long {
+            true => (block_size / 4 - short_size / 4, &state.window_slopes[0]),
+            false => (0, &state.window_slopes[mode.long_block as usize]),
+        };
+        let (right_start, right_slope) = match mode.long_block && !next_long {
+            true => (block_size * 3 / 4 - short_size / 4, &state.window_slopes[0]),
+            false => (half_size, &state.window_slopes[mode.long_block as usize]),
+        };
+
+        // The output goes from the center of the previous block to the center of this one
+        let frame_count = state.previous_block_size.map_or(0, |previous_block_size| {
+            previous_block_size / 4 + block_size / 4
+        });
+        let pending_offset = self.pending_samples.len();
+        self.pending_samples
+            .resize(pending_offset + frame_count * channel_count, 0);
+
+        let imdct = &mut state.imdcts[mode.long_block as usize];
+        for channel in 0..channel_count {
+            let spectrum = &mut state.spectra[channel][..half_size];
+            if state.floor_used[channel] {
+                for (value, floor_value) in
+                    spectrum.iter_mut().zip(state.floor_curves[channel].iter())
+                {
+                    *value *= floor_value;
+                }
+            } else {
+                spectrum.fill(0.0);
+            }
+
+            let block = &mut state.block[..block_size];
+            imdct.compute(spectrum, block);
+
+            // Apply the window
+            block[..left_start].fill(0.0);
+            for (value, slope_value) in block[left_start..].iter_mut().zip(left_slope.iter()) {
+                *value *= slope_value;
+            }
+            let right_end = right_start + right_slope.len();
+            for (value, slope_value) in block[right_start..right_end]
+                .iter_mut()
+                .zip(right_slope.iter().rev())
+            {
+                *value *= slope_value;
+            }
+            block[right_end..].fill(0.0);
+
+            // Overlap-add with the previous block, aligning the centers of their overlapping halves
+            if let Some(previous_block_size) = state.previous_block_size {
+                let previous_half = &state.previous_halves[channel];
+                let start = (block_size / 4) as isize - (previous_block_size / 4) as isize;
+                for i in 0..frame_count {
+                    let previous_value = previous_half.get(i).copied().unwrap_or(0.0);
+                    let current_index = start + i as isize;
+                    let current_value = match current_index {
+                        0.. => block[current_index as usize],
+                        _ => 0.0,
+                    };
+                    let value = (previous_value + current_value) * i16::MAX as f32;
+                    self.pending_samples[pending_offset + i * channel_count + channel] =
+                        value.clamp(i16::MIN as f32, i16::MAX as f32) as i16;
+                }
+            }
+
+            let previous_half = &mut state.previous_halves[channel];
+            previous_half.clear();
+            previous_half.extend_from_slice(&block[half_size..]);
+        }
+
+        state.previous_block_size = Some(block_size);
+        frame_count
+    }
+
+    fn decode_next_packet(&mut self) -> Result<bool> {
+        let packet = match self.reader.read_packet()? {
+            Some(packet) => packet,
+            None => return Ok(false),
+        };
+
+        let pending_offset = self.pending_samples.len();
+        let mut frame_count = self.decode_packet(&packet.data) as u64;
+        if packet.end_of_stream {
+            // The last packet may be padded, in which case the granule position tells the actual end
+            if let Some(granule_position) = packet.granule_position {
+                frame_count =
+                    frame_count.min(granule_position.saturating_sub(self.decoded_frame_count));
+            }
+            self.pending_samples
+                .truncate(pending_offset + frame_count as usize * self.info.channel_count as usize);
+        }
+        self.decoded_frame_count += frame_count;
+        Ok(!packet.end_of_stream)
+    }
+}
+
+impl<S: Source> Decoder for VorbisDecoder<S> {
+    fn get_channel_count(&self) -> u16 {
+        self.info.channel_count
+    }
+
+    fn get_sample_rate(&self) -> u32 {
+        self.info.sample_rate
+    }
+
+    fn get_frame_count(&self) -> Option<usize> {
+        self.frame_count
+    }
+
+    fn decode(&mut self, out_samples: &mut [i16]) -> Result<usize> {
+        let channel_count = self.info.channel_count as usize;
+        let max_frame_count = check_out_samples(out_samples, self.info.channel_count)?;
+
+        while (self.pending_samples.len() - self.pending_offset) < (max_frame_count * channel_count)
+            && !self.finished
+        {
+            if self.pending_offset > 0 {
+                self.pending_samples.drain(..self.pending_offset);
+                self.pending_offset = 0;
+            }
+            self.finished = !self.decode_next_packet()?;
+        }
+
+        let sample_count =
+            (self.pending_samples.len() - self.pending_offset).min(max_frame_count * channel_count);
+        out_samples[..sample_count].copy_from_slice(
+            &self.pending_samples[self.pending_offset..self.pending_offset + sample_count],
+        );
+        self.pending_offset += sample_count;
+        Ok(sample_count / channel_count)
+    }
+
+    fn rewind(&mut self) -> Result<()> {
+        self.reader.rewind();
+        // The headers were already parsed, so they are just skipped
+        for _ in 0..3 {
+            Self::read_header_packet(&mut self.reader)?;
+        }
+
+        self.state.reset();
+        self.decoded_frame_count = 0;
+        self.pending_samples.clear();
+        self.pending_offset = 0;
+        self.finished = false;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::audio::decode::{SliceSource, ogg, wav};
+
+    const STEREO: &[u8] = audio_fixture!("vorbis_stereo.ogg");
+    // The reference decoding of the stereo file
+    const STEREO_DECODED: &[u8] = audio_fixture!("vorbis_stereo.wav");
+    // A setup header with a codebook of 2^24 entries
+    const HUGE_CODEBOOK: &[u8] = audio_fixture!("vorbis_huge_codebook.ogg");
+
+    fn decode(data: &[u8], chunk_frame_count: usize) -> Result<Vec<i16>> {
+        let mut decoder = VorbisDecoder::new(SliceSource::new(data))?;
+        let mut samples = Vec::new();
+        let mut chunk = vec![0; chunk_frame_count * 2];
+        loop {
+            let frame_count = decoder.decode(&mut chunk)?;
+            if frame_count == 0 {
+                return Ok(samples);
+            }
+            samples.extend_from_slice(&chunk[..frame_count * 2]);
+        }
+    }
+
+    fn reference_samples() -> Vec<i16> {
+        let mut decoder = wav::WavDecoder::new(SliceSource::new(STEREO_DECODED)).unwrap();
+        let mut samples = vec![0; decoder.get_info().get_frame_count() * 2];
+        assert_eq!(decoder.decode(&mut samples), Ok(samples.len() / 2));
+        samples
+    }
+
+    fn assert_matches_reference(samples: &[i16], reference: &[i16]) {
+        assert_eq!(samples.len(), reference.len());
+        // Floating point differences may round differently
+        for (&sample, &reference) in samples.iter().zip(reference.iter()) {
+            assert!((sample as i32 - reference as i32).abs() <= 1);
+        }
+    }
+
+    #[test]
+    fn bit_reader() {
+        let mut reader = BitReader::new(&[0b1010_1101, 0xFF, 0x01]);
+        assert_eq!(reader.get_remaining_bits(), 24);
+        assert_eq!(reader.read(3), Some(0b101));
+        assert_eq!(reader.read_flag(), Some(true));
+        assert_eq!(reader.read(8), Some(0xFA));
+        assert_eq!(reader.get_remaining_bits(), 12);
+        assert_eq!(reader.read(13), None);
+        // Failed reads consume the rest of the packet
+        assert_eq!(reader.get_remaining_bits(), 0);
+        assert_eq!(reader.read(1), None);
+        assert_eq!(reader.read(0), Some(0));
+
+        let mut reader = BitReader::new(&[0x78, 0x56, 0x34, 0x12, 0xFF]);
+        assert_eq!(reader.read(32), Some(0x12345678));
+    }
+
+    #[test]
+    fn header_allocations() {
+        let reader = BitReader::new(&[0; 4]);
+        assert!(alloc_entries::<u8>(&reader, 32, 1).is_ok());
+        assert!(alloc_entries::<u8>(&reader, 8, 4).is_ok());
+        assert_eq!(
+            alloc_entries::<u8>(&reader, 33, 1).map(|_| ()),
+            rc::ResultInvalidFormat::make_err()
+        );
+        assert_eq!(
+            alloc_entries::<u8>(&reader, usize::MAX, 2).map(|_| ()),
+            rc::ResultInvalidFormat::make_err()
+        );
+    }
+
+    #[test]
+    fn codebook_values() {
+        assert_eq!(float32_unpack(0x62800001), 1.0);
+        assert_eq!(float32_unpack(0xE2800002), -2.0);
+        assert_eq!(float32_unpack(0x62400001), 0.25);
+        assert_eq!(lookup1_values(16, 2), 4);
+        assert_eq!(lookup1_values(17, 2), 4);
+        assert_eq!(lookup1_values(15, 2), 3);
+        assert_eq!(lookup1_values(1000, 3), 10);
+        assert_eq!(lookup1_values(5, 1), 5);
+    }
+
+    #[test]
+    fn info() {
+        let decoder = VorbisDecoder::new(SliceSource::new(STEREO)).unwrap();
+        let info = decoder.get_info();
+        assert_eq!(info.channel_count, 2);
+        assert_eq!(info.sample_rate, 8000);
+        assert_eq!(info.bitrate_nominal, 64000);
+        assert_eq!((info.block_size_0, info.block_size_1), (256, 512));
+        // The granule position of the last page trims the last packet
+        assert_eq!(decoder.get_frame_count(), Some(6555));
+    }
+
+    #[test]
+    fn reference_decoding() {
+        let reference = reference_samples();
+        for chunk_frame_count in [1, 100, 128, 4096, 10000] {
+            assert_matches_reference(&decode(STEREO, chunk_frame_count).unwrap(), &reference);
+        }
+    }
+
+    #[test]
+    fn rewind() {
+        let reference = reference_samples();
+        let mut decoder = VorbisDecoder::new(SliceSource::new(STEREO)).unwrap();
+        let mut samples = vec![0; 2000];
+        assert_eq!(decoder.decode(&mut samples), Ok(1000));
+        decoder.rewind().unwrap();
+        assert_eq!(decoder.decode(&mut samples), Ok(1000));
+        assert_matches_reference(&samples, &reference[..2000]);
+    }
+
+    #[test]
+    fn huge_codebook() {
+        assert_eq!(
+            VorbisDecoder::new(SliceSource::new(HUGE_CODEBOOK)).map(|_| ()),
+            rc::ResultInvalidFormat::make_err()
+        );
+    }
+
+    #[test]
+    fn untrusted_granule_position() {
+        // Patch the granule position of the last page, fixing its CRC
+        let mut data = STEREO.to_vec();
+        let page_offset = data.windows(4).rposition(|magic| magic == b"OggS").unwrap();
+        data[page_offset + 6..page_offset + 14].copy_from_slice(&(u64::MAX - 1).to_le_bytes());
+        data[page_offset + 22..page_offset + 26].fill(0);
+        let crc = ogg::update_crc(0, &data[page_offset..]);
+        data[page_offset + 22..page_offset + 26].copy_from_slice(&crc.to_le_bytes());
+
+        let decoder = VorbisDecoder::new(SliceSource::new(&data)).unwrap();
+        assert_eq!(decoder.get_frame_count(), None);
+        // Only the last packet is then left untrimmed
+        let samples = decode(&data, 4096).unwrap();
+        assert!(samples.len() > reference_samples().len());
+        assert_matches_reference(&samples[..13110], &reference_samples());
+    }
+
+    #[test]
+    fn truncated_and_corrupt() {
+        // Truncated headers
+        for size in [0, 30, 57, 100, 238] {
+            assert!(VorbisDecoder::new(SliceSource::new(&STEREO[..size])).is_err());
+        }
+
+        // Truncated pages fail once they are reached
+        let reference = reference_samples();
+        let mut decoder =
+            VorbisDecoder::new(SliceSource::new(&STEREO[..STEREO.len() - 100])).unwrap();
+        let mut samples = vec![0; 2000];
+        assert_eq!(decoder.decode(&mut samples), Ok(1000));
+        assert_matches_reference(&samples, &reference[..2000]);
+        assert!(decode(&STEREO[..STEREO.len() - 100], 4096).is_err());
+
+        // Corrupt pages fail their CRC check
+        let mut data = STEREO.to_vec();
+        let last_offset = data.len() - 1;
+        data[last_offset] ^= 1;
+        assert_eq!(
+            decode(&data, 4096).map(|_| ()),
+            rc::ResultInvalidFormat::make_err()
+        );
+    }
+}
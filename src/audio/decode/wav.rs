@@ -0,0 +1,429 @@
+//! WAV decoding
+//!
+//! Supports RIFF WAVE files with integer PCM (8, 16, 24 or 32 bits) or IEEE float32 samples, including `WAVE_FORMAT_EXTENSIBLE` ones
+
+use super::{Decoder, Source, check_out_samples};
+use crate::audio::rc;
+use crate::result::*;
+use alloc::vec::Vec;
+
+const WAVE_FORMAT_PCM: u16 = 1;
+const WAVE_FORMAT_IEEE_FLOAT: u16 = 3;
+const WAVE_FORMAT_EXTENSIBLE: u16 = 0xFFFE;
+
+/// Represents the sample encodings supported in WAV files
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum SampleFormat {
+    Pcm8,
+    Pcm16,
+    Pcm24,
+    Pcm32,
+    Float32,
+}
+
+impl SampleFormat {
+    /// Gets the size of a sample in this format
+    pub const fn get_sample_size(self) -> usize {
+        match self {
+            Self::Pcm8 => 1,
+            Self::Pcm16 => 2,
+            Self::Pcm24 => 3,
+            Self::Pcm32 | Self::Float32 => 4,
+        }
+    }
+
+    /// Converts a sample in this format to PCM16
+    ///
+    /// # Arguments
+    ///
+    /// * `sample`: The encoded sample, which must be [`get_sample_size`][`SampleFormat::get_sample_size`] bytes long
+    pub fn convert_sample(self, sample: &[u8]) -> i16 {
+        match self {
+            // 8-bit samples are the only unsigned ones
+            Self::Pcm8 => ((sample[0] as i16) - 0x80) << 8,
+            Self::Pcm16 => i16::from_le_bytes([sample[0], sample[1]]),
+            Self::Pcm24 => i16::from_le_bytes([sample[1], sample[2]]),
+            Self::Pcm32 => i16::from_le_bytes([sample[2], sample[3]]),
+            Self::Float32 => {
+                let sample = f32::from_le_bytes([sample[0], sample[1], sample[2], sample[3]]);
+                (sample * i16::MAX as f32).clamp(i16::MIN as f32, i16::MAX as f32) as i16
+            }
+        }
+    }
+}
+
+/// Represents the format information of a WAV file
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct WavInfo {
+    pub sample_format: SampleFormat,
+    pub channel_count: u16,
+    pub sample_rate: u32,
+    /// The offset of the sample data in the file
+    pub data_offset: usize,
+    /// The size of the sample data
+    pub data_size: usize,
+}
+
+impl WavInfo {
+    /// Gets the size of a frame
+    #[inline]
+    pub const fn get_frame_size(&self) -> usize {
+        self.sample_format.get_sample_size() * self.channel_count as usize
+    }
+
+    /// Gets the total frame count
+    #[inline]
+    pub const fn get_frame_count(&self) -> usize {
+        self.data_size / self.get_frame_size()
+    }
+}
+
+#[inline]
+fn read_u16(data: &[u8], offset: usize) -> u16 {
+    u16::from_le_bytes([data[offset], data[offset + 1]])
+}
+
+#[inline]
+fn read_u32(data: &[u8], offset: usize) -> u32 {
+    u32::from_le_bytes([
+        data[offset],
+        data[offset + 1],
+        data[offset + 2],
+        data[offset + 3],
+    ])
+}
+
+fn parse_fmt_chunk(fmt: &[u8]) -> Result<(SampleFormat, u16, u32)> {
+    result_return_unless!(fmt.len() >= 0x10, rc::ResultInvalidFormat);
+
+    let mut format_tag = read_u16(fmt, 0);
+    let channel_count = read_u16(fmt, 2);
+    let sample_rate = read_u32(fmt, 4);
+    let bits_per_sample = read_u16(fmt, 0xE);
+    if format_tag == WAVE_FORMAT_EXTENSIBLE {
+        // The actual format tag is the start of the sub-format GUID
+        result_return_unless!(fmt.len() >= 0x1A, rc::ResultInvalidFormat);
+        format_tag = read_u16(fmt, 0x18);
+    }
+
+    let sample_format = match (format_tag, bits_per_sample) {
+        (WAVE_FORMAT_PCM, 8) => SampleFormat::Pcm8,
+        (WAVE_FORMAT_PCM, 16) => SampleFormat::Pcm16,
+        (WAVE_FORMAT_PCM, 24) => SampleFormat::Pcm24,
+        (WAVE_FORMAT_PCM, 32) => SampleFormat::Pcm32,
+        (WAVE_FORMAT_IEEE_FLOAT, 32) => SampleFormat::Float32,
+        _ => return rc::ResultUnsupportedFormat::make_err(),
+    };
+    result_return_if!(channel_count == 0, rc::ResultInvalidChannelCount);
+    result_return_if!(sample_rate == 0, rc::ResultInvalidFormat);
+
+    Ok((sample_format, channel_count, sample_rate))
+}
+
+/// Parses the chunks of a WAV file until the sample data is found
+///
+/// # Arguments
+///
+/// * `source`: The source, whose position is left unspecified
+pub fn read_info<S: Source>(source: &mut S) -> Result<WavInfo> {
+    let size = source.get_size()?;
+
+    let mut riff_header = [0u8; 12];
+    source.seek(0)?;
+    source.read_exact(&mut riff_header)?;
+    result_return_unless!(
+        (&riff_header[..4] == b"RIFF") && (&riff_header[8..] == b"WAVE"),
+        rc::ResultInvalidFormat
+    );
+
+    let mut format: Option<(SampleFormat, u16, u32)> = None;
+    let mut offset = riff_header.len();
+    loop {
+        let mut chunk_header = [0u8; 8];
+        source.seek(offset)?;
+        source.read_exact(&mut chunk_header)?;
+        let chunk_size = read_u32(&chunk_header, 4) as usize;
+        let chunk_offset = offset + chunk_header.len();
+
+        match &chunk_header[..4] {
+            b"fmt " => {
+                result_return_if!(chunk_offset + chunk_size > size, rc::ResultInvalidFormat);
+                let mut fmt = vec![0u8; chunk_size];
+                source.read_exact(&mut fmt)?;
+                format = Some(parse_fmt_chunk(&fmt)?);
+            }
+            b"data" => {
+                // The format chunk must come before the data
+                let (sample_format, channel_count, sample_rate) =
+                    format.ok_or(rc::ResultInvalidFormat::make())?;
+                // Some writers leave the size unset when streaming, so it's clamped to the actual file size
+                let data_size = chunk_size.min(size.saturating_sub(chunk_offset));
+                return Ok(WavInfo {
+                    sample_format,
+                    channel_count,
+                    sample_rate,
+                    data_offset: chunk_offset,
+                    data_size,
+                });
+            }
+            _ => {}
+        }
+
+        // Chunks are padded to an even size
+        offset = chunk_offset + chunk_size + (chunk_size & 1);
+    }
+}
+
+/// Represents a streaming WAV decoder
+pub struct WavDecoder<S: Source> {
+    source: S,
+    info: WavInfo,
+    frame_offset: usize,
+    read_buf: Vec<u8>,
+}
+
+impl<S: Source> WavDecoder<S> {
+    /// Creates a new [`WavDecoder`]
+    ///
+    /// # Arguments
+    ///
+    /// * `source`: The source to decode
+    pub fn new(mut source: S) -> Result<Self> {
+        let info = read_info(&mut source)?;
+        source.seek(info.data_offset)?;
+        Ok(Self {
+            source,
+            info,
+            frame_offset: 0,
+            read_buf: Vec::new(),
+        })
+    }
+
+    /// Gets the format information
+    #[inline]
+    pub fn get_info(&self) -> &WavInfo {
+        &self.info
+    }
+
+    /// Seeks to the given frame
+    ///
+    /// # Arguments
+    ///
+    /// * `frame`: The frame, which is clamped to the frame count
+    pub fn seek_frame(&mut self, frame: usize) -> Result<()> {
+        let frame = frame.min(self.info.get_frame_count());
+        self.source
+            .seek(self.info.data_offset + frame * self.info.get_frame_size())?;
+        self.frame_offset = frame;
+        Ok(())
+    }
+}
+
+impl<S: Source> Decoder for WavDecoder<S> {
+    fn get_channel_count(&self) -> u16 {
+        self.info.channel_count
+    }
+
+    fn get_sample_rate(&self) -> u32 {
+        self.info.sample_rate
+    }
+
+    fn get_frame_count(&self) -> Option<usize> {
+        Some(self.info.get_frame_count())
+    }
+
+    fn decode(&mut self, out_samples: &mut [i16]) -> Result<usize> {
+        let max_frame_count = check_out_samples(out_samples, self.info.channel_count)?;
+        let frame_count = max_frame_count.min(self.info.get_frame_count() - self.frame_offset);
+        if frame_count == 0 {
+            return Ok(0);
+        }
+
+        let sample_size = self.info.sample_format.get_sample_size();
+        self.read_buf
+            .resize(frame_count * self.info.get_frame_size(), 0);
+        self.source.read_exact(&mut self.read_buf)?;
+        for (out_sample, sample) in out_samples
+            .iter_mut()
+            .zip(self.read_buf.chunks_exact(sample_size))
+        {
+            *out_sample = self.info.sample_format.convert_sample(sample);
+        }
+
+        self.frame_offset += frame_count;
+        Ok(frame_count)
+    }
+
+    fn rewind(&mut self) -> Result<()> {
+        self.seek_frame(0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::audio::decode::SliceSource;
+
+    const PCM16_STEREO: &[u8] = audio_fixture!("pcm16_stereo.wav");
+    const PCM8_MONO: &[u8] = audio_fixture!("pcm8_mono.wav");
+    const PCM24_MONO: &[u8] = audio_fixture!("pcm24_mono.wav");
+    const FLOAT32_STEREO: &[u8] = audio_fixture!("float32_stereo.wav");
+
+    fn decode(data: &[u8], chunk_frame_count: usize) -> Result<Vec<i16>> {
+        let mut decoder = WavDecoder::new(SliceSource::new(data))?;
+        let channel_count = decoder.get_channel_count() as usize;
+        let mut samples = Vec::new();
+        let mut chunk = vec![0; chunk_frame_count * channel_count];
+        loop {
+            let frame_count = decoder.decode(&mut chunk)?;
+            if frame_count == 0 {
+                return Ok(samples);
+            }
+            samples.extend_from_slice(&chunk[..frame_count * channel_count]);
+        }
+    }
+
+    fn left_channel(samples: &[i16]) -> Vec<i16> {
+        samples.iter().step_by(2).copied().collect()
+    }
+
+    #[test]
+    fn info() {
+        let info = read_info(&mut SliceSource::new(PCM16_STEREO)).unwrap();
+        assert_eq!(
+            info,
+            WavInfo {
+                sample_format: SampleFormat::Pcm16,
+                channel_count: 2,
+                sample_rate: 8000,
+                data_offset: 0x2C,
+                data_size: 2000,
+            }
+        );
+        assert_eq!(info.get_frame_size(), 4);
+        assert_eq!(info.get_frame_count(), 500);
+
+        // The odd-sized chunks before and after the format one are padded
+        let info = read_info(&mut SliceSource::new(PCM24_MONO)).unwrap();
+        assert_eq!(info.sample_format, SampleFormat::Pcm24);
+        assert_eq!(info.sample_rate, 22050);
+        assert_eq!(info.data_offset, 0x50);
+        assert_eq!(info.get_frame_count(), 500);
+
+        let info = read_info(&mut SliceSource::new(FLOAT32_STEREO)).unwrap();
+        assert_eq!(info.sample_format, SampleFormat::Float32);
+        assert_eq!(info.channel_count, 2);
+    }
+
+    #[test]
+    fn sample_formats() {
+        let pcm16 = decode(PCM16_STEREO, 64).unwrap();
+        assert_eq!(pcm16.len(), 1000);
+        assert_eq!(&pcm16[20..22], &[i16::MAX, i16::MIN]);
+        let left = left_channel(&pcm16);
+
+        // 8-bit samples only keep the high byte
+        let pcm8 = decode(PCM8_MONO, 64).unwrap();
+        let expected: Vec<i16> = left.iter().map(|sample| sample & !0xFF).collect();
+        assert_eq!(pcm8, expected);
+
+        // 24-bit samples drop the low byte
+        assert_eq!(decode(PCM24_MONO, 64).unwrap(), left);
+
+        let float32 = decode(FLOAT32_STEREO, 64).unwrap();
+        assert_eq!(float32.len(), pcm16.len());
+        assert!(
+            float32
+                .iter()
+                .zip(pcm16.iter())
+                .all(|(&float, &pcm)| (float as i32 - pcm as i32).abs() <= 1)
+        );
+    }
+
+    #[test]
+    fn chunked_decoding() {
+        let samples = decode(PCM16_STEREO, 500).unwrap();
+        for chunk_frame_count in [1, 7, 499, 1000] {
+            assert_eq!(decode(PCM16_STEREO, chunk_frame_count).unwrap(), samples);
+        }
+    }
+
+    #[test]
+    fn seeking() {
+        let samples = decode(PCM16_STEREO, 500).unwrap();
+        let mut decoder = WavDecoder::new(SliceSource::new(PCM16_STEREO)).unwrap();
+        let mut chunk = [0; 8];
+
+        decoder.seek_frame(100).unwrap();
+        assert_eq!(decoder.decode(&mut chunk), Ok(4));
+        assert_eq!(&chunk, &samples[200..208]);
+
+        // Seeking past the end is clamped
+        decoder.seek_frame(1000).unwrap();
+        assert_eq!(decoder.decode(&mut chunk), Ok(0));
+
+        decoder.rewind().unwrap();
+        assert_eq!(decoder.decode(&mut chunk), Ok(4));
+        assert_eq!(&chunk, &samples[..8]);
+    }
+
+    #[test]
+    fn truncated() {
+        // The data size is clamped to what the file holds, without splitting frames
+        let samples = decode(PCM16_STEREO, 64).unwrap();
+        let truncated = decode(&PCM16_STEREO[..PCM16_STEREO.len() - 6], 64).unwrap();
+        assert_eq!(truncated, &samples[..samples.len() - 4]);
+
+        // Missing format or data chunks
+        assert!(WavDecoder::new(SliceSource::new(&PCM16_STEREO[..0x2C])).is_ok());
+        assert!(WavDecoder::new(SliceSource::new(&PCM16_STEREO[..0x28])).is_err());
+        assert!(WavDecoder::new(SliceSource::new(&PCM16_STEREO[..0x20])).is_err());
+        assert!(WavDecoder::new(SliceSource::new(&PCM16_STEREO[..8])).is_err());
+        assert!(WavDecoder::new(SliceSource::new(&PCM24_MONO[..0x30])).is_err());
+    }
+
+    #[test]
+    fn corrupt() {
+        let patched = |offset: usize, value: &[u8]| {
+            let mut data = PCM16_STEREO.to_vec();
+            data[offset..offset + value.len()].copy_from_slice(value);
+            data
+        };
+        let open = |data: &[u8]| WavDecoder::new(SliceSource::new(data)).map(|_| ());
+
+        // Format chunk bigger than the file
+        assert_eq!(
+            open(&patched(0x10, &0x10000u32.to_le_bytes())),
+            rc::ResultInvalidFormat::make_err()
+        );
+        // Data chunk before the format one
+        assert_eq!(
+            open(&patched(0xC, b"data")),
+            rc::ResultInvalidFormat::make_err()
+        );
+        assert_eq!(
+            open(&patched(0x22, &12u16.to_le_bytes())),
+            rc::ResultUnsupportedFormat::make_err()
+        );
+        assert_eq!(
+            open(&patched(0x14, &2u16.to_le_bytes())),
+            rc::ResultUnsupportedFormat::make_err()
+        );
+        assert_eq!(
+            open(&patched(0x16, &0u16.to_le_bytes())),
+            rc::ResultInvalidChannelCount::make_err()
+        );
+        assert_eq!(
+            open(&patched(0x18, &0u32.to_le_bytes())),
+            rc::ResultInvalidFormat::make_err()
+        );
+        assert_eq!(
+            open(&patched(8, b"AVI ")),
+            rc::ResultInvalidFormat::make_err()
+        );
+
+        // An oversized data chunk is clamped to the file size
+        let data = patched(0x28, &u32::MAX.to_le_bytes());
+        assert_eq!(decode(&data, 64).unwrap().len(), 1000);
+    }
+}
@@ -0,0 +1,462 @@
+//! Audio input (`audin`) capture support
+
+use super::queue::{BufferQueue, SampleRing};
+use super::rc;
+use crate::arm;
+use crate::ipc::sf;
+use crate::mem::alloc;
+use crate::result::*;
+use crate::service;
+use crate::svc;
+use crate::time::Timeout;
+use crate::wait::RemoteEvent;
+use ::alloc::string::String;
+use ::alloc::vec::Vec;
+
+pub use crate::service::audin::*;
+
+/// The default input sample rate
+pub const DEFAULT_SAMPLE_RATE: u32 = 48000;
+
+/// The default input channel count
+pub const DEFAULT_CHANNEL_COUNT: u16 = 2;
+
+/// The default amount of frames held by each input buffer
+pub const DEFAULT_BUFFER_FRAME_COUNT: usize = 1024;
+
+/// The default input buffer count
+pub const DEFAULT_BUFFER_COUNT: usize = 4;
+
+/// The maximum input buffer count (the maximum amount of buffers the audio services can hold per input)
+pub const MAX_BUFFER_COUNT: usize = 32;
+
+/// Lists the names of the available audio inputs (like the built-in headset jack or USB microphones)
+pub fn list_inputs() -> Result<Vec<String>> {
+    let audin_srv = service::new_service_object::<AudioInManagerService>()?;
+
+    let mut names: [AudioDeviceName; 0x10] = Default::default();
+    let count = audin_srv.list_audio_ins(sf::Buffer::from_mut_array(&mut names))? as usize;
+    Ok(names[..count.min(names.len())]
+        .iter()
+        .map(|name| String::from(name.get_str().unwrap_or("")))
+        .collect())
+}
+
+/// Represents an opened audio input, which captures interleaved PCM16 samples
+///
+/// Empty buffers are appended to the audio services, and the captured frames of the buffers they release are moved into an internal ring, from which they are read
+pub struct Input {
+    audio_in: AudioIn,
+    buffer_event: RemoteEvent,
+    sample_buffers: Vec<alloc::Buffer<i16>>,
+    buffer_size: usize,
+    buffer_frame_count: usize,
+    queue: BufferQueue,
+    ring: SampleRing,
+    dropped_frame_count: usize,
+    capturing: bool,
+    name: AudioDeviceName,
+    sample_rate: u32,
+    channel_count: u16,
+}
+
+impl Input {
+    /// Opens the default audio input with the default settings
+    pub fn open_default() -> Result<Self> {
+        Self::open(
+            DEFAULT_SAMPLE_RATE,
+            DEFAULT_CHANNEL_COUNT,
+            DEFAULT_BUFFER_FRAME_COUNT,
+            DEFAULT_BUFFER_COUNT,
+        )
+    }
+
+    /// Opens the default audio input
+    ///
+    /// # Arguments
+    ///
+    /// * `sample_rate`: The desired sample rate (the audio services may choose a different one, see [`Input::get_sample_rate`])
+    /// * `channel_count`: The desired channel count (`1`-`2`)
+    /// * `buffer_frame_count`: The amount of frames held by each input buffer
+    /// * `buffer_count`: The input buffer count (`2`-[`MAX_BUFFER_COUNT`])
+    pub fn open(
+        sample_rate: u32,
+        channel_count: u16,
+        buffer_frame_count: usize,
+        buffer_count: usize,
+    ) -> Result<Self> {
+        Self::open_device(
+            "",
+            sample_rate,
+            channel_count,
+            buffer_frame_count,
+            buffer_count,
+        )
+    }
+
+    /// Opens the given audio input
+    ///
+    /// The internal sample ring is able to hold as many frames as all the input buffers
+    ///
+    /// # Arguments
+    ///
+    /// * `name`: The input name (see [`list_inputs`]), an empty name meaning the default input
+    /// * `sample_rate`: The desired sample rate (the audio services may choose a different one, see [`Input::get_sample_rate`])
+    /// * `channel_count`: The desired channel count (`1`-`2`)
+    /// * `buffer_frame_count`: The amount of frames held by each input buffer
+    /// * `buffer_count`: The input buffer count (`2`-[`MAX_BUFFER_COUNT`])
+    pub fn open_device(
+        name: &str,
+        sample_rate: u32,
+        channel_count: u16,
+        buffer_frame_count: usize,
+        buffer_count: usize,
+    ) -> Result<Self> {
+        result_return_unless!(
+            (1..=2).contains(&channel_count),
+            rc::ResultInvalidChannelCount
+        );
+        result_return_unless!(buffer_frame_count > 0, rc::ResultInvalidSampleCount);
+        result_return_unless!(
+            (2..=MAX_BUFFER_COUNT).contains(&buffer_count),
+            rc::ResultInvalidBufferCount
+        );
+
+        let mut audin_srv = service::new_service_object::<AudioInManagerService>()?;
+
+        let name = AudioDeviceName::from_str(name);
+        let mut out_name = AudioDeviceName::new();
+        let parameter = AudioInParameter {
+            sample_rate,
+            channel_count,
+            reserved: 0,
+        };
+        let aruid = AppletResourceUserId::from_global();
+        let (parameter_internal, mut audio_in) = audin_srv.open_audio_in(
+            parameter,
+            aruid,
+            sf::CopyHandle::from(svc::CURRENT_PROCESS_PSEUDO_HANDLE),
+            sf::Buffer::from_var(&name),
+            sf::Buffer::from_mut_var(&mut out_name),
+        )?;
+        result_return_unless!(
+            parameter_internal.sample_format == PcmFormat::Int16,
+            rc::ResultUnsupportedSampleFormat
+        );
+
+        let buffer_event = RemoteEvent::new(audio_in.register_buffer_event()?.handle);
+
+        // The channel count is not necessarily the one we asked for, thus the buffer size depends on the actual one
+        let actual_channel_count = parameter_internal.channel_count as usize;
+        let buffer_size = (buffer_frame_count * actual_channel_count * size_of::<i16>())
+            .next_multiple_of(AUDIO_BUFFER_ALIGNMENT);
+        let mut sample_buffers = Vec::with_capacity(buffer_count);
+        for _ in 0..buffer_count {
+            sample_buffers.push(alloc::Buffer::new(
+                AUDIO_BUFFER_ALIGNMENT,
+                buffer_size / size_of::<i16>(),
+            )?);
+        }
+
+        Ok(Self {
+            audio_in,
+            buffer_event,
+            sample_buffers,
+            buffer_size,
+            buffer_frame_count,
+            queue: BufferQueue::new(buffer_count),
+            ring: SampleRing::new(buffer_frame_count * actual_channel_count * buffer_count),
+            dropped_frame_count: 0,
+            capturing: false,
+            name: out_name,
+            sample_rate: parameter_internal.sample_rate,
+            channel_count: actual_channel_count as u16,
+        })
+    }
+
+    /// Gets the name of the opened audio input
+    #[inline]
+    pub fn get_name(&self) -> &str {
+        self.name.get_str().unwrap_or("")
+    }
+
+    /// Gets the actual sample rate of the input
+    #[inline]
+    pub fn get_sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+
+    /// Gets the actual channel count of the input
+    #[inline]
+    pub fn get_channel_count(&self) -> u16 {
+        self.channel_count
+    }
+
+    /// Gets the amount of captured frames which can currently be read without blocking
+    #[inline]
+    pub fn get_available_frames(&self) -> usize {
+        self.ring.len() / self.channel_count as usize
+    }
+
+    /// Gets the amount of captured frames which were dropped since the ring was full (because they were not read fast enough)
+    #[inline]
+    pub fn get_dropped_frame_count(&self) -> usize {
+        self.dropped_frame_count
+    }
+
+    /// Gets the underlying [`IAudioInClient`] object
+    #[inline]
+    pub fn get_audio_in(&mut self) -> &mut AudioIn {
+        &mut self.audio_in
+    }
+
+    /// Gets the state of the input
+    #[inline]
+    pub fn get_state(&self) -> Result<AudioInState> {
+        self.audio_in.get_audio_in_state()
+    }
+
+    /// Starts capturing
+    ///
+    /// All the free buffers are appended before starting
+    pub fn start(&mut self) -> Result<()> {
+        self.capturing = true;
+        self.append_buffers()?;
+        self.audio_in.start()
+    }
+
+    /// Stops capturing
+    ///
+    /// Frames captured so far are kept in the ring, and any buffers still held by the audio services are dropped
+    pub fn stop(&mut self) -> Result<()> {
+        self.capturing = false;
+        self.audio_in.stop()?;
+        self.release_buffers()?;
+        // Buffers not released after stopping will never be filled
+        self.queue.reset();
+        Ok(())
+    }
+
+    /// Sets the device gain (`1.0` being the default one)
+    ///
+    /// # Arguments
+    ///
+    /// * `gain`: The gain
+    #[inline]
+    pub fn set_gain(&mut self, gain: f32) -> Result<()> {
+        self.audio_in.set_device_gain(gain)
+    }
+
+    /// Gets the device gain
+    #[inline]
+    pub fn get_gain(&self) -> Result<f32> {
+        self.audio_in.get_device_gain()
+    }
+
+    fn release_buffers(&mut self) -> Result<()> {
+        let channel_count = self.channel_count as usize;
+        let mut tags = [0u64; MAX_BUFFER_COUNT];
+        loop {
+            let count = self
+                .audio_in
+                .get_released_audio_in_buffers(sf::Buffer::from_mut_array(&mut tags))?
+                as usize;
+            if count == 0 {
+                return Ok(());
+            }
+
+            for tag in &tags[..count.min(tags.len())] {
+                let index = *tag as usize;
+                self.queue.release(index)?;
+
+                let sample_count = self.buffer_frame_count * channel_count;
+                let sample_buffer = &self.sample_buffers[index];
+                // The samples were written by the audio services, thus any stale cache lines must be discarded
+                arm::cache_flush(sample_buffer.ptr.cast(), self.buffer_size);
+                let samples =
+                    unsafe { core::slice::from_raw_parts(sample_buffer.ptr, sample_count) };
+                let pushed_count = self.ring.push(samples);
+                self.dropped_frame_count += (sample_count - pushed_count) / channel_count;
+            }
+        }
+    }
+
+    fn append_buffers(&mut self) -> Result<()> {
+        let channel_count = self.channel_count as usize;
+        while let Some(index) = self.queue.acquire() {
+            let sample_buffer = &self.sample_buffers[index];
+            // Make sure no dirty cache lines are written back over the captured samples
+            arm::cache_flush(sample_buffer.ptr.cast(), self.buffer_size);
+
+            let audio_buffer = AudioBuffer {
+                next: 0,
+                sample_buffer: sample_buffer.ptr as u64,
+                buffer_capacity: self.buffer_size as u64,
+                data_size: (self.buffer_frame_count * channel_count * size_of::<i16>()) as u64,
+                data_offset: 0,
+            };
+            if let Err(rc) = self
+                .audio_in
+                .append_audio_in_buffer(index as u64, sf::Buffer::from_var(&audio_buffer))
+            {
+                self.queue.cancel(index)?;
+                return Err(rc);
+            }
+            self.queue.submit(index)?;
+        }
+
+        Ok(())
+    }
+
+    /// Moves the frames of the buffers released by the audio services into the ring, and appends them again while capturing
+    ///
+    /// This is already done by the reading functions, but it may be called periodically to keep capturing without reading (the ring keeps the oldest frames, newer ones are dropped when it's full)
+    pub fn update(&mut self) -> Result<()> {
+        self.release_buffers()?;
+        if self.capturing {
+            self.append_buffers()?;
+        }
+        Ok(())
+    }
+
+    /// Reads as many whole captured frames as possible without blocking, returning the amount of read frames
+    ///
+    /// # Arguments
+    ///
+    /// * `out_samples`: The interleaved samples to read into, whose length must be a multiple of the channel count
+    pub fn read(&mut self, out_samples: &mut [i16]) -> Result<usize> {
+        let channel_count = self.channel_count as usize;
+        result_return_unless!(
+            out_samples.len() % channel_count == 0,
+            rc::ResultInvalidSampleCount
+        );
+
+        self.update()?;
+        let frame_count = self
+            .get_available_frames()
+            .min(out_samples.len() / channel_count);
+        self.ring
+            .pop(&mut out_samples[..frame_count * channel_count]);
+        // Buffers are appended again after the ring has room for them
+        self.update()?;
+        Ok(frame_count)
+    }
+
+    /// Reads captured frames until the given samples are filled, waiting for buffers to be released when needed
+    ///
+    /// Note that this will wait indefinitely if the input is not started
+    ///
+    /// # Arguments
+    ///
+    /// * `out_samples`: The interleaved samples to read into, whose length must be a multiple of the channel count
+    pub fn read_exact(&mut self, out_samples: &mut [i16]) -> Result<()> {
+        let channel_count = self.channel_count as usize;
+        let mut offset = 0;
+        loop {
+            offset += self.read(&mut out_samples[offset..])? * channel_count;
+            if offset >= out_samples.len() {
+                return Ok(());
+            }
+
+            self.buffer_event.wait(Timeout::Infinite)?;
+        }
+    }
+
+    /// Waits until the audio services release a buffer (or the timeout expires)
+    ///
+    /// # Arguments
+    ///
+    /// * `timeout`: The wait timeout
+    #[inline]
+    pub fn wait_buffer_released(&self, timeout: impl Into<Timeout>) -> Result<()> {
+        self.buffer_event.wait(timeout)
+    }
+
+    /// Delivers captured chunks of the given frame count to a callback until it returns `false`
+    ///
+    /// Capturing is started if needed, and it's left running afterwards
+    ///
+    /// # Arguments
+    ///
+    /// * `chunk_frame_count`: The frame count of each chunk
+    /// * `callback`: The callback, which receives the interleaved samples of each chunk
+    pub fn capture<F: FnMut(&[i16]) -> bool>(
+        &mut self,
+        chunk_frame_count: usize,
+        mut callback: F,
+    ) -> Result<()> {
+        result_return_unless!(chunk_frame_count > 0, rc::ResultInvalidSampleCount);
+        if !self.capturing {
+            self.start()?;
+        }
+
+        let mut chunk = vec![0i16; chunk_frame_count * self.channel_count as usize];
+        loop {
+            self.read_exact(&mut chunk)?;
+            if !callback(&chunk) {
+                return Ok(());
+            }
+        }
+    }
+
+    /// Gets an iterator over captured chunks of the given frame count, which waits for each chunk to be captured
+    ///
+    /// Capturing is started on the first iteration if needed, and the iterator never ends unless an error happens (in which case the error is its last item)
+    ///
+    /// # Arguments
+    ///
+    /// * `chunk_frame_count`: The frame count of each chunk
+    #[inline]
+    pub fn chunks(&mut self, chunk_frame_count: usize) -> Chunks<'_> {
+        Chunks {
+            input: self,
+            chunk_frame_count,
+            failed: false,
+        }
+    }
+
+    /// Discards all the captured frames which were not read yet
+    #[inline]
+    pub fn clear(&mut self) {
+        self.ring.clear();
+    }
+}
+
+impl Drop for Input {
+    /// Stops the input, since the sample buffers must not be freed while still in use by the audio services
+    fn drop(&mut self) {
+        let _ = self.audio_in.stop();
+    }
+}
+
+/// Represents an iterator over captured chunks (see [`Input::chunks`])
+pub struct Chunks<'a> {
+    input: &'a mut Input,
+    chunk_frame_count: usize,
+    failed: bool,
+}
+
+impl Iterator for Chunks<'_> {
+    type Item = Result<Vec<i16>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.failed {
+            return None;
+        }
+
+        let result: Result<Vec<i16>> = try {
+            if self.chunk_frame_count == 0 {
+                Err(rc::ResultInvalidSampleCount::make())?;
+            }
+            if !self.input.capturing {
+                self.input.start()?;
+            }
+
+            let mut chunk = vec![0i16; self.chunk_frame_count * self.input.channel_count as usize];
+            self.input.read_exact(&mut chunk)?;
+            chunk
+        };
+        self.failed = result.is_err();
+        Some(result)
+    }
+}
@@ -0,0 +1,450 @@
+//! Software voice mixer
+//!
+//! The mixer resamples and mixes any amount of voices into interleaved PCM16 frames, and is used as a fallback when the audio renderer services are not available
+//!
+//! This has no dependencies on the audio services, thus it can be used (and tested) outside of the console
+
+use super::rc;
+use crate::result::*;
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+
+/// Represents a sound: interleaved PCM16 samples along with their format
+#[derive(Clone, Debug)]
+pub struct Sound {
+    samples: Arc<[i16]>,
+    channel_count: u16,
+    sample_rate: u32,
+}
+
+impl Sound {
+    /// Creates a new [`Sound`]
+    ///
+    /// # Arguments
+    ///
+    /// * `samples`: The interleaved samples, whose length must be a multiple of the channel count
+    /// * `channel_count`: The channel count
+    /// * `sample_rate`: The sample rate
+    pub fn new(
+        samples: impl Into<Arc<[i16]>>,
+        channel_count: u16,
+        sample_rate: u32,
+    ) -> Result<Self> {
+        let samples = samples.into();
+        result_return_if!(channel_count == 0, rc::ResultInvalidChannelCount);
+        result_return_unless!(
+            (samples.len() % channel_count as usize) == 0,
+            rc::ResultInvalidSampleCount
+        );
+        result_return_if!(sample_rate == 0, rc::ResultInvalidSound);
+
+        Ok(Self {
+            samples,
+            channel_count,
+            sample_rate,
+        })
+    }
+
+    /// Gets whether this [`Sound`] shares the same samples with another one (like clones of the same [`Sound`])
+    ///
+    /// # Arguments
+    ///
+    /// * `other`: The other sound
+    #[inline]
+    pub fn is_same(&self, other: &Sound) -> bool {
+        Arc::ptr_eq(&self.samples, &other.samples)
+    }
+
+    /// Gets the interleaved samples
+    #[inline]
+    pub fn get_samples(&self) -> &[i16] {
+        &self.samples
+    }
+
+    /// Gets the channel count
+    #[inline]
+    pub fn get_channel_count(&self) -> u16 {
+        self.channel_count
+    }
+
+    /// Gets the sample rate
+    #[inline]
+    pub fn get_sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+
+    /// Gets the frame count (samples per channel)
+    #[inline]
+    pub fn get_frame_count(&self) -> usize {
+        self.samples.len() / self.channel_count as usize
+    }
+}
+
+/// Represents the playback parameters of a voice
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct VoiceParams {
+    /// The volume (`1.0` being the original one)
+    pub volume: f32,
+    /// The pitch, as a playback speed ratio (`1.0` being the original one)
+    pub pitch: f32,
+    /// Whether the sound is played again from the start when it finishes
+    pub looping: bool,
+}
+
+impl Default for VoiceParams {
+    fn default() -> Self {
+        Self {
+            volume: 1.0,
+            pitch: 1.0,
+            looping: false,
+        }
+    }
+}
+
+// Positions are fixed-point frame offsets with this amount of fractional bits
+const POSITION_FRACTION_BITS: u32 = 32;
+
+struct MixerVoice {
+    sound: Sound,
+    params: VoiceParams,
+    position: u64,
+    playing: bool,
+}
+
+impl MixerVoice {
+    #[inline]
+    fn get_frame_sample(&self, frame: usize, channel: usize) -> f32 {
+        let channel_count = self.sound.channel_count as usize;
+        self.sound.samples[frame * channel_count + (channel % channel_count)] as f32
+    }
+
+    fn mix_into(&mut self, out: &mut [f32], out_channel_count: usize, out_sample_rate: u32) {
+        let frame_count = self.sound.get_frame_count();
+        if frame_count == 0 {
+            self.playing = false;
+            return;
+        }
+
+        let step = ((self.sound.sample_rate as f64 * self.params.pitch.max(0.0) as f64
+            / out_sample_rate as f64)
+            * (1u64 << POSITION_FRACTION_BITS) as f64) as u64;
+        let end_position = (frame_count as u64) << POSITION_FRACTION_BITS;
+
+        for out_frame in out.chunks_exact_mut(out_channel_count) {
+            if self.position >= end_position {
+                if self.params.looping {
+                    self.position %= end_position;
+                } else {
+                    self.playing = false;
+                    return;
+                }
+            }
+
+            let frame = (self.position >> POSITION_FRACTION_BITS) as usize;
+            let next_frame = match frame + 1 {
+                next_frame if next_frame < frame_count => next_frame,
+                _ if self.params.looping => 0,
+                _ => frame,
+            };
+            let fraction = (self.position & ((1u64 << POSITION_FRACTION_BITS) - 1)) as f32
+                / (1u64 << POSITION_FRACTION_BITS) as f32;
+
+            // Sources with less channels than the output get their channels repeated
+            for (channel, out_sample) in out_frame.iter_mut().enumerate() {
+                let sample = self.get_frame_sample(frame, channel);
+                let next_sample = self.get_frame_sample(next_frame, channel);
+                *out_sample += (sample + (next_sample - sample) * fraction) * self.params.volume;
+            }
+
+            self.position += step;
+        }
+    }
+}
+
+/// Mixes voices into interleaved PCM16 frames of a fixed format
+pub struct Mixer {
+    sample_rate: u32,
+    channel_count: u16,
+    voices: Vec<Option<MixerVoice>>,
+    mix_buffer: Vec<f32>,
+}
+
+impl Mixer {
+    /// Creates a new [`Mixer`]
+    ///
+    /// # Arguments
+    ///
+    /// * `sample_rate`: The output sample rate
+    /// * `channel_count`: The output channel count
+    /// * `voice_count`: The maximum amount of voices
+    pub fn new(sample_rate: u32, channel_count: u16, voice_count: usize) -> Result<Self> {
+        result_return_if!(channel_count == 0, rc::ResultInvalidChannelCount);
+
+        let mut voices = Vec::with_capacity(voice_count);
+        voices.resize_with(voice_count, || None);
+        Ok(Self {
+            sample_rate,
+            channel_count,
+            voices,
+            mix_buffer: Vec::new(),
+        })
+    }
+
+    /// Gets the output sample rate
+    #[inline]
+    pub fn get_sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+
+    /// Gets the output channel count
+    #[inline]
+    pub fn get_channel_count(&self) -> u16 {
+        self.channel_count
+    }
+
+    /// Gets the maximum amount of voices
+    #[inline]
+    pub fn get_voice_count(&self) -> usize {
+        self.voices.len()
+    }
+
+    fn get_voice_mut(&mut self, voice_id: usize) -> Result<&mut MixerVoice> {
+        self.voices
+            .get_mut(voice_id)
+            .and_then(Option::as_mut)
+            .ok_or(rc::ResultInvalidVoice::make())
+    }
+
+    /// Allocates a voice which starts playing the given sound right away, returning its ID
+    ///
+    /// # Arguments
+    ///
+    /// * `sound`: The sound to play
+    /// * `params`: The playback parameters
+    pub fn play(&mut self, sound: Sound, params: VoiceParams) -> Result<usize> {
+        let voice_id = self
+            .voices
+            .iter()
+            .position(Option::is_none)
+            .ok_or(rc::ResultNoFreeVoices::make())?;
+        self.voices[voice_id] = Some(MixerVoice {
+            sound,
+            params,
+            position: 0,
+            playing: true,
+        });
+        Ok(voice_id)
+    }
+
+    /// Stops a voice and frees it
+    ///
+    /// # Arguments
+    ///
+    /// * `voice_id`: The voice ID
+    pub fn stop(&mut self, voice_id: usize) -> Result<()> {
+        self.get_voice_mut(voice_id)?;
+        self.voices[voice_id] = None;
+        Ok(())
+    }
+
+    /// Pauses or resumes a voice
+    ///
+    /// # Arguments
+    ///
+    /// * `voice_id`: The voice ID
+    /// * `paused`: Whether the voice is paused
+    pub fn set_paused(&mut self, voice_id: usize, paused: bool) -> Result<()> {
+        self.get_voice_mut(voice_id)?.playing = !paused;
+        Ok(())
+    }
+
+    /// Sets the playback parameters of a voice
+    ///
+    /// # Arguments
+    ///
+    /// * `voice_id`: The voice ID
+    /// * `params`: The playback parameters
+    pub fn set_params(&mut self, voice_id: usize, params: VoiceParams) -> Result<()> {
+        self.get_voice_mut(voice_id)?.params = params;
+        Ok(())
+    }
+
+    /// Gets the playback parameters of a voice
+    ///
+    /// # Arguments
+    ///
+    /// * `voice_id`: The voice ID
+    pub fn get_params(&mut self, voice_id: usize) -> Result<VoiceParams> {
+        Ok(self.get_voice_mut(voice_id)?.params)
+    }
+
+    /// Gets whether a voice is allocated (it may be paused)
+    ///
+    /// Non-looping voices are freed when they finish playing
+    ///
+    /// # Arguments
+    ///
+    /// * `voice_id`: The voice ID
+    pub fn is_active(&self, voice_id: usize) -> bool {
+        matches!(self.voices.get(voice_id), Some(Some(_)))
+    }
+
+    /// Mixes all the playing voices into the given interleaved frames, advancing them
+    ///
+    /// # Arguments
+    ///
+    /// * `out_samples`: The interleaved samples to mix into, whose length must be a multiple of the output channel count (any previous contents are overwritten)
+    pub fn mix(&mut self, out_samples: &mut [i16]) -> Result<()> {
+        let channel_count = self.channel_count as usize;
+        result_return_unless!(
+            out_samples.len().is_multiple_of(channel_count),
+            rc::ResultInvalidSampleCount
+        );
+
+        self.mix_buffer.clear();
+        self.mix_buffer.resize(out_samples.len(), 0.0);
+        for voice_slot in self.voices.iter_mut() {
+            if let Some(voice) = voice_slot {
+                if !voice.playing {
+                    continue;
+                }
+
+                voice.mix_into(&mut self.mix_buffer, channel_count, self.sample_rate);
+                if !voice.playing {
+                    // The voice finished
+                    *voice_slot = None;
+                }
+            }
+        }
+
+        for (out_sample, mixed_sample) in out_samples.iter_mut().zip(self.mix_buffer.iter()) {
+            *out_sample = mixed_sample.clamp(i16::MIN as f32, i16::MAX as f32) as i16;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn mix(mixer: &mut Mixer, frame_count: usize) -> Vec<i16> {
+        let mut out = vec![0; frame_count * mixer.get_channel_count() as usize];
+        mixer.mix(&mut out).unwrap();
+        out
+    }
+
+    #[test]
+    fn sound_validation() {
+        assert!(Sound::new(vec![0i16; 4], 0, 48000).is_err());
+        assert!(Sound::new(vec![0i16; 3], 2, 48000).is_err());
+        assert!(Sound::new(vec![0i16; 4], 2, 0).is_err());
+
+        let sound = Sound::new(vec![0i16; 4], 2, 48000).unwrap();
+        assert_eq!(sound.get_frame_count(), 2);
+        assert!(sound.is_same(&sound.clone()));
+        assert!(!sound.is_same(&Sound::new(vec![0i16; 4], 2, 48000).unwrap()));
+    }
+
+    #[test]
+    fn mono_to_stereo() {
+        let mut mixer = Mixer::new(48000, 2, 4).unwrap();
+        let sound = Sound::new(vec![100i16, -200, 300], 1, 48000).unwrap();
+        let voice_id = mixer.play(sound, VoiceParams::default()).unwrap();
+
+        assert_eq!(mix(&mut mixer, 3), [100, 100, -200, -200, 300, 300]);
+        assert!(mixer.is_active(voice_id));
+
+        // The voice is freed once it finishes
+        assert_eq!(mix(&mut mixer, 2), [0; 4]);
+        assert!(!mixer.is_active(voice_id));
+    }
+
+    #[test]
+    fn volume_and_clamping() {
+        let mut mixer = Mixer::new(48000, 1, 4).unwrap();
+        let sound = Sound::new(vec![30000i16, -30000, 1000], 1, 48000).unwrap();
+        mixer.play(sound.clone(), VoiceParams::default()).unwrap();
+        mixer
+            .play(
+                sound,
+                VoiceParams {
+                    volume: 0.5,
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+
+        assert_eq!(mix(&mut mixer, 3), [i16::MAX, i16::MIN, 1500]);
+    }
+
+    #[test]
+    fn resampling() {
+        // Half the output sample rate, thus frames are interpolated
+        let mut mixer = Mixer::new(48000, 1, 1).unwrap();
+        let sound = Sound::new(vec![0i16, 100], 1, 24000).unwrap();
+        mixer.play(sound, VoiceParams::default()).unwrap();
+        assert_eq!(mix(&mut mixer, 6), [0, 50, 100, 100, 0, 0]);
+
+        // Doubling the pitch skips every other frame
+        let sound = Sound::new(vec![0i16, 1, 2, 3, 4, 5], 1, 48000).unwrap();
+        mixer
+            .play(
+                sound,
+                VoiceParams {
+                    pitch: 2.0,
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+        assert_eq!(mix(&mut mixer, 4), [0, 2, 4, 0]);
+    }
+
+    #[test]
+    fn looping_and_pausing() {
+        let mut mixer = Mixer::new(48000, 1, 1).unwrap();
+        let sound = Sound::new(vec![1i16, 2], 1, 48000).unwrap();
+        let voice_id = mixer
+            .play(
+                sound,
+                VoiceParams {
+                    looping: true,
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+
+        assert_eq!(mix(&mut mixer, 5), [1, 2, 1, 2, 1]);
+        mixer.set_paused(voice_id, true).unwrap();
+        assert_eq!(mix(&mut mixer, 2), [0, 0]);
+        assert!(mixer.is_active(voice_id));
+        mixer.set_paused(voice_id, false).unwrap();
+        assert_eq!(mix(&mut mixer, 3), [2, 1, 2]);
+
+        let params = VoiceParams {
+            volume: 2.0,
+            ..mixer.get_params(voice_id).unwrap()
+        };
+        mixer.set_params(voice_id, params).unwrap();
+        assert_eq!(mixer.get_params(voice_id).unwrap(), params);
+        assert_eq!(mix(&mut mixer, 2), [2, 4]);
+    }
+
+    #[test]
+    fn voice_allocation() {
+        let mut mixer = Mixer::new(48000, 2, 1).unwrap();
+        let sound = Sound::new(vec![0i16; 8], 2, 48000).unwrap();
+        let voice_id = mixer.play(sound.clone(), VoiceParams::default()).unwrap();
+        assert!(mixer.play(sound.clone(), VoiceParams::default()).is_err());
+
+        mixer.stop(voice_id).unwrap();
+        assert!(!mixer.is_active(voice_id));
+        assert!(mixer.stop(voice_id).is_err());
+        assert!(mixer.set_paused(1, true).is_err());
+        assert_eq!(mixer.play(sound, VoiceParams::default()).unwrap(), voice_id);
+
+        // Output samples must be whole frames
+        assert!(mixer.mix(&mut [0; 3]).is_err());
+        assert!(Mixer::new(48000, 0, 1).is_err());
+    }
+}
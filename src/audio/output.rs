@@ -0,0 +1,362 @@
+//! Audio output (`audout`) streaming support
+
+use super::queue::{BufferQueue, SampleRing};
+use super::rc;
+use crate::arm;
+use crate::ipc::sf;
+use crate::mem::alloc;
+use crate::result::*;
+use crate::service;
+use crate::svc;
+use crate::time::Timeout;
+use crate::wait::RemoteEvent;
+use ::alloc::string::String;
+use ::alloc::vec::Vec;
+
+pub use crate::service::audout::*;
+
+/// The default output sample rate
+pub const DEFAULT_SAMPLE_RATE: u32 = 48000;
+
+/// The default output channel count
+pub const DEFAULT_CHANNEL_COUNT: u16 = 2;
+
+/// The default amount of frames held by each output buffer
+pub const DEFAULT_BUFFER_FRAME_COUNT: usize = 1024;
+
+/// The default output buffer count (double buffering)
+pub const DEFAULT_BUFFER_COUNT: usize = 2;
+
+/// The maximum output buffer count (the maximum amount of buffers the audio services can hold per output)
+pub const MAX_BUFFER_COUNT: usize = 32;
+
+/// Lists the names of the available audio outputs
+pub fn list_outputs() -> Result<Vec<String>> {
+    let audout_srv = service::new_service_object::<AudioOutManagerService>()?;
+
+    let mut names: [AudioDeviceName; 0x10] = Default::default();
+    let count = audout_srv.list_audio_outs(sf::Buffer::from_mut_array(&mut names))? as usize;
+    Ok(names[..count.min(names.len())]
+        .iter()
+        .map(|name| String::from(name.get_str().unwrap_or("")))
+        .collect())
+}
+
+/// Represents an opened audio output, which streams interleaved PCM16 samples
+///
+/// Samples are written into an internal ring, from which the output buffers are filled and appended as soon as the audio services release them
+pub struct Output {
+    audio_out: AudioOut,
+    buffer_event: RemoteEvent,
+    sample_buffers: Vec<alloc::Buffer<i16>>,
+    buffer_size: usize,
+    buffer_frame_count: usize,
+    queue: BufferQueue,
+    ring: SampleRing,
+    name: AudioDeviceName,
+    sample_rate: u32,
+    channel_count: u16,
+}
+
+impl Output {
+    /// Opens the default audio output with the default settings
+    pub fn open_default() -> Result<Self> {
+        Self::open(
+            DEFAULT_SAMPLE_RATE,
+            DEFAULT_CHANNEL_COUNT,
+            DEFAULT_BUFFER_FRAME_COUNT,
+            DEFAULT_BUFFER_COUNT,
+        )
+    }
+
+    /// Opens the default audio output
+    ///
+    /// The internal sample ring is able to hold as many frames as all the output buffers
+    ///
+    /// # Arguments
+    ///
+    /// * `sample_rate`: The desired sample rate (the audio services may choose a different one, see [`Output::get_sample_rate`])
+    /// * `channel_count`: The desired channel count (`1`-`6`)
+    /// * `buffer_frame_count`: The amount of frames held by each output buffer
+    /// * `buffer_count`: The output buffer count (`2`-[`MAX_BUFFER_COUNT`])
+    pub fn open(
+        sample_rate: u32,
+        channel_count: u16,
+        buffer_frame_count: usize,
+        buffer_count: usize,
+    ) -> Result<Self> {
+        result_return_unless!(
+            (1..=6).contains(&channel_count),
+            rc::ResultInvalidChannelCount
+        );
+        result_return_unless!(buffer_frame_count > 0, rc::ResultInvalidSampleCount);
+        result_return_unless!(
+            (2..=MAX_BUFFER_COUNT).contains(&buffer_count),
+            rc::ResultInvalidBufferCount
+        );
+
+        let mut audout_srv = service::new_service_object::<AudioOutManagerService>()?;
+
+        let name = AudioDeviceName::new();
+        let mut out_name = AudioDeviceName::new();
+        let parameter = AudioOutParameter {
+            sample_rate,
+            channel_count,
+            reserved: 0,
+        };
+        let aruid = AppletResourceUserId::from_global();
+        let (parameter_internal, mut audio_out) = audout_srv.open_audio_out(
+            parameter,
+            aruid,
+            sf::CopyHandle::from(svc::CURRENT_PROCESS_PSEUDO_HANDLE),
+            sf::Buffer::from_var(&name),
+            sf::Buffer::from_mut_var(&mut out_name),
+        )?;
+        result_return_unless!(
+            parameter_internal.sample_format == PcmFormat::Int16,
+            rc::ResultUnsupportedSampleFormat
+        );
+
+        let buffer_event = RemoteEvent::new(audio_out.register_buffer_event()?.handle);
+
+        // The channel count is not necessarily the one we asked for, thus the buffer size depends on the actual one
+        let actual_channel_count = parameter_internal.channel_count as usize;
+        let buffer_size = (buffer_frame_count * actual_channel_count * size_of::<i16>())
+            .next_multiple_of(AUDIO_BUFFER_ALIGNMENT);
+        let mut sample_buffers = Vec::with_capacity(buffer_count);
+        for _ in 0..buffer_count {
+            sample_buffers.push(alloc::Buffer::new(
+                AUDIO_BUFFER_ALIGNMENT,
+                buffer_size / size_of::<i16>(),
+            )?);
+        }
+
+        Ok(Self {
+            audio_out,
+            buffer_event,
+            sample_buffers,
+            buffer_size,
+            buffer_frame_count,
+            queue: BufferQueue::new(buffer_count),
+            ring: SampleRing::new(buffer_frame_count * actual_channel_count * buffer_count),
+            name: out_name,
+            sample_rate: parameter_internal.sample_rate,
+            channel_count: actual_channel_count as u16,
+        })
+    }
+
+    /// Gets the name of the opened audio output
+    #[inline]
+    pub fn get_name(&self) -> &str {
+        self.name.get_str().unwrap_or("")
+    }
+
+    /// Gets the actual sample rate of the output
+    #[inline]
+    pub fn get_sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+
+    /// Gets the actual channel count of the output
+    #[inline]
+    pub fn get_channel_count(&self) -> u16 {
+        self.channel_count
+    }
+
+    /// Gets the amount of frames which can currently be written without blocking
+    #[inline]
+    pub fn get_available_frames(&self) -> usize {
+        self.ring.available() / self.channel_count as usize
+    }
+
+    /// Gets the underlying [`IAudioOutClient`] object
+    #[inline]
+    pub fn get_audio_out(&mut self) -> &mut AudioOut {
+        &mut self.audio_out
+    }
+
+    /// Gets the state of the output
+    #[inline]
+    pub fn get_state(&self) -> Result<AudioOutState> {
+        self.audio_out.get_audio_out_state()
+    }
+
+    /// Starts playback
+    ///
+    /// Any full buffers of already written frames are appended before starting
+    pub fn start(&mut self) -> Result<()> {
+        self.update()?;
+        self.audio_out.start()
+    }
+
+    /// Stops playback
+    ///
+    /// Frames still in the ring are kept, thus playback can be resumed via [`Output::start`]
+    pub fn stop(&mut self) -> Result<()> {
+        self.audio_out.stop()?;
+        self.release_buffers()
+    }
+
+    /// Sets the output volume (`1.0` being the default one)
+    ///
+    /// # Arguments
+    ///
+    /// * `volume`: The volume
+    #[inline]
+    pub fn set_volume(&mut self, volume: f32) -> Result<()> {
+        self.audio_out.set_audio_out_volume(volume)
+    }
+
+    /// Gets the output volume
+    #[inline]
+    pub fn get_volume(&self) -> Result<f32> {
+        self.audio_out.get_audio_out_volume()
+    }
+
+    /// Gets the amount of samples played so far
+    #[inline]
+    pub fn get_played_sample_count(&self) -> Result<u64> {
+        self.audio_out.get_audio_out_played_sample_count()
+    }
+
+    fn release_buffers(&mut self) -> Result<()> {
+        let mut tags = [0u64; MAX_BUFFER_COUNT];
+        loop {
+            let count = self
+                .audio_out
+                .get_released_audio_out_buffers(sf::Buffer::from_mut_array(&mut tags))?
+                as usize;
+            if count == 0 {
+                return Ok(());
+            }
+
+            for tag in &tags[..count.min(tags.len())] {
+                self.queue.release(*tag as usize)?;
+            }
+        }
+    }
+
+    fn append_buffers(&mut self, allow_partial: bool) -> Result<()> {
+        let channel_count = self.channel_count as usize;
+        while let Some(index) = self.queue.acquire() {
+            let frame_count = (self.ring.len() / channel_count).min(self.buffer_frame_count);
+            if (frame_count == 0) || (!allow_partial && (frame_count < self.buffer_frame_count)) {
+                return self.queue.cancel(index);
+            }
+
+            let sample_count = frame_count * channel_count;
+            let sample_buffer = &self.sample_buffers[index];
+            let samples =
+                unsafe { core::slice::from_raw_parts_mut(sample_buffer.ptr, sample_count) };
+            self.ring.pop(samples);
+            arm::cache_flush(sample_buffer.ptr.cast(), self.buffer_size);
+
+            let audio_buffer = AudioBuffer {
+                next: 0,
+                sample_buffer: sample_buffer.ptr as u64,
+                buffer_capacity: self.buffer_size as u64,
+                data_size: (sample_count * size_of::<i16>()) as u64,
+                data_offset: 0,
+            };
+            if let Err(rc) = self
+                .audio_out
+                .append_audio_out_buffer(index as u64, sf::Buffer::from_var(&audio_buffer))
+            {
+                self.queue.cancel(index)?;
+                return Err(rc);
+            }
+            self.queue.submit(index)?;
+        }
+
+        Ok(())
+    }
+
+    /// Processes released buffers and appends free ones filled with the written frames
+    ///
+    /// Only full buffers are appended, remaining frames are kept until more are written or [`Output::drain`] is called
+    ///
+    /// This is already done by the writing functions, but it may be called periodically to keep streaming without writing
+    pub fn update(&mut self) -> Result<()> {
+        self.release_buffers()?;
+        self.append_buffers(false)
+    }
+
+    /// Writes as many whole frames as possible without blocking, returning the amount of written frames
+    ///
+    /// # Arguments
+    ///
+    /// * `samples`: The interleaved samples to write, whose length must be a multiple of the channel count
+    pub fn write(&mut self, samples: &[i16]) -> Result<usize> {
+        let channel_count = self.channel_count as usize;
+        result_return_unless!(
+            samples.len() % channel_count == 0,
+            rc::ResultInvalidSampleCount
+        );
+
+        self.update()?;
+        let frame_count = self
+            .get_available_frames()
+            .min(samples.len() / channel_count);
+        self.ring.push(&samples[..frame_count * channel_count]);
+        self.append_buffers(false)?;
+        Ok(frame_count)
+    }
+
+    /// Writes all the given frames, waiting for buffers to be released when needed
+    ///
+    /// Note that this will wait indefinitely if the output is not started
+    ///
+    /// # Arguments
+    ///
+    /// * `samples`: The interleaved samples to write, whose length must be a multiple of the channel count
+    pub fn write_all(&mut self, samples: &[i16]) -> Result<()> {
+        let channel_count = self.channel_count as usize;
+        let mut offset = 0;
+        loop {
+            offset += self.write(&samples[offset..])? * channel_count;
+            if offset >= samples.len() {
+                return Ok(());
+            }
+
+            self.buffer_event.wait(Timeout::Infinite)?;
+        }
+    }
+
+    /// Appends all the remaining frames (even if they don't fill a whole buffer) and waits for all the appended buffers to be played
+    ///
+    /// Note that this will wait indefinitely if the output is not started
+    pub fn drain(&mut self) -> Result<()> {
+        loop {
+            self.release_buffers()?;
+            self.append_buffers(true)?;
+            if self.ring.is_empty() && (self.queue.get_queued_count() == 0) {
+                return Ok(());
+            }
+
+            self.buffer_event.wait(Timeout::Infinite)?;
+        }
+    }
+
+    /// Waits until the audio services release a buffer (or the timeout expires)
+    ///
+    /// # Arguments
+    ///
+    /// * `timeout`: The wait timeout
+    #[inline]
+    pub fn wait_buffer_released(&self, timeout: impl Into<Timeout>) -> Result<()> {
+        self.buffer_event.wait(timeout)
+    }
+
+    /// Discards all the written frames which were not appended yet
+    #[inline]
+    pub fn clear(&mut self) {
+        self.ring.clear();
+    }
+}
+
+impl Drop for Output {
+    /// Stops the output, since the sample buffers must not be freed while still in use by the audio services
+    fn drop(&mut self) {
+        let _ = self.audio_out.stop();
+    }
+}
@@ -0,0 +1,330 @@
+//! Sample ring and buffer queue management
+//!
+//! These types only keep track of samples and buffer states, and have no dependencies on the audio services, thus they can be used (and tested) outside of the console
+
+use super::rc;
+use crate::result::*;
+use alloc::collections::VecDeque;
+use alloc::vec::Vec;
+
+/// Represents a fixed-capacity ring of interleaved samples
+pub struct SampleRing {
+    samples: Vec<i16>,
+    read_offset: usize,
+    len: usize,
+}
+
+impl SampleRing {
+    /// Creates a new, empty [`SampleRing`]
+    ///
+    /// # Arguments
+    ///
+    /// * `capacity`: The maximum amount of samples the ring can hold
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            samples: vec![0; capacity],
+            read_offset: 0,
+            len: 0,
+        }
+    }
+
+    /// Gets the maximum amount of samples the ring can hold
+    #[inline]
+    pub fn capacity(&self) -> usize {
+        self.samples.len()
+    }
+
+    /// Gets the amount of samples currently in the ring
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Gets whether the ring is empty
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Gets the amount of samples which can still be pushed to the ring
+    #[inline]
+    pub fn available(&self) -> usize {
+        self.capacity() - self.len
+    }
+
+    /// Pushes as many samples as possible to the ring, returning the amount of pushed samples
+    ///
+    /// # Arguments
+    ///
+    /// * `samples`: The samples to push
+    pub fn push(&mut self, samples: &[i16]) -> usize {
+        let count = samples.len().min(self.available());
+        let capacity = self.capacity();
+        let write_offset = (self.read_offset + self.len) % capacity.max(1);
+
+        let first_count = count.min(capacity - write_offset);
+        self.samples[write_offset..write_offset + first_count]
+            .copy_from_slice(&samples[..first_count]);
+        self.samples[..count - first_count].copy_from_slice(&samples[first_count..count]);
+
+        self.len += count;
+        count
+    }
+
+    /// Pops as many samples as possible from the ring, returning the amount of popped samples
+    ///
+    /// # Arguments
+    ///
+    /// * `out_samples`: The array to pop the samples into
+    pub fn pop(&mut self, out_samples: &mut [i16]) -> usize {
+        let count = out_samples.len().min(self.len);
+        let capacity = self.capacity();
+
+        let first_count = count.min(capacity - self.read_offset);
+        out_samples[..first_count]
+            .copy_from_slice(&self.samples[self.read_offset..self.read_offset + first_count]);
+        out_samples[first_count..count].copy_from_slice(&self.samples[..count - first_count]);
+
+        self.read_offset = (self.read_offset + count) % capacity.max(1);
+        self.len -= count;
+        count
+    }
+
+    /// Discards all the samples in the ring
+    #[inline]
+    pub fn clear(&mut self) {
+        self.read_offset = 0;
+        self.len = 0;
+    }
+}
+
+/// Represents the state of a buffer in a [`BufferQueue`]
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum BufferState {
+    /// The buffer is free to be acquired
+    Free,
+    /// The buffer was acquired and is being filled
+    Filling,
+    /// The buffer was submitted and is owned by the audio services until it's released
+    Queued,
+}
+
+/// Keeps track of the states of a fixed set of buffers, identified by their index
+///
+/// The usual cycle of a buffer is [`acquire`][`BufferQueue::acquire`] -> [`submit`][`BufferQueue::submit`] -> [`release`][`BufferQueue::release`], where released buffers become free again
+pub struct BufferQueue {
+    states: Vec<BufferState>,
+    free_indices: VecDeque<usize>,
+    queued_indices: VecDeque<usize>,
+}
+
+impl BufferQueue {
+    /// Creates a new [`BufferQueue`] with all buffers free
+    ///
+    /// # Arguments
+    ///
+    /// * `count`: The buffer count
+    pub fn new(count: usize) -> Self {
+        Self {
+            states: vec![BufferState::Free; count],
+            free_indices: (0..count).collect(),
+            queued_indices: VecDeque::with_capacity(count),
+        }
+    }
+
+    /// Gets the buffer count
+    #[inline]
+    pub fn get_count(&self) -> usize {
+        self.states.len()
+    }
+
+    /// Gets the state of a buffer
+    ///
+    /// # Arguments
+    ///
+    /// * `index`: The buffer index
+    pub fn get_state(&self, index: usize) -> Result<BufferState> {
+        self.states
+            .get(index)
+            .copied()
+            .ok_or(rc::ResultInvalidBufferIndex::make())
+    }
+
+    /// Gets the amount of free buffers
+    #[inline]
+    pub fn get_free_count(&self) -> usize {
+        self.free_indices.len()
+    }
+
+    /// Gets the amount of queued buffers
+    #[inline]
+    pub fn get_queued_count(&self) -> usize {
+        self.queued_indices.len()
+    }
+
+    /// Gets the indices of the queued buffers, in submission order
+    pub fn get_queued_indices(&self) -> impl Iterator<Item = usize> + '_ {
+        self.queued_indices.iter().copied()
+    }
+
+    fn transition(&mut self, index: usize, from: BufferState, to: BufferState) -> Result<()> {
+        let state = self
+            .states
+            .get_mut(index)
+            .ok_or(rc::ResultInvalidBufferIndex::make())?;
+        result_return_unless!(*state == from, rc::ResultInvalidBufferState);
+        *state = to;
+        Ok(())
+    }
+
+    /// Acquires the oldest free buffer for filling, returning its index (or [`None`] if no buffers are free)
+    pub fn acquire(&mut self) -> Option<usize> {
+        let index = self.free_indices.pop_front()?;
+        self.states[index] = BufferState::Filling;
+        Some(index)
+    }
+
+    /// Returns an acquired buffer back to the free buffers without submitting it
+    ///
+    /// # Arguments
+    ///
+    /// * `index`: The buffer index
+    pub fn cancel(&mut self, index: usize) -> Result<()> {
+        self.transition(index, BufferState::Filling, BufferState::Free)?;
+        self.free_indices.push_front(index);
+        Ok(())
+    }
+
+    /// Marks an acquired buffer as queued
+    ///
+    /// # Arguments
+    ///
+    /// * `index`: The buffer index
+    pub fn submit(&mut self, index: usize) -> Result<()> {
+        self.transition(index, BufferState::Filling, BufferState::Queued)?;
+        self.queued_indices.push_back(index);
+        Ok(())
+    }
+
+    /// Marks a queued buffer as free again
+    ///
+    /// Buffers may be released in any order
+    ///
+    /// # Arguments
+    ///
+    /// * `index`: The buffer index
+    pub fn release(&mut self, index: usize) -> Result<()> {
+        self.transition(index, BufferState::Queued, BufferState::Free)?;
+        self.queued_indices
+            .retain(|&queued_index| queued_index != index);
+        self.free_indices.push_back(index);
+        Ok(())
+    }
+
+    /// Marks all buffers as free again, like after the audio services drop all of them
+    pub fn reset(&mut self) {
+        let count = self.get_count();
+        self.states.fill(BufferState::Free);
+        self.free_indices.clear();
+        self.free_indices.extend(0..count);
+        self.queued_indices.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sample_ring_wraps_around() {
+        let mut ring = SampleRing::new(8);
+        assert!(ring.is_empty());
+        assert_eq!(ring.push(&[1, 2, 3, 4, 5, 6]), 6);
+        assert_eq!(ring.available(), 2);
+
+        let mut out = [0; 4];
+        assert_eq!(ring.pop(&mut out), 4);
+        assert_eq!(out, [1, 2, 3, 4]);
+
+        // Only 6 samples fit now, and they wrap around the end of the ring
+        assert_eq!(ring.push(&[7, 8, 9, 10, 11, 12, 13]), 6);
+        assert_eq!(ring.len(), 8);
+        assert_eq!(ring.available(), 0);
+        assert_eq!(ring.push(&[14]), 0);
+
+        let mut out = [0; 10];
+        assert_eq!(ring.pop(&mut out), 8);
+        assert_eq!(out[..8], [5, 6, 7, 8, 9, 10, 11, 12]);
+        assert!(ring.is_empty());
+        assert_eq!(ring.pop(&mut out), 0);
+    }
+
+    #[test]
+    fn sample_ring_clear_and_empty() {
+        let mut ring = SampleRing::new(4);
+        ring.push(&[1, 2, 3]);
+        ring.clear();
+        assert!(ring.is_empty());
+        assert_eq!(ring.available(), 4);
+
+        let mut out = [0; 4];
+        assert_eq!(ring.push(&[4, 5, 6, 7]), 4);
+        assert_eq!(ring.pop(&mut out), 4);
+        assert_eq!(out, [4, 5, 6, 7]);
+
+        // A zero-capacity ring never holds anything
+        let mut ring = SampleRing::new(0);
+        assert_eq!(ring.push(&[1, 2]), 0);
+        assert_eq!(ring.pop(&mut out), 0);
+    }
+
+    #[test]
+    fn buffer_queue_cycle() {
+        let mut queue = BufferQueue::new(3);
+        assert_eq!(queue.get_free_count(), 3);
+
+        let first = queue.acquire().unwrap();
+        let second = queue.acquire().unwrap();
+        let third = queue.acquire().unwrap();
+        assert_eq!((first, second, third), (0, 1, 2));
+        assert_eq!(queue.acquire(), None);
+        assert_eq!(queue.get_state(first).unwrap(), BufferState::Filling);
+
+        queue.submit(second).unwrap();
+        queue.submit(first).unwrap();
+        queue.cancel(third).unwrap();
+        assert_eq!(
+            queue.get_queued_indices().collect::<Vec<_>>(),
+            [second, first]
+        );
+        assert_eq!(queue.get_free_count(), 1);
+
+        // Released out of order, and cancelled buffers are acquired first
+        queue.release(first).unwrap();
+        assert_eq!(queue.get_state(first).unwrap(), BufferState::Free);
+        assert_eq!(queue.get_queued_indices().collect::<Vec<_>>(), [second]);
+        assert_eq!(queue.acquire(), Some(third));
+        assert_eq!(queue.acquire(), Some(first));
+
+        queue.reset();
+        assert_eq!(queue.get_free_count(), 3);
+        assert_eq!(queue.get_queued_count(), 0);
+    }
+
+    #[test]
+    fn buffer_queue_invalid_transitions() {
+        let mut queue = BufferQueue::new(2);
+        assert!(queue.get_state(2).is_err());
+        assert!(queue.submit(0).is_err());
+        assert!(queue.release(0).is_err());
+        assert!(queue.cancel(5).is_err());
+
+        let index = queue.acquire().unwrap();
+        assert!(queue.release(index).is_err());
+        queue.submit(index).unwrap();
+        assert!(queue.submit(index).is_err());
+        assert!(queue.cancel(index).is_err());
+        queue.release(index).unwrap();
+        assert!(queue.release(index).is_err());
+    }
+}
@@ -0,0 +1,23 @@
+//! Audio-specific result definitions
+
+use crate::rc;
+
+/// Result Submodule ID for the parent module
+pub const RESULT_SUBMODULE: u32 = 1500;
+
+result_define_subgroup!(rc::RESULT_MODULE, RESULT_SUBMODULE => {
+    InvalidBufferIndex: 1,
+    InvalidBufferState: 2,
+    InvalidChannelCount: 3,
+    InvalidSampleCount: 4,
+    InvalidBufferCount: 5,
+    UnsupportedSampleFormat: 6,
+    InvalidUpdateData: 7,
+    UpdateBufferTooSmall: 8,
+    NoFreeVoices: 9,
+    InvalidVoice: 10,
+    NoFreeMemoryPools: 11,
+    InvalidSound: 12,
+    InvalidFormat: 13,
+    UnsupportedFormat: 14
+});
@@ -0,0 +1,279 @@
+//! High-level voice renderer
+//!
+//! The renderer plays [`Sound`]s through voices with their own volume, pitch and looping settings. It's backed by the audio renderer services (`audren`) when available, and otherwise by the software [`Mixer`] streaming into an [`Output`]
+
+use super::mixer::{Mixer, Sound, VoiceParams};
+use super::output::Output;
+use crate::result::*;
+use crate::time::Timeout;
+use alloc::vec::Vec;
+
+pub mod update;
+
+mod hardware;
+use hardware::HardwareBackend;
+
+/// Represents the configuration of a [`Renderer`]
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct RendererConfig {
+    /// The output sample rate
+    pub sample_rate: u32,
+    /// The output channel count
+    pub channel_count: u16,
+    /// The maximum amount of simultaneously allocated voices
+    pub voice_count: usize,
+    /// The amount of frames rendered on each update
+    pub frame_count: usize,
+}
+
+impl Default for RendererConfig {
+    fn default() -> Self {
+        Self {
+            sample_rate: 48000,
+            channel_count: 2,
+            voice_count: 24,
+            frame_count: 240,
+        }
+    }
+}
+
+/// Represents a voice allocated by a [`Renderer`]
+///
+/// Voice IDs may be reused after the voice is stopped or finishes playing
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+pub struct VoiceId(usize);
+
+struct SoftwareBackend {
+    mixer: Mixer,
+    output: Output,
+    frame_samples: Vec<i16>,
+}
+
+impl SoftwareBackend {
+    fn new(config: &RendererConfig) -> Result<Self> {
+        let mut output = Output::open(
+            config.sample_rate,
+            config.channel_count,
+            config.frame_count,
+            super::output::DEFAULT_BUFFER_COUNT,
+        )?;
+        // The mixer must match the actual output format
+        let mixer = Mixer::new(
+            output.get_sample_rate(),
+            output.get_channel_count(),
+            config.voice_count,
+        )?;
+        let frame_samples = vec![0; config.frame_count * output.get_channel_count() as usize];
+        output.start()?;
+
+        Ok(Self {
+            mixer,
+            output,
+            frame_samples,
+        })
+    }
+
+    fn update(&mut self) -> Result<()> {
+        let frame_count = self.frame_samples.len() / self.mixer.get_channel_count() as usize;
+        self.output.update()?;
+        while self.output.get_available_frames() >= frame_count {
+            self.mixer.mix(&mut self.frame_samples)?;
+            self.output.write(&self.frame_samples)?;
+        }
+        Ok(())
+    }
+}
+
+enum Backend {
+    Hardware(HardwareBackend),
+    Software(SoftwareBackend),
+}
+
+/// Represents a voice renderer
+pub struct Renderer {
+    backend: Backend,
+}
+
+impl Renderer {
+    /// Creates a new [`Renderer`], falling back to software mixing if the audio renderer services are not available
+    ///
+    /// # Arguments
+    ///
+    /// * `config`: The configuration
+    pub fn new(config: RendererConfig) -> Result<Self> {
+        match HardwareBackend::new(&config) {
+            Ok(backend) => Ok(Self {
+                backend: Backend::Hardware(backend),
+            }),
+            Err(_) => Self::new_software(config),
+        }
+    }
+
+    /// Creates a new [`Renderer`] which always uses software mixing
+    ///
+    /// # Arguments
+    ///
+    /// * `config`: The configuration
+    pub fn new_software(config: RendererConfig) -> Result<Self> {
+        Ok(Self {
+            backend: Backend::Software(SoftwareBackend::new(&config)?),
+        })
+    }
+
+    /// Gets whether this [`Renderer`] uses software mixing
+    #[inline]
+    pub fn is_software(&self) -> bool {
+        matches!(self.backend, Backend::Software(_))
+    }
+
+    /// Gets the actual output sample rate
+    pub fn get_sample_rate(&self) -> u32 {
+        match &self.backend {
+            Backend::Hardware(backend) => backend.get_sample_rate(),
+            Backend::Software(backend) => backend.mixer.get_sample_rate(),
+        }
+    }
+
+    /// Gets the actual output channel count
+    pub fn get_channel_count(&self) -> u16 {
+        match &self.backend {
+            Backend::Hardware(backend) => backend.get_channel_count(),
+            Backend::Software(backend) => backend.mixer.get_channel_count(),
+        }
+    }
+
+    /// Allocates a voice which starts playing the given sound
+    ///
+    /// With the audio renderer services, playback actually starts on the next [`Renderer::update`]
+    ///
+    /// # Arguments
+    ///
+    /// * `sound`: The sound to play
+    /// * `params`: The playback parameters
+    pub fn play(&mut self, sound: &Sound, params: VoiceParams) -> Result<VoiceId> {
+        let voice_id = match &mut self.backend {
+            Backend::Hardware(backend) => backend.play(sound, params)?,
+            Backend::Software(backend) => backend.mixer.play(sound.clone(), params)?,
+        };
+        Ok(VoiceId(voice_id))
+    }
+
+    /// Stops a voice and frees it
+    ///
+    /// # Arguments
+    ///
+    /// * `voice`: The voice
+    pub fn stop(&mut self, voice: VoiceId) -> Result<()> {
+        match &mut self.backend {
+            Backend::Hardware(backend) => backend.stop(voice.0),
+            Backend::Software(backend) => backend.mixer.stop(voice.0),
+        }
+    }
+
+    /// Pauses or resumes a voice
+    ///
+    /// # Arguments
+    ///
+    /// * `voice`: The voice
+    /// * `paused`: Whether the voice is paused
+    pub fn set_paused(&mut self, voice: VoiceId, paused: bool) -> Result<()> {
+        match &mut self.backend {
+            Backend::Hardware(backend) => backend.set_paused(voice.0, paused),
+            Backend::Software(backend) => backend.mixer.set_paused(voice.0, paused),
+        }
+    }
+
+    /// Gets the playback parameters of a voice
+    ///
+    /// # Arguments
+    ///
+    /// * `voice`: The voice
+    pub fn get_params(&mut self, voice: VoiceId) -> Result<VoiceParams> {
+        match &mut self.backend {
+            Backend::Hardware(backend) => backend.get_params(voice.0),
+            Backend::Software(backend) => backend.mixer.get_params(voice.0),
+        }
+    }
+
+    /// Sets the playback parameters of a voice
+    ///
+    /// # Arguments
+    ///
+    /// * `voice`: The voice
+    /// * `params`: The playback parameters
+    pub fn set_params(&mut self, voice: VoiceId, params: VoiceParams) -> Result<()> {
+        match &mut self.backend {
+            Backend::Hardware(backend) => backend.set_params(voice.0, params),
+            Backend::Software(backend) => backend.mixer.set_params(voice.0, params),
+        }
+    }
+
+    /// Sets the volume of a voice (`1.0` being the original one)
+    ///
+    /// # Arguments
+    ///
+    /// * `voice`: The voice
+    /// * `volume`: The volume
+    pub fn set_volume(&mut self, voice: VoiceId, volume: f32) -> Result<()> {
+        let params = self.get_params(voice)?;
+        self.set_params(voice, VoiceParams { volume, ..params })
+    }
+
+    /// Sets the pitch of a voice, as a playback speed ratio (`1.0` being the original one)
+    ///
+    /// # Arguments
+    ///
+    /// * `voice`: The voice
+    /// * `pitch`: The pitch
+    pub fn set_pitch(&mut self, voice: VoiceId, pitch: f32) -> Result<()> {
+        let params = self.get_params(voice)?;
+        self.set_params(voice, VoiceParams { pitch, ..params })
+    }
+
+    /// Sets whether a voice loops
+    ///
+    /// # Arguments
+    ///
+    /// * `voice`: The voice
+    /// * `looping`: Whether the voice loops
+    pub fn set_looping(&mut self, voice: VoiceId, looping: bool) -> Result<()> {
+        let params = self.get_params(voice)?;
+        self.set_params(voice, VoiceParams { looping, ..params })
+    }
+
+    /// Gets whether a voice is still allocated (it may be paused)
+    ///
+    /// Non-looping voices are freed when they finish playing
+    ///
+    /// # Arguments
+    ///
+    /// * `voice`: The voice
+    pub fn is_active(&self, voice: VoiceId) -> bool {
+        match &self.backend {
+            Backend::Hardware(backend) => backend.is_active(voice.0),
+            Backend::Software(backend) => backend.mixer.is_active(voice.0),
+        }
+    }
+
+    /// Applies all voice changes and processes finished voices
+    ///
+    /// This must be called periodically (like once per frame), otherwise no voice changes will take effect (or, with software mixing, playback will stall)
+    pub fn update(&mut self) -> Result<()> {
+        match &mut self.backend {
+            Backend::Hardware(backend) => backend.update(),
+            Backend::Software(backend) => backend.update(),
+        }
+    }
+
+    /// Waits until the renderer is able to render more frames (or the timeout expires), so that [`Renderer::update`] can be called in a loop
+    ///
+    /// # Arguments
+    ///
+    /// * `timeout`: The wait timeout
+    pub fn wait(&self, timeout: impl Into<Timeout>) -> Result<()> {
+        match &self.backend {
+            Backend::Hardware(backend) => backend.wait(timeout.into()),
+            Backend::Software(backend) => backend.output.wait_buffer_released(timeout),
+        }
+    }
+}
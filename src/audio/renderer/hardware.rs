@@ -0,0 +1,450 @@
+use super::RendererConfig;
+use super::update::{UpdateInput, UpdateOutput, get_output_size};
+use crate::audio::mixer::{Sound, VoiceParams};
+use crate::audio::rc;
+use crate::ipc::client::IClientObject;
+use crate::ipc::sf;
+use crate::mem::{alloc, wait_for_permission};
+use crate::result::*;
+use crate::service;
+use crate::service::audren::*;
+use crate::svc;
+use crate::time::Timeout;
+use crate::wait::RemoteEvent;
+use ::alloc::vec::Vec;
+
+struct MemoryPool {
+    /// Keeps the pool memory alive while the renderer may access it
+    _buffer: alloc::Buffer<u8>,
+    sound: Sound,
+    voice_ref_count: usize,
+}
+
+struct Voice {
+    pool_index: usize,
+    channel_resource_ids: Vec<usize>,
+    params: VoiceParams,
+}
+
+fn release_work_buffer(work_buffer: &alloc::Buffer<u8>, work_buffer_handle: svc::Handle) {
+    let _ = svc::close_handle(work_buffer_handle);
+    let _ = wait_for_permission(
+        work_buffer.ptr,
+        svc::MemoryPermission::Write(),
+        Timeout::Infinite,
+    );
+}
+
+/// Renderer backend using the audio renderer services
+///
+/// The whole state of every renderer object (memory pools, voice channel resources, voices, mixes and sinks) is kept here and sent on each update
+pub(super) struct HardwareBackend {
+    renderer: AudioRenderer,
+    event: RemoteEvent,
+    work_buffer: alloc::Buffer<u8>,
+    work_buffer_handle: svc::Handle,
+    revision: u32,
+    sample_rate: u32,
+    channel_count: u16,
+    memory_pools_in: Vec<MemoryPoolInfoIn>,
+    memory_pools: Vec<Option<MemoryPool>>,
+    channel_resources_in: Vec<VoiceChannelResourceIn>,
+    voices_in: Vec<VoiceInfoIn>,
+    voices: Vec<Option<Voice>>,
+    mixes_in: Vec<MixInfoIn>,
+    sinks_in: Vec<SinkInfoIn>,
+    output_buffer: Vec<u8>,
+}
+
+impl HardwareBackend {
+    pub fn new(config: &RendererConfig) -> Result<Self> {
+        result_return_unless!(
+            (1..=MAX_SINK_INPUT_COUNT as u16).contains(&config.channel_count),
+            rc::ResultInvalidChannelCount
+        );
+
+        let mut audren_srv = service::new_service_object::<AudioRendererManagerService>()?;
+
+        let revision = make_revision_magic(get_supported_revision());
+        let parameter = AudioRendererParameter {
+            sample_rate: config.sample_rate,
+            sample_count: config.frame_count as u32,
+            mix_buffer_count: config.channel_count as u32,
+            sub_mix_count: 0,
+            voice_count: config.voice_count as u32,
+            sink_count: 1,
+            effect_count: 0,
+            performance_frame_count: 0,
+            voice_drop_enabled: false,
+            pad_1: 0,
+            execution_mode: ExecutionMode::Auto,
+            pad_2: 0,
+            splitter_count: 0,
+            splitter_send_channel_count: 0,
+            reserved: 0,
+            revision,
+        };
+
+        let work_buffer_size = (audren_srv.get_work_buffer_size(parameter)? as usize)
+            .next_multiple_of(alloc::PAGE_ALIGNMENT);
+        let work_buffer = alloc::Buffer::new(alloc::PAGE_ALIGNMENT, work_buffer_size)?;
+        let work_buffer_handle = svc::create_transfer_memory(
+            work_buffer.ptr,
+            work_buffer_size,
+            svc::MemoryPermission::None(),
+        )?;
+
+        let aruid = AppletResourceUserId::from_global();
+        let setup: Result<(AudioRenderer, RemoteEvent)> = try {
+            let mut renderer = audren_srv.open_audio_renderer(
+                parameter,
+                work_buffer_size as u64,
+                aruid,
+                sf::CopyHandle::from(work_buffer_handle),
+                sf::CopyHandle::from(svc::CURRENT_PROCESS_PSEUDO_HANDLE),
+            )?;
+            let event = RemoteEvent::new(renderer.query_system_event()?.handle);
+            (renderer, event)
+        };
+        let (renderer, event) = match setup {
+            Ok(setup) => setup,
+            Err(rc) => {
+                release_work_buffer(&work_buffer, work_buffer_handle);
+                return Err(rc);
+            }
+        };
+
+        let final_mix = MixInfoIn {
+            volume: 1.0,
+            sample_rate: config.sample_rate,
+            buffer_count: config.channel_count as u32,
+            is_used: true,
+            mix_id: FINAL_MIX_ID,
+            node_id: make_node_id(NodeType::Mix, FINAL_MIX_ID, 0),
+            dest_mix_id: UNUSED_MIX_ID,
+            dest_splitter_id: UNUSED_SPLITTER_ID,
+            ..Default::default()
+        };
+
+        let mut device_sink = DeviceSinkInfoIn {
+            name: get_main_device_sink_name(),
+            input_count: config.channel_count as u32,
+            ..Default::default()
+        };
+        for (i, input) in device_sink
+            .inputs
+            .iter_mut()
+            .take(config.channel_count as usize)
+            .enumerate()
+        {
+            *input = i as u8;
+        }
+        let sink = SinkInfoIn {
+            sink_type: SinkType::Device,
+            is_used: true,
+            node_id: make_node_id(NodeType::Sink, 0, 0),
+            device_sink,
+            ..Default::default()
+        };
+
+        let memory_pool_count = parameter.get_memory_pool_count() as usize;
+        let mut memory_pools = Vec::with_capacity(memory_pool_count);
+        memory_pools.resize_with(memory_pool_count, || None);
+        let mut voices = Vec::with_capacity(config.voice_count);
+        voices.resize_with(config.voice_count, || None);
+
+        let mut backend = Self {
+            renderer,
+            event,
+            work_buffer,
+            work_buffer_handle,
+            revision,
+            sample_rate: config.sample_rate,
+            channel_count: config.channel_count,
+            memory_pools_in: vec![MemoryPoolInfoIn::default(); memory_pool_count],
+            memory_pools,
+            channel_resources_in: (0..config.voice_count)
+                .map(|i| VoiceChannelResourceIn {
+                    id: i as u32,
+                    ..Default::default()
+                })
+                .collect(),
+            voices_in: (0..config.voice_count)
+                .map(|i| VoiceInfoIn {
+                    id: i as u32,
+                    ..Default::default()
+                })
+                .collect(),
+            voices,
+            mixes_in: vec![final_mix; parameter.get_mix_count() as usize],
+            sinks_in: vec![sink],
+            output_buffer: vec![0; get_output_size(memory_pool_count, config.voice_count, 0, 1)],
+        };
+
+        // Send the initial state before starting, so that the final mix and the sink are ready
+        backend.update()?;
+        backend.renderer.start()?;
+        Ok(backend)
+    }
+
+    #[inline]
+    pub fn get_sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+
+    #[inline]
+    pub fn get_channel_count(&self) -> u16 {
+        self.channel_count
+    }
+
+    fn get_voice_mut(&mut self, voice_id: usize) -> Result<&mut Voice> {
+        self.voices
+            .get_mut(voice_id)
+            .and_then(Option::as_mut)
+            .ok_or(rc::ResultInvalidVoice::make())
+    }
+
+    fn acquire_memory_pool(&mut self, sound: &Sound) -> Result<usize> {
+        // Voices playing the same sound share its memory pool while it's not being detached
+        let existing_index = self.memory_pools.iter().enumerate().position(|(i, pool)| {
+            pool.as_ref().is_some_and(|pool| {
+                pool.sound.is_same(sound)
+                    && (self.memory_pools_in[i].state != MemoryPoolState::RequestDetach)
+            })
+        });
+        if let Some(index) = existing_index {
+            if let Some(pool) = self.memory_pools[index].as_mut() {
+                pool.voice_ref_count += 1;
+            }
+            return Ok(index);
+        }
+
+        let index = self
+            .memory_pools
+            .iter()
+            .position(Option::is_none)
+            .ok_or(rc::ResultNoFreeMemoryPools::make())?;
+
+        let samples = sound.get_samples();
+        let data_size = core::mem::size_of_val(samples);
+        let pool_size = data_size.next_multiple_of(MEMORY_POOL_ALIGNMENT);
+        let buffer = alloc::Buffer::<u8>::new(MEMORY_POOL_ALIGNMENT, pool_size)?;
+        unsafe {
+            core::ptr::copy_nonoverlapping(samples.as_ptr().cast::<u8>(), buffer.ptr, data_size);
+        }
+        crate::arm::cache_flush(buffer.ptr, pool_size);
+
+        self.memory_pools_in[index] = MemoryPoolInfoIn {
+            address: buffer.ptr as u64,
+            size: pool_size as u64,
+            state: MemoryPoolState::RequestAttach,
+            reserved: [0; 3],
+        };
+        self.memory_pools[index] = Some(MemoryPool {
+            _buffer: buffer,
+            sound: sound.clone(),
+            voice_ref_count: 1,
+        });
+        Ok(index)
+    }
+
+    pub fn play(&mut self, sound: &Sound, params: VoiceParams) -> Result<usize> {
+        let channel_count = sound.get_channel_count() as usize;
+        result_return_unless!(
+            channel_count <= MAX_VOICE_CHANNEL_COUNT,
+            rc::ResultInvalidChannelCount
+        );
+        result_return_if!(sound.get_frame_count() == 0, rc::ResultInvalidSound);
+
+        let voice_id = self
+            .voices
+            .iter()
+            .position(Option::is_none)
+            .ok_or(rc::ResultNoFreeVoices::make())?;
+        let channel_resource_ids: Vec<usize> = self
+            .channel_resources_in
+            .iter()
+            .enumerate()
+            .filter(|(_, resource)| !resource.is_used)
+            .map(|(i, _)| i)
+            .take(channel_count)
+            .collect();
+        result_return_unless!(
+            channel_resource_ids.len() == channel_count,
+            rc::ResultNoFreeVoices
+        );
+
+        let pool_index = self.acquire_memory_pool(sound)?;
+        let pool_address = self.memory_pools_in[pool_index].address;
+
+        // Output channels take the sound channels cyclically (like the software mixer does)
+        for (channel, &resource_id) in channel_resource_ids.iter().enumerate() {
+            let resource = &mut self.channel_resources_in[resource_id];
+            resource.is_used = true;
+            resource.mix_volumes = [0.0; MAX_MIX_BUFFER_COUNT];
+            for out_channel in (channel..self.channel_count as usize).step_by(channel_count) {
+                resource.mix_volumes[out_channel] = 1.0;
+            }
+        }
+
+        let voice_in = &mut self.voices_in[voice_id];
+        *voice_in = VoiceInfoIn {
+            id: voice_id as u32,
+            node_id: make_node_id(NodeType::Voice, voice_id as u32, 0),
+            is_new: true,
+            is_used: true,
+            play_state: VoicePlayState::Started,
+            sample_format: PcmFormat::Int16 as u8,
+            sample_rate: sound.get_sample_rate(),
+            channel_count: channel_count as u32,
+            wave_buffer_count: 1,
+            dest_mix_id: FINAL_MIX_ID,
+            dest_splitter_id: UNUSED_SPLITTER_ID,
+            ..Default::default()
+        };
+        voice_in.wave_buffers[0] = WaveBuffer {
+            address: pool_address,
+            size: core::mem::size_of_val(sound.get_samples()) as u64,
+            start_sample_offset: 0,
+            end_sample_offset: sound.get_frame_count() as i32,
+            ..Default::default()
+        };
+        for (channel, &resource_id) in channel_resource_ids.iter().enumerate() {
+            voice_in.channel_resource_ids[channel] = resource_id as u32;
+        }
+
+        self.voices[voice_id] = Some(Voice {
+            pool_index,
+            channel_resource_ids,
+            params,
+        });
+        self.apply_params(voice_id);
+        Ok(voice_id)
+    }
+
+    fn apply_params(&mut self, voice_id: usize) {
+        if let Some(voice) = self.voices[voice_id].as_ref() {
+            let voice_in = &mut self.voices_in[voice_id];
+            voice_in.volume = voice.params.volume;
+            voice_in.pitch = voice.params.pitch;
+            voice_in.wave_buffers[0].is_looping = voice.params.looping;
+            voice_in.wave_buffers[0].end_of_stream = !voice.params.looping;
+        }
+    }
+
+    fn free_voice(&mut self, voice_id: usize) {
+        if let Some(voice) = self.voices[voice_id].take() {
+            for resource_id in voice.channel_resource_ids {
+                self.channel_resources_in[resource_id].is_used = false;
+            }
+            if let Some(pool) = self.memory_pools[voice.pool_index].as_mut() {
+                pool.voice_ref_count -= 1;
+            }
+            self.voices_in[voice_id].is_used = false;
+        }
+    }
+
+    pub fn stop(&mut self, voice_id: usize) -> Result<()> {
+        self.get_voice_mut(voice_id)?;
+        self.free_voice(voice_id);
+        Ok(())
+    }
+
+    pub fn set_paused(&mut self, voice_id: usize, paused: bool) -> Result<()> {
+        self.get_voice_mut(voice_id)?;
+        self.voices_in[voice_id].play_state = match paused {
+            true => VoicePlayState::Paused,
+            false => VoicePlayState::Started,
+        };
+        Ok(())
+    }
+
+    pub fn get_params(&mut self, voice_id: usize) -> Result<VoiceParams> {
+        Ok(self.get_voice_mut(voice_id)?.params)
+    }
+
+    pub fn set_params(&mut self, voice_id: usize, params: VoiceParams) -> Result<()> {
+        self.get_voice_mut(voice_id)?.params = params;
+        self.apply_params(voice_id);
+        Ok(())
+    }
+
+    pub fn is_active(&self, voice_id: usize) -> bool {
+        matches!(self.voices.get(voice_id), Some(Some(_)))
+    }
+
+    pub fn update(&mut self) -> Result<()> {
+        let input = UpdateInput {
+            revision: self.revision,
+            behavior_flags: 0,
+            memory_pools: &self.memory_pools_in,
+            voice_channel_resources: &self.channel_resources_in,
+            voices: &self.voices_in,
+            effects: &[],
+            mixes: &self.mixes_in,
+            sinks: &self.sinks_in,
+            performance_mode: 0,
+        }
+        .to_vec();
+        // Performance metrics are disabled, thus there's no performance data to receive
+        let mut performance_buffer: [u8; 0] = [];
+        self.renderer.request_update(
+            sf::Buffer::from_array(&input),
+            sf::Buffer::from_mut_array(&mut self.output_buffer),
+            sf::Buffer::from_mut_array(&mut performance_buffer),
+        )?;
+        let output = UpdateOutput::read(&self.output_buffer)?;
+
+        for voice_in in self.voices_in.iter_mut() {
+            voice_in.is_new = false;
+            voice_in.wave_buffers[0].sent_to_server = voice_in.is_used;
+        }
+
+        for (voice_id, voice_out) in output.voices.iter().enumerate() {
+            let finished = match self.voices.get(voice_id) {
+                Some(Some(voice)) => {
+                    !voice.params.looping && (voice_out.consumed_wave_buffer_count > 0)
+                }
+                _ => false,
+            };
+            if finished {
+                self.free_voice(voice_id);
+            }
+        }
+
+        for (pool_index, pool_out) in output.memory_pools.iter().enumerate() {
+            if pool_out.new_state == MemoryPoolState::Attached as u32 {
+                self.memory_pools_in[pool_index].state = MemoryPoolState::Attached;
+            } else if pool_out.new_state == MemoryPoolState::Detached as u32 {
+                // The renderer no longer accesses the pool, thus its memory can be freed
+                self.memory_pools_in[pool_index] = MemoryPoolInfoIn::default();
+                self.memory_pools[pool_index] = None;
+            }
+        }
+
+        // Unused pools are detached on the next update, once the renderer got their voices freed
+        for (pool_index, pool) in self.memory_pools.iter().enumerate() {
+            if pool.as_ref().is_some_and(|pool| pool.voice_ref_count == 0)
+                && (self.memory_pools_in[pool_index].state == MemoryPoolState::Attached)
+            {
+                self.memory_pools_in[pool_index].state = MemoryPoolState::RequestDetach;
+            }
+        }
+
+        Ok(())
+    }
+
+    #[inline]
+    pub fn wait(&self, timeout: Timeout) -> Result<()> {
+        self.event.wait(timeout)
+    }
+}
+
+impl Drop for HardwareBackend {
+    /// Stops and closes the renderer, since the work buffer and the memory pools must not be freed while still in use
+    fn drop(&mut self) {
+        let _ = self.renderer.stop();
+        self.renderer.get_session_mut().close();
+        release_work_buffer(&self.work_buffer, self.work_buffer_handle);
+    }
+}
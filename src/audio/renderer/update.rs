@@ -0,0 +1,409 @@
+//! Renderer update data serialization
+//!
+//! Update data is exchanged with the renderer via [`IAudioRendererClient::request_update`][`crate::ipc::sf::audren::IAudioRendererClient::request_update`]: the input data contains the state of every renderer object, and the output data contains their updated state
+//!
+//! This has no dependencies on the audio services, thus it can be used (and tested) outside of the console
+
+use crate::audio::rc;
+use crate::ipc::sf::audren::*;
+use crate::result::*;
+use alloc::vec::Vec;
+use core::mem;
+use core::ptr;
+
+#[inline]
+fn get_section_size<T>(items: &[T]) -> u32 {
+    mem::size_of_val(items) as u32
+}
+
+fn write_section<T: Copy>(buf: &mut [u8], offset: &mut usize, items: &[T]) {
+    let size = mem::size_of_val(items);
+    // SAFETY: the caller ensures the buffer is big enough, and all the update types are plain data without implicit padding
+    unsafe {
+        ptr::copy_nonoverlapping(
+            items.as_ptr().cast::<u8>(),
+            buf[*offset..*offset + size].as_mut_ptr(),
+            size,
+        );
+    }
+    *offset += size;
+}
+
+fn read_section<T: Copy>(buf: &[u8], offset: &mut usize, size: u32) -> Result<Vec<T>> {
+    let size = size as usize;
+    result_return_unless!(
+        size.is_multiple_of(mem::size_of::<T>()),
+        rc::ResultInvalidUpdateData
+    );
+    result_return_unless!((*offset + size) <= buf.len(), rc::ResultInvalidUpdateData);
+
+    let items = (0..size / mem::size_of::<T>())
+        .map(|i| {
+            // SAFETY: the range is checked above, and all output update types are valid for any bit pattern
+            unsafe {
+                ptr::read_unaligned(
+                    buf.as_ptr()
+                        .add(*offset + i * mem::size_of::<T>())
+                        .cast::<T>(),
+                )
+            }
+        })
+        .collect();
+    *offset += size;
+    Ok(items)
+}
+
+fn read_single<T: Copy + Default>(buf: &[u8], offset: &mut usize, size: u32) -> Result<T> {
+    match size {
+        // Some sections may be absent depending on the revision
+        0 => Ok(T::default()),
+        _ => {
+            result_return_unless!(
+                size as usize == mem::size_of::<T>(),
+                rc::ResultInvalidUpdateData
+            );
+            Ok(read_section::<T>(buf, offset, size)?[0])
+        }
+    }
+}
+
+/// Represents the input update data sent to the renderer
+pub struct UpdateInput<'a> {
+    /// The revision magic (see [`make_revision_magic`])
+    pub revision: u32,
+    pub behavior_flags: u64,
+    pub memory_pools: &'a [MemoryPoolInfoIn],
+    pub voice_channel_resources: &'a [VoiceChannelResourceIn],
+    pub voices: &'a [VoiceInfoIn],
+    pub effects: &'a [EffectInfoIn],
+    /// The final mix must be the first one
+    pub mixes: &'a [MixInfoIn],
+    pub sinks: &'a [SinkInfoIn],
+    pub performance_mode: u32,
+}
+
+impl UpdateInput<'_> {
+    fn make_header(&self) -> UpdateDataHeader {
+        let mut header = UpdateDataHeader {
+            revision: self.revision,
+            behavior_size: mem::size_of::<BehaviorInfoIn>() as u32,
+            memory_pools_size: get_section_size(self.memory_pools),
+            voices_size: get_section_size(self.voices),
+            voice_channel_resources_size: get_section_size(self.voice_channel_resources),
+            effects_size: get_section_size(self.effects),
+            mixes_size: get_section_size(self.mixes),
+            sinks_size: get_section_size(self.sinks),
+            performance_size: mem::size_of::<PerformanceInfoIn>() as u32,
+            ..Default::default()
+        };
+        header.total_size = mem::size_of::<UpdateDataHeader>() as u32
+            + header.behavior_size
+            + header.memory_pools_size
+            + header.voices_size
+            + header.voice_channel_resources_size
+            + header.effects_size
+            + header.mixes_size
+            + header.sinks_size
+            + header.performance_size;
+        header
+    }
+
+    /// Gets the size of the serialized input data
+    #[inline]
+    pub fn get_size(&self) -> usize {
+        self.make_header().total_size as usize
+    }
+
+    /// Serializes the input data into the given buffer, returning the serialized size
+    ///
+    /// # Arguments
+    ///
+    /// * `buf`: The buffer to serialize into, which must be at least [`get_size`][`UpdateInput::get_size`] bytes long
+    pub fn write(&self, buf: &mut [u8]) -> Result<usize> {
+        let header = self.make_header();
+        result_return_unless!(
+            buf.len() >= header.total_size as usize,
+            rc::ResultUpdateBufferTooSmall
+        );
+
+        let behavior = BehaviorInfoIn {
+            revision: self.revision,
+            pad: 0,
+            flags: self.behavior_flags,
+        };
+        let performance = PerformanceInfoIn {
+            mode: self.performance_mode,
+            reserved: [0; 3],
+        };
+
+        let mut offset = 0;
+        write_section(buf, &mut offset, &[header]);
+        write_section(buf, &mut offset, &[behavior]);
+        write_section(buf, &mut offset, self.memory_pools);
+        write_section(buf, &mut offset, self.voice_channel_resources);
+        write_section(buf, &mut offset, self.voices);
+        write_section(buf, &mut offset, self.effects);
+        write_section(buf, &mut offset, self.mixes);
+        write_section(buf, &mut offset, self.sinks);
+        write_section(buf, &mut offset, &[performance]);
+        Ok(offset)
+    }
+
+    /// Serializes the input data into a new buffer
+    pub fn to_vec(&self) -> Vec<u8> {
+        let mut buf = vec![0u8; self.get_size()];
+        // The buffer is created with the required size, thus this can't fail
+        let _ = self.write(&mut buf);
+        buf
+    }
+}
+
+/// Gets the size required for the output update data buffer
+///
+/// # Arguments
+///
+/// * `memory_pool_count`: The memory pool count
+/// * `voice_count`: The voice count
+/// * `effect_count`: The effect count
+/// * `sink_count`: The sink count
+pub const fn get_output_size(
+    memory_pool_count: usize,
+    voice_count: usize,
+    effect_count: usize,
+    sink_count: usize,
+) -> usize {
+    mem::size_of::<UpdateDataHeader>()
+        + memory_pool_count * mem::size_of::<MemoryPoolInfoOut>()
+        + voice_count * mem::size_of::<VoiceInfoOut>()
+        + effect_count * mem::size_of::<EffectInfoOut>()
+        + sink_count * mem::size_of::<SinkInfoOut>()
+        + mem::size_of::<PerformanceInfoOut>()
+        + mem::size_of::<BehaviorInfoOut>()
+        + mem::size_of::<RenderInfoOut>()
+}
+
+/// Represents the output update data received from the renderer
+#[derive(Clone, Debug, Default)]
+pub struct UpdateOutput {
+    pub revision: u32,
+    pub memory_pools: Vec<MemoryPoolInfoOut>,
+    pub voices: Vec<VoiceInfoOut>,
+    pub effects: Vec<EffectInfoOut>,
+    pub sinks: Vec<SinkInfoOut>,
+    pub performance: PerformanceInfoOut,
+    pub behavior: BehaviorInfoOut,
+    pub render_info: RenderInfoOut,
+}
+
+impl UpdateOutput {
+    /// Deserializes the output data from the given buffer
+    ///
+    /// # Arguments
+    ///
+    /// * `buf`: The buffer to deserialize from
+    pub fn read(buf: &[u8]) -> Result<Self> {
+        let mut offset = 0;
+        let header: UpdateDataHeader =
+            read_single(buf, &mut offset, mem::size_of::<UpdateDataHeader>() as u32)?;
+        result_return_unless!(
+            (header.total_size as usize) <= buf.len(),
+            rc::ResultInvalidUpdateData
+        );
+        let buf = &buf[..header.total_size as usize];
+
+        Ok(Self {
+            revision: header.revision,
+            memory_pools: read_section(buf, &mut offset, header.memory_pools_size)?,
+            voices: read_section(buf, &mut offset, header.voices_size)?,
+            effects: read_section(buf, &mut offset, header.effects_size)?,
+            sinks: read_section(buf, &mut offset, header.sinks_size)?,
+            performance: read_single(buf, &mut offset, header.performance_size)?,
+            behavior: read_single(buf, &mut offset, header.behavior_size)?,
+            render_info: read_single(buf, &mut offset, header.render_info_size)?,
+        })
+    }
+
+    /// Serializes the output data into a new buffer, as the renderer would do
+    ///
+    /// This is mostly useful to emulate the renderer (like for testing)
+    pub fn to_vec(&self) -> Vec<u8> {
+        let mut header = UpdateDataHeader {
+            revision: self.revision,
+            behavior_size: mem::size_of::<BehaviorInfoOut>() as u32,
+            memory_pools_size: get_section_size(&self.memory_pools),
+            voices_size: get_section_size(&self.voices),
+            effects_size: get_section_size(&self.effects),
+            sinks_size: get_section_size(&self.sinks),
+            performance_size: mem::size_of::<PerformanceInfoOut>() as u32,
+            render_info_size: mem::size_of::<RenderInfoOut>() as u32,
+            ..Default::default()
+        };
+        header.total_size = get_output_size(
+            self.memory_pools.len(),
+            self.voices.len(),
+            self.effects.len(),
+            self.sinks.len(),
+        ) as u32;
+
+        let mut buf = vec![0u8; header.total_size as usize];
+        let mut offset = 0;
+        write_section(&mut buf, &mut offset, &[header]);
+        write_section(&mut buf, &mut offset, &self.memory_pools);
+        write_section(&mut buf, &mut offset, &self.voices);
+        write_section(&mut buf, &mut offset, &self.effects);
+        write_section(&mut buf, &mut offset, &self.sinks);
+        write_section(&mut buf, &mut offset, &[self.performance]);
+        write_section(&mut buf, &mut offset, &[self.behavior]);
+        write_section(&mut buf, &mut offset, &[self.render_info]);
+        buf
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn read_u32(buf: &[u8], offset: usize) -> u32 {
+        u32::from_le_bytes(buf[offset..offset + 4].try_into().unwrap())
+    }
+
+    fn make_output() -> UpdateOutput {
+        UpdateOutput {
+            revision: make_revision_magic(5),
+            memory_pools: vec![MemoryPoolInfoOut {
+                new_state: MemoryPoolState::Attached as u32,
+                reserved: [0; 3],
+            }],
+            voices: vec![
+                VoiceInfoOut {
+                    played_sample_count: 0x0012_3456_789A,
+                    consumed_wave_buffer_count: 2,
+                    dropped_voice_count: 0,
+                },
+                VoiceInfoOut::default(),
+            ],
+            effects: Vec::new(),
+            sinks: vec![SinkInfoOut {
+                last_written_offset: 0x800,
+                ..Default::default()
+            }],
+            performance: PerformanceInfoOut::default(),
+            behavior: BehaviorInfoOut {
+                error_count: 1,
+                ..Default::default()
+            },
+            render_info: RenderInfoOut {
+                elapsed_frame_count: 42,
+                reserved: 0,
+            },
+        }
+    }
+
+    fn assert_same_output(a: &UpdateOutput, b: &UpdateOutput) {
+        assert_eq!(a.revision, b.revision);
+        assert_eq!(a.memory_pools, b.memory_pools);
+        assert_eq!(a.voices, b.voices);
+        assert_eq!(a.effects, b.effects);
+        assert_eq!(a.sinks, b.sinks);
+        assert_eq!(a.performance, b.performance);
+        assert_eq!(a.behavior, b.behavior);
+        assert_eq!(a.render_info, b.render_info);
+    }
+
+    #[test]
+    fn input_layout() {
+        let memory_pools = [MemoryPoolInfoIn {
+            address: 0x1000,
+            size: 0x2000,
+            state: MemoryPoolState::RequestAttach,
+            reserved: [0; 3],
+        }];
+        let voices = [VoiceInfoIn::default(); 2];
+        let input = UpdateInput {
+            revision: make_revision_magic(5),
+            behavior_flags: 0,
+            memory_pools: &memory_pools,
+            voice_channel_resources: &[],
+            voices: &voices,
+            effects: &[],
+            mixes: &[],
+            sinks: &[],
+            performance_mode: 0,
+        };
+
+        let expected_size = mem::size_of::<UpdateDataHeader>()
+            + mem::size_of::<BehaviorInfoIn>()
+            + mem::size_of_val(&memory_pools)
+            + mem::size_of_val(&voices)
+            + mem::size_of::<PerformanceInfoIn>();
+        assert_eq!(input.get_size(), expected_size);
+
+        let buf = input.to_vec();
+        assert_eq!(buf.len(), expected_size);
+        assert_eq!(read_u32(&buf, 0x0), make_revision_magic(5));
+        assert_eq!(read_u32(&buf, 0x3C) as usize, expected_size);
+        // The behavior section repeats the revision, and the memory pools follow it
+        assert_eq!(read_u32(&buf, 0x40), make_revision_magic(5));
+        assert_eq!(read_u32(&buf, 0x50), 0x1000);
+        assert_eq!(read_u32(&buf, 0x58), 0x2000);
+
+        let mut small_buf = vec![0u8; expected_size - 1];
+        assert!(input.write(&mut small_buf).is_err());
+        let mut big_buf = vec![0u8; expected_size + 0x10];
+        assert_eq!(input.write(&mut big_buf).unwrap(), expected_size);
+    }
+
+    #[test]
+    fn output_round_trip() {
+        let output = make_output();
+        let buf = output.to_vec();
+        assert_eq!(buf.len(), get_output_size(1, 2, 0, 1));
+        assert_same_output(&UpdateOutput::read(&buf).unwrap(), &output);
+
+        // Trailing data past the total size is ignored
+        let mut padded_buf = buf.clone();
+        padded_buf.extend_from_slice(&[0xFF; 0x20]);
+        assert_same_output(&UpdateOutput::read(&padded_buf).unwrap(), &output);
+    }
+
+    #[test]
+    fn output_absent_sections() {
+        let output = make_output();
+        let mut buf = output.to_vec();
+
+        // Older revisions don't send the render info
+        let render_info_size = mem::size_of::<RenderInfoOut>();
+        let total_size = buf.len() - render_info_size;
+        buf[0x28..0x2C].copy_from_slice(&0u32.to_le_bytes());
+        buf[0x3C..0x40].copy_from_slice(&(total_size as u32).to_le_bytes());
+        buf.truncate(total_size);
+
+        let read_output = UpdateOutput::read(&buf).unwrap();
+        assert_eq!(read_output.render_info, RenderInfoOut::default());
+        assert_eq!(read_output.voices, output.voices);
+    }
+
+    #[test]
+    fn output_invalid() {
+        let buf = make_output().to_vec();
+
+        // Truncated header and sections
+        assert!(UpdateOutput::read(&buf[..0x20]).is_err());
+        assert!(UpdateOutput::read(&buf[..buf.len() - 1]).is_err());
+
+        // Section sizes which aren't a multiple of their item size
+        let mut bad_voices_size = buf.clone();
+        bad_voices_size[0xC..0x10].copy_from_slice(&0x18u32.to_le_bytes());
+        assert!(UpdateOutput::read(&bad_voices_size).is_err());
+
+        // Sections past the total size
+        let mut bad_sinks_size = buf.clone();
+        bad_sinks_size[0x1C..0x20].copy_from_slice(&0x1000u32.to_le_bytes());
+        assert!(UpdateOutput::read(&bad_sinks_size).is_err());
+
+        // Single-item sections of the wrong size
+        let mut bad_performance_size = buf.clone();
+        bad_performance_size[0x20..0x24].copy_from_slice(&0x20u32.to_le_bytes());
+        assert!(UpdateOutput::read(&bad_performance_size).is_err());
+    }
+}
@@ -12,6 +12,7 @@ pub mod vty {
 
     use crate::gpu::canvas::{AlphaBlend, CanvasManager, RGBA8, sealed::CanvasColorFormat};
     use crate::result::Result;
+    use crate::time::Timeout;
 
     use embedded_graphics_core::Pixel;
     pub use embedded_graphics_core::draw_target::DrawTarget;
@@ -113,7 +114,7 @@ pub mod vty {
 
             self.canvas.render_prepared_buffer(self.buffer.as_ref())?;
 
-            self.canvas.wait_vsync_event(None)
+            self.canvas.wait_vsync_event(Timeout::Infinite)
         }
 
         fn fill_contiguous<I>(&mut self, area: &Rectangle, colors: I) -> Result<()>
@@ -144,7 +145,7 @@ pub mod vty {
             }
             self.canvas.render_prepared_buffer(self.buffer.as_ref())?;
 
-            self.canvas.wait_vsync_event(None)
+            self.canvas.wait_vsync_event(Timeout::Infinite)
         }
 
         fn fill_solid(&mut self, area: &Rectangle, color: Self::Color) -> Result<()> {
@@ -159,7 +160,7 @@ pub mod vty {
             }
             self.canvas.render_prepared_buffer(self.buffer.as_ref())?;
 
-            self.canvas.wait_vsync_event(None)
+            self.canvas.wait_vsync_event(Timeout::Infinite)
         }
     }
 }
@@ -184,6 +185,7 @@ pub mod scrollback {
 
     use crate::sync::Mutex;
     use crate::thread::{Builder, JoinHandle};
+    use crate::time::Timeout;
     use alloc::{
         collections::vec_deque::VecDeque,
         string::{String, ToString},
@@ -234,7 +236,7 @@ pub mod scrollback {
                             }
                             console.draw()?;
 
-                            console.wait_vsync_event(None)?;
+                            console.wait_vsync_event(Timeout::Infinite)?;
                         }
                         Ok(())
                     })?
@@ -453,7 +455,7 @@ pub mod scrollback {
 
         /// Wait for a vsync event to ensure that the previously submitted frame has been fully rendered to the display.
         #[inline(always)]
-        pub fn wait_vsync_event(&self, timeout: Option<i64>) -> Result<()> {
+        pub fn wait_vsync_event(&self, timeout: impl Into<Timeout>) -> Result<()> {
             self.canvas.wait_vsync_event(timeout)
         }
     }
@@ -5,3 +5,5 @@ pub mod rc;
 pub mod abort;
 
 pub mod log;
+
+pub mod backtrace;
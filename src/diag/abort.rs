@@ -4,6 +4,13 @@ use crate::mem::alloc;
 use crate::result::*;
 use crate::rrt0;
 use crate::svc;
+use core::fmt;
+
+#[cfg(feature = "services")]
+use crate::diag::backtrace;
+
+#[cfg(feature = "services")]
+use core::sync::atomic::Ordering;
 
 #[cfg(feature = "services")]
 use crate::ipc::sf;
@@ -54,7 +61,70 @@ impl AbortLevel {
     }
 }
 
-fn do_abort(level: AbortLevel, rc: ResultCode) {
+/// Represents extra information to abort with, besides the [`ResultCode`]
+#[derive(Copy, Clone, Default)]
+struct AbortInfo<'a> {
+    message: Option<&'a dyn fmt::Display>,
+    #[cfg(feature = "services")]
+    cpu_context: Option<&'a fatal::FatalCpuContext>,
+}
+
+#[cfg(feature = "services")]
+fn make_current_cpu_context() -> fatal::FatalCpuContext {
+    let mut context = fatal::FatalAarch64Context::new();
+    context.start_address = rrt0::MODULE_BASE_ADDRESS.load(Ordering::Relaxed) as u64;
+    context.set_backtrace(
+        backtrace::capture()
+            .get_frames()
+            .iter()
+            .map(|frame| frame.address as u64),
+    );
+    fatal::FatalCpuContext::from_aarch64(context, 0)
+}
+
+/// Formats a message into a fixed-size, NUL-terminated buffer, truncating it if needed
+///
+/// No heap allocations are involved, since aborting may happen precisely because they aren't available
+struct MessageBuffer {
+    buf: [u8; Self::SIZE],
+    len: usize,
+}
+
+impl MessageBuffer {
+    const SIZE: usize = 0x400;
+
+    const fn new() -> Self {
+        Self {
+            buf: [0; Self::SIZE],
+            len: 0,
+        }
+    }
+
+    fn as_c_str(&self) -> &core::ffi::CStr {
+        // The last byte is never written, thus there's always a NUL terminator
+        core::ffi::CStr::from_bytes_until_nul(&self.buf).unwrap_or_default()
+    }
+}
+
+impl fmt::Write for MessageBuffer {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        let available = Self::SIZE - 1 - self.len;
+        let len = s.len().min(available);
+        self.buf[self.len..self.len + len].copy_from_slice(&s.as_bytes()[..len]);
+        self.len += len;
+        Ok(())
+    }
+}
+
+fn log_message(rc: ResultCode, message: &dyn fmt::Display) {
+    use core::fmt::Write;
+
+    let mut buf = MessageBuffer::new();
+    let _ = writeln!(buf, "Aborting with result {rc}: {message}");
+    let _ = unsafe { svc::output_debug_string(buf.as_c_str()) };
+}
+
+fn do_abort(level: AbortLevel, rc: ResultCode, info: AbortInfo<'_>) {
     if level.contains(AbortLevel::NeedsHeapAllocation()) && !alloc::is_enabled() {
         // Prevent abort methods which will allocate from running if we cannot allocate, to avoid infinite alloc-error recursions
         return;
@@ -65,15 +135,33 @@ fn do_abort(level: AbortLevel, rc: ResultCode) {
         {
             use crate::service::fatal::{FatalService, IFatalClient};
             if let Ok(fatal) = service::new_service_object::<FatalService>() {
-                let _ = fatal.throw_fatal_with_policy(
-                    rc,
-                    fatal::FatalPolicy::ErrorScreen,
-                    sf::ProcessId::new(),
-                );
+                // Without a context (like the one of an exception), at least the current backtrace is shown
+                let cpu_context = match info.cpu_context {
+                    Some(cpu_context) => *cpu_context,
+                    None => make_current_cpu_context(),
+                };
+                if fatal
+                    .throw_fatal_with_cpu_context(
+                        rc,
+                        fatal::FatalPolicy::ErrorReportAndErrorScreen,
+                        sf::ProcessId::new(),
+                        sf::InMapAliasBuffer::from_var(&cpu_context),
+                    )
+                    .is_err()
+                {
+                    let _ = fatal.throw_fatal_with_policy(
+                        rc,
+                        fatal::FatalPolicy::ErrorReportAndErrorScreen,
+                        sf::ProcessId::new(),
+                    );
+                }
             }
         }
     } else if level == AbortLevel::Panic() {
-        panic!("{rc:?}");
+        match info.message {
+            Some(message) => panic!("{message}"),
+            None => panic!("Aborted with result {rc}"),
+        }
     } else if level == AbortLevel::ProcessExit() {
         rrt0::exit(rc);
     } else if level == AbortLevel::SvcBreak() {
@@ -89,6 +177,31 @@ fn do_abort(level: AbortLevel, rc: ResultCode) {
     // return so we can try the next level.
 }
 
+fn abort_impl(desired_level: AbortLevel, rc: ResultCode, info: AbortInfo<'_>) -> ! {
+    let mut current_level = desired_level;
+    let mut message_logged = false;
+
+    loop {
+        // Only panics show the message, thus it's logged before any other level (which would otherwise lose it)
+        if let Some(message) = info.message
+            && !message_logged
+            && current_level != AbortLevel::Panic()
+        {
+            log_message(rc, message);
+            message_logged = true;
+        }
+
+        do_abort(current_level, rc, info);
+
+        if let Some(next_level) = current_level.get_next_level() {
+            current_level = next_level;
+        } else {
+            // This should never happen, since the last level is guaranteed to work
+            unreachable!();
+        }
+    }
+}
+
 /// Attempts to abort at the specified [`AbortLevel`]
 ///
 /// Note that a certain [`AbortLevel`] may not work/be available (heap allocation is not available and that level requires allocations, etc.)
@@ -104,16 +217,54 @@ fn do_abort(level: AbortLevel, rc: ResultCode) {
 /// * `desired_level`: Desired [`AbortLevel`]
 /// * `rc`: [`ResultCode`] to abort with
 pub fn abort(desired_level: AbortLevel, rc: ResultCode) -> ! {
-    let mut current_level = desired_level;
+    abort_impl(desired_level, rc, AbortInfo::default())
+}
 
-    loop {
-        do_abort(current_level, rc);
+/// Same as [`abort`], but showing a custom message instead of the [`ResultCode`] when aborting via panic
+///
+/// When aborting via any other level, the message is logged through [`output_debug_string`][`svc::output_debug_string`] beforehand
+///
+/// This is what allows [`ContextError`][`crate::result::context::ContextError`]s to show their context chain when aborting
+///
+/// # Arguments
+///
+/// * `desired_level`: Desired [`AbortLevel`]
+/// * `rc`: [`ResultCode`] to abort with
+/// * `message`: The message to panic with
+pub fn abort_with_message(
+    desired_level: AbortLevel,
+    rc: ResultCode,
+    message: &dyn fmt::Display,
+) -> ! {
+    abort_impl(
+        desired_level,
+        rc,
+        AbortInfo {
+            message: Some(message),
+            ..Default::default()
+        },
+    )
+}
 
-        if let Some(next_level) = current_level.get_next_level() {
-            current_level = next_level;
-        } else {
-            // This should never happen, since the last level is guaranteed to work
-            unreachable!();
-        }
-    }
+/// Same as [`abort`], but sending the given CPU context when aborting via fatal (instead of just the current backtrace), thus showing it in the fatal screen and report
+///
+/// # Arguments
+///
+/// * `desired_level`: Desired [`AbortLevel`]
+/// * `rc`: [`ResultCode`] to abort with
+/// * `cpu_context`: The CPU context
+#[cfg(feature = "services")]
+pub fn abort_with_cpu_context(
+    desired_level: AbortLevel,
+    rc: ResultCode,
+    cpu_context: &fatal::FatalCpuContext,
+) -> ! {
+    abort_impl(
+        desired_level,
+        rc,
+        AbortInfo {
+            cpu_context: Some(cpu_context),
+            ..Default::default()
+        },
+    )
 }
@@ -0,0 +1,221 @@
+//! Backtrace support
+//!
+//! Backtraces are captured by unwinding through the module's `.eh_frame_hdr` (see [`capture`]), and frames are symbolized through the module's dynamic symbol table (`.dynsym` and `.dynstr`)
+//!
+//! Note that only symbols exported to the dynamic symbol table can be resolved, any other frame is shown by its offset inside the module
+
+use crate::arm;
+use crate::elf;
+use crate::rrt0;
+use crate::svc;
+use alloc::vec::Vec;
+use core::ffi::c_void;
+use core::fmt;
+use core::sync::atomic::Ordering;
+use unwinding::abi::{_Unwind_Backtrace, _Unwind_GetIP, UnwindContext, UnwindReasonCode};
+
+/// The maximum number of frames captured
+pub const MAX_FRAME_COUNT: usize = 0x40;
+
+/// Represents a resolved symbol
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct Symbol {
+    /// The (possibly mangled) symbol name
+    pub name: &'static str,
+    /// The offset inside the symbol
+    pub offset: usize,
+}
+
+impl fmt::Display for Symbol {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}+0x{:X}", self.name, self.offset)
+    }
+}
+
+/// Represents a backtrace frame
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct Frame {
+    /// The frame address
+    pub address: usize,
+    /// The offset of the address from the start of the module, if it's inside this process's module
+    pub module_offset: Option<usize>,
+    /// The symbol the address belongs to, if it could be resolved
+    pub symbol: Option<Symbol>,
+}
+
+impl Frame {
+    /// Creates a new [`Frame`], symbolizing the address
+    ///
+    /// # Arguments
+    ///
+    /// * `address`: The frame address
+    /// * `is_return_address`: Whether the address is a return address, which is looked up right before it (since the call might be the last instruction of a function)
+    pub fn new(address: usize, is_return_address: bool) -> Self {
+        let lookup_address = match is_return_address {
+            true => address.saturating_sub(1),
+            false => address,
+        };
+        let module_offset = get_module_offset(lookup_address);
+        let symbol = module_offset.and_then(find_symbol).map(|mut symbol| {
+            // Show the offset of the actual address, not the looked up one
+            symbol.offset += address - lookup_address;
+            symbol
+        });
+
+        Self {
+            address,
+            module_offset: module_offset.map(|offset| offset + (address - lookup_address)),
+            symbol,
+        }
+    }
+}
+
+fn get_mapping_base_address(address: usize) -> Option<usize> {
+    svc::query_memory(core::ptr::with_exposed_provenance(address))
+        .ok()
+        .map(|(info, _)| info.base_address)
+}
+
+/// Gets the offset of an address from the start of this process's module, if it's inside its code
+///
+/// # Arguments
+///
+/// * `address`: The address
+pub fn get_module_offset(address: usize) -> Option<usize> {
+    match rrt0::MODULE_BASE_ADDRESS.load(Ordering::Relaxed) {
+        0 => None,
+        base_address => match get_mapping_base_address(address) == Some(base_address) {
+            true => Some(address - base_address),
+            false => None,
+        },
+    }
+}
+
+fn get_symbol_table() -> Option<elf::DynamicSymbolTable> {
+    match rrt0::MODULE_BASE_ADDRESS.load(Ordering::Relaxed) {
+        0 => None,
+        base_address => {
+            let base_address: *mut u8 = core::ptr::with_exposed_provenance_mut(base_address);
+            let mod0 = elf::mod0::Header::from_text_start_addr(base_address);
+            unsafe { elf::DynamicSymbolTable::from_dyn(base_address, mod0.get_dyn_start()) }
+        }
+    }
+}
+
+/// Finds the symbol containing an offset of this process's module
+///
+/// # Arguments
+///
+/// * `module_offset`: The offset from the start of the module
+pub fn find_symbol(module_offset: usize) -> Option<Symbol> {
+    let symbol_table = get_symbol_table()?;
+    let (symbol, offset) = symbol_table.find_function_symbol(module_offset)?;
+    Some(Symbol {
+        name: symbol_table.get_symbol_name(symbol)?,
+        offset,
+    })
+}
+
+/// Represents a captured backtrace, from the innermost frame to the outermost one
+#[derive(Clone, PartialEq, Eq, Debug, Default)]
+pub struct Backtrace {
+    frames: Vec<Frame>,
+}
+
+impl Backtrace {
+    /// Gets the frames
+    #[inline]
+    pub fn get_frames(&self) -> &[Frame] {
+        &self.frames
+    }
+}
+
+impl fmt::Display for Backtrace {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, frame) in self.frames.iter().enumerate() {
+            write!(f, "  #{:<2} 0x{:016X}", i, frame.address)?;
+            match (&frame.symbol, frame.module_offset) {
+                (Some(symbol), _) => writeln!(f, " in {}", symbol)?,
+                (None, Some(module_offset)) => writeln!(f, " in <module>+0x{:X}", module_offset)?,
+                (None, None) => writeln!(f)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+extern "C" fn trace_frame(ctx: &UnwindContext<'_>, arg: *mut c_void) -> UnwindReasonCode {
+    let addresses = unsafe { &mut *(arg as *mut Vec<usize>) };
+    match _Unwind_GetIP(ctx) {
+        0 => UnwindReasonCode::END_OF_STACK,
+        address => {
+            addresses.push(address);
+            match addresses.len() < MAX_FRAME_COUNT {
+                true => UnwindReasonCode::NO_REASON,
+                false => UnwindReasonCode::END_OF_STACK,
+            }
+        }
+    }
+}
+
+/// Captures a backtrace of the current thread, starting at the caller of this function
+///
+/// Frames are unwound through the module's `.eh_frame_hdr`, thus this works regardless of frame pointers
+#[inline(never)]
+pub fn capture() -> Backtrace {
+    let mut addresses: Vec<usize> = Vec::with_capacity(MAX_FRAME_COUNT);
+    _Unwind_Backtrace(
+        trace_frame,
+        &mut addresses as *mut Vec<usize> as *mut c_void,
+    );
+
+    Backtrace {
+        // The first frame is this function itself
+        frames: addresses
+            .into_iter()
+            .skip(1)
+            .map(|address| Frame::new(address, true))
+            .collect(),
+    }
+}
+
+fn is_readable(address: usize) -> bool {
+    svc::query_memory(core::ptr::with_exposed_provenance(address))
+        .is_ok_and(|(info, _)| info.permission.contains(svc::MemoryPermission::Read()))
+}
+
+/// Captures a backtrace from a CPU context (like the one of an exception), starting at its PC
+///
+/// Since the context doesn't belong to the current execution, frames are walked through the frame pointer chain instead of being unwound, thus frames of code built without frame pointers will be missing
+///
+/// # Arguments
+///
+/// * `context`: The CPU context
+pub fn capture_from_context(context: &arm::ThreadContext) -> Backtrace {
+    let mut frames = vec![Frame::new(context.pc.get_x() as usize, false)];
+
+    let mut fp = context.fp as usize;
+    while frames.len() < MAX_FRAME_COUNT && fp != 0 && fp.is_multiple_of(8) {
+        // Each frame record holds the previous frame pointer and the return address, and both may lie in different mappings
+        let Some(return_address_ptr) = fp.checked_add(size_of::<usize>()) else {
+            break;
+        };
+        if !is_readable(fp) || !is_readable(return_address_ptr) {
+            break;
+        }
+        let record: *const usize = core::ptr::with_exposed_provenance(fp);
+        let (prev_fp, return_address) = unsafe { (*record, *record.add(1)) };
+        if return_address == 0 {
+            break;
+        }
+        frames.push(Frame::new(return_address, true));
+
+        // The stack grows downwards, thus anything else means the chain is broken
+        if prev_fp <= fp {
+            break;
+        }
+        fp = prev_fp;
+    }
+
+    Backtrace { frames }
+}
@@ -20,6 +20,8 @@ pub struct LogMetadata {
     pub fn_name: &'static str,
     /// The source line number
     pub line_number: u32,
+    /// The source module path (empty if unknown), used for filtering (see [`registry`])
+    pub module_path: &'static str,
 }
 
 impl LogMetadata {
@@ -49,8 +51,20 @@ impl LogMetadata {
             file_name,
             fn_name,
             line_number,
+            module_path: "",
         }
     }
+
+    /// Sets the source module path, returning the modified metadata
+    ///
+    /// # Arguments
+    ///
+    /// * `module_path`: The source module path (like the one `module_path!()` expands to)
+    #[inline]
+    pub const fn with_module_path(mut self, module_path: &'static str) -> Self {
+        self.module_path = module_path;
+        self
+    }
 }
 
 /// Represents a logging object
@@ -77,7 +91,7 @@ pub fn log_with<L: Logger>(metadata: &LogMetadata) {
     logger.log(metadata);
 }
 
-fn format_plain_string_log_impl(metadata: &LogMetadata, log_type: &str) -> CString {
+fn format_plain_log_impl(metadata: &LogMetadata, log_type: &str) -> String {
     let severity_str = match metadata.severity {
         LogSeverity::Trace => "Trace",
         LogSeverity::Info => "Info",
@@ -94,24 +108,25 @@ fn format_plain_string_log_impl(metadata: &LogMetadata, log_type: &str) -> CStri
             .unwrap_or("<unknown>")
     };
 
-    // SAFETY - This is find as we are only writing ascii string generated in this function, an the thread_name with is read through
-    // a conversion from a CStr, and a log message that has been escaped of null bytes.
-    unsafe {
-        CString::from_vec_unchecked(
-            format!(
-                "[ {} (severity: {}, verbosity: {}) from {} in thread {}, at {}:{} ] {}\0",
-                log_type,
-                severity_str,
-                metadata.verbosity,
-                metadata.fn_name,
-                thread_name,
-                metadata.file_name,
-                metadata.line_number,
-                metadata.msg.replace('\0', "\\0")
-            )
-            .into_bytes(),
-        )
-    }
+    format!(
+        "[ {} (severity: {}, verbosity: {}) from {} in thread {}, at {}:{} ] {}",
+        log_type,
+        severity_str,
+        metadata.verbosity,
+        metadata.fn_name,
+        thread_name,
+        metadata.file_name,
+        metadata.line_number,
+        metadata.msg
+    )
+}
+
+fn format_plain_string_log_impl(metadata: &LogMetadata, log_type: &str) -> CString {
+    let mut msg = format_plain_log_impl(metadata, log_type).replace('\0', "\\0");
+    msg.push('\0');
+
+    // SAFETY - This is fine as we are only writing a string which has been escaped of null bytes (other than the terminating one)
+    unsafe { CString::from_vec_unchecked(msg.into_bytes()) }
 }
 
 pub mod svc;
@@ -121,3 +136,19 @@ pub mod fs;
 
 #[cfg(feature = "services")]
 pub mod lm;
+
+#[cfg(feature = "nxlink")]
+pub mod nxlink;
+
+pub mod packet;
+
+pub mod registry;
+
+#[cfg(feature = "socket")]
+pub mod network;
+
+#[cfg(feature = "fs")]
+pub mod file;
+
+#[cfg(feature = "log")]
+pub mod bridge;
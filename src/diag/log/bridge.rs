@@ -0,0 +1,65 @@
+//! Bridge between the [`log`] crate facade and the logging registry
+//!
+//! Once [`initialize`] is called, the logs of any crate using the [`log`] macros (like `log::info!`) are dispatched to the [`registry`][`super::registry`] sinks, along with the ones logged through [`RegistryLogger`][`super::registry::RegistryLogger`]
+
+use super::registry;
+use super::*;
+use crate::result::*;
+use alloc::string::ToString;
+
+/// Converts a [`log`] level to a severity and verbosity, since there's no severity below [`LogSeverity::Trace`]
+const fn convert_level(level: ::log::Level) -> (LogSeverity, bool) {
+    match level {
+        ::log::Level::Error => (LogSeverity::Error, false),
+        ::log::Level::Warn => (LogSeverity::Warn, false),
+        ::log::Level::Info => (LogSeverity::Info, false),
+        ::log::Level::Debug => (LogSeverity::Trace, false),
+        ::log::Level::Trace => (LogSeverity::Trace, true),
+    }
+}
+
+struct RegistryBridge;
+
+impl ::log::Log for RegistryBridge {
+    fn enabled(&self, metadata: &::log::Metadata) -> bool {
+        let (severity, _) = convert_level(metadata.level());
+        registry::is_enabled(metadata.target(), &severity)
+    }
+
+    fn log(&self, record: &::log::Record) {
+        let (severity, verbosity) = convert_level(record.level());
+        // The target is usually the module path, but it might also be a custom one
+        if !registry::is_enabled(record.target(), &severity) {
+            return;
+        }
+
+        let module_path = record.module_path_static().unwrap_or("");
+        let metadata = LogMetadata::new(
+            severity,
+            verbosity,
+            record.args().to_string(),
+            record.file_static().unwrap_or("<unknown>"),
+            record.module_path_static().unwrap_or("<unknown>"),
+            record.line().unwrap_or(0),
+        )
+        .with_module_path(module_path);
+        registry::log(&metadata);
+    }
+
+    fn flush(&self) {
+        registry::flush();
+    }
+}
+
+static G_REGISTRY_BRIDGE: RegistryBridge = RegistryBridge;
+
+/// Sets the registry as the [`log`] crate's logger
+///
+/// This fails if the [`log`] crate already has a logger set
+pub fn initialize() -> Result<()> {
+    ::log::set_logger(&G_REGISTRY_BRIDGE)
+        .map_err(|_| crate::diag::rc::ResultLoggerAlreadySet::make())?;
+    // Filtering is done by the registry itself
+    ::log::set_max_level(::log::LevelFilter::Trace);
+    Ok(())
+}
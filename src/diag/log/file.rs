@@ -0,0 +1,96 @@
+//! Rotating file log sink implementation
+
+use super::registry::LogSink;
+use super::*;
+use crate::fs::{self, FileAccessor, FileOpenOption};
+use crate::result::*;
+
+/// Represents a sink writing logs to a file (like on the SD card), as plain text lines
+///
+/// Once the file would exceed its maximum size it's rotated: it's renamed to `<path>.1` (renaming the previous `<path>.1` to `<path>.2` and so on, removing the oldest one), and a new file is started
+///
+/// Write errors are ignored, since there's nowhere to report them
+pub struct RotatingFileSink {
+    path: String,
+    max_size: usize,
+    backup_count: usize,
+    file: Option<FileAccessor>,
+    size: usize,
+}
+
+impl RotatingFileSink {
+    /// Creates a new [`RotatingFileSink`], appending to the file if it already exists
+    ///
+    /// # Arguments
+    ///
+    /// * `path`: The file path (see [`fs::format_path`])
+    /// * `max_size`: The maximum size of each file
+    /// * `backup_count`: The number of rotated files to keep, where `0` means the file is just truncated when full
+    pub fn new(path: &str, max_size: usize, backup_count: usize) -> Result<Self> {
+        let mut file = Self::open_file(path)?;
+        let size = file.get_size()?;
+        Ok(Self {
+            path: String::from(path),
+            max_size,
+            backup_count,
+            file: Some(file),
+            size,
+        })
+    }
+
+    fn open_file(path: &str) -> Result<FileAccessor> {
+        fs::open_file(
+            path,
+            FileOpenOption::Create() | FileOpenOption::Write() | FileOpenOption::Append(),
+        )
+    }
+
+    #[inline]
+    fn get_backup_path(&self, index: usize) -> String {
+        format!("{}.{}", self.path, index)
+    }
+
+    fn rotate(&mut self) -> Result<()> {
+        // The file needs to be closed before renaming it
+        self.file = None;
+
+        match self.backup_count {
+            0 => fs::remove_file(&self.path)?,
+            backup_count => {
+                let _ = fs::remove_file(&self.get_backup_path(backup_count));
+                for index in (1..backup_count).rev() {
+                    let _ = fs::rename_file(
+                        &self.get_backup_path(index),
+                        &self.get_backup_path(index + 1),
+                    );
+                }
+                fs::rename_file(&self.path, &self.get_backup_path(1))?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl LogSink for RotatingFileSink {
+    fn write(&mut self, metadata: &LogMetadata) {
+        let mut line = format_plain_log_impl(metadata, "FileLog");
+        line.push('\n');
+
+        if (self.size > 0) && (self.size + line.len() > self.max_size) {
+            let _ = self.rotate();
+        }
+        // The file is reopened after rotating (or after failing to, in which case logs are appended to it)
+        if self.file.is_none() {
+            self.file = Self::open_file(&self.path).ok();
+            self.size = match self.file.as_mut() {
+                Some(file) => file.get_size().unwrap_or(0),
+                None => 0,
+            };
+        }
+        if let Some(file) = self.file.as_mut()
+            && file.write_array(line.as_bytes()).is_ok()
+        {
+            self.size += line.len();
+        }
+    }
+}
@@ -1,13 +1,16 @@
-//! `LogManager` logger implementation
+//! `LogManager` logger and log reader implementations
 
 use alloc::string::ToString;
 
 use super::*;
 use crate::ipc::sf;
+use crate::result::*;
 use crate::rrt0;
 use crate::service;
-use crate::service::lm::{self, ILoggerClient, ILoggingClient, LoggingService};
+use crate::service::lm::{self, ILogGetterClient, ILoggerClient, ILoggingClient, LoggingService};
 use crate::svc;
+use alloc::vec::Vec;
+use packet::{LogPacket, LogRecord, LogRecordDecoder};
 
 /// Represents a logger through [`LogService`][`lm::LoggingService`] services
 pub struct LmLogger {
@@ -61,3 +64,60 @@ impl Logger for LmLogger {
         }
     }
 }
+
+/// The size of the buffer packets are read into, which is the maximum size of a packet
+const LOG_BUFFER_SIZE: usize = 0x1000;
+
+/// Represents a reader of the system logs (of every process logging through `lm`), through the [`LogGetterService`][`lm::LogGetterService`]
+///
+/// Logging to the reader is started on creation and stopped when it's dropped
+pub struct LmLogReader {
+    getter: lm::LogGetterService,
+    buffer: Vec<u8>,
+    decoder: LogRecordDecoder,
+    drop_count: u64,
+}
+
+impl LmLogReader {
+    /// Creates a new [`LmLogReader`]
+    pub fn new() -> Result<Self> {
+        let mut getter = service::new_service_object::<lm::LogGetterService>()?;
+        getter.start_logging()?;
+        Ok(Self {
+            getter,
+            buffer: vec![0u8; LOG_BUFFER_SIZE],
+            decoder: LogRecordDecoder::new(),
+            drop_count: 0,
+        })
+    }
+
+    /// Reads the next log, if any is available
+    ///
+    /// If logs were dropped (due to not reading them fast enough), their count is added to the next log's drop count
+    pub fn read(&mut self) -> Result<Option<LogRecord>> {
+        loop {
+            let (log_size, drop_count) = self
+                .getter
+                .get_log(sf::Buffer::from_mut_array(&mut self.buffer))?;
+            self.drop_count += drop_count as u64;
+            if log_size == 0 {
+                return Ok(None);
+            }
+
+            let (packet, _) = LogPacket::parse(&self.buffer[..log_size.min(self.buffer.len())])?;
+            if let Some(mut record) = self.decoder.push_packet(&packet)? {
+                if self.drop_count > 0 {
+                    record.drop_count = Some(record.drop_count.unwrap_or(0) + self.drop_count);
+                    self.drop_count = 0;
+                }
+                return Ok(Some(record));
+            }
+        }
+    }
+}
+
+impl Drop for LmLogReader {
+    fn drop(&mut self) {
+        let _ = self.getter.stop_logging();
+    }
+}
@@ -0,0 +1,71 @@
+//! Network log sink implementation
+
+use super::registry::LogSink;
+use super::*;
+use crate::result::*;
+use crate::socket::net::traits::SocketCommon;
+use crate::socket::net::{TcpStream, ToSocketAddrs, UdpSocket};
+use core::net::Ipv4Addr;
+
+enum Transport {
+    Udp(UdpSocket),
+    Tcp(TcpStream),
+}
+
+/// Represents a sink sending logs over the network, as plain text lines
+///
+/// UDP sinks send each log as a single datagram (thus logs might be lost or reordered), while TCP sinks send them through a stream (like to a `nc -l` listener)
+///
+/// Send errors are ignored, since there's nowhere to report them
+pub struct NetworkSink {
+    transport: Transport,
+}
+
+impl NetworkSink {
+    /// Creates a new [`NetworkSink`] sending logs as UDP datagrams
+    ///
+    /// # Arguments
+    ///
+    /// * `destination`: The destination address (see [`ToSocketAddrs`])
+    pub fn new_udp<A: ToSocketAddrs>(destination: A) -> Result<Self> {
+        let socket = UdpSocket::bind((Ipv4Addr::UNSPECIFIED, 0))?;
+        socket.connect(destination)?;
+        Ok(Self {
+            transport: Transport::Udp(socket),
+        })
+    }
+
+    /// Creates a new [`NetworkSink`] sending logs through a TCP connection
+    ///
+    /// # Arguments
+    ///
+    /// * `destination`: The address to connect to (see [`ToSocketAddrs`])
+    pub fn new_tcp<A: ToSocketAddrs>(destination: A) -> Result<Self> {
+        Ok(Self {
+            transport: Transport::Tcp(TcpStream::connect(destination)?),
+        })
+    }
+}
+
+impl LogSink for NetworkSink {
+    fn write(&mut self, metadata: &LogMetadata) {
+        let mut line = format_plain_log_impl(metadata, "NetworkLog");
+        line.push('\n');
+
+        match &self.transport {
+            Transport::Udp(socket) => {
+                let _ = socket.send(line.as_bytes());
+            }
+            Transport::Tcp(stream) => {
+                let mut data = line.as_bytes();
+                while !data.is_empty() {
+                    match stream.send(data) {
+                        // Nothing sent means the connection can't take more data, which would loop forever
+                        Ok(0) | Err(_) => break,
+                        Ok(sent_size) => data = &data[(sent_size as usize).min(data.len())..],
+                    }
+                }
+            }
+        }
+    }
+}
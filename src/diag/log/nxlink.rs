@@ -0,0 +1,20 @@
+//! nxlink logger implementation
+
+use super::*;
+use crate::nxlink;
+
+/// Represents a logger through the nxlink host connection (see [`nxlink::initialize`])
+///
+/// Logs are discarded if the connection isn't initialized
+pub struct NxlinkLogger;
+
+impl Logger for NxlinkLogger {
+    fn new() -> Self {
+        Self {}
+    }
+
+    fn log(&mut self, metadata: &LogMetadata) {
+        let msg = format_plain_string_log_impl(metadata, "NxlinkLog");
+        let _ = nxlink::write(msg.as_bytes()).and_then(|_| nxlink::write(b"\n"));
+    }
+}
@@ -0,0 +1,628 @@
+//! Decoding of the binary log packets used by `lm` (like the ones read through [`LogGetterService`][`crate::service::lm::LogGetterService`])
+//!
+//! Each packet consists of a header followed by a payload of data chunks, each of them being a key and a size (both ULEB128-encoded) followed by its data. Long logs are split in several packets, the first one being flagged as the head and the last one as the tail
+//!
+//! Nothing here depends on system services, thus it can be used (and tested) on any platform
+
+use super::*;
+use crate::diag::rc;
+use crate::result::*;
+use alloc::vec::Vec;
+
+/// The size of a packet header
+pub const LOG_PACKET_HEADER_SIZE: usize = 0x18;
+
+define_bit_set! {
+    /// Represents the flags of a packet
+    LogPacketFlags (u8) {
+        /// The packet is the first one of a log
+        Head = bit!(0),
+        /// The packet is the last one of a log
+        Tail = bit!(1),
+        /// The packet's multi-byte values are little-endian
+        LittleEndian = bit!(2)
+    }
+}
+
+/// Represents the header of a packet
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Default)]
+pub struct LogPacketHeader {
+    pub process_id: u64,
+    pub thread_id: u64,
+    pub flags: LogPacketFlags,
+    /// The raw severity value (see [`LogRecord::get_severity`])
+    pub severity: u8,
+    pub verbosity: bool,
+    pub payload_size: u32,
+}
+
+impl LogPacketHeader {
+    /// Parses a packet header
+    ///
+    /// # Arguments
+    ///
+    /// * `data`: The header data, which must be at least [`LOG_PACKET_HEADER_SIZE`] bytes long
+    pub fn parse(data: &[u8]) -> Result<Self> {
+        result_return_if!(
+            data.len() < LOG_PACKET_HEADER_SIZE,
+            rc::ResultInvalidLogPacket
+        );
+
+        let flags = LogPacketFlags::from(data[0x10]);
+        // Byte 0x11 is padding
+        let mut header = Self {
+            process_id: 0,
+            thread_id: 0,
+            flags,
+            severity: data[0x12],
+            verbosity: data[0x13] != 0,
+            payload_size: 0,
+        };
+        let process_id: [u8; 8] = data[0x0..0x8].try_into().unwrap();
+        let thread_id: [u8; 8] = data[0x8..0x10].try_into().unwrap();
+        let payload_size: [u8; 4] = data[0x14..0x18].try_into().unwrap();
+        match flags.contains(LogPacketFlags::LittleEndian()) {
+            true => {
+                header.process_id = u64::from_le_bytes(process_id);
+                header.thread_id = u64::from_le_bytes(thread_id);
+                header.payload_size = u32::from_le_bytes(payload_size);
+            }
+            false => {
+                header.process_id = u64::from_be_bytes(process_id);
+                header.thread_id = u64::from_be_bytes(thread_id);
+                header.payload_size = u32::from_be_bytes(payload_size);
+            }
+        }
+        Ok(header)
+    }
+
+    /// Gets whether the packet is the first one of a log
+    #[inline]
+    pub const fn is_head(&self) -> bool {
+        self.flags.contains(LogPacketFlags::Head())
+    }
+
+    /// Gets whether the packet is the last one of a log
+    #[inline]
+    pub const fn is_tail(&self) -> bool {
+        self.flags.contains(LogPacketFlags::Tail())
+    }
+}
+
+/// Represents the keys of the data chunks
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum LogDataChunkKey {
+    LogSessionBegin,
+    LogSessionEnd,
+    TextLog,
+    LineNumber,
+    FileName,
+    FunctionName,
+    ModuleName,
+    ThreadName,
+    LogPacketDropCount,
+    UserSystemClock,
+    ProcessName,
+    Other(u64),
+}
+
+impl LogDataChunkKey {
+    /// Gets the [`LogDataChunkKey`] with the given value
+    ///
+    /// # Arguments
+    ///
+    /// * `value`: The key value
+    pub const fn from_value(value: u64) -> Self {
+        match value {
+            0 => Self::LogSessionBegin,
+            1 => Self::LogSessionEnd,
+            2 => Self::TextLog,
+            3 => Self::LineNumber,
+            4 => Self::FileName,
+            5 => Self::FunctionName,
+            6 => Self::ModuleName,
+            7 => Self::ThreadName,
+            8 => Self::LogPacketDropCount,
+            9 => Self::UserSystemClock,
+            10 => Self::ProcessName,
+            _ => Self::Other(value),
+        }
+    }
+}
+
+/// Represents a (parsed) packet, whose payload chunks are decoded on demand
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct LogPacket<'a> {
+    pub header: LogPacketHeader,
+    pub payload: &'a [u8],
+}
+
+fn read_uleb128(data: &[u8], offset: &mut usize) -> Result<u64> {
+    let mut value: u64 = 0;
+    for shift in (0..64).step_by(7) {
+        let byte = *data
+            .get(*offset)
+            .ok_or(rc::ResultInvalidLogPacket::make())?;
+        *offset += 1;
+        // The last byte can only hold the top bit of the value
+        result_return_if!(
+            (shift == 63) && ((byte & 0x7E) != 0),
+            rc::ResultInvalidLogPacket
+        );
+        value |= ((byte & 0x7F) as u64) << shift;
+        if (byte & 0x80) == 0 {
+            return Ok(value);
+        }
+    }
+    rc::ResultInvalidLogPacket::make_err()
+}
+
+impl<'a> LogPacket<'a> {
+    /// Parses a packet, returning it along with the remaining data (since packets can be concatenated)
+    ///
+    /// # Arguments
+    ///
+    /// * `data`: The packet data
+    pub fn parse(data: &'a [u8]) -> Result<(Self, &'a [u8])> {
+        let header = LogPacketHeader::parse(data)?;
+        let payload_end = LOG_PACKET_HEADER_SIZE.saturating_add(header.payload_size as usize);
+        result_return_if!(data.len() < payload_end, rc::ResultInvalidLogPacket);
+
+        let packet = Self {
+            header,
+            payload: &data[LOG_PACKET_HEADER_SIZE..payload_end],
+        };
+        Ok((packet, &data[payload_end..]))
+    }
+
+    /// Decodes the payload chunks, as their keys and data
+    pub fn get_chunks(&self) -> Result<Vec<(LogDataChunkKey, &'a [u8])>> {
+        let mut chunks = Vec::new();
+        let mut offset = 0;
+        while offset < self.payload.len() {
+            let key = LogDataChunkKey::from_value(read_uleb128(self.payload, &mut offset)?);
+            let size = read_uleb128(self.payload, &mut offset)? as usize;
+            let data = self
+                .payload
+                .get(offset..offset.saturating_add(size))
+                .ok_or(rc::ResultInvalidLogPacket::make())?;
+            offset += size;
+            chunks.push((key, data));
+        }
+        Ok(chunks)
+    }
+
+    fn read_integer(&self, data: &[u8]) -> u64 {
+        // Integer chunks might be shorter than 8 bytes (like line numbers, which are 4 bytes long)
+        let mut bytes = [0u8; 8];
+        let size = data.len().min(bytes.len());
+        match self.header.flags.contains(LogPacketFlags::LittleEndian()) {
+            true => {
+                bytes[..size].copy_from_slice(&data[..size]);
+                u64::from_le_bytes(bytes)
+            }
+            false => {
+                bytes[8 - size..].copy_from_slice(&data[..size]);
+                u64::from_be_bytes(bytes)
+            }
+        }
+    }
+}
+
+/// Represents a decoded log, like a [`LogMetadata`] with owned strings and the remaining packet information
+///
+/// Fields whose chunks weren't present in the packets are `None`
+#[derive(Clone, PartialEq, Eq, Debug, Default)]
+pub struct LogRecord {
+    pub process_id: u64,
+    pub thread_id: u64,
+    /// The raw severity value (see [`LogRecord::get_severity`])
+    pub severity: u8,
+    pub verbosity: bool,
+    /// The log message, concatenated from all the packets of the log
+    pub text: String,
+    pub line_number: Option<u32>,
+    pub file_name: Option<String>,
+    pub function_name: Option<String>,
+    pub module_name: Option<String>,
+    pub thread_name: Option<String>,
+    pub process_name: Option<String>,
+    /// The number of packets dropped before this log
+    pub drop_count: Option<u64>,
+    /// The user system clock time (in seconds) of the log
+    pub user_system_clock: Option<u64>,
+    /// Whether the log marks the start of a log session
+    pub session_begin: bool,
+    /// Whether the log marks the end of a log session
+    pub session_end: bool,
+}
+
+impl LogRecord {
+    /// Gets the severity, if the raw value is valid
+    pub const fn get_severity(&self) -> Option<LogSeverity> {
+        match self.severity {
+            0 => Some(LogSeverity::Trace),
+            1 => Some(LogSeverity::Info),
+            2 => Some(LogSeverity::Warn),
+            3 => Some(LogSeverity::Error),
+            4 => Some(LogSeverity::Fatal),
+            _ => None,
+        }
+    }
+}
+
+/// Decodes logs from their packets, which might be split across several of them
+#[derive(Default)]
+pub struct LogRecordDecoder {
+    pending: Option<LogRecord>,
+}
+
+impl LogRecordDecoder {
+    /// Creates a new [`LogRecordDecoder`]
+    #[inline]
+    pub const fn new() -> Self {
+        Self { pending: None }
+    }
+
+    /// Decodes a packet, returning the log once its last packet is decoded
+    ///
+    /// Packets of a log must be pushed in order: a head packet discards any incomplete log being decoded
+    ///
+    /// # Arguments
+    ///
+    /// * `packet`: The packet to decode
+    pub fn push_packet(&mut self, packet: &LogPacket) -> Result<Option<LogRecord>> {
+        let header = &packet.header;
+        if header.is_head() || self.pending.is_none() {
+            self.pending = Some(LogRecord {
+                process_id: header.process_id,
+                thread_id: header.thread_id,
+                severity: header.severity,
+                verbosity: header.verbosity,
+                ..Default::default()
+            });
+        }
+
+        let record = self.pending.as_mut().unwrap();
+        for (key, data) in packet.get_chunks()? {
+            let string = || String::from_utf8_lossy(data).into_owned();
+            match key {
+                LogDataChunkKey::LogSessionBegin => record.session_begin = true,
+                LogDataChunkKey::LogSessionEnd => record.session_end = true,
+                LogDataChunkKey::TextLog => record.text.push_str(&String::from_utf8_lossy(data)),
+                LogDataChunkKey::LineNumber => {
+                    record.line_number = Some(packet.read_integer(data) as u32)
+                }
+                LogDataChunkKey::FileName => record.file_name = Some(string()),
+                LogDataChunkKey::FunctionName => record.function_name = Some(string()),
+                LogDataChunkKey::ModuleName => record.module_name = Some(string()),
+                LogDataChunkKey::ThreadName => record.thread_name = Some(string()),
+                LogDataChunkKey::ProcessName => record.process_name = Some(string()),
+                LogDataChunkKey::LogPacketDropCount => {
+                    record.drop_count = Some(packet.read_integer(data))
+                }
+                LogDataChunkKey::UserSystemClock => {
+                    record.user_system_clock = Some(packet.read_integer(data))
+                }
+                // Unknown chunks are skipped, for forward compatibility
+                LogDataChunkKey::Other(_) => {}
+            }
+        }
+
+        match header.is_tail() {
+            true => Ok(self.pending.take()),
+            false => Ok(None),
+        }
+    }
+
+    /// Decodes all the (concatenated) packets in a buffer, returning the completed logs
+    ///
+    /// # Arguments
+    ///
+    /// * `data`: The packets data
+    pub fn push_data(&mut self, mut data: &[u8]) -> Result<Vec<LogRecord>> {
+        let mut records = Vec::new();
+        while !data.is_empty() {
+            let (packet, rest) = LogPacket::parse(data)?;
+            if let Some(record) = self.push_packet(&packet)? {
+                records.push(record);
+            }
+            data = rest;
+        }
+        Ok(records)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::vec;
+
+    const PROCESS_ID: u64 = 0x0123_4567_89AB_CDEF;
+    const THREAD_ID: u64 = 0x42;
+
+    fn write_uleb128(mut value: u64, out_data: &mut Vec<u8>) {
+        loop {
+            let byte = (value & 0x7F) as u8;
+            value >>= 7;
+            if value == 0 {
+                out_data.push(byte);
+                return;
+            }
+            out_data.push(byte | 0x80);
+        }
+    }
+
+    fn payload(chunks: &[(u64, &[u8])]) -> Vec<u8> {
+        let mut payload = Vec::new();
+        for (key, data) in chunks {
+            write_uleb128(*key, &mut payload);
+            write_uleb128(data.len() as u64, &mut payload);
+            payload.extend_from_slice(data);
+        }
+        payload
+    }
+
+    /// Builds a packet with the given flags (using the endianness they specify) and payload
+    fn packet(flags: LogPacketFlags, payload: &[u8]) -> Vec<u8> {
+        let little_endian = flags.contains(LogPacketFlags::LittleEndian());
+        let mut data = Vec::new();
+        match little_endian {
+            true => {
+                data.extend_from_slice(&PROCESS_ID.to_le_bytes());
+                data.extend_from_slice(&THREAD_ID.to_le_bytes());
+            }
+            false => {
+                data.extend_from_slice(&PROCESS_ID.to_be_bytes());
+                data.extend_from_slice(&THREAD_ID.to_be_bytes());
+            }
+        }
+        data.extend_from_slice(&[flags.get(), 0, 3, 1]);
+        let payload_size = payload.len() as u32;
+        match little_endian {
+            true => data.extend_from_slice(&payload_size.to_le_bytes()),
+            false => data.extend_from_slice(&payload_size.to_be_bytes()),
+        }
+        data.extend_from_slice(payload);
+        data
+    }
+
+    fn head_tail(little_endian: bool) -> LogPacketFlags {
+        let flags = LogPacketFlags::Head() | LogPacketFlags::Tail();
+        match little_endian {
+            true => flags | LogPacketFlags::LittleEndian(),
+            false => flags,
+        }
+    }
+
+    #[test]
+    fn headers() {
+        for little_endian in [false, true] {
+            let data = packet(head_tail(little_endian), &[0xAA; 5]);
+            let header = LogPacketHeader::parse(&data).unwrap();
+            assert_eq!(header.process_id, PROCESS_ID);
+            assert_eq!(header.thread_id, THREAD_ID);
+            assert_eq!(header.severity, 3);
+            assert!(header.verbosity);
+            assert_eq!(header.payload_size, 5);
+            assert!(header.is_head() && header.is_tail());
+
+            let (packet, rest) = LogPacket::parse(&data).unwrap();
+            assert_eq!(packet.header, header);
+            assert_eq!(packet.payload, [0xAA; 5]);
+            assert!(rest.is_empty());
+        }
+
+        let header = LogPacketHeader::parse(&packet(LogPacketFlags::LittleEndian(), &[])).unwrap();
+        assert!(!header.is_head() && !header.is_tail());
+        assert!(LogPacketHeader::parse(&[0; LOG_PACKET_HEADER_SIZE - 1]).is_err());
+    }
+
+    #[test]
+    fn integer_endianness() {
+        for little_endian in [false, true] {
+            let (line_number, drop_count, clock) = match little_endian {
+                true => (
+                    1234u32.to_le_bytes(),
+                    7u64.to_le_bytes(),
+                    0x1_0000_0000u64.to_le_bytes(),
+                ),
+                false => (
+                    1234u32.to_be_bytes(),
+                    7u64.to_be_bytes(),
+                    0x1_0000_0000u64.to_be_bytes(),
+                ),
+            };
+            let data = packet(
+                head_tail(little_endian),
+                &payload(&[(3, &line_number), (8, &drop_count), (9, &clock)]),
+            );
+            let records = LogRecordDecoder::new().push_data(&data).unwrap();
+            assert_eq!(records.len(), 1);
+            assert_eq!(records[0].line_number, Some(1234));
+            assert_eq!(records[0].drop_count, Some(7));
+            assert_eq!(records[0].user_system_clock, Some(0x1_0000_0000));
+            assert!(matches!(
+                records[0].get_severity(),
+                Some(LogSeverity::Error)
+            ));
+        }
+    }
+
+    #[test]
+    fn multi_packet_logs() {
+        for little_endian in [false, true] {
+            let flags = match little_endian {
+                true => LogPacketFlags::LittleEndian(),
+                false => LogPacketFlags::from(0),
+            };
+            let mut data = packet(
+                flags | LogPacketFlags::Head(),
+                &payload(&[(0, &[0]), (6, b"my_module"), (2, b"Hello, ")]),
+            );
+            data.extend(packet(
+                flags,
+                &payload(&[(2, b"wor"), (77, b"??"), (7, b"Main")]),
+            ));
+            data.extend(packet(
+                flags | LogPacketFlags::Tail(),
+                &payload(&[(2, b"ld!"), (4, b"main.rs"), (5, b"main"), (10, b"app")]),
+            ));
+            data.extend(packet(
+                head_tail(little_endian),
+                &payload(&[(1, &[0]), (2, b"bye")]),
+            ));
+
+            let records = LogRecordDecoder::new().push_data(&data).unwrap();
+            assert_eq!(
+                records,
+                [
+                    LogRecord {
+                        process_id: PROCESS_ID,
+                        thread_id: THREAD_ID,
+                        severity: 3,
+                        verbosity: true,
+                        text: String::from("Hello, world!"),
+                        file_name: Some(String::from("main.rs")),
+                        function_name: Some(String::from("main")),
+                        module_name: Some(String::from("my_module")),
+                        thread_name: Some(String::from("Main")),
+                        process_name: Some(String::from("app")),
+                        session_begin: true,
+                        ..Default::default()
+                    },
+                    LogRecord {
+                        process_id: PROCESS_ID,
+                        thread_id: THREAD_ID,
+                        severity: 3,
+                        verbosity: true,
+                        text: String::from("bye"),
+                        session_end: true,
+                        ..Default::default()
+                    },
+                ]
+            );
+        }
+    }
+
+    #[test]
+    fn packet_by_packet_decoding() {
+        let flags = LogPacketFlags::LittleEndian();
+        let head = packet(flags | LogPacketFlags::Head(), &payload(&[(2, b"lost")]));
+        let restart = packet(flags | LogPacketFlags::Head(), &payload(&[(2, b"a")]));
+        let middle = packet(flags, &payload(&[(2, b"b")]));
+        let tail = packet(flags | LogPacketFlags::Tail(), &payload(&[(2, b"c")]));
+
+        let mut decoder = LogRecordDecoder::new();
+        // A head packet discards the incomplete log
+        for data in [&head, &restart, &middle] {
+            let (packet, _) = LogPacket::parse(data).unwrap();
+            assert_eq!(decoder.push_packet(&packet), Ok(None));
+        }
+        let (packet, _) = LogPacket::parse(&tail).unwrap();
+        let record = decoder.push_packet(&packet).unwrap().unwrap();
+        assert_eq!(record.text, "abc");
+
+        // A log whose head was missed still gets decoded from what is available
+        let records = decoder.push_data(&[middle, tail].concat()).unwrap();
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].text, "bc");
+    }
+
+    #[test]
+    fn uleb128() {
+        for value in [0, 1, 0x7F, 0x80, 0x3FFF, 0x4000, u32::MAX as u64, u64::MAX] {
+            let mut data = Vec::new();
+            write_uleb128(value, &mut data);
+            let mut offset = 0;
+            assert_eq!(read_uleb128(&data, &mut offset), Ok(value));
+            assert_eq!(offset, data.len());
+        }
+
+        // Non-minimal encodings are fine
+        let mut offset = 0;
+        assert_eq!(read_uleb128(&[0x81, 0x80, 0x00], &mut offset), Ok(1));
+        assert_eq!(offset, 3);
+
+        // Truncated values
+        for data in [&[][..], &[0x80], &[0xFF, 0xFF]] {
+            let mut offset = 0;
+            assert!(read_uleb128(data, &mut offset).is_err());
+        }
+
+        // Values which don't fit in 64 bits
+        let mut too_long = vec![0x80; 10];
+        too_long.push(0);
+        let mut offset = 0;
+        assert!(read_uleb128(&too_long, &mut offset).is_err());
+        let mut overflowing = vec![0xFF; 9];
+        overflowing.push(0x02);
+        let mut offset = 0;
+        assert!(read_uleb128(&overflowing, &mut offset).is_err());
+    }
+
+    #[test]
+    fn truncated_chunks() {
+        let flags = head_tail(true);
+        for payload in [
+            // The key is cut
+            &[0x82][..],
+            // The size is missing or cut
+            &[0x02],
+            &[0x02, 0x85],
+            // The data is shorter than the size
+            &[0x02, 0x05, b'a', b'b'],
+            &[
+                0x02, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0x01,
+            ],
+        ] {
+            let data = packet(flags, payload);
+            let (packet, _) = LogPacket::parse(&data).unwrap();
+            assert!(packet.get_chunks().is_err(), "{:?}", payload);
+            assert!(LogRecordDecoder::new().push_data(&data).is_err());
+        }
+
+        let data = packet(flags, &payload(&[(2, b"ab"), (0x4000, b"")]));
+        let (packet, _) = LogPacket::parse(&data).unwrap();
+        assert_eq!(
+            packet.get_chunks().unwrap(),
+            [
+                (LogDataChunkKey::TextLog, &b"ab"[..]),
+                (LogDataChunkKey::Other(0x4000), &b""[..])
+            ]
+        );
+    }
+
+    #[test]
+    fn oversized_payloads() {
+        for little_endian in [false, true] {
+            let mut data = packet(head_tail(little_endian), &payload(&[(2, b"text")]));
+            // One more byte than available, and the largest possible size
+            let payload_size = (data.len() - LOG_PACKET_HEADER_SIZE + 1) as u32;
+            for payload_size in [payload_size, u32::MAX] {
+                let size_bytes = match little_endian {
+                    true => payload_size.to_le_bytes(),
+                    false => payload_size.to_be_bytes(),
+                };
+                data[0x14..0x18].copy_from_slice(&size_bytes);
+                assert!(rc::ResultInvalidLogPacket::matches(
+                    LogPacket::parse(&data).unwrap_err()
+                ));
+                assert!(LogRecordDecoder::new().push_data(&data).is_err());
+            }
+        }
+
+        // Concatenated packets are split by their sizes
+        let first = packet(head_tail(false), &payload(&[(2, b"1")]));
+        let second = packet(head_tail(true), &payload(&[(2, b"2")]));
+        let data = [first.as_slice(), &second].concat();
+        let (packet, rest) = LogPacket::parse(&data).unwrap();
+        assert_eq!(packet.header.payload_size, 3);
+        assert_eq!(rest, second);
+        for size in 1..data.len() {
+            assert!(
+                LogRecordDecoder::new().push_data(&data[..size]).is_err() || size == first.len()
+            );
+        }
+    }
+}
@@ -0,0 +1,355 @@
+//! Global logging registry, dispatching logs to multiple sinks
+//!
+//! Unlike [`Logger`]s (which are created for every log, see [`log_with`]), sinks are registered once and receive every log which passes the registry's filters: a minimum severity, optionally overridden per module
+//!
+//! ```no_run
+//! use nx::diag::log::LogSeverity;
+//! use nx::diag::log::registry::{self, RegistryLogger, RingBufferSink};
+//! use nx::diag_log;
+//!
+//! let sink = RingBufferSink::new(0x100);
+//! let buffer = sink.get_buffer();
+//! registry::add_sink(sink);
+//! registry::set_min_severity(LogSeverity::Info);
+//! registry::set_module_filter("my_crate::noisy", None);
+//!
+//! diag_log!(RegistryLogger { LogSeverity::Info, false } => "Hello there!");
+//! assert_eq!(buffer.len(), 1);
+//! ```
+//!
+//! Sinks are called without the registry locked, thus they can use it (like logging themselves). However, logs dispatched from inside a sink are not sent back to that same sink
+
+use super::*;
+use crate::sync::Mutex;
+use alloc::boxed::Box;
+use alloc::collections::VecDeque;
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+
+/// Represents a destination of the logs dispatched by the registry
+pub trait LogSink: Sync {
+    /// Writes a log (which already passed the registry's filters)
+    ///
+    /// # Arguments
+    ///
+    /// * `metadata`: The metadata to log
+    fn write(&mut self, metadata: &LogMetadata);
+
+    /// Flushes any buffered logs
+    fn flush(&mut self) {}
+}
+
+/// Any [`Logger`] can be registered as a sink, logging through the same object every time
+impl<L: Logger + Sync> LogSink for L {
+    fn write(&mut self, metadata: &LogMetadata) {
+        self.log(metadata);
+    }
+}
+
+/// A registered sink, which is called without the registry locked
+type SharedSink = Arc<Mutex<Box<dyn LogSink>>>;
+
+/// Calls a sink, unless the current thread is already inside it (like a sink logging itself), which would deadlock
+fn with_sink(sink: &SharedSink, f: impl FnOnce(&mut dyn LogSink)) {
+    if !sink.is_locked_by_current_thread() {
+        f(&mut **sink.lock());
+    }
+}
+
+/// Identifies a sink registered with [`add_sink`]
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
+pub struct SinkId(usize);
+
+/// Gets the level of a severity, where higher levels are more severe
+const fn get_severity_level(severity: &LogSeverity) -> u8 {
+    match severity {
+        LogSeverity::Trace => 0,
+        LogSeverity::Info => 1,
+        LogSeverity::Warn => 2,
+        LogSeverity::Error => 3,
+        LogSeverity::Fatal => 4,
+    }
+}
+
+const fn get_level_severity(level: u8) -> LogSeverity {
+    match level {
+        0 => LogSeverity::Trace,
+        1 => LogSeverity::Info,
+        2 => LogSeverity::Warn,
+        3 => LogSeverity::Error,
+        _ => LogSeverity::Fatal,
+    }
+}
+
+struct ModuleFilter {
+    module_path: String,
+    min_level: Option<u8>,
+}
+
+impl ModuleFilter {
+    fn matches(&self, module_path: &str) -> bool {
+        module_path
+            .strip_prefix(self.module_path.as_str())
+            .is_some_and(|rest| rest.is_empty() || rest.starts_with("::"))
+    }
+}
+
+struct Registry {
+    min_level: u8,
+    module_filters: Vec<ModuleFilter>,
+    sinks: Vec<(SinkId, SharedSink)>,
+    next_sink_id: usize,
+}
+
+impl Registry {
+    fn is_enabled(&self, module_path: &str, severity: &LogSeverity) -> bool {
+        if self.sinks.is_empty() {
+            return false;
+        }
+
+        // The most specific (longest) matching module filter takes precedence
+        let min_level = self
+            .module_filters
+            .iter()
+            .filter(|filter| filter.matches(module_path))
+            .max_by_key(|filter| filter.module_path.len())
+            .map_or(Some(self.min_level), |filter| filter.min_level);
+        min_level.is_some_and(|min_level| get_severity_level(severity) >= min_level)
+    }
+}
+
+static G_REGISTRY: Mutex<Registry> = Mutex::new(Registry {
+    min_level: 0,
+    module_filters: Vec::new(),
+    sinks: Vec::new(),
+    next_sink_id: 0,
+});
+
+/// Registers a sink, returning its ID
+///
+/// # Arguments
+///
+/// * `sink`: The sink to register
+pub fn add_sink(sink: impl LogSink + 'static) -> SinkId {
+    let mut registry = G_REGISTRY.lock();
+    let id = SinkId(registry.next_sink_id);
+    registry.next_sink_id += 1;
+    let sink: Box<dyn LogSink> = Box::new(sink);
+    registry.sinks.push((id, Arc::new(Mutex::new(sink))));
+    id
+}
+
+/// Unregisters a sink (flushing it), returning whether it was registered
+///
+/// # Arguments
+///
+/// * `id`: The ID of the sink
+pub fn remove_sink(id: SinkId) -> bool {
+    let sink = {
+        let mut registry = G_REGISTRY.lock();
+        match registry
+            .sinks
+            .iter()
+            .position(|(sink_id, _)| *sink_id == id)
+        {
+            Some(index) => registry.sinks.remove(index).1,
+            None => return false,
+        }
+    };
+    with_sink(&sink, |sink| sink.flush());
+    true
+}
+
+/// Unregisters (and flushes) all the sinks
+pub fn clear_sinks() {
+    let sinks = core::mem::take(&mut G_REGISTRY.lock().sinks);
+    for (_, sink) in sinks {
+        with_sink(&sink, |sink| sink.flush());
+    }
+}
+
+/// Sets the minimum severity of the logs to dispatch, for modules without a filter (see [`set_module_filter`])
+///
+/// All logs are dispatched by default
+///
+/// # Arguments
+///
+/// * `min_severity`: The minimum severity
+pub fn set_min_severity(min_severity: LogSeverity) {
+    G_REGISTRY.lock().min_level = get_severity_level(&min_severity);
+}
+
+/// Gets the minimum severity of the logs to dispatch (see [`set_min_severity`])
+pub fn get_min_severity() -> LogSeverity {
+    get_level_severity(G_REGISTRY.lock().min_level)
+}
+
+/// Sets the minimum severity of the logs to dispatch from a module (and its submodules), replacing any previous filter for it
+///
+/// The most specific filter matching a module is the one applied
+///
+/// # Arguments
+///
+/// * `module_path`: The module path (like `my_crate::network`)
+/// * `min_severity`: The minimum severity, or `None` to not dispatch any log from the module
+pub fn set_module_filter(module_path: &str, min_severity: Option<LogSeverity>) {
+    let min_level = min_severity.as_ref().map(get_severity_level);
+    let mut registry = G_REGISTRY.lock();
+    match registry
+        .module_filters
+        .iter_mut()
+        .find(|filter| filter.module_path == module_path)
+    {
+        Some(filter) => filter.min_level = min_level,
+        None => registry.module_filters.push(ModuleFilter {
+            module_path: String::from(module_path),
+            min_level,
+        }),
+    }
+}
+
+/// Removes the filter of a module (see [`set_module_filter`])
+///
+/// # Arguments
+///
+/// * `module_path`: The module path
+pub fn remove_module_filter(module_path: &str) {
+    G_REGISTRY
+        .lock()
+        .module_filters
+        .retain(|filter| filter.module_path != module_path);
+}
+
+/// Gets whether logs with the given module path and severity would be dispatched to any sink
+///
+/// # Arguments
+///
+/// * `module_path`: The source module path
+/// * `severity`: The log severity
+pub fn is_enabled(module_path: &str, severity: &LogSeverity) -> bool {
+    G_REGISTRY.lock().is_enabled(module_path, severity)
+}
+
+/// Dispatches a log to all the sinks, if it passes the filters
+///
+/// # Arguments
+///
+/// * `metadata`: The metadata to log
+pub fn log(metadata: &LogMetadata) {
+    let sinks = {
+        let registry = G_REGISTRY.lock();
+        if !registry.is_enabled(metadata.module_path, &metadata.severity) {
+            return;
+        }
+        get_sinks(&registry)
+    };
+    for sink in sinks.iter() {
+        with_sink(sink, |sink| sink.write(metadata));
+    }
+}
+
+/// Flushes all the sinks
+pub fn flush() {
+    let sinks = get_sinks(&G_REGISTRY.lock());
+    for sink in sinks.iter() {
+        with_sink(sink, |sink| sink.flush());
+    }
+}
+
+/// Gets the registered sinks, so that they can be called once the registry is unlocked
+fn get_sinks(registry: &Registry) -> Vec<SharedSink> {
+    registry
+        .sinks
+        .iter()
+        .map(|(_, sink)| sink.clone())
+        .collect()
+}
+
+/// Represents a logger which dispatches logs through the registry, thus it can be used with [`diag_log`][`crate::diag_log`] and the like
+pub struct RegistryLogger;
+
+impl Logger for RegistryLogger {
+    fn new() -> Self {
+        Self {}
+    }
+
+    fn log(&mut self, metadata: &LogMetadata) {
+        log(metadata);
+    }
+}
+
+/// Represents the shared contents of a [`RingBufferSink`], which can be read while the sink is registered
+#[derive(Clone)]
+pub struct LogBuffer {
+    entries: Arc<Mutex<VecDeque<String>>>,
+}
+
+impl LogBuffer {
+    /// Gets the number of buffered logs
+    pub fn len(&self) -> usize {
+        self.entries.lock().len()
+    }
+
+    /// Gets whether no logs are buffered
+    pub fn is_empty(&self) -> bool {
+        self.entries.lock().is_empty()
+    }
+
+    /// Gets a copy of the buffered logs, from oldest to newest
+    pub fn get_entries(&self) -> Vec<String> {
+        self.entries.lock().iter().cloned().collect()
+    }
+
+    /// Takes the buffered logs (from oldest to newest), leaving the buffer empty
+    pub fn take_entries(&self) -> Vec<String> {
+        self.entries.lock().drain(..).collect()
+    }
+
+    /// Discards the buffered logs
+    pub fn clear(&self) {
+        self.entries.lock().clear();
+    }
+}
+
+/// Represents a sink keeping the latest logs in memory, discarding the oldest ones once full
+pub struct RingBufferSink {
+    buffer: LogBuffer,
+    capacity: usize,
+}
+
+impl RingBufferSink {
+    /// Creates a new, empty [`RingBufferSink`]
+    ///
+    /// # Arguments
+    ///
+    /// * `capacity`: The maximum number of logs to keep
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            buffer: LogBuffer {
+                entries: Arc::new(Mutex::new(VecDeque::with_capacity(capacity))),
+            },
+            capacity,
+        }
+    }
+
+    /// Gets the buffer the logs are kept in
+    #[inline]
+    pub fn get_buffer(&self) -> LogBuffer {
+        self.buffer.clone()
+    }
+}
+
+impl LogSink for RingBufferSink {
+    fn write(&mut self, metadata: &LogMetadata) {
+        if self.capacity == 0 {
+            return;
+        }
+
+        let entry = format_plain_log_impl(metadata, "RingBufferLog");
+        let mut entries = self.buffer.entries.lock();
+        if entries.len() >= self.capacity {
+            entries.pop_front();
+        }
+        entries.push_back(entry);
+    }
+}
@@ -6,5 +6,7 @@ use crate::rc;
 pub const RESULT_SUBMODULE: u32 = 400;
 
 result_define_subgroup!(rc::RESULT_MODULE, RESULT_SUBMODULE => {
-    AssertionFailed: 1
+    AssertionFailed: 1,
+    LoggerAlreadySet: 2,
+    InvalidLogPacket: 3
 });
@@ -24,6 +24,7 @@ pub enum Tag {
     RelaOffset = 7,
     RelaSize = 8,
     RelaEntrySize = 9,
+    StrSize = 10,
     SymEnt = 11,
     RelOffset = 17,
     RelSize = 18,
@@ -34,6 +35,7 @@ pub enum Tag {
     FiniArray = 26,
     InitArraySize = 27,
     FiniArraySize = 28,
+    GnuHash = 0x6FFFFEF5,
     RelaCount = 0x6FFFFFF9,
     RelCount = 0x6FFFFFFA,
 }
@@ -95,6 +97,191 @@ pub struct Rela {
     pub addend: i64,
 }
 
+/// Represents ELF symbol types.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+#[repr(u8)]
+#[allow(missing_docs)]
+pub enum SymbolType {
+    NoType = 0,
+    Object = 1,
+    Function = 2,
+}
+
+/// Represents an ELF symbol.
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Default)]
+#[repr(C)]
+#[allow(missing_docs)]
+pub struct Symbol {
+    pub name_offset: u32,
+    pub info: u8,
+    pub other: u8,
+    pub section_index: u16,
+    pub value: usize,
+    pub size: usize,
+}
+
+impl Symbol {
+    /// Gets whether the symbol has the given type.
+    ///
+    /// # Arguments:
+    ///
+    /// * `symbol_type`: The symbol type.
+    #[inline]
+    pub const fn is_type(&self, symbol_type: SymbolType) -> bool {
+        (self.info & 0xF) == symbol_type as u8
+    }
+
+    /// Gets whether the symbol is defined (instead of being imported from another module).
+    #[inline]
+    pub const fn is_defined(&self) -> bool {
+        self.section_index != 0
+    }
+}
+
+/// Represents the dynamic symbol table (`.dynsym` and `.dynstr`) of a loaded module.
+#[derive(Copy, Clone, Debug)]
+pub struct DynamicSymbolTable {
+    symbols: &'static [Symbol],
+    strings: &'static [u8],
+}
+
+impl DynamicSymbolTable {
+    /// Locates the dynamic symbol table of a module from its [`Dyn`] entries.
+    ///
+    /// The symbol count is taken from the (GNU) hash table, since the dynamic section doesn't hold it.
+    ///
+    /// # Arguments:
+    ///
+    /// * `base_address`: The base address of the module.
+    /// * `start_dyn`: Pointer to the start of the [`Dyn`] list.
+    ///
+    /// # Safety
+    ///
+    /// The caller is responsible for providing valid pointers for `base_address` and `start_dyn`, and the module must stay loaded
+    pub unsafe fn from_dyn(base_address: *const u8, start_dyn: *const Dyn) -> Option<Self> {
+        unsafe {
+            let mut sym_tab_v: Option<usize> = None;
+            let mut sym_entry_size_v: Option<usize> = None;
+            let mut str_tab_v: Option<usize> = None;
+            let mut str_size_v: Option<usize> = None;
+            let mut hash_v: Option<usize> = None;
+            let mut gnu_hash_v: Option<usize> = None;
+
+            // Modules may contain tags which aren't `Tag` variants (reading them as one would be UB), thus the raw values are compared
+            const NULL: i64 = Tag::Null as i64;
+            const SYM_TAB: i64 = Tag::SymTab as i64;
+            const SYM_ENT: i64 = Tag::SymEnt as i64;
+            const STR_TAB: i64 = Tag::StrTab as i64;
+            const STR_SIZE: i64 = Tag::StrSize as i64;
+            const HASH: i64 = Tag::Hash as i64;
+            const GNU_HASH: i64 = Tag::GnuHash as i64;
+
+            let mut cur_dyn = start_dyn;
+            loop {
+                let raw_tag = (&raw const (*cur_dyn).tag).cast::<i64>().read();
+                let value = Some((*cur_dyn).val_ptr);
+                match raw_tag {
+                    NULL => break,
+                    SYM_TAB => sym_tab_v = value,
+                    SYM_ENT => sym_entry_size_v = value,
+                    STR_TAB => str_tab_v = value,
+                    STR_SIZE => str_size_v = value,
+                    HASH => hash_v = value,
+                    GNU_HASH => gnu_hash_v = value,
+                    _ => { /* ignore */ }
+                };
+
+                cur_dyn = cur_dyn.add(1);
+            }
+
+            // Symbols are accessed as a slice, thus their size must be the expected one
+            if sym_entry_size_v.is_some_and(|size| size != core::mem::size_of::<Symbol>()) {
+                return None;
+            }
+
+            let symbol_count = match (hash_v, gnu_hash_v) {
+                // The chain count of the hash table is the symbol count
+                (Some(hash), _) => *(base_address.add(hash) as *const u32).add(1) as usize,
+                (None, Some(gnu_hash)) => {
+                    Self::get_gnu_hash_symbol_count(base_address.add(gnu_hash))
+                }
+                (None, None) => return None,
+            };
+
+            let symbols = core::slice::from_raw_parts(
+                base_address.add(sym_tab_v?) as *const Symbol,
+                symbol_count,
+            );
+            let strings = core::slice::from_raw_parts(base_address.add(str_tab_v?), str_size_v?);
+            Some(Self { symbols, strings })
+        }
+    }
+
+    unsafe fn get_gnu_hash_symbol_count(gnu_hash: *const u8) -> usize {
+        unsafe {
+            let header = gnu_hash as *const u32;
+            let bucket_count = *header as usize;
+            let symbol_offset = *header.add(1) as usize;
+            let bloom_size = *header.add(2) as usize;
+
+            let buckets =
+                gnu_hash.add(4 * 4 + bloom_size * core::mem::size_of::<usize>()) as *const u32;
+            let chains = buckets.add(bucket_count);
+
+            // The symbols not covered by the hash table come first, and the last chain ends with the last symbol
+            let last_bucket_symbol = (0..bucket_count)
+                .map(|i| *buckets.add(i) as usize)
+                .max()
+                .unwrap_or(0);
+            if last_bucket_symbol < symbol_offset {
+                return symbol_offset;
+            }
+
+            let mut symbol = last_bucket_symbol;
+            while (*chains.add(symbol - symbol_offset) & 1) == 0 {
+                symbol += 1;
+            }
+            symbol + 1
+        }
+    }
+
+    /// Gets the symbols.
+    #[inline]
+    pub const fn get_symbols(&self) -> &'static [Symbol] {
+        self.symbols
+    }
+
+    /// Gets the name of a symbol, if it's valid.
+    ///
+    /// # Arguments:
+    ///
+    /// * `symbol`: The symbol.
+    pub fn get_symbol_name(&self, symbol: &Symbol) -> Option<&'static str> {
+        let name = self.strings.get(symbol.name_offset as usize..)?;
+        let name_len = name.iter().position(|&c| c == 0)?;
+        core::str::from_utf8(&name[..name_len]).ok()
+    }
+
+    /// Finds the defined function symbol containing an offset (relative to the module's base address), returning it along with the offset inside it.
+    ///
+    /// Symbols without a size are considered to extend until the next symbol.
+    ///
+    /// # Arguments:
+    ///
+    /// * `offset`: The offset to find.
+    pub fn find_function_symbol(&self, offset: usize) -> Option<(&'static Symbol, usize)> {
+        self.symbols
+            .iter()
+            .filter(|symbol| symbol.is_type(SymbolType::Function) && symbol.is_defined())
+            .filter(|symbol| {
+                symbol.value <= offset && (symbol.size == 0 || offset < symbol.value + symbol.size)
+            })
+            // The closest symbol wins, which also matters for sizeless ones
+            .max_by_key(|symbol| (symbol.value, symbol.size != 0))
+            .map(|symbol| (symbol, offset - symbol.value))
+    }
+}
+
 /// Relocates a base address with its corresponding [`Dyn`] reference.
 ///
 /// # Arguments:
@@ -1,18 +1,382 @@
-//! Exception handling support (stubbed for now).
+//! Exception handling support
+//!
+//! When a user exception (like a data abort) happens, the kernel re-enters the process through `_start` with the exception type and some of the saved registers.
+//! The rest of the CPU context is captured right away (see `exception.s`), and the resulting [`ExceptionInfo`] is dispatched to the handler set with [`set_handler`] (or [`default_handler`] if none was set)
+//!
+//! ```no_run
+//! use nx::exception;
+//!
+//! exception::set_handler(|info| {
+//!     // Log it somewhere, try to recover...
+//!     let _ = info.context.pc;
+//!     exception::default_handler(info)
+//! });
+//! ```
+//!
+//! Note that while a debugger is attached, exceptions are always left to it
 
-use crate::diag::abort::{AbortLevel, abort};
-use crate::result::ResultCode;
+use crate::arm;
+use crate::diag::abort::{self, AbortLevel};
+use crate::diag::backtrace;
+use crate::macros::util::naked_asm;
+use crate::result::*;
+use crate::rrt0;
 use crate::svc;
+use crate::sync::RwLock;
+use alloc::sync::Arc;
+use core::fmt;
+
+#[cfg(feature = "fs")]
+use crate::fs::{self, FileOpenOption};
+
+#[cfg(feature = "services")]
+use crate::service::fatal::{FatalAarch64Context, FatalCpuContext};
+
+#[cfg(feature = "fs")]
+use alloc::string::String;
+
+/// Represents the frame `exception.s` fills with the CPU state at the time of the exception
+#[repr(C)]
+struct ExceptionFrame {
+    context: arm::ThreadContext,
+    far: u64,
+    esr: u32,
+    afsr0: u32,
+    afsr1: u32,
+    /// The exception info the kernel provided, where it restores some of the registers from when returning
+    kernel_info: *mut u8,
+}
+
+// The offsets are hardcoded in `exception.s` and `__nx_exception_return`
+const _: () = assert!(core::mem::offset_of!(arm::ThreadContext, fpu_gprs) == 0x110);
+const _: () = assert!(core::mem::offset_of!(arm::ThreadContext, fpcr) == 0x310);
+const _: () = assert!(core::mem::offset_of!(arm::ThreadContext, tpidr) == 0x318);
+const _: () = assert!(core::mem::offset_of!(ExceptionFrame, far) == 0x320);
+const _: () = assert!(core::mem::offset_of!(ExceptionFrame, kernel_info) == 0x338);
+const _: () = assert!(core::mem::size_of::<ExceptionFrame>() == 0x340);
+
+/// Represents the information of an exception
+#[derive(Copy, Clone)]
+pub struct ExceptionInfo {
+    /// The exception type
+    pub exception_type: svc::ExceptionType,
+    /// The CPU context at the time of the exception
+    pub context: arm::ThreadContext,
+    /// The FAR value (the faulting address, for aborts)
+    pub far: u64,
+    /// The ESR value (the exception syndrome)
+    pub esr: u32,
+    /// The AFSR0 value
+    pub afsr0: u32,
+    /// The AFSR1 value
+    pub afsr1: u32,
+    /// The name of the module the exception happened in, if the PC is inside this process's module
+    pub module_name: Option<rrt0::ModulePath>,
+    /// The offset of the PC from the start of the module, if the PC is inside this process's module
+    pub module_offset: Option<usize>,
+}
+
+impl ExceptionInfo {
+    fn new(exception_type: svc::ExceptionType, frame: &ExceptionFrame) -> Self {
+        let module_offset = backtrace::get_module_offset(frame.context.pc.get_x() as usize);
+        Self {
+            exception_type,
+            context: frame.context,
+            far: frame.far,
+            esr: frame.esr,
+            afsr0: frame.afsr0,
+            afsr1: frame.afsr1,
+            module_name: module_offset.map(|_| rrt0::get_module_name()),
+            module_offset,
+        }
+    }
+
+    /// Captures the backtrace at the time of the exception (see [`backtrace::capture_from_context`])
+    #[inline]
+    pub fn capture_backtrace(&self) -> backtrace::Backtrace {
+        backtrace::capture_from_context(&self.context)
+    }
+
+    /// Creates the CPU context to send to `fatal` (see [`abort_with_cpu_context`][`crate::diag::abort::abort_with_cpu_context`]), with all the registers and the backtrace
+    #[cfg(feature = "services")]
+    pub fn to_fatal_cpu_context(&self) -> FatalCpuContext {
+        let mut context = FatalAarch64Context::new();
+        for (x, reg) in context.x.iter_mut().zip(self.context.gpu_gprs.iter()) {
+            *x = reg.get_x();
+        }
+        context.fp = self.context.fp;
+        context.lr = self.context.lr;
+        context.sp = self.context.sp;
+        context.pc = self.context.pc.get_x();
+        context.pstate = self.context.psr as u64;
+        context.afsr0 = self.afsr0 as u64;
+        context.afsr1 = self.afsr1 as u64;
+        context.esr = self.esr as u64;
+        context.far = self.far;
+        context.register_set_flags = (1 << FatalAarch64Context::REGISTER_COUNT) - 1;
+        context.start_address =
+            rrt0::MODULE_BASE_ADDRESS.load(core::sync::atomic::Ordering::Relaxed) as u64;
+
+        // The PC is already shown on its own, the backtrace is made of the return addresses
+        context.set_backtrace(
+            self.capture_backtrace()
+                .get_frames()
+                .iter()
+                .skip(1)
+                .map(|frame| frame.address as u64),
+        );
+        FatalCpuContext::from_aarch64(context, self.exception_type as u32)
+    }
+}
+
+/// Formats the exception as a human-readable crash report, with all the captured registers and the backtrace
+impl fmt::Display for ExceptionInfo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "Exception: {:?} (0x{:X})",
+            self.exception_type, self.exception_type as u32
+        )?;
+        match (&self.module_name, self.module_offset) {
+            (Some(module_name), Some(module_offset)) => {
+                let name = module_name.get_name();
+                writeln!(
+                    f,
+                    "Module: {} + 0x{:X}",
+                    name.get_str().unwrap_or("<invalid module name>"),
+                    module_offset
+                )?
+            }
+            _ => writeln!(f, "Module: <unknown>")?,
+        }
+        writeln!(
+            f,
+            "FAR: 0x{:016X} ESR: 0x{:08X} AFSR0: 0x{:08X} AFSR1: 0x{:08X}",
+            self.far, self.esr, self.afsr0, self.afsr1
+        )?;
+
+        writeln!(f)?;
+        writeln!(f, "CPU registers:")?;
+        for (i, reg) in self.context.gpu_gprs.iter().enumerate() {
+            writeln!(f, "  X{:<3} 0x{:016X}", i, reg.get_x())?;
+        }
+        writeln!(f, "  FP   0x{:016X}", self.context.fp)?;
+        writeln!(f, "  LR   0x{:016X}", self.context.lr)?;
+        writeln!(f, "  SP   0x{:016X}", self.context.sp)?;
+        writeln!(f, "  PC   0x{:016X}", self.context.pc.get_x())?;
+        writeln!(f, "  PSR  0x{:08X}", self.context.psr)?;
+        writeln!(f, "  TPIDR 0x{:016X}", self.context.tpidr)?;
+
+        writeln!(f)?;
+        writeln!(f, "FPU registers:")?;
+        for (i, reg) in self.context.fpu_gprs.iter().enumerate() {
+            writeln!(f, "  Q{:<3} 0x{:032X}", i, reg.get_v())?;
+        }
+        writeln!(f, "  FPCR 0x{:08X}", self.context.fpcr)?;
+        writeln!(f, "  FPSR 0x{:08X}", self.context.fpsr)?;
+
+        writeln!(f)?;
+        writeln!(f, "Backtrace:")?;
+        write!(f, "{}", self.capture_backtrace())
+    }
+}
+
+/// Represents what to do after an exception was handled
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum ExceptionAction {
+    /// Resumes the execution with the (possibly modified) context of the exception, thus the handler must have dealt with its cause
+    Resume,
+    /// Leaves the exception to the kernel, as if it wasn't handled
+    Unhandled,
+    /// Aborts with the given [`AbortLevel`] (see [`abort::abort`]), sending the exception's CPU context when aborting via fatal (see [`abort::abort_with_cpu_context`])
+    Abort(AbortLevel),
+}
+
+/// Represents a custom exception handler
+///
+/// The handler may modify the CPU context of the [`ExceptionInfo`] (for instance, to skip the faulting instruction), which is restored when resuming
+pub type ExceptionHandler = Arc<dyn Fn(&mut ExceptionInfo) -> ExceptionAction + Send + Sync>;
+
+static G_HANDLER: RwLock<Option<ExceptionHandler>> = RwLock::new(None);
+
+/// Sets a custom exception handler, replacing any previous one
+///
+/// Handlers run in a dedicated exception stack, thus they should avoid large stack allocations
+///
+/// The handler is not locked while it runs, thus it can replace (or reset) itself
+///
+/// # Arguments
+///
+/// * `handler`: The handler
+pub fn set_handler(
+    handler: impl Fn(&mut ExceptionInfo) -> ExceptionAction + Send + Sync + 'static,
+) {
+    *G_HANDLER.write() = Some(Arc::new(handler));
+}
+
+/// Removes the custom exception handler, if any, restoring the [`default_handler`]
+pub fn reset_handler() {
+    *G_HANDLER.write() = None;
+}
+
+/// The directory where [`default_handler`] writes crash reports
+#[cfg(feature = "fs")]
+pub const DEFAULT_CRASH_REPORT_DIRECTORY: &str = "sdmc:/switch/crash_reports";
+
+/// Writes a crash report of the exception (see the [`fmt::Display`] implementation of [`ExceptionInfo`]), returning its path
+///
+/// The report file is named after the current system tick, thus reports never overwrite previous ones
+///
+/// # Arguments
+///
+/// * `info`: The exception information
+/// * `directory`: The directory to write the report in (see [`fs::format_path`]), which is created if needed
+#[cfg(feature = "fs")]
+pub fn write_crash_report(info: &ExceptionInfo, directory: &str) -> Result<String> {
+    // The directory might already exist
+    let _ = fs::create_directory(directory);
+
+    let path = format!("{}/{:016X}.log", directory, arm::get_system_tick());
+    // The new file is empty, and writing past its end requires appending
+    let mut file = fs::open_file(
+        &path,
+        FileOpenOption::Create() | FileOpenOption::Write() | FileOpenOption::Append(),
+    )?;
+    file.write_array(format!("{}", info).as_bytes())?;
+    Ok(path)
+}
+
+/// The exception handler used when no custom one is set
+///
+/// It writes a crash report to [`DEFAULT_CRASH_REPORT_DIRECTORY`] (if the `fs` feature is enabled and the SD card is mounted as `sdmc`), and then exits the process
+///
+/// Custom handlers can abort with [`AbortLevel::FatalThrow`] instead, which shows the registers and backtrace in the fatal screen (and its report)
+///
+/// # Arguments
+///
+/// * `info`: The exception information
+pub fn default_handler(info: &ExceptionInfo) -> ExceptionAction {
+    #[cfg(feature = "fs")]
+    let _ = write_crash_report(info, DEFAULT_CRASH_REPORT_DIRECTORY);
+    #[cfg(not(feature = "fs"))]
+    let _ = info;
+
+    ExceptionAction::Abort(AbortLevel::ProcessExit())
+}
+
+fn is_debugger_attached() -> bool {
+    matches!(
+        svc::get_info(svc::InfoId::DebuggerAttached, svc::INVALID_HANDLE, 0),
+        Ok(1)
+    )
+}
+
+/// Returns from the exception with the (possibly modified) state of the frame
+///
+/// The kernel only restores `x0`-`x8`, `lr`, `sp`, `pc` and `pstate` (from the exception info it provided), thus those are written back there and everything else is restored right away
+#[unsafe(naked)]
+unsafe extern "C" fn __nx_exception_return(frame: *const ExceptionFrame, rc: ResultCode) -> ! {
+    naked_asm!(
+        // The exception info the kernel provided
+        "ldr x2, [x0, #0x338]",
+        // x0-x8
+        "ldp x3, x4, [x0, #0x00]",
+        "stp x3, x4, [x2, #0x00]",
+        "ldp x3, x4, [x0, #0x10]",
+        "stp x3, x4, [x2, #0x10]",
+        "ldp x3, x4, [x0, #0x20]",
+        "stp x3, x4, [x2, #0x20]",
+        "ldp x3, x4, [x0, #0x30]",
+        "stp x3, x4, [x2, #0x30]",
+        "ldr x3, [x0, #0x40]",
+        "str x3, [x2, #0x40]",
+        // lr, sp, pc and pstate
+        "ldp x3, x4, [x0, #0xF0]",
+        "stp x3, x4, [x2, #0x48]",
+        "ldr x3, [x0, #0x100]",
+        "str x3, [x2, #0x58]",
+        "ldr w3, [x0, #0x108]",
+        "str w3, [x2, #0x60]",
+        // q0-q31
+        "add x2, x0, #0x110",
+        "ldp q0, q1, [x2, #0x000]",
+        "ldp q2, q3, [x2, #0x020]",
+        "ldp q4, q5, [x2, #0x040]",
+        "ldp q6, q7, [x2, #0x060]",
+        "ldp q8, q9, [x2, #0x080]",
+        "ldp q10, q11, [x2, #0x0A0]",
+        "ldp q12, q13, [x2, #0x0C0]",
+        "ldp q14, q15, [x2, #0x0E0]",
+        "ldp q16, q17, [x2, #0x100]",
+        "ldp q18, q19, [x2, #0x120]",
+        "ldp q20, q21, [x2, #0x140]",
+        "ldp q22, q23, [x2, #0x160]",
+        "ldp q24, q25, [x2, #0x180]",
+        "ldp q26, q27, [x2, #0x1A0]",
+        "ldp q28, q29, [x2, #0x1C0]",
+        "ldp q30, q31, [x2, #0x1E0]",
+        // fpcr and fpsr
+        "ldr w3, [x0, #0x310]",
+        "msr fpcr, x3",
+        "ldr w3, [x0, #0x314]",
+        "msr fpsr, x3",
+        // x9-x28 and fp (x0-x8 are free to use, since the kernel restores them)
+        "ldr x9, [x0, #0x48]",
+        "ldp x10, x11, [x0, #0x50]",
+        "ldp x12, x13, [x0, #0x60]",
+        "ldp x14, x15, [x0, #0x70]",
+        "ldp x16, x17, [x0, #0x80]",
+        "ldp x18, x19, [x0, #0x90]",
+        "ldp x20, x21, [x0, #0xA0]",
+        "ldp x22, x23, [x0, #0xB0]",
+        "ldp x24, x25, [x0, #0xC0]",
+        "ldp x26, x27, [x0, #0xD0]",
+        "ldp x28, x29, [x0, #0xE0]",
+        // svcReturnFromException(<result>), which never returns
+        "mov w0, w1",
+        "svc 0x28",
+        "brk #0"
+    )
+}
 
 #[unsafe(no_mangle)]
 #[linkage = "weak"]
-pub(crate) unsafe extern "C" fn __nx_exception_dispatch(
-    _reason: svc::ExceptionType,
-    _stack_top: *mut u8,
+unsafe extern "C" fn __nx_exception_dispatch(
+    exception_type: svc::ExceptionType,
+    frame: *mut ExceptionFrame,
 ) -> ! {
-    // immediately exit if a crate consumer hasn't definite their own exception handler.
-    abort(
-        AbortLevel::ProcessExit(),
-        ResultCode::new(0x6C01 /* StopProcessingException */),
-    );
+    let frame = unsafe { &mut *frame };
+    if is_debugger_attached() {
+        // Let the debugger catch it instead
+        unsafe { __nx_exception_return(frame, svc::rc::ResultNotHandled::make()) };
+    }
+
+    let mut info = ExceptionInfo::new(exception_type, frame);
+    // The lock is released before calling the handler, which would deadlock otherwise if it set or reset the handler
+    let handler = G_HANDLER.read().clone();
+    let action = match handler {
+        Some(handler) => handler(&mut info),
+        None => default_handler(&info),
+    };
+
+    match action {
+        ExceptionAction::Resume => {
+            // The handler may have modified the context in order to recover
+            frame.context = info.context;
+            unsafe { __nx_exception_return(frame, ResultSuccess::make()) }
+        }
+        ExceptionAction::Unhandled => unsafe {
+            __nx_exception_return(frame, svc::rc::ResultNotHandled::make())
+        },
+        #[cfg(feature = "services")]
+        ExceptionAction::Abort(level) => abort::abort_with_cpu_context(
+            level,
+            svc::rc::ResultStopProcessingException::make(),
+            &info.to_fatal_cpu_context(),
+        ),
+        #[cfg(not(feature = "services"))]
+        ExceptionAction::Abort(level) => {
+            abort::abort(level, svc::rc::ResultStopProcessingException::make())
+        }
+    }
 }
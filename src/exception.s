@@ -0,0 +1,98 @@
+.section .text.__nx_exception_entry, "ax", %progbits
+.align 2
+
+// Entered from `_start` when the kernel delivers a user exception
+// x0 = <exception-type>, x1 = <exception-info> (the registers the kernel saved: x0-x8, lr, sp, pc, pstate, afsr0, afsr1, esr, far)
+// Everything else is still untouched, so the full context is captured into an exception frame (see `ExceptionFrame` in `exception.rs`) before calling into Rust
+.global __nx_exception_entry
+.type __nx_exception_entry, %function
+__nx_exception_entry:
+	// The kernel-provided stack is way too small, use our own one with the frame at its top
+	adrp x2, __nx_exception_stack_top
+	add x2, x2, #:lo12:__nx_exception_stack_top
+	sub x2, x2, #0x340
+	mov sp, x2
+
+	// x0-x8, as saved by the kernel
+	ldp x3, x4, [x1, #0x00]
+	stp x3, x4, [sp, #0x00]
+	ldp x3, x4, [x1, #0x10]
+	stp x3, x4, [sp, #0x10]
+	ldp x3, x4, [x1, #0x20]
+	stp x3, x4, [sp, #0x20]
+	ldp x3, x4, [x1, #0x30]
+	stp x3, x4, [sp, #0x30]
+	ldr x3, [x1, #0x40]
+
+	// x9-x28 and fp, still untouched
+	stp x3, x9, [sp, #0x40]
+	stp x10, x11, [sp, #0x50]
+	stp x12, x13, [sp, #0x60]
+	stp x14, x15, [sp, #0x70]
+	stp x16, x17, [sp, #0x80]
+	stp x18, x19, [sp, #0x90]
+	stp x20, x21, [sp, #0xA0]
+	stp x22, x23, [sp, #0xB0]
+	stp x24, x25, [sp, #0xC0]
+	stp x26, x27, [sp, #0xD0]
+	stp x28, x29, [sp, #0xE0]
+
+	// lr, sp, pc and pstate, as saved by the kernel
+	ldp x3, x4, [x1, #0x48]
+	stp x3, x4, [sp, #0xF0]
+	ldr x3, [x1, #0x58]
+	str x3, [sp, #0x100]
+	ldr w3, [x1, #0x60]
+	str w3, [sp, #0x108]
+
+	// q0-q31
+	add x2, sp, #0x110
+	stp q0, q1, [x2, #0x000]
+	stp q2, q3, [x2, #0x020]
+	stp q4, q5, [x2, #0x040]
+	stp q6, q7, [x2, #0x060]
+	stp q8, q9, [x2, #0x080]
+	stp q10, q11, [x2, #0x0A0]
+	stp q12, q13, [x2, #0x0C0]
+	stp q14, q15, [x2, #0x0E0]
+	stp q16, q17, [x2, #0x100]
+	stp q18, q19, [x2, #0x120]
+	stp q20, q21, [x2, #0x140]
+	stp q22, q23, [x2, #0x160]
+	stp q24, q25, [x2, #0x180]
+	stp q26, q27, [x2, #0x1A0]
+	stp q28, q29, [x2, #0x1C0]
+	stp q30, q31, [x2, #0x1E0]
+
+	// fpcr, fpsr and tpidr
+	mrs x3, fpcr
+	str w3, [sp, #0x310]
+	mrs x3, fpsr
+	str w3, [sp, #0x314]
+	mrs x3, tpidr_el0
+	str x3, [sp, #0x318]
+
+	// far, esr, afsr0 and afsr1
+	ldr x3, [x1, #0x70]
+	str x3, [sp, #0x320]
+	ldr w3, [x1, #0x6C]
+	str w3, [sp, #0x328]
+	ldp w3, w4, [x1, #0x64]
+	str w3, [sp, #0x32C]
+	str w4, [sp, #0x330]
+
+	// The exception info itself, where the kernel restores x0-x8, lr, sp, pc and pstate from when returning (see `__nx_exception_return` in `exception.rs`)
+	str x1, [sp, #0x338]
+
+	// __nx_exception_dispatch(<exception-type>, <exception-frame>), with a terminated frame chain for unwinders
+	mov x1, sp
+	mov x29, xzr
+	mov x30, xzr
+	b __nx_exception_dispatch
+
+.section .bss.__nx_exception_stack, "aw", %nobits
+.align 4
+
+__nx_exception_stack:
+	.space 0x8000
+__nx_exception_stack_top:
@@ -32,6 +32,69 @@ pub use fsp::fsp_sf::FileWriteOption;
 pub use fsp::fsp_sf::OperationId;
 pub use fsp::fsp_sf::QueryId;
 
+#[cfg(feature = "time")]
+use crate::time;
+
+/// Represents the timestamps of a file, converted to local calendar time
+#[cfg(feature = "time")]
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Default)]
+pub struct FileTimeStamp {
+    /// The creation time
+    pub create: time::LocalTime,
+    /// The last modification time
+    pub modify: time::LocalTime,
+    /// The last access time
+    pub access: time::LocalTime,
+}
+
+#[cfg(feature = "time")]
+impl FileTimeStamp {
+    fn convert_raw(
+        raw: &FileTimeStampRaw,
+        mut convert_fn: impl FnMut(time::PosixTime) -> Result<time::LocalTime>,
+    ) -> Result<Self> {
+        if raw.is_local_time {
+            // The times are already local ones, so there's no offset (or designation) to apply
+            let local_type = time::tz::LocalTimeType::new(0, false, "");
+            Ok(Self {
+                create: time::tz::make_local_time(raw.create, &local_type)?,
+                modify: time::tz::make_local_time(raw.modify, &local_type)?,
+                access: time::tz::make_local_time(raw.access, &local_type)?,
+            })
+        } else {
+            Ok(Self {
+                create: convert_fn(raw.create)?,
+                modify: convert_fn(raw.modify)?,
+                access: convert_fn(raw.access)?,
+            })
+        }
+    }
+
+    /// Converts a [`FileTimeStampRaw`] using the device time zone
+    ///
+    /// This requires [`time`] to be initialized
+    ///
+    /// # Arguments
+    ///
+    /// * `raw`: The raw timestamps
+    pub fn from_raw(raw: &FileTimeStampRaw) -> Result<Self> {
+        Self::convert_raw(raw, time::to_local_time)
+    }
+
+    /// Converts a [`FileTimeStampRaw`] using the given [`TimeZone`][`time::tz::TimeZone`]
+    ///
+    /// # Arguments
+    ///
+    /// * `raw`: The raw timestamps
+    /// * `time_zone`: The time zone to use
+    pub fn from_raw_with_time_zone(
+        raw: &FileTimeStampRaw,
+        time_zone: &time::tz::TimeZone,
+    ) -> Result<Self> {
+        Self::convert_raw(raw, |time| time_zone.to_local_time(time))
+    }
+}
+
 /// Represents a file, abstracted from the IPC client API.
 pub trait File: Sync {
     /// Reads data from the file, returning the actual read size.
@@ -1104,6 +1167,18 @@ pub fn get_file_time_stamp_raw(path: &str) -> Result<FileTimeStampRaw> {
     fs.get_file_time_stamp_raw(processed_path)
 }
 
+/// Gets the [`FileTimeStamp`] of a file, converted using the device time zone
+///
+/// This requires [`time`] to be initialized
+///
+/// # Arguments
+///
+/// * `path`: The path to use
+#[cfg(feature = "time")]
+pub fn get_file_time_stamp(path: &str) -> Result<FileTimeStamp> {
+    FileTimeStamp::from_raw(&get_file_time_stamp_raw(path)?)
+}
+
 /// Queries on a path
 ///
 /// # Arguments
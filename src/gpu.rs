@@ -19,6 +19,7 @@ use crate::service::vi::{
 use crate::svc;
 use crate::svc::MemoryPermission;
 use crate::sync::RwLock;
+use crate::time::Timeout;
 
 pub mod rc;
 
@@ -1042,7 +1043,11 @@ impl Context {
             sf::Handle::from(transfer_mem_handle),
         ) {
             let _ = svc::close_handle(transfer_mem_handle);
-            let _ = wait_for_permission(transfer_mem.ptr, MemoryPermission::Write(), None);
+            let _ = wait_for_permission(
+                transfer_mem.ptr,
+                MemoryPermission::Write(),
+                Timeout::Infinite,
+            );
             return Err(rc);
         };
 
@@ -1076,7 +1081,11 @@ impl Context {
                 let _ = nvdrv_srv.close(transfer_mem_handle);
                 nvdrv_srv.get_session_mut().close();
                 svc::close_handle(transfer_mem_handle).unwrap();
-                let _ = wait_for_permission(transfer_mem.ptr, MemoryPermission::Write(), None);
+                let _ = wait_for_permission(
+                    transfer_mem.ptr,
+                    MemoryPermission::Write(),
+                    Timeout::Infinite,
+                );
                 return Err(rc);
             }
         };
@@ -1133,6 +1142,10 @@ impl Drop for Context {
         self.nvdrv_service.close(self.transfer_mem_handle);
         self.nvdrv_service.get_session_mut().close();
         svc::close_handle(self.transfer_mem_handle).unwrap();
-        wait_for_permission(self.transfer_mem.ptr, MemoryPermission::Write(), None);
+        wait_for_permission(
+            self.transfer_mem.ptr,
+            MemoryPermission::Write(),
+            Timeout::Infinite,
+        );
     }
 }
@@ -10,6 +10,7 @@ use crate::arm;
 use crate::mem::alloc::Buffer;
 use crate::result::Result;
 use crate::sync::RwLock;
+use crate::time::Timeout;
 
 use crate::{ipc::sf::AppletResourceUserId, service::vi::LayerFlags};
 
@@ -301,8 +302,8 @@ impl<ColorFormat: sealed::CanvasColorFormat> CanvasManager<ColorFormat> {
 
     /// Wait for a vsync event to ensure that the previously submitted frame has been fully rendered to the display.
     #[inline(always)]
-    pub fn wait_vsync_event(&self, timeout: Option<i64>) -> Result<()> {
-        self.surface.wait_vsync_event(timeout.unwrap_or(-1))
+    pub fn wait_vsync_event(&self, timeout: impl Into<Timeout>) -> Result<()> {
+        self.surface.wait_vsync_event(timeout)
     }
 
     /// Check out a canvas/framebuffer to draw a new frame.
@@ -764,7 +765,7 @@ impl<ColorFormat: CanvasColorFormat> Drop for BufferedCanvas<'_, ColorFormat> {
         self.convert_buffers();
         arm::cache_flush(self.base_pointer as *mut u8, self.buffer_size);
         let _ = self.manager.surface.queue_buffer(self.slot, self.fences);
-        let _ = self.manager.surface.wait_buffer_event(-1);
+        let _ = self.manager.surface.wait_buffer_event(Timeout::Infinite);
     }
 }
 
@@ -883,6 +884,6 @@ impl<ColorFormat: CanvasColorFormat> Drop for UnbufferedCanvas<'_, ColorFormat>
     fn drop(&mut self) {
         arm::cache_flush(self.base_pointer as *mut u8, self.buffer_size);
         let _ = self.manager.surface.queue_buffer(self.slot, self.fences);
-        let _ = self.manager.surface.wait_buffer_event(-1);
+        let _ = self.manager.surface.wait_buffer_event(Timeout::Infinite);
     }
 }
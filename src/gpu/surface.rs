@@ -11,6 +11,7 @@ use crate::ipc::sf;
 use crate::mem::alloc;
 use crate::service::dispdrv;
 use crate::svc;
+use crate::time::Timeout;
 use crate::wait;
 use core::mem as cmem;
 
@@ -387,7 +388,7 @@ impl Surface {
         let has_fences: bool;
         let fences: MultiFence;
         if is_async {
-            self.wait_buffer_event(-1)?;
+            self.wait_buffer_event(Timeout::Infinite)?;
             loop {
                 match self.binder.dequeue_buffer(
                     true,
@@ -473,10 +474,14 @@ impl Surface {
     /// # Arguments
     ///
     /// * `fences`: The fences
-    /// * `timeout`: The wait timeout
-    pub fn wait_fences(&mut self, fences: MultiFence, timeout: i32) -> Result<()> {
+    /// * `timeout`: The wait timeout for each fence (see [`Timeout`]), with millisecond precision
+    pub fn wait_fences(&mut self, fences: MultiFence, timeout: impl Into<Timeout>) -> Result<()> {
+        let timeout = timeout.into();
         for fence in fences.fences[..fences.fence_count as usize].iter().cloned() {
-            let mut ioctl_syncptwait = ioctl::NvHostCtrlSyncptWait { fence, timeout };
+            let mut ioctl_syncptwait = ioctl::NvHostCtrlSyncptWait {
+                fence,
+                timeout: timeout.get_remaining_millis(),
+            };
 
             if self.do_ioctl(&mut ioctl_syncptwait).is_err() {
                 // Don't error, but stop waiting for fences
@@ -518,8 +523,8 @@ impl Surface {
     ///
     /// # Arguments
     ///
-    /// * `timeout`: The wait timeout
-    pub fn wait_buffer_event(&self, timeout: i64) -> Result<()> {
+    /// * `timeout`: The wait timeout (see [`Timeout`])
+    pub fn wait_buffer_event(&self, timeout: impl Into<Timeout>) -> Result<()> {
         wait::wait_handles(&[self.buffer_event_handle], timeout)?;
         svc::reset_signal(self.buffer_event_handle)
     }
@@ -528,8 +533,8 @@ impl Surface {
     ///
     /// # Arguments
     ///
-    /// * `timeout`: The wait timeout
-    pub fn wait_vsync_event(&self, timeout: i64) -> Result<()> {
+    /// * `timeout`: The wait timeout (see [`Timeout`])
+    pub fn wait_vsync_event(&self, timeout: impl Into<Timeout>) -> Result<()> {
         wait::wait_handles(&[self.vsync_event_handle], timeout)?;
         svc::reset_signal(self.vsync_event_handle)
     }
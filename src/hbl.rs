@@ -200,6 +200,69 @@ pub fn get_loader_info() -> &'static str {
     *G_LOADER_INFO.read()
 }
 
+static G_ARGV: RwLock<&'static str> = RwLock::new("");
+
+pub(crate) fn set_argv(argv: &'static str) {
+    *G_ARGV.write() = argv;
+}
+
+/// Gets the raw argv string, whose arguments are separated by spaces (and can be quoted to contain them)
+///
+/// The first argument is usually the path of the homebrew NRO itself
+///
+/// This value will only be set/useful if the current code is running through HBL
+pub fn get_argv() -> &'static str {
+    *G_ARGV.read()
+}
+
+/// Represents an iterator over the arguments of an argv string (see [`get_args`])
+#[derive(Clone, Debug)]
+pub struct Args<'a> {
+    remaining: &'a str,
+}
+
+impl<'a> Args<'a> {
+    /// Creates a new [`Args`] iterator over the given argv string
+    ///
+    /// # Arguments
+    ///
+    /// * `argv`: The argv string
+    #[inline]
+    pub const fn new(argv: &'a str) -> Self {
+        Self { remaining: argv }
+    }
+}
+
+impl<'a> Iterator for Args<'a> {
+    type Item = &'a str;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.remaining = self.remaining.trim_start_matches(' ');
+        if self.remaining.is_empty() {
+            return None;
+        }
+
+        // Quoted arguments end at the closing quote (or at the end of the string, if missing)
+        let (arg, rest) = match self.remaining.strip_prefix('"') {
+            Some(quoted) => quoted.split_once('"').unwrap_or((quoted, "")),
+            None => self
+                .remaining
+                .split_once(' ')
+                .unwrap_or((self.remaining, "")),
+        };
+        self.remaining = rest;
+        Some(arg)
+    }
+}
+
+/// Gets an iterator over the arguments of the argv string (see [`get_argv`])
+///
+/// This value will only be set/useful if the current code is running through HBL
+#[inline]
+pub fn get_args() -> Args<'static> {
+    Args::new(get_argv())
+}
+
 pub static G_NEXT_LOAD_PATH: Mutex<Option<&'static mut ArrayString<512>>> = Mutex::new(None);
 pub static G_NEXT_LOAD_ARGV: Mutex<Option<&'static mut ArrayString<2048>>> = Mutex::new(None);
 
@@ -242,10 +305,7 @@ pub fn get_next_load_argv() -> Option<ArrayString<2048>> {
 ///
 /// Returns true if the buffers have been initialized, else false.
 ///
-pub fn set_next_load_entry(
-    next_load_path: &'static str,
-    next_load_argv: &'static str,
-) -> Result<(bool, bool)> {
+pub fn set_next_load_entry(next_load_path: &str, next_load_argv: &str) -> Result<(bool, bool)> {
     Ok((
         {
             let mut path_handle = G_NEXT_LOAD_PATH.lock();
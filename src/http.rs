@@ -0,0 +1,747 @@
+//! HTTP/1.1 client and server support
+//!
+//! Requests are sent over [`TcpStream`]s (or [`TlsStream`]s for `https` URLs), supporting chunked bodies, redirects and keep-alive connections.
+//!
+//! All the protocol handling is done by the [`parse`] module, which is independent of the connections.
+//!
+//! A simple file server is also available in the [`server`] module.
+//!
+//! ```no_run
+//! use nx::http::{Client, Request};
+//!
+//! let mut client = Client::new();
+//! let index = client.send(&Request::get("http://example.com/index.json")?)?.text()?;
+//!
+//! let mut response = client.get("https://example.com/update.nro")?;
+//! response.download_to_path("sdmc:/switch/update.nro", |downloaded, total| {
+//!     // Report the progress...
+//! })?;
+//! ```
+
+use crate::fs;
+use crate::result::*;
+use crate::socket::net::TcpStream;
+use crate::socket::net::traits::SocketCommon;
+use crate::socket::tls::{SslVersion, TlsContext, TlsStream};
+use alloc::string::{String, ToString};
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+
+pub mod rc;
+
+pub mod parse;
+
+pub mod server;
+
+pub use parse::{BodyLength, Headers, Method, ResponseHead, Scheme, Url, Version};
+
+/// The maximum size of a response head, to avoid buffering endless invalid responses
+pub const MAX_RESPONSE_HEAD_SIZE: usize = 0x4000;
+
+/// The default maximum number of redirects followed by a [`Client`]
+pub const DEFAULT_MAX_REDIRECTS: u32 = 10;
+
+/// The default `User-Agent` sent by a [`Client`]
+pub const DEFAULT_USER_AGENT: &str = "nx-http/1.1";
+
+/// The size of the chunks read from connections
+const RECV_CHUNK_SIZE: usize = 0x4000;
+
+/// The maximum size of a redirect response body to discard in order to reuse its connection
+const MAX_DISCARDED_BODY_SIZE: u64 = 0x10000;
+
+enum Stream {
+    Tcp(TcpStream),
+    Tls(TlsStream),
+}
+
+impl Stream {
+    fn recv(&self, data: &mut [u8]) -> Result<usize> {
+        match self {
+            Self::Tcp(stream) => stream.recv(data),
+            Self::Tls(stream) => stream.recv(data),
+        }
+    }
+
+    fn send_all(&self, mut data: &[u8]) -> Result<()> {
+        match self {
+            Self::Tcp(stream) => {
+                while !data.is_empty() {
+                    let sent_size = stream.send(data)? as usize;
+                    data = &data[sent_size.min(data.len())..];
+                }
+                Ok(())
+            }
+            Self::Tls(stream) => stream.send_all(data),
+        }
+    }
+}
+
+struct Connection {
+    stream: Stream,
+    scheme: Scheme,
+    host: String,
+    port: u16,
+    /// Received data which hasn't been consumed yet
+    buffer: Vec<u8>,
+    /// Whether any data was received since the last request was sent
+    response_started: bool,
+}
+
+impl Connection {
+    fn new(url: &Url, tls_context: Option<&Arc<TlsContext>>) -> Result<Self> {
+        let tcp_stream = TcpStream::connect((url.host.as_str(), url.port))?;
+        let stream = match (url.scheme, tls_context) {
+            (Scheme::Https, Some(tls_context)) => Stream::Tls(TlsStream::connect(
+                tls_context.clone(),
+                tcp_stream,
+                &url.host,
+            )?),
+            _ => Stream::Tcp(tcp_stream),
+        };
+
+        Ok(Self {
+            stream,
+            scheme: url.scheme,
+            host: url.host.clone(),
+            port: url.port,
+            buffer: Vec::new(),
+            response_started: false,
+        })
+    }
+
+    fn is_for(&self, url: &Url) -> bool {
+        (self.scheme == url.scheme)
+            && self.host.eq_ignore_ascii_case(&url.host)
+            && (self.port == url.port)
+    }
+
+    /// Receives more data into the buffer, returning the received size (`0` if the connection was closed)
+    fn fill(&mut self) -> Result<usize> {
+        let offset = self.buffer.len();
+        self.buffer.resize(offset + RECV_CHUNK_SIZE, 0);
+        let recv_result = self.stream.recv(&mut self.buffer[offset..]);
+        let recv_size = *recv_result.as_ref().unwrap_or(&0);
+        self.buffer.truncate(offset + recv_size);
+        self.response_started |= recv_size > 0;
+        recv_result
+    }
+
+    /// Moves buffered data to the given buffer, returning the moved size
+    fn take_buffered(&mut self, data: &mut [u8]) -> usize {
+        let size = data.len().min(self.buffer.len());
+        data[..size].copy_from_slice(&self.buffer[..size]);
+        self.buffer.drain(..size);
+        size
+    }
+
+    fn read_response_head(&mut self) -> Result<ResponseHead> {
+        loop {
+            if let Some((head, head_size)) = parse::parse_response_head(&self.buffer)? {
+                self.buffer.drain(..head_size);
+                return Ok(head);
+            }
+
+            result_return_if!(self.fill()? == 0, rc::ResultConnectionClosed);
+        }
+    }
+}
+
+enum BodyState {
+    Fixed { remaining: u64 },
+    Chunked(parse::ChunkedDecoder),
+    UntilClose,
+    Done,
+}
+
+struct BodyReader {
+    connection: Connection,
+    state: BodyState,
+    keep_alive: bool,
+}
+
+impl BodyReader {
+    fn new(connection: Connection, length: BodyLength, keep_alive: bool) -> Self {
+        let state = match length {
+            BodyLength::None | BodyLength::Fixed(0) => BodyState::Done,
+            BodyLength::Fixed(size) => BodyState::Fixed { remaining: size },
+            BodyLength::Chunked => BodyState::Chunked(parse::ChunkedDecoder::new()),
+            BodyLength::UntilClose => BodyState::UntilClose,
+        };
+        Self {
+            connection,
+            // The end of the body can't be told apart from the end of the connection otherwise
+            keep_alive: keep_alive && !matches!(state, BodyState::UntilClose),
+            state,
+        }
+    }
+
+    #[inline]
+    fn is_done(&self) -> bool {
+        matches!(self.state, BodyState::Done)
+    }
+
+    /// Gets whether the connection can be used for another request
+    #[inline]
+    fn is_reusable(&self) -> bool {
+        self.keep_alive && self.is_done()
+    }
+
+    fn read(&mut self, data: &mut [u8]) -> Result<usize> {
+        if data.is_empty() {
+            return Ok(0);
+        }
+
+        loop {
+            match &mut self.state {
+                BodyState::Done => return Ok(0),
+                BodyState::Fixed { remaining } => {
+                    if self.connection.buffer.is_empty() {
+                        result_return_if!(self.connection.fill()? == 0, rc::ResultConnectionClosed);
+                    }
+
+                    let max_size = (*remaining).min(data.len() as u64) as usize;
+                    let read_size = self.connection.take_buffered(&mut data[..max_size]);
+                    *remaining -= read_size as u64;
+                    if *remaining == 0 {
+                        self.state = BodyState::Done;
+                    }
+                    return Ok(read_size);
+                }
+                BodyState::UntilClose => {
+                    if self.connection.buffer.is_empty() && (self.connection.fill()? == 0) {
+                        self.state = BodyState::Done;
+                        return Ok(0);
+                    }
+                    return Ok(self.connection.take_buffered(data));
+                }
+                BodyState::Chunked(decoder) => {
+                    if self.connection.buffer.is_empty() {
+                        result_return_if!(self.connection.fill()? == 0, rc::ResultConnectionClosed);
+                    }
+
+                    let (consumed, produced) = decoder.decode(&self.connection.buffer, data)?;
+                    self.connection.buffer.drain(..consumed);
+                    if decoder.is_done() {
+                        self.state = BodyState::Done;
+                    }
+                    if produced > 0 {
+                        return Ok(produced);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Reads and discards the rest of the body (if it's small enough), so that the connection can be reused
+    fn discard(&mut self) -> Result<()> {
+        let mut discarded_size: u64 = 0;
+        let mut data = vec![0u8; RECV_CHUNK_SIZE];
+        while !self.is_done() && (discarded_size < MAX_DISCARDED_BODY_SIZE) {
+            discarded_size += self.read(&mut data)? as u64;
+        }
+        Ok(())
+    }
+}
+
+/// Represents a request to send with a [`Client`]
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Request {
+    method: Method,
+    url: Url,
+    headers: Headers,
+    body: Vec<u8>,
+}
+
+impl Request {
+    /// Creates a new [`Request`], without any headers or body
+    ///
+    /// # Arguments
+    ///
+    /// * `method`: The request method
+    /// * `url`: The request URL
+    pub fn new(method: Method, url: &str) -> Result<Self> {
+        Ok(Self {
+            method,
+            url: Url::parse(url)?,
+            headers: Headers::new(),
+            body: Vec::new(),
+        })
+    }
+
+    /// Same as [`Request::new`] with [`Method::Get`]
+    #[inline]
+    pub fn get(url: &str) -> Result<Self> {
+        Self::new(Method::Get, url)
+    }
+
+    /// Same as [`Request::new`] with [`Method::Head`]
+    #[inline]
+    pub fn head(url: &str) -> Result<Self> {
+        Self::new(Method::Head, url)
+    }
+
+    /// Same as [`Request::new`] with [`Method::Post`]
+    #[inline]
+    pub fn post(url: &str) -> Result<Self> {
+        Self::new(Method::Post, url)
+    }
+
+    /// Same as [`Request::new`] with [`Method::Put`]
+    #[inline]
+    pub fn put(url: &str) -> Result<Self> {
+        Self::new(Method::Put, url)
+    }
+
+    /// Same as [`Request::new`] with [`Method::Delete`]
+    #[inline]
+    pub fn delete(url: &str) -> Result<Self> {
+        Self::new(Method::Delete, url)
+    }
+
+    /// Adds a header field to the request
+    ///
+    /// `Host`, `User-Agent`, `Connection` and `Content-Length` are automatically sent unless they are specified
+    ///
+    /// # Arguments
+    ///
+    /// * `name`: The field name
+    /// * `value`: The field value
+    pub fn with_header(mut self, name: &str, value: &str) -> Self {
+        self.headers.add(name, value);
+        self
+    }
+
+    /// Sets the request body
+    ///
+    /// # Arguments
+    ///
+    /// * `body`: The body data
+    pub fn with_body(mut self, body: impl Into<Vec<u8>>) -> Self {
+        self.body = body.into();
+        self
+    }
+
+    /// Gets the request method
+    #[inline]
+    pub fn get_method(&self) -> Method {
+        self.method
+    }
+
+    /// Gets the request URL
+    #[inline]
+    pub fn get_url(&self) -> &Url {
+        &self.url
+    }
+
+    /// Gets the request headers
+    #[inline]
+    pub fn get_headers(&self) -> &Headers {
+        &self.headers
+    }
+
+    /// Gets the request headers, mutably
+    #[inline]
+    pub fn get_headers_mut(&mut self) -> &mut Headers {
+        &mut self.headers
+    }
+
+    /// Gets the request body
+    #[inline]
+    pub fn get_body(&self) -> &[u8] {
+        &self.body
+    }
+}
+
+/// Represents a HTTP client, which keeps the last used connection alive for further requests to the same host
+pub struct Client {
+    connection: Option<Connection>,
+    tls_context: Option<Arc<TlsContext>>,
+    follow_redirects: bool,
+    max_redirects: u32,
+    keep_alive: bool,
+    user_agent: String,
+}
+
+impl Client {
+    /// Creates a new [`Client`], which follows redirects and keeps connections alive
+    pub fn new() -> Self {
+        Self {
+            connection: None,
+            tls_context: None,
+            follow_redirects: true,
+            max_redirects: DEFAULT_MAX_REDIRECTS,
+            keep_alive: true,
+            user_agent: DEFAULT_USER_AGENT.to_string(),
+        }
+    }
+
+    /// Sets whether redirect responses are followed (up to the maximum redirect count) instead of being returned
+    ///
+    /// # Arguments
+    ///
+    /// * `follow`: Whether to follow redirects
+    #[inline]
+    pub fn set_follow_redirects(&mut self, follow: bool) {
+        self.follow_redirects = follow;
+    }
+
+    /// Sets the maximum number of redirects to follow for a request, failing with [`rc::ResultTooManyRedirects`] when exceeded
+    ///
+    /// # Arguments
+    ///
+    /// * `max_redirects`: The maximum redirect count
+    #[inline]
+    pub fn set_max_redirects(&mut self, max_redirects: u32) {
+        self.max_redirects = max_redirects;
+    }
+
+    /// Sets whether connections are kept alive for further requests
+    ///
+    /// # Arguments
+    ///
+    /// * `keep_alive`: Whether to keep connections alive
+    pub fn set_keep_alive(&mut self, keep_alive: bool) {
+        self.keep_alive = keep_alive;
+        if !keep_alive {
+            self.connection = None;
+        }
+    }
+
+    /// Sets the `User-Agent` sent in requests which don't specify one
+    ///
+    /// # Arguments
+    ///
+    /// * `user_agent`: The user agent
+    #[inline]
+    pub fn set_user_agent(&mut self, user_agent: &str) {
+        self.user_agent = user_agent.to_string();
+    }
+
+    /// Sets the [`TlsContext`] used for `https` connections (otherwise, a default one is created when needed)
+    ///
+    /// # Arguments
+    ///
+    /// * `tls_context`: The context to use
+    #[inline]
+    pub fn set_tls_context(&mut self, tls_context: Arc<TlsContext>) {
+        self.tls_context = Some(tls_context);
+    }
+
+    fn connect(&mut self, url: &Url) -> Result<Connection> {
+        if (url.scheme == Scheme::Https) && self.tls_context.is_none() {
+            self.tls_context = Some(Arc::new(TlsContext::new(SslVersion::Auto())?));
+        }
+        Connection::new(url, self.tls_context.as_ref())
+    }
+
+    fn exchange_on(
+        connection: &mut Connection,
+        method: Method,
+        target: &str,
+        headers: &Headers,
+        body: &[u8],
+    ) -> Result<ResponseHead> {
+        let mut data = parse::serialize_request_head(method, target, headers)?;
+        data.extend_from_slice(body);
+        // Leftover data from a previous response counts as a reply to this request
+        connection.response_started = !connection.buffer.is_empty();
+        connection.stream.send_all(&data)?;
+
+        loop {
+            let head = connection.read_response_head()?;
+            // Informational responses (like "100 Continue") come before the actual one
+            if !head.is_informational() || (head.status_code == 101) {
+                return Ok(head);
+            }
+        }
+    }
+
+    fn exchange(
+        &mut self,
+        method: Method,
+        url: &Url,
+        headers: &Headers,
+        body: &[u8],
+    ) -> Result<(Connection, ResponseHead)> {
+        let mut headers = headers.clone();
+        if !headers.contains("Host") {
+            headers.set("Host", &url.get_host_header());
+        }
+        if !headers.contains("User-Agent") {
+            headers.set("User-Agent", &self.user_agent);
+        }
+        if !headers.contains("Connection") {
+            headers.set(
+                "Connection",
+                match self.keep_alive {
+                    true => "keep-alive",
+                    false => "close",
+                },
+            );
+        }
+        if !headers.contains("Content-Length")
+            && (!body.is_empty() || matches!(method, Method::Post | Method::Put | Method::Patch))
+        {
+            headers.set("Content-Length", &body.len().to_string());
+        }
+
+        if let Some(mut connection) = self
+            .connection
+            .take()
+            .filter(|connection| connection.is_for(url))
+        {
+            // The server may have closed the idle connection in the meantime, thus retry with a new one if nothing was received (and the request is safe to repeat, as the server may have processed it anyway)
+            match Self::exchange_on(&mut connection, method, &url.path, &headers, body) {
+                Ok(head) => return Ok((connection, head)),
+                Err(rc) if !method.is_idempotent() || connection.response_started => {
+                    return Err(rc);
+                }
+                Err(_) => {}
+            }
+        }
+
+        let mut connection = self.connect(url)?;
+        let head = Self::exchange_on(&mut connection, method, &url.path, &headers, body)?;
+        Ok((connection, head))
+    }
+
+    /// Sends a request, returning the response once its head is received
+    ///
+    /// Redirects are followed if enabled: `303` responses (and `301`/`302` ones to `POST` requests) are followed with `GET` requests without body, and `Authorization` headers are not sent to other hosts
+    ///
+    /// If a kept-alive connection was closed before any response data arrived, [idempotent][`Method::is_idempotent`] requests are sent again over a new connection, while other ones fail
+    ///
+    /// Header fields containing line breaks or other control characters fail with [`rc::ResultInvalidHeader`]
+    ///
+    /// # Arguments
+    ///
+    /// * `request`: The request to send
+    pub fn send(&mut self, request: &Request) -> Result<Response<'_>> {
+        let mut method = request.method;
+        let mut url = request.url.clone();
+        let mut headers = request.headers.clone();
+        let mut body = request.body.as_slice();
+        let mut redirect_count: u32 = 0;
+
+        loop {
+            let (connection, head) = self.exchange(method, &url, &headers, body)?;
+            let body_length = parse::get_response_body_length(method, &head)?;
+            let mut body_reader = BodyReader::new(
+                connection,
+                body_length,
+                self.keep_alive && head.is_keep_alive(),
+            );
+
+            if self.follow_redirects && head.is_redirect() {
+                result_return_unless!(
+                    redirect_count < self.max_redirects,
+                    rc::ResultTooManyRedirects
+                );
+                redirect_count += 1;
+
+                let next_url = url.join(head.headers.get("Location").unwrap_or_default())?;
+                if (head.status_code == 303)
+                    || (matches!(head.status_code, 301 | 302) && (method == Method::Post))
+                {
+                    if method != Method::Head {
+                        method = Method::Get;
+                    }
+                    body = &[];
+                    headers.remove("Content-Length");
+                    headers.remove("Content-Type");
+                }
+                if !next_url.host.eq_ignore_ascii_case(&url.host) {
+                    headers.remove("Authorization");
+                    headers.remove("Host");
+                }
+
+                if body_reader.discard().is_ok() && body_reader.is_reusable() {
+                    self.connection = Some(body_reader.connection);
+                }
+                url = next_url;
+                continue;
+            }
+
+            return Ok(Response {
+                client: self,
+                head,
+                url,
+                content_length: match body_length {
+                    BodyLength::None => Some(0),
+                    BodyLength::Fixed(size) => Some(size),
+                    _ => None,
+                },
+                body_reader: Some(body_reader),
+            });
+        }
+    }
+
+    /// Sends a `GET` request (see [`Client::send`])
+    ///
+    /// # Arguments
+    ///
+    /// * `url`: The URL to request
+    #[inline]
+    pub fn get(&mut self, url: &str) -> Result<Response<'_>> {
+        self.send(&Request::get(url)?)
+    }
+}
+
+impl Default for Client {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Represents a response, whose body is received as it's read
+///
+/// Once the body is fully read, the connection is given back to the [`Client`] to be reused (if possible) when the response is dropped
+pub struct Response<'a> {
+    client: &'a mut Client,
+    head: ResponseHead,
+    url: Url,
+    content_length: Option<u64>,
+    body_reader: Option<BodyReader>,
+}
+
+impl Response<'_> {
+    /// Gets the response status code
+    #[inline]
+    pub fn get_status_code(&self) -> u16 {
+        self.head.status_code
+    }
+
+    /// Gets whether the status code is a successful (`2xx`) one
+    #[inline]
+    pub fn is_success(&self) -> bool {
+        (200..300).contains(&self.head.status_code)
+    }
+
+    /// Gets the response head (status line and headers)
+    #[inline]
+    pub fn get_head(&self) -> &ResponseHead {
+        &self.head
+    }
+
+    /// Gets the response headers
+    #[inline]
+    pub fn get_headers(&self) -> &Headers {
+        &self.head.headers
+    }
+
+    /// Gets the URL this response comes from, which differs from the request one if redirects were followed
+    #[inline]
+    pub fn get_url(&self) -> &Url {
+        &self.url
+    }
+
+    /// Gets the body size, if it's known beforehand
+    #[inline]
+    pub fn get_content_length(&self) -> Option<u64> {
+        self.content_length
+    }
+
+    /// Reads body data into the given buffer, returning the read size (`0` once the body ends)
+    ///
+    /// # Arguments
+    ///
+    /// * `data`: The buffer to read into
+    pub fn read(&mut self, data: &mut [u8]) -> Result<usize> {
+        match self.body_reader.as_mut() {
+            Some(body_reader) => body_reader.read(data),
+            None => Ok(0),
+        }
+    }
+
+    /// Reads the rest of the body, appending it to the given buffer and returning the read size
+    ///
+    /// # Arguments
+    ///
+    /// * `out_data`: The buffer to append the body to
+    pub fn read_to_end(&mut self, out_data: &mut Vec<u8>) -> Result<usize> {
+        let start_len = out_data.len();
+        loop {
+            let offset = out_data.len();
+            out_data.resize(offset + RECV_CHUNK_SIZE, 0);
+            let read_result = self.read(&mut out_data[offset..]);
+            let read_size = *read_result.as_ref().unwrap_or(&0);
+            out_data.truncate(offset + read_size);
+            if read_result? == 0 {
+                return Ok(out_data.len() - start_len);
+            }
+        }
+    }
+
+    /// Reads the whole (remaining) body
+    pub fn bytes(mut self) -> Result<Vec<u8>> {
+        let mut data = Vec::with_capacity(self.content_length.unwrap_or(0).min(0x100000) as usize);
+        self.read_to_end(&mut data)?;
+        Ok(data)
+    }
+
+    /// Reads the whole (remaining) body as text, replacing invalid UTF-8 sequences
+    pub fn text(self) -> Result<String> {
+        let data = self.bytes()?;
+        Ok(String::from_utf8(data)
+            .unwrap_or_else(|err| String::from_utf8_lossy(err.as_bytes()).into_owned()))
+    }
+
+    /// Writes the rest of the body to a file (from its current position), returning the written size
+    ///
+    /// # Arguments
+    ///
+    /// * `file`: The file to write to
+    /// * `progress`: Called after each written piece with the total written size and the expected body size (if known)
+    pub fn download_to(
+        &mut self,
+        file: &mut fs::FileAccessor,
+        mut progress: impl FnMut(u64, Option<u64>),
+    ) -> Result<u64> {
+        let mut data = vec![0u8; RECV_CHUNK_SIZE];
+        let mut written_size: u64 = 0;
+        loop {
+            let read_size = self.read(&mut data)?;
+            if read_size == 0 {
+                return Ok(written_size);
+            }
+
+            file.write_array(&data[..read_size])?;
+            written_size += read_size as u64;
+            progress(written_size, self.content_length);
+        }
+    }
+
+    /// Same as [`Response::download_to`], but replacing (or creating) the file at the given path
+    ///
+    /// # Arguments
+    ///
+    /// * `path`: The path of the file to write to
+    /// * `progress`: Called after each written piece with the total written size and the expected body size (if known)
+    pub fn download_to_path(
+        &mut self,
+        path: &str,
+        progress: impl FnMut(u64, Option<u64>),
+    ) -> Result<u64> {
+        // Otherwise the previous contents would remain after the written ones
+        let _ = fs::remove_file(path);
+        // The new file is empty, and writing past its end requires appending
+        let mut file = fs::open_file(
+            path,
+            fs::FileOpenOption::Create()
+                | fs::FileOpenOption::Write()
+                | fs::FileOpenOption::Append(),
+        )?;
+        self.download_to(&mut file, progress)
+    }
+}
+
+impl Drop for Response<'_> {
+    fn drop(&mut self) {
+        if let Some(body_reader) = self.body_reader.take()
+            && body_reader.is_reusable()
+        {
+            self.client.connection = Some(body_reader.connection);
+        }
+    }
+}
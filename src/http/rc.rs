@@ -0,0 +1,20 @@
+//! HTTP-specific result definitions
+
+use crate::rc;
+
+/// Result Submodule ID for the parent module
+pub const RESULT_SUBMODULE: u32 = 1700;
+
+result_define_subgroup!(rc::RESULT_MODULE, RESULT_SUBMODULE => {
+    InvalidUrl: 1,
+    UnsupportedScheme: 2,
+    InvalidResponse: 3,
+    InvalidChunk: 4,
+    ResponseHeadTooLarge: 5,
+    ConnectionClosed: 6,
+    TooManyRedirects: 7,
+    InvalidHeader: 8,
+    InvalidRequest: 9,
+    UnsupportedMethod: 10,
+    RequestHeadTooLarge: 11
+});
@@ -0,0 +1,910 @@
+//! HTTP/1.1 file server
+//!
+//! A [`FileServer`] serves the files and directories under a root path of any [`FileSystem`], and (unless it's read-only) accepts file uploads (`PUT`) and removals (`DELETE`).
+//!
+//! All connections are served from the thread calling [`FileServer::poll`], waiting for socket events with [`poll`][`crate::socket::net::poll`].
+//!
+//! ```no_run
+//! use nx::http::server::FileServer;
+//! use core::net::Ipv4Addr;
+//!
+//! let mut server = FileServer::bind_path("sdmc:/switch", Ipv4Addr::UNSPECIFIED, 8080)?;
+//! server.set_basic_auth("user", "password");
+//! server.run(|| true)?;
+//! ```
+
+use super::parse::{self, BodyLength, ByteRange, Headers, Method, RequestHead};
+use crate::fs::{
+    self, DirectoryEntry, DirectoryEntryType, DirectoryOpenMode, File, FileAttribute, FileOpenMode,
+    FileReadOption, FileSystem, FileWriteOption,
+};
+use crate::result::*;
+use crate::service::fsp::fsp_sf;
+use crate::socket;
+use crate::socket::PollFlags;
+use crate::socket::net::traits::{Pollable, SocketCommon};
+use crate::socket::net::{TcpListener, TcpStream};
+use crate::time::{Instant, Timeout};
+use alloc::boxed::Box;
+use alloc::string::{String, ToString};
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use core::fmt::Write;
+use core::net::Ipv4Addr;
+use core::time::Duration;
+
+/// The default maximum number of simultaneous connections of a [`FileServer`]
+pub const DEFAULT_MAX_CONNECTIONS: usize = 8;
+
+/// The time after which idle connections are closed
+pub const CONNECTION_IDLE_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// The maximum size of a request head, to avoid buffering endless invalid requests
+pub const MAX_REQUEST_HEAD_SIZE: usize = 0x4000;
+
+/// The size of the chunks received from connections and read from files
+const CHUNK_SIZE: usize = 0x4000;
+
+/// The `EAGAIN`/`EWOULDBLOCK` errno, returned by non-blocking sockets which aren't ready
+const EAGAIN: u32 = 11;
+
+/// The methods supported by servers which accept writes
+const ALLOWED_METHODS: &str = "GET, HEAD, PUT, DELETE, OPTIONS";
+
+/// The methods supported by read-only servers
+const READ_ONLY_ALLOWED_METHODS: &str = "GET, HEAD, OPTIONS";
+
+#[inline]
+fn is_would_block(rc: ResultCode) -> bool {
+    rc.get_value() == crate::result::pack_value(socket::rc::RESULT_MODULE, 1000 + EAGAIN)
+}
+
+/// Compares a received secret with the expected one, taking the same time wherever they differ (so that response times don't reveal how much of a guess was right)
+fn constant_time_eq(received: &[u8], expected: &[u8]) -> bool {
+    let mut diff = received.len() ^ expected.len();
+    for (i, &byte) in expected.iter().enumerate() {
+        diff |= (received.get(i).copied().unwrap_or_default() ^ byte) as usize;
+    }
+    core::hint::black_box(diff) == 0
+}
+
+/// Allows polling raw socket descriptors together, since [`poll`][`crate::socket::net::poll`] takes a single [`Pollable`] type
+struct PollTarget(i32);
+
+impl Pollable for PollTarget {
+    fn get_poll_fd(&self) -> i32 {
+        self.0
+    }
+}
+
+/// The (connection-independent) server settings
+struct ServerConfig {
+    fs: Arc<dyn FileSystem>,
+    root: String,
+    credentials: Option<(String, String)>,
+    read_only: bool,
+}
+
+impl ServerConfig {
+    fn get_allowed_methods(&self) -> &'static str {
+        match self.read_only {
+            true => READ_ONLY_ALLOWED_METHODS,
+            false => ALLOWED_METHODS,
+        }
+    }
+
+    /// Converts a (decoded) request path to the corresponding filesystem path, rejecting paths which would escape the root
+    fn get_fs_path(&self, request_path: &str) -> Option<String> {
+        let mut fs_path = self.root.clone();
+        for segment in request_path
+            .split('/')
+            .filter(|segment| !segment.is_empty())
+        {
+            if (segment == ".") || (segment == "..") || segment.contains(['\\', ':', '\0']) {
+                return None;
+            }
+            fs_path.push('/');
+            fs_path.push_str(segment);
+        }
+        if fs_path.is_empty() {
+            fs_path.push('/');
+        }
+        Some(fs_path)
+    }
+
+    fn is_authorized(&self, head: &RequestHead) -> bool {
+        match &self.credentials {
+            Some((user, password)) => head
+                .headers
+                .get("Authorization")
+                .and_then(parse::parse_basic_auth)
+                .is_some_and(|(request_user, request_password)| {
+                    // Both are always compared, not revealing which one was wrong either
+                    constant_time_eq(request_user.as_bytes(), user.as_bytes())
+                        & constant_time_eq(request_password.as_bytes(), password.as_bytes())
+                }),
+            None => true,
+        }
+    }
+}
+
+enum UploadBody {
+    Fixed { remaining: u64 },
+    Chunked(parse::ChunkedDecoder),
+}
+
+struct FileSource {
+    file: Box<dyn File>,
+    offset: usize,
+    remaining: usize,
+}
+
+enum ConnectionState {
+    ReadingHead,
+    ReceivingUpload {
+        file: Box<dyn File>,
+        offset: usize,
+        body: UploadBody,
+        created: bool,
+    },
+    Sending {
+        data: Vec<u8>,
+        sent_size: usize,
+        file: Option<FileSource>,
+    },
+    Closed,
+}
+
+struct ServerConnection {
+    stream: TcpStream,
+    /// Received data which hasn't been processed yet
+    buffer: Vec<u8>,
+    state: ConnectionState,
+    /// Interim response data (like `100 Continue`) which hasn't been sent yet, which goes before anything else
+    interim: Vec<u8>,
+    keep_alive: bool,
+    last_activity: Instant,
+}
+
+impl ServerConnection {
+    fn new(stream: TcpStream) -> Result<Self> {
+        stream.set_nonblocking(true)?;
+        Ok(Self {
+            stream,
+            buffer: Vec::new(),
+            state: ConnectionState::ReadingHead,
+            interim: Vec::new(),
+            keep_alive: true,
+            last_activity: Instant::now(),
+        })
+    }
+
+    #[inline]
+    fn is_closed(&self) -> bool {
+        matches!(self.state, ConnectionState::Closed)
+    }
+
+    fn get_poll_flags(&self) -> PollFlags {
+        match self.state {
+            ConnectionState::Sending { .. } => PollFlags::PollOut(),
+            _ if !self.interim.is_empty() => PollFlags::PollIn() | PollFlags::PollOut(),
+            _ => PollFlags::PollIn(),
+        }
+    }
+
+    /// Sends as much queued interim response data as the socket accepts without blocking
+    fn send_interim(&mut self) -> Result<()> {
+        if self.interim.is_empty() {
+            return Ok(());
+        }
+
+        match self.stream.send(&self.interim) {
+            Ok(sent_size) => {
+                self.interim
+                    .drain(..(sent_size as usize).min(self.interim.len()));
+                Ok(())
+            }
+            Err(rc) if is_would_block(rc) => Ok(()),
+            Err(rc) => Err(rc),
+        }
+    }
+
+    fn respond(
+        &mut self,
+        status_code: u16,
+        mut headers: Headers,
+        body: &[u8],
+        file: Option<FileSource>,
+        is_head: bool,
+    ) {
+        // No content responses can't have any length
+        if !headers.contains("Content-Length") && (status_code != 204) {
+            let content_length = match &file {
+                Some(file) => file.remaining,
+                None => body.len(),
+            };
+            headers.set("Content-Length", &content_length.to_string());
+        }
+        headers.set(
+            "Connection",
+            match self.keep_alive {
+                true => "keep-alive",
+                false => "close",
+            },
+        );
+        headers.set("Server", super::DEFAULT_USER_AGENT);
+
+        let Ok(mut data) = parse::serialize_response_head(status_code, &headers) else {
+            // Invalid handler-provided headers, the ones set above are always valid
+            self.keep_alive = false;
+            return self.respond(500, Headers::new(), &[], None, is_head);
+        };
+        if !is_head {
+            data.extend_from_slice(body);
+        }
+        self.state = ConnectionState::Sending {
+            data,
+            sent_size: 0,
+            file: file.filter(|_| !is_head),
+        };
+    }
+
+    fn respond_status(&mut self, status_code: u16, mut headers: Headers, is_head: bool) {
+        let body = alloc::format!(
+            "{} {}\n",
+            status_code,
+            parse::get_status_reason(status_code)
+        );
+        if !headers.contains("Content-Type") {
+            headers.set("Content-Type", "text/plain; charset=utf-8");
+        }
+        self.respond(status_code, headers, body.as_bytes(), None, is_head);
+    }
+
+    fn respond_error(&mut self, rc: ResultCode, is_head: bool) {
+        let status_code = if fsp_sf::rc::ResultPathNotFound::matches(rc) {
+            404
+        } else if fsp_sf::rc::ResultPathAlreadyExists::matches(rc) {
+            409
+        } else {
+            500
+        };
+        self.respond_status(status_code, Headers::new(), is_head);
+    }
+
+    fn handle_request(&mut self, config: &ServerConfig, head: RequestHead) {
+        self.keep_alive = head.is_keep_alive();
+        let is_head = head.method == Method::Head;
+
+        let body_length = match parse::get_request_body_length(&head) {
+            Ok(body_length) => body_length,
+            Err(_) => {
+                self.keep_alive = false;
+                return self.respond_status(400, Headers::new(), is_head);
+            }
+        };
+        let has_body = !matches!(body_length, BodyLength::None | BodyLength::Fixed(0));
+        // The body would be taken as the next request otherwise
+        if has_body && (head.method != Method::Put) {
+            self.keep_alive = false;
+        }
+
+        if !config.is_authorized(&head) {
+            self.keep_alive &= !has_body;
+            let mut headers = Headers::new();
+            headers.set("WWW-Authenticate", "Basic realm=\"nx\", charset=\"UTF-8\"");
+            return self.respond_status(401, headers, is_head);
+        }
+
+        let Some(request_path) = head.get_path() else {
+            self.keep_alive &= !has_body;
+            return self.respond_status(400, Headers::new(), is_head);
+        };
+        let Some(fs_path) = config.get_fs_path(&request_path) else {
+            self.keep_alive &= !has_body;
+            return self.respond_status(403, Headers::new(), is_head);
+        };
+
+        let result = match head.method {
+            Method::Get | Method::Head => self.handle_get(config, &head, &request_path, &fs_path),
+            Method::Put if !config.read_only => {
+                self.handle_put(config, &head, &request_path, &fs_path, body_length)
+            }
+            Method::Delete if !config.read_only => self.handle_delete(config, &fs_path),
+            Method::Options => {
+                let mut headers = Headers::new();
+                headers.set("Allow", config.get_allowed_methods());
+                self.respond(204, headers, &[], None, false);
+                Ok(())
+            }
+            _ => {
+                self.keep_alive &= !has_body;
+                let mut headers = Headers::new();
+                headers.set("Allow", config.get_allowed_methods());
+                self.respond_status(405, headers, is_head);
+                Ok(())
+            }
+        };
+
+        if let Err(rc) = result {
+            self.keep_alive &= !has_body;
+            self.respond_error(rc, is_head);
+        }
+    }
+
+    fn handle_get(
+        &mut self,
+        config: &ServerConfig,
+        head: &RequestHead,
+        request_path: &str,
+        fs_path: &str,
+    ) -> Result<()> {
+        let is_head = head.method == Method::Head;
+        match config.fs.get_entry_type(fs_path)? {
+            DirectoryEntryType::Directory => {
+                if !request_path.ends_with('/') {
+                    // Relative links in the listing need the trailing slash
+                    let mut headers = Headers::new();
+                    headers.set(
+                        "Location",
+                        &(parse::percent_encode_path(request_path) + "/"),
+                    );
+                    self.respond_status(301, headers, is_head);
+                    return Ok(());
+                }
+
+                let listing = Self::make_directory_listing(config, request_path, fs_path)?;
+                let mut headers = Headers::new();
+                headers.set("Content-Type", "text/html; charset=utf-8");
+                self.respond(200, headers, listing.as_bytes(), None, is_head);
+            }
+            DirectoryEntryType::File => {
+                let mut file = config.fs.open_file(fs_path, FileOpenMode::Read())?;
+                let size = file.get_size()?;
+
+                let mut headers = Headers::new();
+                headers.set("Content-Type", get_content_type(fs_path));
+                headers.set("Accept-Ranges", "bytes");
+                let range = match head.headers.get("Range") {
+                    Some(range) if head.method == Method::Get => {
+                        parse::parse_byte_range(range, size as u64)
+                    }
+                    _ => ByteRange::Full,
+                };
+                match range {
+                    ByteRange::Full => {
+                        let source = FileSource {
+                            file,
+                            offset: 0,
+                            remaining: size,
+                        };
+                        self.respond(200, headers, &[], Some(source), is_head);
+                    }
+                    ByteRange::Partial { start, end } => {
+                        headers.set(
+                            "Content-Range",
+                            &alloc::format!("bytes {}-{}/{}", start, end, size),
+                        );
+                        let source = FileSource {
+                            file,
+                            offset: start as usize,
+                            remaining: (end - start + 1) as usize,
+                        };
+                        self.respond(206, headers, &[], Some(source), is_head);
+                    }
+                    ByteRange::NotSatisfiable => {
+                        headers.set("Content-Range", &alloc::format!("bytes */{}", size));
+                        self.respond_status(416, headers, is_head);
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn handle_put(
+        &mut self,
+        config: &ServerConfig,
+        head: &RequestHead,
+        request_path: &str,
+        fs_path: &str,
+        body_length: BodyLength,
+    ) -> Result<()> {
+        if request_path.ends_with('/') {
+            // Uploading to a directory path creates the directory
+            self.keep_alive &= matches!(body_length, BodyLength::None | BodyLength::Fixed(0));
+            config.fs.create_directory(fs_path)?;
+            self.respond(201, Headers::new(), &[], None, false);
+            return Ok(());
+        }
+
+        let created = match config.fs.get_entry_type(fs_path) {
+            Ok(DirectoryEntryType::File) => false,
+            Ok(DirectoryEntryType::Directory) => {
+                self.keep_alive = false;
+                self.respond_status(409, Headers::new(), false);
+                return Ok(());
+            }
+            Err(_) => {
+                config.fs.create_file(fs_path, FileAttribute::None(), 0)?;
+                true
+            }
+        };
+        let mut file = config
+            .fs
+            .open_file(fs_path, FileOpenMode::Write() | FileOpenMode::Append())?;
+        file.set_size(0)?;
+
+        let body = match body_length {
+            BodyLength::Fixed(size) => UploadBody::Fixed { remaining: size },
+            BodyLength::Chunked => UploadBody::Chunked(parse::ChunkedDecoder::new()),
+            _ => UploadBody::Fixed { remaining: 0 },
+        };
+        // Clients waiting for this would otherwise delay sending the body (it's sent once the socket is writable, without blocking other connections)
+        if head.headers.contains_token("Expect", "100-continue") {
+            self.interim
+                .extend_from_slice(b"HTTP/1.1 100 Continue\r\n\r\n");
+        }
+        self.state = ConnectionState::ReceivingUpload {
+            file,
+            offset: 0,
+            body,
+            created,
+        };
+        Ok(())
+    }
+
+    fn handle_delete(&mut self, config: &ServerConfig, fs_path: &str) -> Result<()> {
+        if fs_path == config.root || fs_path == "/" {
+            self.respond_status(403, Headers::new(), false);
+            return Ok(());
+        }
+
+        match config.fs.get_entry_type(fs_path)? {
+            DirectoryEntryType::Directory => config.fs.remove_dir_all(fs_path)?,
+            DirectoryEntryType::File => config.fs.remove_file(fs_path)?,
+        }
+        self.respond(204, Headers::new(), &[], None, false);
+        Ok(())
+    }
+
+    fn make_directory_listing(
+        config: &ServerConfig,
+        request_path: &str,
+        fs_path: &str,
+    ) -> Result<String> {
+        let dir = config.fs.open_directory(
+            fs_path,
+            DirectoryOpenMode::ReadDirectories() | DirectoryOpenMode::ReadFiles(),
+        )?;
+
+        let mut entries: Vec<(String, DirectoryEntryType, usize)> = Vec::new();
+        let mut entry_buf = [DirectoryEntry::default(); 0x10];
+        loop {
+            let read_count = dir.read(&mut entry_buf)?;
+            if read_count == 0 {
+                break;
+            }
+            for entry in &entry_buf[..read_count] {
+                if let Ok(name) = entry.name.get_str() {
+                    entries.push((name.to_string(), entry.entry_type, entry.file_size));
+                }
+            }
+        }
+        // Directories first, then alphabetically
+        entries.sort_by(|a, b| {
+            (a.1 != DirectoryEntryType::Directory, &a.0)
+                .cmp(&(b.1 != DirectoryEntryType::Directory, &b.0))
+        });
+
+        let title = html_escape(request_path);
+        let mut listing = String::new();
+        let _ = write!(
+            listing,
+            "<!DOCTYPE html>\n<html><head><meta charset=\"utf-8\"><title>Index of {title}</title></head>\n<body><h1>Index of {title}</h1>\n<ul>\n"
+        );
+        if request_path != "/" {
+            listing.push_str("<li><a href=\"../\">../</a></li>\n");
+        }
+        for (name, entry_type, size) in entries {
+            let (suffix, size) = match entry_type {
+                DirectoryEntryType::Directory => ("/", String::new()),
+                DirectoryEntryType::File => ("", alloc::format!(" ({} bytes)", size)),
+            };
+            let _ = writeln!(
+                listing,
+                "<li><a href=\"{}{}\">{}{}</a>{}</li>",
+                parse::percent_encode_path(&name),
+                suffix,
+                html_escape(&name),
+                suffix,
+                size
+            );
+        }
+        listing.push_str("</ul></body></html>\n");
+        Ok(listing)
+    }
+
+    /// Receives available data, returning whether the connection is still open
+    fn receive(&mut self) -> Result<bool> {
+        let offset = self.buffer.len();
+        self.buffer.resize(offset + CHUNK_SIZE, 0);
+        let recv_result = self.stream.recv(&mut self.buffer[offset..]);
+        let recv_size = *recv_result.as_ref().unwrap_or(&0);
+        self.buffer.truncate(offset + recv_size);
+        match recv_result {
+            Ok(0) => Ok(false),
+            Ok(_) => Ok(true),
+            Err(rc) if is_would_block(rc) => Ok(true),
+            Err(rc) => Err(rc),
+        }
+    }
+
+    /// Processes buffered data (request heads and upload bodies) as far as possible
+    fn process_buffer(&mut self, config: &ServerConfig) {
+        loop {
+            match &mut self.state {
+                ConnectionState::ReadingHead => match parse::parse_request_head(&self.buffer) {
+                    Ok(Some((head, head_size))) => {
+                        self.buffer.drain(..head_size);
+                        self.handle_request(config, head);
+                    }
+                    Ok(None) => return,
+                    Err(rc) => {
+                        self.keep_alive = false;
+                        let status_code = if super::rc::ResultUnsupportedMethod::matches(rc) {
+                            501
+                        } else if super::rc::ResultRequestHeadTooLarge::matches(rc) {
+                            431
+                        } else {
+                            400
+                        };
+                        self.respond_status(status_code, Headers::new(), false);
+                        return;
+                    }
+                },
+                ConnectionState::ReceivingUpload {
+                    file,
+                    offset,
+                    body,
+                    created,
+                } => {
+                    let created = *created;
+                    match Self::write_upload(file.as_mut(), offset, body, &mut self.buffer) {
+                        Ok(true) => {
+                            let status_code = match created {
+                                true => 201,
+                                false => 204,
+                            };
+                            let _ = file.flush();
+                            self.respond(status_code, Headers::new(), &[], None, false);
+                        }
+                        Ok(false) => return,
+                        Err(rc) => {
+                            self.keep_alive = false;
+                            self.respond_error(rc, false);
+                        }
+                    }
+                }
+                ConnectionState::Sending { .. } | ConnectionState::Closed => return,
+            }
+        }
+    }
+
+    /// Writes buffered upload data to the file, returning whether the upload is complete
+    fn write_upload(
+        file: &mut dyn File,
+        offset: &mut usize,
+        body: &mut UploadBody,
+        buffer: &mut Vec<u8>,
+    ) -> Result<bool> {
+        match body {
+            UploadBody::Fixed { remaining } => {
+                let write_size = (*remaining).min(buffer.len() as u64) as usize;
+                if write_size > 0 {
+                    file.write(*offset, &buffer[..write_size], FileWriteOption::None())?;
+                    buffer.drain(..write_size);
+                    *offset += write_size;
+                    *remaining -= write_size as u64;
+                }
+                Ok(*remaining == 0)
+            }
+            UploadBody::Chunked(decoder) => {
+                let mut data = vec![0u8; CHUNK_SIZE];
+                while !buffer.is_empty() && !decoder.is_done() {
+                    let (consumed, produced) = decoder.decode(buffer, &mut data)?;
+                    buffer.drain(..consumed);
+                    if produced > 0 {
+                        file.write(*offset, &data[..produced], FileWriteOption::None())?;
+                        *offset += produced;
+                    }
+                }
+                Ok(decoder.is_done())
+            }
+        }
+    }
+
+    /// Sends pending response data, returning whether the whole response has been sent
+    fn send_pending(&mut self) -> Result<bool> {
+        let ConnectionState::Sending {
+            data,
+            sent_size,
+            file,
+        } = &mut self.state
+        else {
+            return Ok(true);
+        };
+
+        if *sent_size == data.len() {
+            let Some(source) = file.as_mut().filter(|source| source.remaining > 0) else {
+                return Ok(true);
+            };
+
+            data.resize(CHUNK_SIZE.min(source.remaining), 0);
+            let read_size = source
+                .file
+                .read(source.offset, data, FileReadOption::None())?;
+            // The file was truncated meanwhile, thus the promised length can't be sent
+            result_return_if!(read_size == 0, super::rc::ResultConnectionClosed);
+            data.truncate(read_size);
+            source.offset += read_size;
+            source.remaining -= read_size;
+            *sent_size = 0;
+        }
+
+        match self.stream.send(&data[*sent_size..]) {
+            Ok(size) => *sent_size += size as usize,
+            Err(rc) if is_would_block(rc) => {}
+            Err(rc) => return Err(rc),
+        }
+        Ok((*sent_size == data.len()) && file.as_ref().is_none_or(|source| source.remaining == 0))
+    }
+
+    /// Handles the socket events of the connection
+    fn handle_events(&mut self, config: &ServerConfig, events: PollFlags) -> Result<()> {
+        self.last_activity = Instant::now();
+        self.send_interim()?;
+        match self.state {
+            // The final response waits until interim ones are sent
+            ConnectionState::Sending { .. } if !self.interim.is_empty() => {}
+            ConnectionState::Sending { .. } => {
+                if self.send_pending()? {
+                    if self.keep_alive {
+                        // Pipelined requests may be already buffered
+                        self.state = ConnectionState::ReadingHead;
+                        self.process_buffer(config);
+                    } else {
+                        self.state = ConnectionState::Closed;
+                    }
+                }
+            }
+            _ => {
+                if !self.receive()? {
+                    self.state = ConnectionState::Closed;
+                    return Ok(());
+                }
+                self.process_buffer(config);
+            }
+        }
+
+        if events.intersects(PollFlags::PollError() | PollFlags::PollInvalid()) {
+            self.state = ConnectionState::Closed;
+        }
+        Ok(())
+    }
+}
+
+fn html_escape(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Guesses the `Content-Type` of a file from its extension
+fn get_content_type(path: &str) -> &'static str {
+    let extension = path
+        .rsplit_once('.')
+        .map(|(_, extension)| extension)
+        .unwrap_or_default();
+    match extension.to_ascii_lowercase().as_str() {
+        "txt" | "log" | "ini" | "cfg" => "text/plain; charset=utf-8",
+        "html" | "htm" => "text/html; charset=utf-8",
+        "css" => "text/css",
+        "js" => "text/javascript",
+        "json" => "application/json",
+        "xml" => "application/xml",
+        "png" => "image/png",
+        "jpg" | "jpeg" => "image/jpeg",
+        "gif" => "image/gif",
+        "bmp" => "image/bmp",
+        "mp4" => "video/mp4",
+        "wav" => "audio/wav",
+        "ogg" => "audio/ogg",
+        "zip" => "application/zip",
+        _ => "application/octet-stream",
+    }
+}
+
+/// Represents a HTTP file server
+pub struct FileServer {
+    listener: TcpListener,
+    config: ServerConfig,
+    connections: Vec<ServerConnection>,
+    max_connections: usize,
+}
+
+impl FileServer {
+    /// Creates a new [`FileServer`] listening on the given address, serving the whole filesystem
+    ///
+    /// # Arguments
+    ///
+    /// * `fs`: The filesystem to serve
+    /// * `ip`: The address to listen on
+    /// * `port`: The port to listen on
+    pub fn bind(fs: Arc<dyn FileSystem>, ip: Ipv4Addr, port: u16) -> Result<Self> {
+        Ok(Self {
+            listener: TcpListener::bind((ip, port))?,
+            config: ServerConfig {
+                fs,
+                root: String::new(),
+                credentials: None,
+                read_only: false,
+            },
+            connections: Vec::new(),
+            max_connections: DEFAULT_MAX_CONNECTIONS,
+        })
+    }
+
+    /// Creates a new [`FileServer`] listening on the given address, serving a path of a mounted filesystem (like `sdmc:/switch`)
+    ///
+    /// # Arguments
+    ///
+    /// * `path`: The path to serve (see [`fs::format_path`])
+    /// * `ip`: The address to listen on
+    /// * `port`: The port to listen on
+    pub fn bind_path(path: &str, ip: Ipv4Addr, port: u16) -> Result<Self> {
+        let (fs, root) = fs::format_path(path)?;
+        let mut server = Self::bind(fs, ip, port)?;
+        server.set_root(root);
+        Ok(server)
+    }
+
+    /// Sets the filesystem path served as the server root, outside of which nothing can be accessed
+    ///
+    /// # Arguments
+    ///
+    /// * `root`: The root path
+    pub fn set_root(&mut self, root: &str) {
+        self.config.root = root.trim_end_matches('/').to_string();
+        if !self.config.root.is_empty() && !self.config.root.starts_with('/') {
+            self.config.root.insert(0, '/');
+        }
+    }
+
+    /// Requires `Basic` authentication with the given credentials for all requests
+    ///
+    /// # Arguments
+    ///
+    /// * `user`: The user name
+    /// * `password`: The password
+    pub fn set_basic_auth(&mut self, user: &str, password: &str) {
+        self.config.credentials = Some((user.to_string(), password.to_string()));
+    }
+
+    /// Removes the authentication requirement
+    #[inline]
+    pub fn clear_basic_auth(&mut self) {
+        self.config.credentials = None;
+    }
+
+    /// Sets whether the server is read-only, rejecting `PUT` and `DELETE` requests
+    ///
+    /// # Arguments
+    ///
+    /// * `read_only`: Whether the server is read-only
+    #[inline]
+    pub fn set_read_only(&mut self, read_only: bool) {
+        self.config.read_only = read_only;
+    }
+
+    /// Sets the maximum number of simultaneous connections (further ones are not accepted until others are closed)
+    ///
+    /// # Arguments
+    ///
+    /// * `max_connections`: The maximum connection count
+    #[inline]
+    pub fn set_max_connections(&mut self, max_connections: usize) {
+        self.max_connections = max_connections.max(1);
+    }
+
+    /// Gets the current connection count
+    #[inline]
+    pub fn get_connection_count(&self) -> usize {
+        self.connections.len()
+    }
+
+    /// Gets the underlying [`TcpListener`]
+    #[inline]
+    pub fn get_listener(&self) -> &TcpListener {
+        &self.listener
+    }
+
+    /// Waits for socket events and handles them: accepts new connections, receives requests and sends responses
+    ///
+    /// While there are open connections, this returns at least every second (regardless of the timeout) in order to close idle ones
+    ///
+    /// # Arguments
+    ///
+    /// * `timeout`: The wait timeout
+    pub fn poll(&mut self, timeout: impl Into<Timeout>) -> Result<()> {
+        let mut deadline = timeout.into().get_deadline();
+        if !self.connections.is_empty() {
+            let idle_check_deadline = Instant::now() + Duration::from_secs(1);
+            deadline = Some(deadline.map_or(idle_check_deadline, |deadline| {
+                deadline.min(idle_check_deadline)
+            }));
+        }
+
+        let accepts_connections = self.connections.len() < self.max_connections;
+        let mut pollers: Vec<(PollTarget, PollFlags)> = self
+            .connections
+            .iter()
+            .map(|connection| {
+                (
+                    PollTarget(connection.stream.as_raw_fd()),
+                    connection.get_poll_flags(),
+                )
+            })
+            .collect();
+        if accepts_connections {
+            pollers.push((PollTarget(self.listener.get_poll_fd()), PollFlags::PollIn()));
+        }
+
+        let ready_events: Vec<(usize, PollFlags)> =
+            socket::net::poll(&pollers, deadline)?.collect();
+        for (index, events) in ready_events {
+            match self.connections.get_mut(index) {
+                Some(connection) => {
+                    if connection.handle_events(&self.config, events).is_err() {
+                        connection.state = ConnectionState::Closed;
+                    }
+                }
+                None => {
+                    // Errors accepting (like the client giving up) don't affect the server
+                    if let Ok((stream, _)) = self.listener.accept()
+                        && let Ok(connection) = ServerConnection::new(stream)
+                    {
+                        self.connections.push(connection);
+                    }
+                }
+            }
+        }
+
+        self.connections.retain(|connection| {
+            !connection.is_closed()
+                && (connection.last_activity.elapsed() < CONNECTION_IDLE_TIMEOUT)
+        });
+        Ok(())
+    }
+
+    /// Serves requests (see [`FileServer::poll`]) while the given callback returns `true`, checking it at least every second
+    ///
+    /// # Arguments
+    ///
+    /// * `should_continue`: Whether to keep serving
+    pub fn run(&mut self, mut should_continue: impl FnMut() -> bool) -> Result<()> {
+        while should_continue() {
+            self.poll(Duration::from_secs(1))?;
+        }
+        Ok(())
+    }
+}
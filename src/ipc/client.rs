@@ -1,6 +1,10 @@
 use sf::hipc;
 
 use super::*;
+use crate::time::Timeout;
+use crate::wait;
+use alloc::boxed::Box;
+use core::marker::PhantomData;
 
 pub trait RequestCommandParameter {
     fn before_request_write(
@@ -187,3 +191,138 @@ pub trait IClientObject {
         self.get_info().is_domain()
     }
 }
+
+/// Size of the page-aligned buffer holding the message of asynchronous requests
+pub const ASYNC_REQUEST_BUFFER_SIZE: usize = 0x1000;
+
+/// Size of the TLS message buffer, the only part of the async buffer which gets actually used
+const MSG_BUFFER_SIZE: usize = 0x100;
+
+/// Represents the (page-aligned) user buffer the kernel uses as the message buffer for asynchronous requests
+#[derive(Clone)]
+#[repr(C, align(0x1000))]
+pub struct AsyncRequestBuffer([u8; ASYNC_REQUEST_BUFFER_SIZE]);
+const_assert!(core::mem::align_of::<AsyncRequestBuffer>() == ASYNC_REQUEST_BUFFER_SIZE);
+
+impl AsyncRequestBuffer {
+    /// Creates a new, zeroed [`AsyncRequestBuffer`]
+    pub const fn new() -> Self {
+        Self([0; ASYNC_REQUEST_BUFFER_SIZE])
+    }
+}
+
+impl Default for AsyncRequestBuffer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Reads the output parameters of a request once its response has been received
+pub type ResponseReadFn<O> = fn(&mut DataWalker, &mut CommandContext) -> Result<O>;
+
+/// Represents a request which was sent asynchronously (see [`send_async_request_with_user_data`][`svc::send_async_request_with_user_data`]) and whose response has not been read yet
+///
+/// The `'req` lifetime covers every buffer passed to the request, since the server may access them until it replies
+///
+/// The event handle gets signaled once the server replies, so a pending request can be waited on (see [`get_waiter`][`PendingRequest::get_waiter`]) alongside other requests or events
+pub struct PendingRequest<'req, O> {
+    ctx: CommandContext,
+    event_handle: svc::Handle,
+    buffer: Box<AsyncRequestBuffer>,
+    read_response_fn: ResponseReadFn<O>,
+    response_read: bool,
+    _request_lifetime: PhantomData<&'req ()>,
+}
+
+impl<O> PendingRequest<'_, O> {
+    /// Sends the request currently written on the thread's message buffer asynchronously
+    ///
+    /// This is meant to be called by the async client functions of the IPC interface traits, right after the request is written
+    ///
+    /// # Arguments
+    ///
+    /// * `ctx`: The context the request was written with
+    /// * `read_response_fn`: The function reading the output parameters after the response is received
+    pub fn send(ctx: CommandContext, read_response_fn: ResponseReadFn<O>) -> Result<Self> {
+        let mut buffer = Box::new(AsyncRequestBuffer::new());
+        unsafe {
+            ptr::copy_nonoverlapping(get_msg_buffer(), buffer.0.as_mut_ptr(), MSG_BUFFER_SIZE);
+        }
+
+        let event_handle = unsafe {
+            svc::send_async_request_with_user_data(&mut buffer.0, ctx.object_info.handle)?
+        };
+        Ok(Self {
+            ctx,
+            event_handle,
+            buffer,
+            read_response_fn,
+            response_read: false,
+            _request_lifetime: PhantomData,
+        })
+    }
+
+    /// Gets the handle of the event which is signaled when the response is received
+    #[inline]
+    pub fn get_event_handle(&self) -> svc::Handle {
+        self.event_handle
+    }
+
+    /// Gets a [`Waiter`][`wait::Waiter`] for this request, to be used with [`wait::wait`]
+    #[inline]
+    pub fn get_waiter(&self) -> wait::Waiter {
+        wait::Waiter::from_handle(self.event_handle)
+    }
+
+    /// Returns whether the response has already been received, without blocking
+    pub fn is_ready(&self) -> Result<bool> {
+        match svc::wait_synchronization_one(self.event_handle, 0) {
+            Ok(()) => Ok(true),
+            Err(rc) if svc::rc::ResultTimedOut::matches(rc) => Ok(false),
+            Err(rc) => Err(rc),
+        }
+    }
+
+    /// Waits for the response to be received with a given timeout
+    ///
+    /// # Arguments
+    ///
+    /// * `timeout` - Wait timeout (see [`Timeout`])
+    #[inline]
+    pub fn wait(&self, timeout: impl Into<Timeout>) -> Result<()> {
+        wait::wait_handles(&[self.event_handle], timeout).map(|_| ())
+    }
+
+    /// Waits for the response (if it wasn't received yet) and reads its output parameters
+    pub fn get_response(mut self) -> Result<O> {
+        self.wait(Timeout::Infinite)?;
+        self.response_read = true;
+
+        unsafe {
+            ptr::copy_nonoverlapping(self.buffer.0.as_ptr(), get_msg_buffer(), MSG_BUFFER_SIZE);
+        }
+        match self.ctx.object_info.protocol {
+            CommandProtocol::Cmif => {
+                cmif::client::read_request_command_response_from_msg_buffer(&mut self.ctx)?
+            }
+            CommandProtocol::Tipc => {
+                tipc::client::read_request_command_response_from_msg_buffer(&mut self.ctx)?
+            }
+        };
+
+        let mut walker = DataWalker::new(self.ctx.out_params.data_offset);
+        (self.read_response_fn)(&mut walker, &mut self.ctx)
+    }
+}
+
+impl<O> Drop for PendingRequest<'_, O> {
+    /// Destroys the [`PendingRequest`], closing its event handle
+    ///
+    /// If the response wasn't read, this waits for it first: the kernel would otherwise write the reply into an already freed buffer
+    fn drop(&mut self) {
+        if !self.response_read {
+            let _ = self.wait(Timeout::Infinite);
+        }
+        let _ = svc::close_handle(self.event_handle);
+    }
+}
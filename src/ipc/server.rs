@@ -1,5 +1,6 @@
 use super::*;
 use crate::sync::Mutex;
+use crate::time::Duration;
 use crate::wait;
 use alloc::rc::Rc;
 use alloc::vec::Vec;
@@ -249,6 +250,9 @@ pub trait IServerObject: ISessionObject {
         Self: Sized;
 }
 
+/// Represents a server object of a mitm'd service
+///
+/// Any request the object doesn't handle (request IDs not present in its interface, objects of a domain it didn't create, or commands returning [`ResultShouldForwardToSession`][`sm::mitm::rc::ResultShouldForwardToSession`]) is forwarded as-is to the original service, thus mitm interfaces only need to declare the commands they intend to override
 pub trait IMitmServerObject: ISessionObject {
     fn new(info: sm::mitm::MitmProcessInfo) -> Self
     where
@@ -280,6 +284,8 @@ pub enum WaitHandleType {
 pub struct DomainTable {
     pub table: Vec<cmif::DomainObjectId>,
     pub domains: Vec<ServerHolder>,
+    /// Objects of the original service's domain (for mitm'd domains), as `(our ID, original ID)`
+    pub forwarded_ids: Vec<(cmif::DomainObjectId, cmif::DomainObjectId)>,
 }
 
 impl DomainTable {
@@ -287,6 +293,7 @@ impl DomainTable {
         Self {
             table: Vec::new(),
             domains: Vec::new(),
+            forwarded_ids: Vec::new(),
         }
     }
 
@@ -334,6 +341,46 @@ impl DomainTable {
         self.table.retain(|&id| id != domain_object_id);
         self.domains
             .retain(|holder| holder.info.domain_object_id != domain_object_id);
+        self.forwarded_ids.retain(|&(id, _)| id != domain_object_id);
+    }
+
+    /// Maps an object of the original service's domain to one of our IDs
+    ///
+    /// Our IDs and the original service's ones are allocated independently, thus the original ID can't be reused as-is (it might already refer to one of our objects)
+    ///
+    /// # Arguments
+    ///
+    /// * `forward_domain_object_id`: The ID of the object in the original service's domain
+    /// * `specific_domain_object_id`: The ID to map it to, if any specific one is needed (otherwise a new one is allocated)
+    pub fn allocate_forwarded_id(
+        &mut self,
+        forward_domain_object_id: cmif::DomainObjectId,
+        specific_domain_object_id: Option<cmif::DomainObjectId>,
+    ) -> Result<cmif::DomainObjectId> {
+        let domain_object_id = match specific_domain_object_id {
+            Some(specific_domain_object_id) => {
+                self.allocate_specific_id(specific_domain_object_id)?
+            }
+            None => self.allocate_id()?,
+        };
+        self.forwarded_ids
+            .push((domain_object_id, forward_domain_object_id));
+        Ok(domain_object_id)
+    }
+
+    /// Gets the ID in the original service's domain of one of our IDs, if it's mapped to an object of the original service (see [`allocate_forwarded_id`][`DomainTable::allocate_forwarded_id`])
+    ///
+    /// # Arguments
+    ///
+    /// * `domain_object_id`: Our object ID
+    pub fn find_forwarded_id(
+        &self,
+        domain_object_id: cmif::DomainObjectId,
+    ) -> Option<cmif::DomainObjectId> {
+        self.forwarded_ids
+            .iter()
+            .find(|&&(id, _)| id == domain_object_id)
+            .map(|&(_, forward_id)| forward_id)
     }
 }
 
@@ -497,7 +544,10 @@ impl ServerHolder {
                 let forward_object_id =
                     self.mitm_forward_info.convert_current_object_to_domain()?;
                 self.mitm_forward_info.domain_object_id = forward_object_id;
-                dom_table.lock().allocate_specific_id(forward_object_id)?
+                // Keep the same ID as the original object, for clarity
+                dom_table
+                    .lock()
+                    .allocate_forwarded_id(forward_object_id, Some(forward_object_id))?
             }
             false => dom_table.lock().allocate_id()?,
         };
@@ -541,6 +591,150 @@ impl Drop for ServerHolder {
     }
 }
 
+/// Forwards a received request (as backed up in `ipc_buf_backup`) to the original session of a mitm'd service, leaving its response on the message buffer to be replied with
+///
+/// Buffers and handles are forwarded as they were received. The only adjustments are:
+///
+/// * Receive statics are redirected to our pointer buffer, where the original service will write the response pointer data
+/// * The process ID (if sent) is tagged as forwarded, so that the original service sees the mitm'd client's process
+/// * Copy handles of both the request and the response are added to `handles_to_close`, since our copies are kept after sending them
+/// * For domain requests, the target object and input object IDs are translated to the original service's IDs, and output object IDs are mapped to newly allocated IDs of ours (see [`DomainTable::allocate_forwarded_id`])
+///
+/// # Arguments
+///
+/// * `forward_handle`: The handle of the original service's session
+/// * `ipc_buf_backup`: The request, as it was received
+/// * `pointer_buffer`: The server's pointer buffer
+/// * `domain_table`: The domain table, if the request was sent to a domain
+/// * `handles_to_close`: The handles to close after replying
+pub fn forward_request(
+    forward_handle: svc::Handle,
+    ipc_buf_backup: &[u8],
+    pointer_buffer: &mut [u8],
+    domain_table: Option<&Rc<Mutex<DomainTable>>>,
+    handles_to_close: &mut Vec<svc::Handle>,
+) -> Result<()> {
+    let ipc_buf = get_msg_buffer();
+    unsafe {
+        core::ptr::copy(ipc_buf_backup.as_ptr(), ipc_buf, ipc_buf_backup.len());
+    }
+
+    let mut request_ctx = CommandContext::empty();
+    cmif::server::read_command_from_msg_buffer(&mut request_ctx);
+
+    if let Some(domain_table) = domain_table {
+        // Only objects of the original service can be sent there
+        let domain_table = domain_table.lock();
+        let translate_id = |domain_object_id: cmif::DomainObjectId| {
+            domain_table
+                .find_forwarded_id(domain_object_id)
+                .ok_or(rc::ResultDomainNotFound::make())
+        };
+
+        unsafe {
+            let domain_header =
+                get_aligned_data_offset(request_ctx.in_params.data_words_offset, ipc_buf)
+                    as *mut cmif::DomainInDataHeader;
+            let mut header = domain_header.read_unaligned();
+            header.domain_object_id = translate_id(header.domain_object_id)?;
+            domain_header.write_unaligned(header);
+
+            let objects = (domain_header.add(1) as *mut u8).add(header.data_size as usize)
+                as *mut cmif::DomainObjectId;
+            for i in 0..header.object_count as usize {
+                let object = objects.add(i);
+                object.write_unaligned(translate_id(object.read_unaligned())?);
+            }
+        }
+    }
+
+    handles_to_close.extend_from_slice(&request_ctx.in_params.copy_handles);
+
+    unsafe {
+        if request_ctx.in_params.send_process_id {
+            let process_id_ptr = ipc_buf
+                .add(mem::size_of::<CommandHeader>() + mem::size_of::<CommandSpecialHeader>())
+                as *mut u64;
+            process_id_ptr.write_unaligned(sm::mitm::make_forwarded_process_id(
+                request_ctx.in_params.process_id,
+            ));
+        }
+
+        if !request_ctx.receive_statics.is_empty() && !pointer_buffer.is_empty() {
+            // Use a single receive static (our whole pointer buffer) for all the response pointer data
+            let command_header = ipc_buf as *mut CommandHeader;
+            let header = *command_header;
+            *command_header = CommandHeader::new(
+                header.get_command_type(),
+                header.get_send_static_count(),
+                header.get_send_buffer_count(),
+                header.get_receive_buffer_count(),
+                header.get_exchange_buffer_count(),
+                header.get_data_word_count(),
+                0xFF,
+                header.get_has_special_header(),
+            );
+
+            let receive_statics = request_ctx
+                .in_params
+                .data_words_offset
+                .add(request_ctx.in_params.data_size as usize)
+                as *mut ReceiveStaticDescriptor;
+            receive_statics.write_unaligned(ReceiveStaticDescriptor::new(
+                pointer_buffer.as_ptr(),
+                pointer_buffer.len(),
+            ));
+        }
+    }
+
+    svc::send_sync_request(forward_handle)?;
+
+    let mut response_ctx = CommandContext::empty();
+    cmif::client::read_command_response_from_msg_buffer(&mut response_ctx);
+    handles_to_close.extend_from_slice(&response_ctx.out_params.copy_handles);
+
+    if let Some(domain_table) = domain_table {
+        unsafe {
+            let data_offset =
+                get_aligned_data_offset(response_ctx.out_params.data_words_offset, ipc_buf);
+            let domain_header = data_offset as *const cmif::DomainOutDataHeader;
+            let data_header = domain_header.add(1) as *const cmif::DataHeader;
+            let header = data_header.read_unaligned();
+
+            if header.magic == cmif::OUT_DATA_HEADER_MAGIC && header.value == 0 {
+                // The output data size isn't known to us, thus (like with the input data of requests) it's whatever is left of the data words besides the padding, the headers and the object IDs, which follow it
+                let object_count = domain_header.read_unaligned().out_object_count as usize;
+                let data_word_count = (*(ipc_buf as *const CommandHeader)).get_data_word_count();
+                response_ctx.out_params.data_size = (data_word_count as usize
+                    * mem::size_of::<u32>())
+                .checked_sub(
+                    DATA_PADDING as usize
+                        + mem::size_of::<cmif::DomainOutDataHeader>()
+                        + mem::size_of::<cmif::DataHeader>()
+                        + object_count * mem::size_of::<cmif::DomainObjectId>(),
+                )
+                .ok_or(cmif::rc::ResultInvalidOutputHeader::make())?
+                    as u32;
+                response_ctx.out_params.data_offset = data_header.add(1) as *mut u8;
+                response_ctx.out_params.objects_offset = response_ctx
+                    .out_params
+                    .data_offset
+                    .add(response_ctx.out_params.data_size as usize);
+
+                let objects = response_ctx.out_params.objects_offset as *mut cmif::DomainObjectId;
+                let mut domain_table = domain_table.lock();
+                for i in 0..object_count {
+                    let object = objects.add(i);
+                    object.write_unaligned(
+                        domain_table.allocate_forwarded_id(object.read_unaligned(), None)?,
+                    );
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
 pub struct HipcManager<'a> {
     server_holder: &'a mut ServerHolder,
     pointer_buf_size: usize,
@@ -707,6 +901,7 @@ impl<const P: usize> ServerManager<P> {
     }
 
     #[inline(always)]
+    #[allow(clippy::too_many_arguments)]
     fn handle_request_command(
         &mut self,
         ctx: &mut CommandContext,
@@ -715,42 +910,57 @@ impl<const P: usize> ServerManager<P> {
         domain_command_type: cmif::DomainCommandType,
         ipc_buf_backup: &[u8],
         domain_table: Option<Rc<Mutex<DomainTable>>>,
+        handles_to_close: &mut Vec<svc::Handle>,
     ) -> Result<()> {
         let is_domain = ctx.object_info.is_domain();
         let domain_table_clone = domain_table.clone();
+        let pointer_buffer = &mut self.pointer_buffer;
+        let server_holders = &mut self.server_holders;
         let do_handle_request = || -> Result<()> {
             let mut new_sessions: Vec<ServerHolder> = Vec::new();
-            for server_holder in &mut self.server_holders {
+            for server_holder in server_holders.iter_mut() {
                 let server_info = server_holder.info;
                 if server_info.handle == ctx.object_info.handle {
-                    let send_to_forward_handle = || -> Result<()> {
-                        let ipc_buf = get_msg_buffer();
-                        unsafe {
-                            core::ptr::copy(ipc_buf_backup.as_ptr(), ipc_buf, ipc_buf_backup.len());
-                        }
+                    let mut send_to_forward_handle = || -> Result<()> {
                         // Let the original service take care of the command for us.
-                        svc::send_sync_request(server_holder.mitm_forward_info.handle)
+                        forward_request(
+                            server_holder.mitm_forward_info.handle,
+                            ipc_buf_backup,
+                            pointer_buffer,
+                            domain_table_clone.as_ref().filter(|_| is_domain),
+                            handles_to_close,
+                        )
                     };
 
                     let target_server = match is_domain {
                         true => match ctx.object_info.owns_handle {
-                            true => server_holder
-                                .server
-                                .clone()
-                                .ok_or(rc::ResultSignaledServerNotFound::make())?,
-                            false => domain_table
+                            true => Some(
+                                server_holder
+                                    .server
+                                    .clone()
+                                    .ok_or(rc::ResultSignaledServerNotFound::make())?,
+                            ),
+                            false => match domain_table
                                 .ok_or(rc::ResultDomainNotFound::make())?
                                 .lock()
-                                .find_domain(ctx.object_info.domain_object_id)?,
+                                .find_domain(ctx.object_info.domain_object_id)
+                            {
+                                Ok(target_server) => Some(target_server),
+                                // Objects we don't know about were created by the original service, so they're handled there
+                                Err(_) if server_holder.is_mitm_service => None,
+                                Err(rc) => return Err(rc),
+                            },
                         },
-                        false => server_holder
-                            .server
-                            .clone()
-                            .ok_or(rc::ResultSignaledServerNotFound::make())?,
+                        false => Some(
+                            server_holder
+                                .server
+                                .clone()
+                                .ok_or(rc::ResultSignaledServerNotFound::make())?,
+                        ),
                     };
                     // Nothing done on success here, as if the command succeeds it will automatically respond by itself.
                     let mut command_found = false;
-                    {
+                    if let Some(target_server) = target_server {
                         let protocol = ctx.object_info.protocol;
                         let mut server_ctx = ServerContext::new(
                             ctx,
@@ -806,7 +1016,7 @@ impl<const P: usize> ServerManager<P> {
                 }
             }
 
-            self.server_holders.append(&mut new_sessions);
+            server_holders.append(&mut new_sessions);
 
             Ok(())
         };
@@ -822,8 +1032,30 @@ impl<const P: usize> ServerManager<P> {
             cmif::DomainCommandType::SendMessage => do_handle_request()?,
             cmif::DomainCommandType::Close => {
                 if !ctx.object_info.owns_handle {
-                    domain_table_clone
-                        .ok_or(rc::ResultDomainNotFound::make())?
+                    let domain_table = domain_table_clone.ok_or(rc::ResultDomainNotFound::make())?;
+                    let is_forwarded_object = domain_table
+                        .lock()
+                        .find_forwarded_id(ctx.object_info.domain_object_id)
+                        .is_some();
+                    if is_forwarded_object
+                        && let Some(server_holder) = self
+                            .server_holders
+                            .iter()
+                            .find(|holder| holder.info.handle == ctx.object_info.handle)
+                        && server_holder.is_mitm_service
+                    {
+                        // The object belongs to the original service, let it close it (with its original ID)
+                        forward_request(
+                            server_holder.mitm_forward_info.handle,
+                            ipc_buf_backup,
+                            &mut self.pointer_buffer,
+                            Some(&domain_table),
+                            handles_to_close,
+                        )?;
+                    }
+
+                    // Either way, drop our ID of it
+                    domain_table
                         .lock()
                         .deallocate_domain(ctx.object_info.domain_object_id);
                 } else {
@@ -909,6 +1141,7 @@ impl<const P: usize> ServerManager<P> {
         let mut rq_id: u32 = 0;
         let mut ipc_buf_backup: [u8; 0x100] = [0; 0x100];
         let mut domain_table: Option<Rc<Mutex<DomainTable>>> = None;
+        let mut handles_to_close: Vec<svc::Handle> = Vec::new();
 
         for server_holder in &mut self.server_holders {
             let server_info = server_holder.info;
@@ -1038,8 +1271,14 @@ impl<const P: usize> ServerManager<P> {
                     domain_cmd_type,
                     &ipc_buf_backup,
                     domain_table,
+                    &mut handles_to_close,
                 )?;
                 reply_impl()?;
+
+                // The (forwarded) copy handles stay in our handle table after being sent
+                for handle in handles_to_close {
+                    let _ = svc::close_handle(handle);
+                }
             }
             cmif::CommandType::Control | cmif::CommandType::ControlWithContext => {
                 self.handle_control_command(&mut ctx, rq_id, command_type)?;
@@ -1133,7 +1372,7 @@ impl<const P: usize> ServerManager<P> {
 
     pub fn process(&mut self) -> Result<()> {
         let handles = self.prepare_wait_handles();
-        let index = wait::wait_handles(handles, 100_000)?;
+        let index = wait::wait_handles(handles, Duration::from_micros(100))?;
 
         let signaled_handle = self.wait_handles[index];
         self.process_signaled_handle(signaled_handle)?;
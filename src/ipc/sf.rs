@@ -788,3 +788,17 @@ pub mod ncm;
 pub mod lr;
 
 pub mod bsd;
+
+pub mod time;
+
+pub mod audout;
+
+pub mod audren;
+
+pub mod audin;
+
+pub mod nifm;
+
+pub mod sfdnsres;
+
+pub mod ssl;
@@ -0,0 +1,114 @@
+use crate::ipc::sf;
+use crate::version;
+
+use nx_derive::{Request, Response};
+
+pub use super::AppletResourceUserId;
+
+// Input devices share the naming, sample formats and buffer layout with output devices
+pub use super::audout::{AUDIO_BUFFER_ALIGNMENT, AudioBuffer, AudioDeviceName, PcmFormat};
+
+#[derive(Request, Response, Copy, Clone, PartialEq, Eq, Debug, Default)]
+#[repr(u32)]
+pub enum AudioInState {
+    #[default]
+    Started = 0,
+    Stopped = 1,
+}
+
+#[derive(Request, Response, Copy, Clone, PartialEq, Eq, Debug, Default)]
+#[repr(C)]
+pub struct AudioInParameter {
+    pub sample_rate: u32,
+    pub channel_count: u16,
+    pub reserved: u16,
+}
+const_assert!(core::mem::size_of::<AudioInParameter>() == 0x8);
+
+#[derive(Request, Response, Copy, Clone, PartialEq, Eq, Debug, Default)]
+#[repr(C)]
+pub struct AudioInParameterInternal {
+    pub sample_rate: u32,
+    pub channel_count: u32,
+    pub sample_format: PcmFormat,
+    pub state: AudioInState,
+}
+const_assert!(core::mem::size_of::<AudioInParameterInternal>() == 0x10);
+
+#[nx_derive::ipc_trait]
+#[default_client]
+pub trait AudioIn {
+    #[ipc_rid(0)]
+    fn get_audio_in_state(&self) -> AudioInState;
+    #[ipc_rid(1)]
+    fn start(&mut self);
+    #[ipc_rid(2)]
+    fn stop(&mut self);
+    #[ipc_rid(3)]
+    fn append_audio_in_buffer(&mut self, tag: u64, buffer: sf::InMapAliasBuffer<'_, AudioBuffer>);
+    #[ipc_rid(4)]
+    fn register_buffer_event(&mut self) -> sf::CopyHandle;
+    #[ipc_rid(5)]
+    fn get_released_audio_in_buffers(&mut self, out_tags: sf::OutMapAliasBuffer<'_, u64>) -> u32;
+    #[ipc_rid(6)]
+    fn contains_audio_in_buffer(&self, tag: u64) -> bool;
+    #[ipc_rid(8)]
+    #[version(version::VersionInterval::from(version::Version::new(3, 0, 0)))]
+    fn append_audio_in_buffer_auto(
+        &mut self,
+        tag: u64,
+        buffer: sf::InAutoSelectBuffer<'_, AudioBuffer>,
+    );
+    #[ipc_rid(9)]
+    #[version(version::VersionInterval::from(version::Version::new(3, 0, 0)))]
+    fn get_released_audio_in_buffers_auto(
+        &mut self,
+        out_tags: sf::OutAutoSelectBuffer<'_, u64>,
+    ) -> u32;
+    #[ipc_rid(11)]
+    #[version(version::VersionInterval::from(version::Version::new(4, 0, 0)))]
+    fn get_audio_in_buffer_count(&self) -> u32;
+    #[ipc_rid(12)]
+    #[version(version::VersionInterval::from(version::Version::new(4, 0, 0)))]
+    fn set_device_gain(&mut self, gain: f32);
+    #[ipc_rid(13)]
+    #[version(version::VersionInterval::from(version::Version::new(4, 0, 0)))]
+    fn get_device_gain(&self) -> f32;
+    #[ipc_rid(14)]
+    #[version(version::VersionInterval::from(version::Version::new(6, 0, 0)))]
+    fn flush_audio_in_buffers(&mut self) -> bool;
+}
+
+#[nx_derive::ipc_trait]
+pub trait AudioInManager {
+    #[ipc_rid(0)]
+    fn list_audio_ins(&self, out_names: sf::OutMapAliasBuffer<'_, AudioDeviceName>) -> u32;
+    #[ipc_rid(1)]
+    fn open_audio_in(
+        &mut self,
+        parameter: AudioInParameter,
+        aruid: AppletResourceUserId,
+        self_process_handle: sf::CopyHandle,
+        name: sf::InMapAliasBuffer<'_, AudioDeviceName>,
+        out_name: sf::OutMapAliasBuffer<'_, AudioDeviceName>,
+    ) -> (AudioInParameterInternal, AudioIn);
+    #[ipc_rid(2)]
+    #[version(version::VersionInterval::from(version::Version::new(3, 0, 0)))]
+    fn list_audio_ins_auto(&self, out_names: sf::OutAutoSelectBuffer<'_, AudioDeviceName>) -> u32;
+    #[ipc_rid(3)]
+    #[version(version::VersionInterval::from(version::Version::new(3, 0, 0)))]
+    fn open_audio_in_auto(
+        &mut self,
+        parameter: AudioInParameter,
+        aruid: AppletResourceUserId,
+        self_process_handle: sf::CopyHandle,
+        name: sf::InAutoSelectBuffer<'_, AudioDeviceName>,
+        out_name: sf::OutAutoSelectBuffer<'_, AudioDeviceName>,
+    ) -> (AudioInParameterInternal, AudioIn);
+    #[ipc_rid(4)]
+    #[version(version::VersionInterval::from(version::Version::new(3, 0, 0)))]
+    fn list_audio_ins_auto_filtered(
+        &self,
+        out_names: sf::OutAutoSelectBuffer<'_, AudioDeviceName>,
+    ) -> u32;
+}
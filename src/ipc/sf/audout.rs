@@ -0,0 +1,147 @@
+use crate::ipc::sf;
+use crate::util;
+use crate::version;
+
+use nx_derive::{Request, Response};
+
+pub use super::AppletResourceUserId;
+
+/// Represents the name of an audio device (like `DeviceOut`)
+pub type AudioDeviceName = util::ArrayString<0x100>;
+
+#[derive(Request, Response, Copy, Clone, PartialEq, Eq, Debug, Default)]
+#[repr(u32)]
+pub enum PcmFormat {
+    #[default]
+    Invalid = 0,
+    Int8 = 1,
+    Int16 = 2,
+    Int24 = 3,
+    Int32 = 4,
+    Float = 5,
+    Adpcm = 6,
+}
+
+#[derive(Request, Response, Copy, Clone, PartialEq, Eq, Debug, Default)]
+#[repr(u32)]
+pub enum AudioOutState {
+    #[default]
+    Started = 0,
+    Stopped = 1,
+}
+
+#[derive(Request, Response, Copy, Clone, PartialEq, Eq, Debug, Default)]
+#[repr(C)]
+pub struct AudioOutParameter {
+    pub sample_rate: u32,
+    pub channel_count: u16,
+    pub reserved: u16,
+}
+const_assert!(core::mem::size_of::<AudioOutParameter>() == 0x8);
+
+#[derive(Request, Response, Copy, Clone, PartialEq, Eq, Debug, Default)]
+#[repr(C)]
+pub struct AudioOutParameterInternal {
+    pub sample_rate: u32,
+    pub channel_count: u32,
+    pub sample_format: PcmFormat,
+    pub state: AudioOutState,
+}
+const_assert!(core::mem::size_of::<AudioOutParameterInternal>() == 0x10);
+
+/// Represents a buffer appended to an audio output or input
+///
+/// The sample buffer (and its capacity) must be aligned to [`AUDIO_BUFFER_ALIGNMENT`]
+#[derive(Request, Response, Copy, Clone, PartialEq, Eq, Debug, Default)]
+#[repr(C)]
+pub struct AudioBuffer {
+    /// Unused by the services
+    pub next: u64,
+    /// The address of the sample buffer
+    pub sample_buffer: u64,
+    /// The size of the sample buffer
+    pub buffer_capacity: u64,
+    /// The size of the actual sample data
+    pub data_size: u64,
+    /// The offset of the actual sample data
+    pub data_offset: u64,
+}
+const_assert!(core::mem::size_of::<AudioBuffer>() == 0x28);
+
+/// The alignment required for audio sample buffers (and their sizes)
+pub const AUDIO_BUFFER_ALIGNMENT: usize = 0x1000;
+
+#[nx_derive::ipc_trait]
+#[default_client]
+pub trait AudioOut {
+    #[ipc_rid(0)]
+    fn get_audio_out_state(&self) -> AudioOutState;
+    #[ipc_rid(1)]
+    fn start(&mut self);
+    #[ipc_rid(2)]
+    fn stop(&mut self);
+    #[ipc_rid(3)]
+    fn append_audio_out_buffer(&mut self, tag: u64, buffer: sf::InMapAliasBuffer<'_, AudioBuffer>);
+    #[ipc_rid(4)]
+    fn register_buffer_event(&mut self) -> sf::CopyHandle;
+    #[ipc_rid(5)]
+    fn get_released_audio_out_buffers(&mut self, out_tags: sf::OutMapAliasBuffer<'_, u64>) -> u32;
+    #[ipc_rid(6)]
+    fn contains_audio_out_buffer(&self, tag: u64) -> bool;
+    #[ipc_rid(7)]
+    #[version(version::VersionInterval::from(version::Version::new(3, 0, 0)))]
+    fn append_audio_out_buffer_auto(
+        &mut self,
+        tag: u64,
+        buffer: sf::InAutoSelectBuffer<'_, AudioBuffer>,
+    );
+    #[ipc_rid(8)]
+    #[version(version::VersionInterval::from(version::Version::new(3, 0, 0)))]
+    fn get_released_audio_out_buffers_auto(
+        &mut self,
+        out_tags: sf::OutAutoSelectBuffer<'_, u64>,
+    ) -> u32;
+    #[ipc_rid(9)]
+    #[version(version::VersionInterval::from(version::Version::new(4, 0, 0)))]
+    fn get_audio_out_buffer_count(&self) -> u32;
+    #[ipc_rid(10)]
+    #[version(version::VersionInterval::from(version::Version::new(4, 0, 0)))]
+    fn get_audio_out_played_sample_count(&self) -> u64;
+    #[ipc_rid(11)]
+    #[version(version::VersionInterval::from(version::Version::new(4, 0, 0)))]
+    fn flush_audio_out_buffers(&mut self) -> bool;
+    #[ipc_rid(12)]
+    #[version(version::VersionInterval::from(version::Version::new(6, 0, 0)))]
+    fn set_audio_out_volume(&mut self, volume: f32);
+    #[ipc_rid(13)]
+    #[version(version::VersionInterval::from(version::Version::new(6, 0, 0)))]
+    fn get_audio_out_volume(&self) -> f32;
+}
+
+#[nx_derive::ipc_trait]
+pub trait AudioOutManager {
+    #[ipc_rid(0)]
+    fn list_audio_outs(&self, out_names: sf::OutMapAliasBuffer<'_, AudioDeviceName>) -> u32;
+    #[ipc_rid(1)]
+    fn open_audio_out(
+        &mut self,
+        parameter: AudioOutParameter,
+        aruid: AppletResourceUserId,
+        self_process_handle: sf::CopyHandle,
+        name: sf::InMapAliasBuffer<'_, AudioDeviceName>,
+        out_name: sf::OutMapAliasBuffer<'_, AudioDeviceName>,
+    ) -> (AudioOutParameterInternal, AudioOut);
+    #[ipc_rid(2)]
+    #[version(version::VersionInterval::from(version::Version::new(3, 0, 0)))]
+    fn list_audio_outs_auto(&self, out_names: sf::OutAutoSelectBuffer<'_, AudioDeviceName>) -> u32;
+    #[ipc_rid(3)]
+    #[version(version::VersionInterval::from(version::Version::new(3, 0, 0)))]
+    fn open_audio_out_auto(
+        &mut self,
+        parameter: AudioOutParameter,
+        aruid: AppletResourceUserId,
+        self_process_handle: sf::CopyHandle,
+        name: sf::InAutoSelectBuffer<'_, AudioDeviceName>,
+        out_name: sf::OutAutoSelectBuffer<'_, AudioDeviceName>,
+    ) -> (AudioOutParameterInternal, AudioOut);
+}
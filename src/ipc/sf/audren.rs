@@ -0,0 +1,537 @@
+use crate::ipc::sf;
+use crate::util;
+use crate::version;
+
+use nx_derive::{Request, Response};
+
+pub use super::AppletResourceUserId;
+pub use super::audout::{AudioDeviceName, PcmFormat};
+
+/// The maximum amount of mix buffers (and thus channels mixes can have)
+pub const MAX_MIX_BUFFER_COUNT: usize = 24;
+
+/// The maximum amount of channels a voice can have
+pub const MAX_VOICE_CHANNEL_COUNT: usize = 6;
+
+/// The maximum amount of wave buffers a voice can have queued
+pub const MAX_WAVE_BUFFER_COUNT: usize = 4;
+
+/// The maximum amount of inputs a sink can have
+pub const MAX_SINK_INPUT_COUNT: usize = 6;
+
+/// The alignment required for memory pools (and their sizes)
+pub const MEMORY_POOL_ALIGNMENT: usize = 0x1000;
+
+/// The ID of the final mix, which is always present
+pub const FINAL_MIX_ID: u32 = 0;
+
+/// Mix ID used for voices/mixes without a destination mix
+pub const UNUSED_MIX_ID: u32 = 0x7FFF_FFFF;
+
+/// Splitter ID used for voices/mixes without a destination splitter
+pub const UNUSED_SPLITTER_ID: u32 = 0xFFFF_FFFF;
+
+/// Gets the name of the main audio output device, used for device sinks
+#[inline]
+pub fn get_main_device_sink_name() -> AudioDeviceName {
+    util::ArrayString::from_str("MainAudioOut")
+}
+
+/// Gets the magic value of a given renderer revision (`REV<revision>`)
+///
+/// # Arguments
+///
+/// * `revision`: The revision number
+#[inline]
+pub const fn make_revision_magic(revision: u32) -> u32 {
+    u32::from_le_bytes(*b"REV0") + (revision << 24)
+}
+
+/// Gets the newest renderer revision supported by the current system version
+pub fn get_supported_revision() -> u32 {
+    let cur_version = version::get_version();
+    if cur_version >= version::Version::new(6, 0, 0) {
+        5
+    } else if cur_version >= version::Version::new(4, 0, 0) {
+        4
+    } else if cur_version >= version::Version::new(3, 0, 0) {
+        3
+    } else if cur_version >= version::Version::new(2, 0, 0) {
+        2
+    } else {
+        1
+    }
+}
+
+/// Represents the kind of renderer nodes, used to build node IDs
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+#[repr(u32)]
+pub enum NodeType {
+    Invalid = 0,
+    Voice = 1,
+    Mix = 2,
+    Sink = 3,
+    Effect = 4,
+}
+
+/// Makes a renderer node ID
+///
+/// # Arguments
+///
+/// * `node_type`: The node type
+/// * `base`: The base (index of the voice/mix/sink/effect)
+/// * `id`: The sub-ID (usually `0`)
+#[inline]
+pub const fn make_node_id(node_type: NodeType, base: u32, id: u32) -> u32 {
+    ((node_type as u32) << 28) | ((base & 0xFFF) << 16) | (id & 0xFFFF)
+}
+
+#[derive(Request, Response, Copy, Clone, PartialEq, Eq, Debug, Default)]
+#[repr(u8)]
+pub enum ExecutionMode {
+    #[default]
+    Auto = 0,
+    Manual = 1,
+}
+
+#[derive(Request, Response, Copy, Clone, PartialEq, Eq, Debug, Default)]
+#[repr(C)]
+pub struct AudioRendererParameter {
+    pub sample_rate: u32,
+    pub sample_count: u32,
+    pub mix_buffer_count: u32,
+    pub sub_mix_count: u32,
+    pub voice_count: u32,
+    pub sink_count: u32,
+    pub effect_count: u32,
+    pub performance_frame_count: u32,
+    pub voice_drop_enabled: bool,
+    pub pad_1: u8,
+    pub execution_mode: ExecutionMode,
+    pub pad_2: u8,
+    pub splitter_count: u32,
+    pub splitter_send_channel_count: u32,
+    pub reserved: u32,
+    /// The revision magic (see [`make_revision_magic`])
+    pub revision: u32,
+}
+const_assert!(core::mem::size_of::<AudioRendererParameter>() == 0x34);
+
+impl AudioRendererParameter {
+    /// Gets the amount of memory pools the renderer works with
+    ///
+    /// Each voice can use up to four of them (one per wave buffer) and each effect one
+    #[inline]
+    pub const fn get_memory_pool_count(&self) -> u32 {
+        self.effect_count + self.voice_count * MAX_WAVE_BUFFER_COUNT as u32
+    }
+
+    /// Gets the amount of mixes the renderer works with (the final mix plus the sub-mixes)
+    #[inline]
+    pub const fn get_mix_count(&self) -> u32 {
+        self.sub_mix_count + 1
+    }
+}
+
+#[derive(Request, Response, Copy, Clone, PartialEq, Eq, Debug, Default)]
+#[repr(u32)]
+pub enum AudioRendererState {
+    #[default]
+    Started = 0,
+    Stopped = 1,
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Default)]
+#[repr(C)]
+pub struct UpdateDataHeader {
+    /// The revision magic (see [`make_revision_magic`])
+    pub revision: u32,
+    pub behavior_size: u32,
+    pub memory_pools_size: u32,
+    pub voices_size: u32,
+    pub voice_channel_resources_size: u32,
+    pub effects_size: u32,
+    pub mixes_size: u32,
+    pub sinks_size: u32,
+    pub performance_size: u32,
+    pub reserved_1: u32,
+    pub render_info_size: u32,
+    pub reserved_2: [u32; 4],
+    /// The total size, including this header
+    pub total_size: u32,
+}
+const_assert!(core::mem::size_of::<UpdateDataHeader>() == 0x40);
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Default)]
+#[repr(C)]
+pub struct BehaviorInfoIn {
+    /// The revision magic (see [`make_revision_magic`])
+    pub revision: u32,
+    pub pad: u32,
+    pub flags: u64,
+}
+const_assert!(core::mem::size_of::<BehaviorInfoIn>() == 0x10);
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Default)]
+#[repr(C)]
+pub struct BehaviorErrorInfo {
+    pub result: u32,
+    pub pad: u32,
+    pub extra_info: u64,
+}
+const_assert!(core::mem::size_of::<BehaviorErrorInfo>() == 0x10);
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Default)]
+#[repr(C)]
+pub struct BehaviorInfoOut {
+    pub errors: [BehaviorErrorInfo; 10],
+    pub error_count: u32,
+    pub reserved: [u32; 3],
+}
+const_assert!(core::mem::size_of::<BehaviorInfoOut>() == 0xB0);
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Default)]
+#[repr(u32)]
+pub enum MemoryPoolState {
+    #[default]
+    Invalid = 0,
+    New = 1,
+    RequestDetach = 2,
+    Detached = 3,
+    RequestAttach = 4,
+    Attached = 5,
+    Released = 6,
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Default)]
+#[repr(C)]
+pub struct MemoryPoolInfoIn {
+    pub address: u64,
+    pub size: u64,
+    pub state: MemoryPoolState,
+    pub reserved: [u32; 3],
+}
+const_assert!(core::mem::size_of::<MemoryPoolInfoIn>() == 0x20);
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Default)]
+#[repr(C)]
+pub struct MemoryPoolInfoOut {
+    /// The raw [`MemoryPoolState`] the memory pool transitioned to
+    pub new_state: u32,
+    pub reserved: [u32; 3],
+}
+const_assert!(core::mem::size_of::<MemoryPoolInfoOut>() == 0x10);
+
+#[derive(Copy, Clone, PartialEq, Debug, Default)]
+#[repr(C)]
+pub struct VoiceChannelResourceIn {
+    pub id: u32,
+    /// The volume this voice channel is mixed with into each mix buffer of its destination mix
+    pub mix_volumes: [f32; MAX_MIX_BUFFER_COUNT],
+    pub is_used: bool,
+    pub reserved: [u8; 11],
+}
+const_assert!(core::mem::size_of::<VoiceChannelResourceIn>() == 0x70);
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Default)]
+#[repr(u8)]
+pub enum VoicePlayState {
+    #[default]
+    Started = 0,
+    Stopped = 1,
+    Paused = 2,
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Default)]
+#[repr(C)]
+pub struct BiquadFilter {
+    pub enabled: bool,
+    pub pad: u8,
+    pub numerator: [i16; 3],
+    pub denominator: [i16; 2],
+}
+const_assert!(core::mem::size_of::<BiquadFilter>() == 0xC);
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Default)]
+#[repr(C)]
+pub struct WaveBuffer {
+    /// The address of the sample data, which must be inside an attached memory pool
+    pub address: u64,
+    pub size: u64,
+    pub start_sample_offset: i32,
+    pub end_sample_offset: i32,
+    pub is_looping: bool,
+    pub end_of_stream: bool,
+    /// Whether the wave buffer was already sent (must be [`false`] only on the first update it's sent in)
+    pub sent_to_server: bool,
+    pub pad: [u8; 5],
+    pub context_address: u64,
+    pub context_size: u64,
+    pub reserved: u64,
+}
+const_assert!(core::mem::size_of::<WaveBuffer>() == 0x38);
+
+#[derive(Copy, Clone, PartialEq, Debug, Default)]
+#[repr(C)]
+pub struct VoiceInfoIn {
+    pub id: u32,
+    pub node_id: u32,
+    /// Whether the voice was just allocated (must be [`true`] only on the first update it's used in)
+    pub is_new: bool,
+    pub is_used: bool,
+    pub play_state: VoicePlayState,
+    pub sample_format: u8,
+    pub sample_rate: u32,
+    pub priority: u32,
+    pub sorting_order: u32,
+    pub channel_count: u32,
+    pub pitch: f32,
+    pub volume: f32,
+    pub biquad_filters: [BiquadFilter; 2],
+    pub wave_buffer_count: u32,
+    pub wave_buffer_head: u16,
+    pub pad_1: u16,
+    pub pad_2: u32,
+    pub extra_params_address: u64,
+    pub extra_params_size: u64,
+    pub dest_mix_id: u32,
+    pub dest_splitter_id: u32,
+    pub wave_buffers: [WaveBuffer; MAX_WAVE_BUFFER_COUNT],
+    /// The IDs of the [`VoiceChannelResourceIn`]s used by each channel
+    pub channel_resource_ids: [u32; MAX_VOICE_CHANNEL_COUNT],
+    pub reserved: [u32; 6],
+}
+const_assert!(core::mem::size_of::<VoiceInfoIn>() == 0x170);
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Default)]
+#[repr(C)]
+pub struct VoiceInfoOut {
+    pub played_sample_count: u64,
+    pub consumed_wave_buffer_count: u32,
+    pub dropped_voice_count: u32,
+}
+const_assert!(core::mem::size_of::<VoiceInfoOut>() == 0x10);
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Default)]
+#[repr(u8)]
+pub enum EffectType {
+    #[default]
+    Invalid = 0,
+    BufferMixer = 1,
+    Aux = 2,
+    Delay = 3,
+    Reverb = 4,
+    I3dl2Reverb = 5,
+    BiquadFilter = 6,
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+#[repr(C)]
+pub struct EffectInfoIn {
+    pub effect_type: EffectType,
+    pub is_new: bool,
+    pub is_enabled: bool,
+    pub pad_1: u8,
+    pub mix_id: u32,
+    pub buffer_address: u64,
+    pub buffer_size: u64,
+    pub priority: u32,
+    pub pad_2: u32,
+    /// Effect-specific parameters
+    pub params: [u8; 0xA0],
+}
+const_assert!(core::mem::size_of::<EffectInfoIn>() == 0xC0);
+
+impl Default for EffectInfoIn {
+    fn default() -> Self {
+        Self {
+            effect_type: EffectType::Invalid,
+            is_new: false,
+            is_enabled: false,
+            pad_1: 0,
+            mix_id: 0,
+            buffer_address: 0,
+            buffer_size: 0,
+            priority: 0,
+            pad_2: 0,
+            params: [0; 0xA0],
+        }
+    }
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Default)]
+#[repr(C)]
+pub struct EffectInfoOut {
+    pub state: u8,
+    pub reserved: [u8; 15],
+}
+const_assert!(core::mem::size_of::<EffectInfoOut>() == 0x10);
+
+#[derive(Copy, Clone, PartialEq, Debug, Default)]
+#[repr(C)]
+pub struct MixInfoIn {
+    pub volume: f32,
+    pub sample_rate: u32,
+    pub buffer_count: u32,
+    pub is_used: bool,
+    pub pad_1: [u8; 3],
+    pub mix_id: u32,
+    pub reserved_1: u32,
+    pub node_id: u32,
+    pub reserved_2: [u32; 2],
+    /// The volume each mix buffer (first index) is mixed with into each mix buffer (second index) of the destination mix
+    pub mix_volumes: [[f32; MAX_MIX_BUFFER_COUNT]; MAX_MIX_BUFFER_COUNT],
+    pub dest_mix_id: u32,
+    pub dest_splitter_id: u32,
+    pub reserved_3: u32,
+}
+const_assert!(core::mem::size_of::<MixInfoIn>() == 0x930);
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Default)]
+#[repr(u8)]
+pub enum SinkType {
+    #[default]
+    Invalid = 0,
+    Device = 1,
+    CircularBuffer = 2,
+}
+
+#[derive(Copy, Clone, PartialEq, Debug, Default)]
+#[repr(C)]
+pub struct DeviceSinkInfoIn {
+    pub name: AudioDeviceName,
+    pub input_count: u32,
+    /// The mix buffer indices of the final mix this sink outputs
+    pub inputs: [u8; MAX_SINK_INPUT_COUNT],
+    pub pad: u8,
+    pub downmix_params_enabled: bool,
+    pub downmix_params: [f32; 4],
+    pub reserved: u32,
+}
+const_assert!(core::mem::size_of::<DeviceSinkInfoIn>() == 0x120);
+
+#[derive(Copy, Clone, PartialEq, Debug, Default)]
+#[repr(C)]
+pub struct SinkInfoIn {
+    pub sink_type: SinkType,
+    pub is_used: bool,
+    pub pad: [u8; 2],
+    pub node_id: u32,
+    pub reserved: [u64; 3],
+    /// Only device sinks are supported here, circular buffer sinks share this same space
+    pub device_sink: DeviceSinkInfoIn,
+}
+const_assert!(core::mem::size_of::<SinkInfoIn>() == 0x140);
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Default)]
+#[repr(C)]
+pub struct SinkInfoOut {
+    pub last_written_offset: u32,
+    pub pad: u32,
+    pub reserved: [u64; 3],
+}
+const_assert!(core::mem::size_of::<SinkInfoOut>() == 0x20);
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Default)]
+#[repr(C)]
+pub struct PerformanceInfoIn {
+    pub mode: u32,
+    pub reserved: [u32; 3],
+}
+const_assert!(core::mem::size_of::<PerformanceInfoIn>() == 0x10);
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Default)]
+#[repr(C)]
+pub struct PerformanceInfoOut {
+    pub history_size: u32,
+    pub reserved: [u32; 3],
+}
+const_assert!(core::mem::size_of::<PerformanceInfoOut>() == 0x10);
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Default)]
+#[repr(C)]
+pub struct RenderInfoOut {
+    pub elapsed_frame_count: u64,
+    pub reserved: u64,
+}
+const_assert!(core::mem::size_of::<RenderInfoOut>() == 0x10);
+
+#[nx_derive::ipc_trait]
+#[default_client]
+pub trait AudioRenderer {
+    #[ipc_rid(0)]
+    fn get_sample_rate(&self) -> u32;
+    #[ipc_rid(1)]
+    fn get_sample_count(&self) -> u32;
+    #[ipc_rid(2)]
+    fn get_mix_buffer_count(&self) -> u32;
+    #[ipc_rid(3)]
+    fn get_state(&self) -> AudioRendererState;
+    #[ipc_rid(4)]
+    fn request_update(
+        &mut self,
+        input: sf::InMapAliasBuffer<'_, u8>,
+        out_output: sf::OutMapAliasBuffer<'_, u8>,
+        out_performance: sf::OutMapAliasBuffer<'_, u8>,
+    );
+    #[ipc_rid(5)]
+    fn start(&mut self);
+    #[ipc_rid(6)]
+    fn stop(&mut self);
+    #[ipc_rid(7)]
+    fn query_system_event(&mut self) -> sf::CopyHandle;
+    #[ipc_rid(8)]
+    fn set_rendering_time_limit(&mut self, limit_percent: u32);
+    #[ipc_rid(9)]
+    fn get_rendering_time_limit(&self) -> u32;
+    #[ipc_rid(10)]
+    #[version(version::VersionInterval::from(version::Version::new(3, 0, 0)))]
+    fn request_update_auto(
+        &mut self,
+        input: sf::InAutoSelectBuffer<'_, u8>,
+        out_output: sf::OutAutoSelectBuffer<'_, u8>,
+        out_performance: sf::OutAutoSelectBuffer<'_, u8>,
+    );
+    #[ipc_rid(11)]
+    #[version(version::VersionInterval::from(version::Version::new(3, 0, 0)))]
+    fn execute_audio_renderer_rendering(&mut self);
+}
+
+#[nx_derive::ipc_trait]
+#[default_client]
+pub trait AudioDevice {
+    #[ipc_rid(0)]
+    fn list_audio_device_name(&self, out_names: sf::OutMapAliasBuffer<'_, AudioDeviceName>) -> u32;
+    #[ipc_rid(1)]
+    fn set_audio_device_output_volume(
+        &mut self,
+        volume: f32,
+        name: sf::InMapAliasBuffer<'_, AudioDeviceName>,
+    );
+    #[ipc_rid(2)]
+    fn get_audio_device_output_volume(
+        &self,
+        name: sf::InMapAliasBuffer<'_, AudioDeviceName>,
+    ) -> f32;
+    #[ipc_rid(3)]
+    fn get_active_audio_device_name(&self, out_name: sf::OutMapAliasBuffer<'_, AudioDeviceName>);
+}
+
+#[nx_derive::ipc_trait]
+pub trait AudioRendererManager {
+    #[ipc_rid(0)]
+    #[return_session]
+    fn open_audio_renderer(
+        &mut self,
+        parameter: AudioRendererParameter,
+        work_buffer_size: u64,
+        aruid: AppletResourceUserId,
+        work_buffer_handle: sf::CopyHandle,
+        self_process_handle: sf::CopyHandle,
+    ) -> AudioRenderer;
+    #[ipc_rid(1)]
+    fn get_work_buffer_size(&self, parameter: AudioRendererParameter) -> u64;
+    #[ipc_rid(2)]
+    #[return_session]
+    fn get_audio_device_service(&self, aruid: AppletResourceUserId) -> AudioDevice;
+}
@@ -330,6 +330,24 @@ impl From<(core::net::Ipv4Addr, u16)> for SocketAddrRepr {
     }
 }
 
+impl From<core::net::SocketAddrV4> for SocketAddrRepr {
+    fn from(value: core::net::SocketAddrV4) -> Self {
+        Self::from((*value.ip(), value.port()))
+    }
+}
+
+impl From<SocketAddrRepr> for core::net::SocketAddrV4 {
+    fn from(value: SocketAddrRepr) -> Self {
+        Self::new(Ipv4Addr::from(value.addr), u16::from_be(value.port))
+    }
+}
+
+impl From<SocketAddrRepr> for core::net::SocketAddr {
+    fn from(value: SocketAddrRepr) -> Self {
+        Self::V4(value.into())
+    }
+}
+
 #[derive(Copy, Clone, Debug, Default, Request, Response, PartialEq, Eq, PartialOrd, Ord)]
 #[repr(C)]
 pub struct BsdDuration {
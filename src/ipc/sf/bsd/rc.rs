@@ -4,5 +4,10 @@ result_define_group!(RESULT_MODULE => {
     NotInitialized: 1,
     InvalidSocketString: 2,
     InvalidSockAddr:3,
-    InvalidTimeout: 4
+    InvalidTimeout: 4,
+    HostNotFound: 5,
+    InvalidAddrInfo: 6,
+    SocketOwnershipMismatch: 7,
+    InvalidMessageHeader: 8,
+    InvalidToken: 9
 });
@@ -11,6 +11,201 @@ pub enum FatalPolicy {
     ErrorScreen,
 }
 
+/// The maximum number of backtrace entries of a CPU context
+pub const FATAL_BACKTRACE_MAX_COUNT: usize = 32;
+
+/// Represents the CPU context of an aarch64 process
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+#[repr(C)]
+pub struct FatalAarch64Context {
+    pub x: [u64; 29],
+    pub fp: u64,
+    pub lr: u64,
+    pub sp: u64,
+    pub pc: u64,
+    pub pstate: u64,
+    pub afsr0: u64,
+    pub afsr1: u64,
+    pub esr: u64,
+    pub far: u64,
+    pub backtrace: [u64; FATAL_BACKTRACE_MAX_COUNT],
+    /// The address of the process's first module
+    pub start_address: u64,
+    /// Which registers are set, where bit `i` corresponds to register `i` (`x0`-`x28`, then `fp`, `lr`, `sp`, `pc`, `pstate`, `afsr0`, `afsr1`, `esr` and `far`)
+    pub register_set_flags: u64,
+    pub backtrace_size: u32,
+}
+
+impl FatalAarch64Context {
+    /// The register set flag of `fp`
+    pub const FP_REGISTER_INDEX: u32 = 29;
+    /// The register set flag of `lr`
+    pub const LR_REGISTER_INDEX: u32 = 30;
+    /// The register set flag of `sp`
+    pub const SP_REGISTER_INDEX: u32 = 31;
+    /// The register set flag of `pc`
+    pub const PC_REGISTER_INDEX: u32 = 32;
+    /// The total number of registers (thus of register set flags)
+    pub const REGISTER_COUNT: u32 = 38;
+
+    /// Creates an empty [`FatalAarch64Context`], without any register set
+    pub const fn new() -> Self {
+        Self {
+            x: [0; 29],
+            fp: 0,
+            lr: 0,
+            sp: 0,
+            pc: 0,
+            pstate: 0,
+            afsr0: 0,
+            afsr1: 0,
+            esr: 0,
+            far: 0,
+            backtrace: [0; FATAL_BACKTRACE_MAX_COUNT],
+            start_address: 0,
+            register_set_flags: 0,
+            backtrace_size: 0,
+        }
+    }
+
+    /// Sets the backtrace, truncating it to [`FATAL_BACKTRACE_MAX_COUNT`] entries
+    ///
+    /// # Arguments
+    ///
+    /// * `addresses`: The backtrace addresses
+    pub fn set_backtrace(&mut self, addresses: impl IntoIterator<Item = u64>) {
+        let mut count = 0;
+        for (entry, address) in self.backtrace.iter_mut().zip(addresses) {
+            *entry = address;
+            count += 1;
+        }
+        self.backtrace_size = count;
+    }
+}
+
+impl Default for FatalAarch64Context {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Represents the CPU context of an aarch32 process
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+#[repr(C)]
+pub struct FatalAarch32Context {
+    pub r: [u32; 11],
+    pub fp: u32,
+    pub ip: u32,
+    pub sp: u32,
+    pub lr: u32,
+    pub pc: u32,
+    pub pstate: u32,
+    pub afsr0: u32,
+    pub afsr1: u32,
+    pub esr: u32,
+    pub far: u32,
+    pub backtrace: [u32; FATAL_BACKTRACE_MAX_COUNT],
+    pub backtrace_size: u32,
+    /// The address of the process's first module
+    pub start_address: u32,
+    /// Which registers are set, where bit `i` corresponds to register `i` (`r0`-`r10`, then `fp`, `ip`, `sp`, `lr`, `pc`, `pstate`, `afsr0`, `afsr1`, `esr` and `far`)
+    pub register_set_flags: u32,
+}
+
+impl FatalAarch32Context {
+    /// Creates an empty [`FatalAarch32Context`], without any register set
+    pub const fn new() -> Self {
+        Self {
+            r: [0; 11],
+            fp: 0,
+            ip: 0,
+            sp: 0,
+            lr: 0,
+            pc: 0,
+            pstate: 0,
+            afsr0: 0,
+            afsr1: 0,
+            esr: 0,
+            far: 0,
+            backtrace: [0; FATAL_BACKTRACE_MAX_COUNT],
+            backtrace_size: 0,
+            start_address: 0,
+            register_set_flags: 0,
+        }
+    }
+}
+
+impl Default for FatalAarch32Context {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Represents the architecture-specific part of a [`FatalCpuContext`]
+#[derive(Copy, Clone)]
+#[repr(C)]
+pub union FatalArchContext {
+    pub aarch64: FatalAarch64Context,
+    pub aarch32: FatalAarch32Context,
+}
+
+/// Represents the CPU context sent with [`throw_fatal_with_cpu_context`][`IFatalClient::throw_fatal_with_cpu_context`], shown in the fatal screen and report
+#[derive(Copy, Clone)]
+#[repr(C)]
+pub struct FatalCpuContext {
+    pub arch: FatalArchContext,
+    pub is_aarch32: bool,
+    /// The exception type (see [`ExceptionType`][`crate::svc::ExceptionType`]), if the context comes from an exception
+    pub exception_type: u32,
+}
+const_assert!(core::mem::size_of::<FatalCpuContext>() == 0x250);
+
+impl FatalCpuContext {
+    /// Creates a [`FatalCpuContext`] from an aarch64 context
+    ///
+    /// # Arguments
+    ///
+    /// * `context`: The aarch64 context
+    /// * `exception_type`: The exception type
+    pub const fn from_aarch64(context: FatalAarch64Context, exception_type: u32) -> Self {
+        Self {
+            arch: FatalArchContext { aarch64: context },
+            is_aarch32: false,
+            exception_type,
+        }
+    }
+
+    /// Creates a [`FatalCpuContext`] from an aarch32 context
+    ///
+    /// # Arguments
+    ///
+    /// * `context`: The aarch32 context
+    /// * `exception_type`: The exception type
+    pub const fn from_aarch32(context: FatalAarch32Context, exception_type: u32) -> Self {
+        Self {
+            arch: FatalArchContext { aarch32: context },
+            is_aarch32: true,
+            exception_type,
+        }
+    }
+
+    /// Gets the aarch64 context, if this is one
+    pub const fn get_aarch64(&self) -> Option<&FatalAarch64Context> {
+        match self.is_aarch32 {
+            true => None,
+            false => Some(unsafe { &self.arch.aarch64 }),
+        }
+    }
+
+    /// Gets the aarch32 context, if this is one
+    pub const fn get_aarch32(&self) -> Option<&FatalAarch32Context> {
+        match self.is_aarch32 {
+            true => Some(unsafe { &self.arch.aarch32 }),
+            false => None,
+        }
+    }
+}
+
 #[nx_derive::ipc_trait]
 pub trait Fatal {
     #[ipc_rid(1)]
@@ -20,4 +215,12 @@ pub trait Fatal {
         policy: FatalPolicy,
         process_id: sf::ProcessId,
     );
+    #[ipc_rid(2)]
+    fn throw_fatal_with_cpu_context(
+        &self,
+        rc: ResultCode,
+        policy: FatalPolicy,
+        process_id: sf::ProcessId,
+        cpu_context: sf::InMapAliasBuffer<'_, FatalCpuContext>,
+    );
 }
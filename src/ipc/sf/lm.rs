@@ -26,3 +26,13 @@ pub trait Logging {
     #[return_session]
     fn open_logger(&mut self, process_id: sf::ProcessId) -> Logger;
 }
+
+#[nx_derive::ipc_trait]
+pub trait LogGetter {
+    #[ipc_rid(0)]
+    fn start_logging(&mut self);
+    #[ipc_rid(1)]
+    fn stop_logging(&mut self);
+    #[ipc_rid(2)]
+    fn get_log(&mut self, out_log_buf: sf::OutAutoSelectBuffer<'_, u8>) -> (usize, u32);
+}
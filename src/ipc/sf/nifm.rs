@@ -0,0 +1,173 @@
+use crate::ipc::sf;
+use crate::version;
+
+use core::net::Ipv4Addr;
+
+use nx_derive::{Request, Response};
+
+#[derive(Request, Response, Copy, Clone, PartialEq, Eq, Debug, Default)]
+#[repr(u8)]
+pub enum InternetConnectionType {
+    #[default]
+    WiFi = 1,
+    Ethernet = 2,
+}
+
+#[derive(Request, Response, Copy, Clone, PartialEq, Eq, Debug, Default)]
+#[repr(u8)]
+pub enum InternetConnectionState {
+    #[default]
+    ConnectingUnknown1 = 0,
+    ConnectingUnknown2 = 1,
+    ConnectingUnknown3 = 2,
+    ConnectingUnknown4 = 3,
+    Connected = 4,
+}
+
+#[derive(Request, Response, Copy, Clone, PartialEq, Eq, Debug, Default)]
+#[repr(C)]
+pub struct InternetConnectionStatus {
+    pub connection_type: InternetConnectionType,
+    /// Wi-Fi signal strength, from `0` to `3` (always `0` on ethernet connections)
+    pub wifi_strength: u8,
+    pub state: InternetConnectionState,
+}
+const_assert!(core::mem::size_of::<InternetConnectionStatus>() == 0x3);
+
+/// Represents an IPv4 address as sent/received by nifm (in network byte order)
+#[derive(Request, Response, Copy, Clone, PartialEq, Eq, Debug, Default)]
+#[repr(C)]
+pub struct IpV4Address {
+    pub address: [u8; 4],
+}
+const_assert!(core::mem::size_of::<IpV4Address>() == 0x4);
+
+impl From<IpV4Address> for Ipv4Addr {
+    #[inline]
+    fn from(value: IpV4Address) -> Self {
+        Ipv4Addr::from(value.address)
+    }
+}
+
+impl From<Ipv4Addr> for IpV4Address {
+    #[inline]
+    fn from(value: Ipv4Addr) -> Self {
+        Self {
+            address: value.octets(),
+        }
+    }
+}
+
+#[derive(Request, Response, Copy, Clone, PartialEq, Eq, Debug, Default)]
+#[repr(C)]
+pub struct IpAddressSetting {
+    pub is_automatic: bool,
+    pub current_address: IpV4Address,
+    pub subnet_mask: IpV4Address,
+    pub gateway: IpV4Address,
+}
+const_assert!(core::mem::size_of::<IpAddressSetting>() == 0xD);
+
+#[derive(Request, Response, Copy, Clone, PartialEq, Eq, Debug, Default)]
+#[repr(C)]
+pub struct DnsSetting {
+    pub is_automatic: bool,
+    pub primary_dns_server: IpV4Address,
+    pub secondary_dns_server: IpV4Address,
+}
+const_assert!(core::mem::size_of::<DnsSetting>() == 0x9);
+
+#[derive(Request, Response, Copy, Clone, PartialEq, Eq, Debug, Default)]
+#[repr(C)]
+pub struct IpConfigInfo {
+    pub ip_address_setting: IpAddressSetting,
+    pub dns_setting: DnsSetting,
+}
+const_assert!(core::mem::size_of::<IpConfigInfo>() == 0x16);
+
+#[derive(Request, Response, Copy, Clone, PartialEq, Eq, Debug, Default)]
+#[repr(u32)]
+pub enum RequestState {
+    #[default]
+    Invalid = 0,
+    /// The request was not submitted (or it was cancelled/rejected)
+    Free = 1,
+    /// The request was submitted and it's being processed
+    OnHold = 2,
+    /// The request was accepted, thus the network connection is available
+    Accepted = 3,
+    Blocking = 4,
+}
+
+#[derive(Request, Response, Copy, Clone, PartialEq, Eq, Debug, Default)]
+#[repr(u32)]
+pub enum RequirementPreset {
+    #[default]
+    None = 0,
+    InternetBestEffort = 1,
+    /// The usual preset for applications requesting internet access
+    InternetGeneric = 2,
+}
+
+#[derive(Request, Response, Copy, Clone, PartialEq, Eq, Debug, Default)]
+#[repr(i8)]
+pub enum ConnectionConfirmationOption {
+    #[default]
+    Invalid = 0,
+    Prohibited = 1,
+    NotRequired = 2,
+    Preferred = 3,
+    Required = 4,
+    Forced = 5,
+}
+
+#[nx_derive::ipc_trait]
+#[default_client]
+pub trait Request {
+    #[ipc_rid(0)]
+    fn get_request_state(&self) -> RequestState;
+    #[ipc_rid(1)]
+    fn get_result(&self);
+    #[ipc_rid(2)]
+    fn get_system_event_readable_handles(&mut self) -> (sf::CopyHandle, sf::CopyHandle);
+    #[ipc_rid(3)]
+    fn cancel(&mut self);
+    #[ipc_rid(4)]
+    fn submit(&mut self);
+    #[ipc_rid(6)]
+    fn set_requirement_preset(&mut self, preset: RequirementPreset);
+    #[ipc_rid(10)]
+    fn set_rejectable(&mut self, rejectable: bool);
+    #[ipc_rid(11)]
+    fn set_connection_confirmation_option(&mut self, option: ConnectionConfirmationOption);
+    #[ipc_rid(12)]
+    fn set_persistent(&mut self, persistent: bool);
+}
+
+#[nx_derive::ipc_trait]
+#[default_client]
+pub trait GeneralService {
+    #[ipc_rid(4)]
+    fn create_request(&mut self, preset: RequirementPreset) -> Request;
+    #[ipc_rid(12)]
+    fn get_current_ip_address(&self) -> IpV4Address;
+    #[ipc_rid(15)]
+    fn get_current_ip_config_info(&self) -> IpConfigInfo;
+    #[ipc_rid(17)]
+    fn is_wireless_communication_enabled(&self) -> bool;
+    #[ipc_rid(18)]
+    #[version(version::VersionInterval::from(version::Version::new(3, 0, 0)))]
+    fn get_internet_connection_status(&self) -> InternetConnectionStatus;
+    #[ipc_rid(20)]
+    #[version(version::VersionInterval::from(version::Version::new(3, 0, 0)))]
+    fn is_ethernet_communication_enabled(&self) -> bool;
+}
+
+#[nx_derive::ipc_trait]
+pub trait Static {
+    #[ipc_rid(4)]
+    fn create_general_service_old(&self) -> GeneralService;
+    #[ipc_rid(5)]
+    #[version(version::VersionInterval::from(version::Version::new(3, 0, 0)))]
+    fn create_general_service(&self, process_id: sf::ProcessId) -> GeneralService;
+}
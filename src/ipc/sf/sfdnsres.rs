@@ -0,0 +1,31 @@
+use crate::ipc::sf;
+
+#[nx_derive::ipc_trait]
+pub trait Resolver {
+    /// Resolves a host name, returning the `(h_errno, errno, serialized size)` of the result, which is written as a serialized `hostent` (see [`deserialize_host_ent`][`crate::socket::addrinfo::deserialize_host_ent`])
+    #[ipc_rid(2)]
+    fn get_host_by_name_request(
+        &self,
+        enable_nsd_resolve: bool,
+        cancel_handle: u32,
+        process_id: sf::ProcessId,
+        name: sf::InMapAliasBuffer<'_, u8>,
+        out_host_ent: sf::OutMapAliasBuffer<'_, u8>,
+    ) -> (i32, i32, u32);
+    /// Resolves a node and/or service name, returning the `(errno, getaddrinfo result, serialized size)` of the result, which is written as a serialized `addrinfo` list (see [`deserialize_addr_info_list`][`crate::socket::addrinfo::deserialize_addr_info_list`])
+    #[ipc_rid(6)]
+    fn get_addr_info_request(
+        &self,
+        enable_nsd_resolve: bool,
+        cancel_handle: u32,
+        process_id: sf::ProcessId,
+        node: sf::InMapAliasBuffer<'_, u8>,
+        service: sf::InMapAliasBuffer<'_, u8>,
+        hints: sf::InMapAliasBuffer<'_, u8>,
+        out_addr_info: sf::OutMapAliasBuffer<'_, u8>,
+    ) -> (i32, i32, u32);
+    #[ipc_rid(8)]
+    fn get_cancel_handle_request(&self, process_id: sf::ProcessId) -> u32;
+    #[ipc_rid(9)]
+    fn cancel_request(&self, cancel_handle: u32, process_id: sf::ProcessId);
+}
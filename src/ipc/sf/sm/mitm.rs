@@ -11,4 +11,20 @@ pub struct MitmProcessInfo {
     pub override_flags: u64,
 }
 
+/// Tag set on the process ID of requests forwarded by a mitm server to the original service
+pub const FORWARDED_PROCESS_ID_TAG: u64 = 0xFFFE_0000_0000_0000;
+
+/// Mask of the actual process ID on forwarded process IDs
+pub const FORWARDED_PROCESS_ID_MASK: u64 = 0x0000_FFFF_FFFF_FFFF;
+
+/// Tags a process ID as forwarded by a mitm server
+///
+/// # Arguments
+///
+/// * `process_id`: The process ID to tag
+#[inline]
+pub const fn make_forwarded_process_id(process_id: u64) -> u64 {
+    FORWARDED_PROCESS_ID_TAG | (process_id & FORWARDED_PROCESS_ID_MASK)
+}
+
 pub mod rc;
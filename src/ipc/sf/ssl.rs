@@ -0,0 +1,224 @@
+use crate::ipc::sf;
+use crate::version;
+
+use nx_derive::{Request, Response};
+
+define_bit_set! {
+    /// Represents the TLS versions a context may use
+    SslVersion (u32) {
+        /// Negotiates the best version supported by both sides
+        Auto = bit!(0),
+        TlsV10 = bit!(3),
+        TlsV11 = bit!(4),
+        TlsV12 = bit!(5),
+        /// Only supported on 11.0.0+
+        TlsV13 = bit!(6)
+    }
+}
+
+define_bit_set! {
+    /// Represents the checks done on the server certificate during the handshake
+    VerifyOption (u32) {
+        None = 0,
+        /// Verifies the certificate chain against the imported/trusted CAs
+        PeerCa = bit!(0),
+        /// Verifies that the certificate matches the connection host name
+        HostName = bit!(1),
+        /// Verifies the certificate validity dates
+        DateCheck = bit!(2),
+        EvCertPartial = bit!(3),
+        EvPolicyOid = bit!(4),
+        EvCertFingerprint = bit!(5)
+    }
+}
+
+impl VerifyOption {
+    /// Gets the options used by default, which are the ones usually needed for secure connections
+    #[inline]
+    pub fn default_checks() -> Self {
+        Self::PeerCa() | Self::HostName() | Self::DateCheck()
+    }
+}
+
+define_bit_set! {
+    /// Represents the events to poll a connection for
+    PollEvent (u32) {
+        None = 0,
+        Read = bit!(0),
+        Write = bit!(1),
+        Except = bit!(2)
+    }
+}
+
+#[derive(Request, Response, Copy, Clone, PartialEq, Eq, Debug, Default)]
+#[repr(u32)]
+pub enum CertificateFormat {
+    #[default]
+    Pem = 1,
+    Der = 2,
+}
+
+#[derive(Request, Response, Copy, Clone, PartialEq, Eq, Debug, Default)]
+#[repr(u32)]
+pub enum InternalPki {
+    #[default]
+    None = 0,
+    /// The console client certificate (only accepted by Nintendo servers)
+    DeviceClientCertDefault = 1,
+}
+
+#[derive(Request, Response, Copy, Clone, PartialEq, Eq, Debug, Default)]
+#[repr(u32)]
+pub enum IoMode {
+    #[default]
+    Blocking = 1,
+    NonBlocking = 2,
+}
+
+#[derive(Request, Response, Copy, Clone, PartialEq, Eq, Debug, Default)]
+#[repr(u32)]
+pub enum ContextOption {
+    #[default]
+    None = 0,
+    CrlImportDateCheckEnable = 1,
+}
+
+#[derive(Request, Response, Copy, Clone, PartialEq, Eq, Debug, Default)]
+#[repr(u32)]
+pub enum ConnectionOption {
+    /// Keeps the socket open when the connection is closed (otherwise the connection takes ownership of it)
+    #[default]
+    DoNotCloseSocket = 0,
+    /// Only supported on 3.0.0+
+    GetServerCertChain = 1,
+    /// Only supported on 5.0.0+
+    SkipDefaultVerify = 2,
+    /// Only supported on 9.0.0+
+    EnableAlpn = 3,
+}
+
+#[derive(Request, Response, Copy, Clone, PartialEq, Eq, Debug, Default)]
+#[repr(u32)]
+pub enum SessionCacheMode {
+    #[default]
+    None = 0,
+    SessionId = 1,
+    SessionTicket = 2,
+}
+
+#[derive(Request, Response, Copy, Clone, PartialEq, Eq, Debug, Default)]
+#[repr(u32)]
+pub enum RenegotiationMode {
+    #[default]
+    None = 0,
+    Secure = 1,
+}
+
+#[nx_derive::ipc_trait]
+#[default_client]
+pub trait SslConnection {
+    /// Sets the bsd socket to use, returning `-1` unless [`ConnectionOption::DoNotCloseSocket`] is enabled (in which case the socket is returned)
+    #[ipc_rid(0)]
+    fn set_socket_descriptor(&mut self, socket_fd: i32) -> i32;
+    #[ipc_rid(1)]
+    fn set_host_name(&mut self, host_name: sf::InMapAliasBuffer<'_, u8>);
+    #[ipc_rid(2)]
+    fn set_verify_option(&mut self, option: VerifyOption);
+    #[ipc_rid(3)]
+    fn set_io_mode(&mut self, mode: IoMode);
+    #[ipc_rid(4)]
+    fn get_socket_descriptor(&self) -> i32;
+    #[ipc_rid(5)]
+    fn get_host_name(&self, out_host_name: sf::OutMapAliasBuffer<'_, u8>) -> u32;
+    #[ipc_rid(6)]
+    fn get_verify_option(&self) -> VerifyOption;
+    #[ipc_rid(7)]
+    fn get_io_mode(&self) -> IoMode;
+    #[ipc_rid(8)]
+    fn do_handshake(&mut self);
+    #[ipc_rid(9)]
+    fn do_handshake_get_server_cert(
+        &mut self,
+        out_cert: sf::OutMapAliasBuffer<'_, u8>,
+    ) -> (u32, u32);
+    #[ipc_rid(10)]
+    fn read(&self, out_data: sf::OutMapAliasBuffer<'_, u8>) -> u32;
+    #[ipc_rid(11)]
+    fn write(&self, data: sf::InMapAliasBuffer<'_, u8>) -> u32;
+    #[ipc_rid(12)]
+    fn pending(&self) -> i32;
+    #[ipc_rid(13)]
+    fn peek(&self, out_data: sf::OutMapAliasBuffer<'_, u8>) -> u32;
+    #[ipc_rid(14)]
+    fn poll(&self, events: PollEvent, timeout_ms: u32) -> PollEvent;
+    #[ipc_rid(15)]
+    fn get_verify_cert_error(&self);
+    #[ipc_rid(16)]
+    fn get_needed_server_cert_buffer_size(&self) -> u32;
+    #[ipc_rid(17)]
+    fn set_session_cache_mode(&mut self, mode: SessionCacheMode);
+    #[ipc_rid(18)]
+    fn get_session_cache_mode(&self) -> SessionCacheMode;
+    #[ipc_rid(19)]
+    fn flush_session_cache(&mut self);
+    #[ipc_rid(20)]
+    fn set_renegotiation_mode(&mut self, mode: RenegotiationMode);
+    #[ipc_rid(21)]
+    fn get_renegotiation_mode(&self) -> RenegotiationMode;
+    #[ipc_rid(22)]
+    fn set_option(&mut self, value: bool, option: ConnectionOption);
+    #[ipc_rid(23)]
+    fn get_option(&self, option: ConnectionOption) -> bool;
+}
+
+#[nx_derive::ipc_trait]
+#[default_client]
+pub trait SslContext {
+    #[ipc_rid(0)]
+    fn set_option(&mut self, option: ContextOption, value: i32);
+    #[ipc_rid(1)]
+    fn get_option(&self, option: ContextOption) -> i32;
+    #[ipc_rid(2)]
+    #[return_session]
+    fn create_connection(&self) -> SslConnection;
+    #[ipc_rid(3)]
+    fn get_connection_count(&self) -> u32;
+    #[ipc_rid(4)]
+    fn import_server_pki(
+        &mut self,
+        format: CertificateFormat,
+        cert: sf::InMapAliasBuffer<'_, u8>,
+    ) -> u64;
+    #[ipc_rid(5)]
+    fn import_client_pki(
+        &mut self,
+        pkcs12: sf::InMapAliasBuffer<'_, u8>,
+        password: sf::InMapAliasBuffer<'_, u8>,
+    ) -> u64;
+    #[ipc_rid(6)]
+    fn remove_server_pki(&mut self, id: u64);
+    #[ipc_rid(7)]
+    fn remove_client_pki(&mut self, id: u64);
+    #[ipc_rid(8)]
+    fn register_internal_pki(&mut self, pki: InternalPki) -> u64;
+    #[ipc_rid(9)]
+    fn add_policy_oid(&mut self, oid: sf::InMapAliasBuffer<'_, u8>);
+    #[ipc_rid(10)]
+    #[version(version::VersionInterval::from(version::Version::new(3, 0, 0)))]
+    fn import_crl(&mut self, crl: sf::InMapAliasBuffer<'_, u8>) -> u64;
+    #[ipc_rid(11)]
+    #[version(version::VersionInterval::from(version::Version::new(3, 0, 0)))]
+    fn remove_crl(&mut self, id: u64);
+}
+
+#[nx_derive::ipc_trait]
+pub trait Ssl {
+    #[ipc_rid(0)]
+    #[return_session]
+    fn create_context(&self, version: SslVersion, process_id: sf::ProcessId) -> SslContext;
+    #[ipc_rid(1)]
+    fn get_context_count(&self) -> u32;
+    #[ipc_rid(5)]
+    #[version(version::VersionInterval::from(version::Version::new(3, 0, 0)))]
+    fn set_interface_version(&mut self, version: u32);
+}
@@ -0,0 +1,264 @@
+use crate::ipc::sf;
+use crate::util;
+use crate::version;
+
+use core::fmt;
+
+use nx_derive::{Request, Response};
+
+/// Represents a POSIX time (seconds since the UNIX epoch, in UTC)
+pub type PosixTime = i64;
+
+/// Represents a time zone location name (like `Europe/Madrid`)
+pub type LocationName = util::ArrayString<0x24>;
+
+#[derive(Request, Response, Copy, Clone, PartialEq, Eq, Debug, Default)]
+#[repr(C)]
+pub struct SteadyClockTimePoint {
+    pub time_point: i64,
+    pub source_id: util::Uuid,
+}
+const_assert!(core::mem::size_of::<SteadyClockTimePoint>() == 0x18);
+
+#[derive(Request, Response, Copy, Clone, PartialEq, Eq, Debug, Default)]
+#[repr(C)]
+pub struct SystemClockContext {
+    pub offset: i64,
+    pub steady_time_point: SteadyClockTimePoint,
+}
+const_assert!(core::mem::size_of::<SystemClockContext>() == 0x20);
+
+#[derive(Request, Response, Copy, Clone, PartialEq, Eq, Debug, Default)]
+#[repr(C)]
+pub struct CalendarTime {
+    pub year: i16,
+    pub month: u8,
+    pub day: u8,
+    pub hour: u8,
+    pub minute: u8,
+    pub second: u8,
+    pub pad: u8,
+}
+const_assert!(core::mem::size_of::<CalendarTime>() == 0x8);
+
+impl CalendarTime {
+    /// Creates a new [`CalendarTime`]
+    ///
+    /// # Arguments
+    ///
+    /// * `year`: The year
+    /// * `month`: The month (`1`-`12`)
+    /// * `day`: The day of the month (`1`-`31`)
+    /// * `hour`: The hour (`0`-`23`)
+    /// * `minute`: The minute (`0`-`59`)
+    /// * `second`: The second (`0`-`59`)
+    pub const fn new(year: i16, month: u8, day: u8, hour: u8, minute: u8, second: u8) -> Self {
+        Self {
+            year,
+            month,
+            day,
+            hour,
+            minute,
+            second,
+            pad: 0,
+        }
+    }
+}
+
+impl fmt::Display for CalendarTime {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{:04}-{:02}-{:02} {:02}:{:02}:{:02}",
+            self.year, self.month, self.day, self.hour, self.minute, self.second
+        )
+    }
+}
+
+#[derive(Request, Response, Copy, Clone, PartialEq, Eq, Debug, Default)]
+#[repr(C)]
+pub struct CalendarAdditionalInfo {
+    /// The day of the week (`0` being Sunday)
+    pub day_of_week: u32,
+    /// The day of the year (`0`-`365`)
+    pub day_of_year: u32,
+    pub time_zone_name: util::ArrayString<8>,
+    pub is_dst: bool,
+    pub pad: [u8; 3],
+    /// The offset from UTC, in seconds
+    pub utc_offset: i32,
+}
+const_assert!(core::mem::size_of::<CalendarAdditionalInfo>() == 0x18);
+
+/// Represents a calendar time along with its additional information
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Default)]
+pub struct LocalTime {
+    pub time: CalendarTime,
+    pub info: CalendarAdditionalInfo,
+}
+
+impl LocalTime {
+    /// Creates a new [`LocalTime`]
+    ///
+    /// # Arguments
+    ///
+    /// * `time`: The calendar time
+    /// * `info`: The additional information
+    pub const fn new(time: CalendarTime, info: CalendarAdditionalInfo) -> Self {
+        Self { time, info }
+    }
+}
+
+impl fmt::Display for LocalTime {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let offset_sign = if self.info.utc_offset < 0 { '-' } else { '+' };
+        let offset_abs = self.info.utc_offset.unsigned_abs();
+        write!(
+            f,
+            "{} {}{:02}:{:02}",
+            self.time,
+            offset_sign,
+            offset_abs / 3600,
+            (offset_abs % 3600) / 60
+        )?;
+
+        let time_zone_name = self.info.time_zone_name.get_str().unwrap_or("");
+        if !time_zone_name.is_empty() {
+            write!(f, " ({time_zone_name})")?;
+        }
+        Ok(())
+    }
+}
+
+/// Represents the time zone rule format used by [`ITimeZoneService`][`ITimeZoneServiceClient`] (an opaque, already processed time zone binary)
+#[derive(Copy, Clone)]
+#[repr(C)]
+pub struct TimeZoneRule {
+    pub data: [u8; 0x4000],
+}
+const_assert!(core::mem::size_of::<TimeZoneRule>() == 0x4000);
+
+impl TimeZoneRule {
+    /// Creates a new, empty [`TimeZoneRule`]
+    pub const fn new() -> Self {
+        Self { data: [0; 0x4000] }
+    }
+}
+
+impl Default for TimeZoneRule {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[nx_derive::ipc_trait]
+#[default_client]
+pub trait SystemClock {
+    #[ipc_rid(0)]
+    fn get_current_time(&self) -> PosixTime;
+    #[ipc_rid(1)]
+    fn set_current_time(&self, time: PosixTime);
+    #[ipc_rid(2)]
+    fn get_system_clock_context(&self) -> SystemClockContext;
+    #[ipc_rid(3)]
+    fn set_system_clock_context(&self, context: SystemClockContext);
+    #[ipc_rid(4)]
+    #[version(version::VersionInterval::from(version::Version::new(9, 0, 0)))]
+    fn get_operation_event_readable_handle(&self) -> sf::CopyHandle;
+}
+
+#[nx_derive::ipc_trait]
+#[default_client]
+pub trait SteadyClock {
+    #[ipc_rid(0)]
+    fn get_current_time_point(&self) -> SteadyClockTimePoint;
+    #[ipc_rid(2)]
+    fn get_test_offset(&self) -> i64;
+    #[ipc_rid(3)]
+    fn set_test_offset(&self, offset: i64);
+    #[ipc_rid(100)]
+    #[version(version::VersionInterval::from(version::Version::new(2, 0, 0)))]
+    fn get_rtc_value(&self) -> i64;
+    #[ipc_rid(101)]
+    #[version(version::VersionInterval::from(version::Version::new(2, 0, 0)))]
+    fn is_rtc_reset_detected(&self) -> bool;
+    #[ipc_rid(102)]
+    #[version(version::VersionInterval::from(version::Version::new(2, 0, 0)))]
+    fn get_setup_result_value(&self) -> u32;
+    #[ipc_rid(200)]
+    #[version(version::VersionInterval::from(version::Version::new(3, 0, 0)))]
+    fn get_internal_offset(&self) -> i64;
+}
+
+#[nx_derive::ipc_trait]
+#[default_client]
+pub trait TimeZoneService {
+    #[ipc_rid(0)]
+    fn get_device_location_name(&self) -> LocationName;
+    #[ipc_rid(1)]
+    fn set_device_location_name(&self, name: LocationName);
+    #[ipc_rid(2)]
+    fn get_total_location_name_count(&self) -> u32;
+    #[ipc_rid(3)]
+    fn load_location_name_list(
+        &self,
+        index: u32,
+        out_names: sf::OutMapAliasBuffer<'_, LocationName>,
+    ) -> u32;
+    #[ipc_rid(4)]
+    fn load_time_zone_rule(
+        &self,
+        name: LocationName,
+        out_rule: sf::OutMapAliasBuffer<'_, TimeZoneRule>,
+    );
+    #[ipc_rid(100)]
+    fn to_calendar_time(
+        &self,
+        time: PosixTime,
+        rule: sf::InMapAliasBuffer<'_, TimeZoneRule>,
+    ) -> (CalendarTime, CalendarAdditionalInfo);
+    #[ipc_rid(101)]
+    fn to_calendar_time_with_my_rule(
+        &self,
+        time: PosixTime,
+    ) -> (CalendarTime, CalendarAdditionalInfo);
+    #[ipc_rid(201)]
+    fn to_posix_time(
+        &self,
+        time: CalendarTime,
+        rule: sf::InMapAliasBuffer<'_, TimeZoneRule>,
+        out_times: sf::OutPointerBuffer<'_, PosixTime>,
+    ) -> u32;
+    #[ipc_rid(202)]
+    fn to_posix_time_with_my_rule(
+        &self,
+        time: CalendarTime,
+        out_times: sf::OutPointerBuffer<'_, PosixTime>,
+    ) -> u32;
+}
+
+#[nx_derive::ipc_trait]
+pub trait Static {
+    #[ipc_rid(0)]
+    #[return_session]
+    fn get_standard_user_system_clock(&self) -> SystemClock;
+    #[ipc_rid(1)]
+    #[return_session]
+    fn get_standard_network_system_clock(&self) -> SystemClock;
+    #[ipc_rid(2)]
+    #[return_session]
+    fn get_standard_steady_clock(&self) -> SteadyClock;
+    #[ipc_rid(3)]
+    #[return_session]
+    fn get_time_zone_service(&self) -> TimeZoneService;
+    #[ipc_rid(4)]
+    #[return_session]
+    fn get_standard_local_system_clock(&self) -> SystemClock;
+    #[ipc_rid(5)]
+    #[return_session]
+    #[version(version::VersionInterval::from(version::Version::new(4, 0, 0)))]
+    fn get_ephemeral_network_system_clock(&self) -> SystemClock;
+    #[ipc_rid(200)]
+    #[version(version::VersionInterval::from(version::Version::new(3, 0, 0)))]
+    fn is_standard_network_system_clock_accuracy_sufficient(&self) -> bool;
+}
@@ -10,6 +10,7 @@ use crate::service::applet::{IStorageAccessorClient, IStorageClient, Storage};
 use crate::service::sm::rc;
 use crate::svc;
 use crate::sync::{Mutex, MutexGuard};
+use crate::time::Timeout;
 use crate::wait;
 use alloc::boxed::Box;
 use applet::LibraryAppletCreator;
@@ -92,8 +93,8 @@ impl LibraryAppletHolder {
     ///
     /// This effectively waits until the library applet exits or the timeout expires
     #[inline]
-    pub fn join(&mut self, timeout: Option<i64>) -> Result<()> {
-        wait::wait_handles(&[self.state_changed_event_handle], timeout.unwrap_or(-1))?;
+    pub fn join(&mut self, timeout: impl Into<Timeout>) -> Result<()> {
+        wait::wait_handles(&[self.state_changed_event_handle], timeout)?;
         Ok(())
     }
 
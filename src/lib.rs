@@ -28,6 +28,20 @@
 //!
 //! - `mii` : Enables mii support, AKA the `nx::mii` module (also enables `services`)
 //!
+//! - `time` : Enables system clock and time zone service support in the `nx::time` module (also enables `services`)
+//!
+//! - `audio` : Enables audio output, input, decoding and voice rendering support, AKA the `nx::audio` module (also enables `services` and `applet`)
+//!
+//! - `net` : Enables network connection status support, AKA the `nx::net` module (also enables `services`)
+//!
+//! - `http` : Enables HTTP/1.1 client and file server support, AKA the `nx::http` module (also enables `socket` and `fs`)
+//!
+//! - `mdns` : Enables mDNS responder and DNS-SD service discovery support, AKA the `nx::mdns` module (also enables `socket` and `net`)
+//!
+//! - `nxlink` : Enables nxlink stdio redirection and NRO upload server support, AKA the `nx::nxlink` module (also enables `socket` and `fs`)
+//!
+//! - `log` : Enables the `log` crate bridge, AKA the `nx::diag::log::bridge` module, which dispatches the logs of any crate using `log` through the `nx::diag::log::registry` sinks
+//!
 //! Note that most of these features/modules are just simplified and easy-to-use wrappers around IPC/raw system features, so not using them doesn't fully block those features (for instance, you could use services using IPC commands more directly without the `services` feature).
 //!
 //! # Contributing
@@ -38,7 +52,8 @@
 //!
 //! Library examples are located at this other [repository](https://github.com/aarch64-switch-rs/examples)
 
-#![no_std]
+// Tests are built for (and run on) the host, thus they need std
+#![cfg_attr(not(test), no_std)]
 // needed to implement the APIs for collection types with custom allocators, and doing raw allocations
 #![feature(allocator_api)]
 // needed to specify weak linkage on some items
@@ -51,12 +66,15 @@
 #![feature(str_from_utf16_endian)]
 //#![warn(missing_docs)]
 #![macro_use]
-use core::arch::global_asm;
 
 // Required assembly bits (those which essentially cannot/shouldn't be inlined)
-global_asm!(include_str!("rrt0.s"));
-global_asm!(include_str!("mod0.s"));
-//global_asm!(include_str!("exception.s"));
+// These are only built for the console itself, so that the target-independent parts of the library can be built and tested on other hosts
+#[cfg(all(target_arch = "aarch64", target_os = "horizon"))]
+core::arch::global_asm!(include_str!("rrt0.s"));
+#[cfg(all(target_arch = "aarch64", target_os = "horizon"))]
+core::arch::global_asm!(include_str!("mod0.s"));
+#[cfg(all(target_arch = "aarch64", target_os = "horizon"))]
+core::arch::global_asm!(include_str!("exception.s"));
 
 extern crate self as nx;
 
@@ -112,6 +130,8 @@ pub mod arm;
 
 pub mod wait;
 
+pub mod time;
+
 pub mod version;
 
 #[cfg(feature = "applet")]
@@ -142,3 +162,18 @@ pub mod socket;
 
 #[cfg(feature = "mii")]
 pub mod mii;
+
+#[cfg(feature = "audio")]
+pub mod audio;
+
+#[cfg(feature = "net")]
+pub mod net;
+
+#[cfg(feature = "http")]
+pub mod http;
+
+#[cfg(feature = "mdns")]
+pub mod mdns;
+
+#[cfg(feature = "nxlink")]
+pub mod nxlink;
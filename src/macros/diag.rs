@@ -13,23 +13,21 @@ macro_rules! diag_assert {
     }};
 }
 
-// TODO: switch to a log system without having to reopen loggers for every log? global logger object(s) like N's log observers?
-
 #[macro_export]
 macro_rules! diag_log {
     ($logger:ty { $severity:expr, $verbosity:expr } => $msg:literal) => {{
-        let metadata = $crate::diag::log::LogMetadata::new($severity, $verbosity, alloc::string::String::from($msg), file!(), $crate::cur_fn_name!(), line!());
+        let metadata = $crate::diag::log::LogMetadata::new($severity, $verbosity, alloc::string::String::from($msg), file!(), $crate::cur_fn_name!(), line!()).with_module_path(module_path!());
         $crate::diag::log::log_with::<$logger>(&metadata);
     }};
 
     ($logger:ty { $severity:expr, $verbosity:expr } => $msg:expr) => {{
-        let metadata = $crate::diag::log::LogMetadata::new($severity, $verbosity, $msg, file!(), $crate::cur_fn_name!(), line!());
+        let metadata = $crate::diag::log::LogMetadata::new($severity, $verbosity, $msg, file!(), $crate::cur_fn_name!(), line!()).with_module_path(module_path!());
         $crate::diag::log::log_with::<$logger>(&metadata);
     }};
 
     ($logger:ty { $severity:expr, $verbosity:expr } => $fmt:literal, $( $params:expr ),*) => {{
         let msg = format!($fmt, $( $params, )*);
-        let metadata = $crate::diag::log::LogMetadata::new($severity, $verbosity, msg, file!(), $crate::cur_fn_name!(), line!());
+        let metadata = $crate::diag::log::LogMetadata::new($severity, $verbosity, msg, file!(), $crate::cur_fn_name!(), line!()).with_module_path(module_path!());
         $crate::diag::log::log_with::<$logger>(&metadata);
     }};
 }
@@ -2,6 +2,8 @@
 
 /// Creates a result definition
 ///
+/// The definition name is also registered, so that [`ResultCode`][`crate::result::ResultCode`]s are displayed with it (see [`get_name`][`crate::result::ResultCode::get_name`])
+///
 /// # Examples
 ///
 /// ```
@@ -31,6 +33,19 @@ macro_rules! result_define {
                     $description
                 }
             }
+
+            const _: () = {
+                // The section is only referenced through its start/stop symbols, which don't prevent the linker from garbage-collecting it (like lld does with start-stop-gc), thus it must be explicitly retained
+                // Plain `#[used]` is already emitted as linker-retained on ELF targets, and unlike `#[used(linker)]` it doesn't need the `used_with_arg` feature in crates expanding this macro
+                #[used]
+                #[unsafe(link_section = "nx_result_names")]
+                static RESULT_NAME: $crate::result::ResultName = $crate::result::ResultName::new(
+                    $module,
+                    $description,
+                    module_path!(),
+                    stringify!([<Result $name>]),
+                );
+            };
         }
     };
 }
@@ -279,3 +279,28 @@ macro_rules! maybe_cfi {
 }
 
 pub(crate) use maybe_cfi;
+
+// The console-specific assembly (SVCs, system registers...) is only assembled for the console itself, so that the target-independent parts of the library can be built and tested on other hosts.
+// There, naked functions just trap (they're never meant to be called outside the console anyway).
+#[cfg(all(target_arch = "aarch64", target_os = "horizon"))]
+macro_rules! naked_asm {
+    ($($asm:tt)*) => {
+        core::arch::naked_asm!($($asm)*)
+    };
+}
+
+#[cfg(all(target_arch = "aarch64", not(target_os = "horizon")))]
+macro_rules! naked_asm {
+    ($($asm:tt)*) => {
+        core::arch::naked_asm!("brk #0")
+    };
+}
+
+#[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+macro_rules! naked_asm {
+    ($($asm:tt)*) => {
+        core::arch::naked_asm!("ud2")
+    };
+}
+
+pub(crate) use naked_asm;
@@ -0,0 +1,717 @@
+//! mDNS (multicast DNS) responder and DNS-SD service discovery
+//!
+//! A [`Responder`] announces the console (as `<host name>.local`) and any number of [`Service`]s on the local network, answering the queries of other devices, thus they can find it without knowing its IP address:
+//!
+//! ```no_run
+//! use nx::mdns::{Responder, Service};
+//!
+//! let mut responder = Responder::new("my-switch")?;
+//! responder.add_service(Service::new("My Switch", "_http._tcp", 8080).with_txt("path=/"))?;
+//! responder.run(|| true)?;
+//! ```
+//!
+//! Likewise, [`browse`] finds the services of a given type announced by other devices (like PCs running a companion tool), and [`resolve_host`] finds the address of a `.local` host name:
+//!
+//! ```no_run
+//! use nx::mdns;
+//! use core::time::Duration;
+//!
+//! for service in mdns::browse("_http._tcp", Duration::from_secs(3))? {
+//!     // ...
+//! }
+//! ```
+//!
+//! Only IPv4 is supported, like in the rest of the socket implementation
+
+use crate::result::*;
+use crate::socket::PollFlags;
+use crate::socket::net::UdpSocket;
+use crate::socket::net::traits::{Pollable, SocketCommon};
+use crate::time::{Instant, Timeout};
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::net::{Ipv4Addr, SocketAddr, SocketAddrV4};
+use core::time::Duration;
+use dns::{Message, Question, Record, RecordData, RecordType};
+
+pub mod dns;
+
+pub mod rc;
+
+/// The mDNS UDP port
+pub const MDNS_PORT: u16 = 5353;
+
+/// The mDNS IPv4 multicast group address
+pub const MDNS_ADDRESS: Ipv4Addr = Ipv4Addr::new(224, 0, 0, 251);
+
+/// The TTL of records containing host names or addresses (`A` and `SRV` records), as recommended by RFC 6762
+pub const HOST_RECORD_TTL: u32 = 120;
+
+/// The TTL of the remaining records (`PTR` and `TXT` records), as recommended by RFC 6762
+pub const OTHER_RECORD_TTL: u32 = 4500;
+
+/// The maximum TTL of the records in responses to legacy (non-mDNS) queriers, as required by RFC 6762
+const LEGACY_RECORD_TTL: u32 = 10;
+
+/// The name DNS-SD clients query to enumerate all the service types on the network
+const SERVICE_TYPE_ENUMERATION_NAME: &str = "_services._dns-sd._udp.local";
+
+/// The top-level domain of every mDNS name
+const LOCAL_DOMAIN: &str = "local";
+
+/// The maximum size of the messages received (and sent)
+const MAX_MESSAGE_SIZE: usize = 9000;
+
+/// The interval between query retransmissions while browsing
+const QUERY_INTERVAL: Duration = Duration::from_secs(1);
+
+const MDNS_SOCKET_ADDRESS: SocketAddrV4 = SocketAddrV4::new(MDNS_ADDRESS, MDNS_PORT);
+
+struct PollTarget(i32);
+
+impl Pollable for PollTarget {
+    fn get_poll_fd(&self) -> i32 {
+        self.0
+    }
+}
+
+/// Waits until the socket has received data, returning whether it has (otherwise the timeout expired)
+fn wait_readable(socket: &UdpSocket, timeout: impl Into<Timeout>) -> Result<bool> {
+    let pollers = [(PollTarget(socket.as_raw_fd()), PollFlags::PollIn())];
+    let is_ready = crate::socket::net::poll(&pollers, timeout)?
+        .next()
+        .is_some();
+    Ok(is_ready)
+}
+
+/// Receives all the (valid) messages already received by the socket, along with their origins
+fn receive_messages(socket: &UdpSocket) -> Result<Vec<(Message, SocketAddr)>> {
+    let mut messages = Vec::new();
+    let mut buffer = vec![0u8; MAX_MESSAGE_SIZE];
+    while let Some((size, origin)) = socket.recv_from_non_blocking(&mut buffer)? {
+        // Invalid messages are expected on a shared network, and just ignored
+        if let Ok(message) = Message::parse(&buffer[..size]) {
+            messages.push((message, origin));
+        }
+    }
+    Ok(messages)
+}
+
+#[inline]
+fn make_local_name(name: &str) -> String {
+    format!("{}.{}", name.trim_end_matches('.'), LOCAL_DOMAIN)
+}
+
+/// Represents a DNS-SD service, as announced by a [`Responder`] or found by [`browse`]
+#[derive(Clone, PartialEq, Eq, Debug, Default)]
+pub struct Service {
+    /// The user-friendly instance name (like `My Switch`), which can't contain dots
+    pub instance_name: String,
+    /// The service type, as its protocol and transport (like `_http._tcp`)
+    pub service_type: String,
+    pub port: u16,
+    /// The TXT record strings, usually `key=value` pairs
+    pub txt: Vec<String>,
+}
+
+impl Service {
+    /// Creates a new [`Service`] without TXT record strings
+    ///
+    /// # Arguments
+    ///
+    /// * `instance_name`: The user-friendly instance name (like `My Switch`), which can't contain dots
+    /// * `service_type`: The service type, as its protocol and transport (like `_http._tcp`)
+    /// * `port`: The service port
+    pub fn new(
+        instance_name: impl Into<String>,
+        service_type: impl Into<String>,
+        port: u16,
+    ) -> Self {
+        Self {
+            instance_name: instance_name.into(),
+            service_type: service_type.into(),
+            port,
+            txt: Vec::new(),
+        }
+    }
+
+    /// Adds a TXT record string, returning the modified service
+    ///
+    /// # Arguments
+    ///
+    /// * `entry`: The string to add, usually a `key=value` pair
+    pub fn with_txt(mut self, entry: impl Into<String>) -> Self {
+        self.txt.push(entry.into());
+        self
+    }
+
+    /// Gets the value of a `key=value` TXT record string (keys are case-insensitive), if present
+    ///
+    /// # Arguments
+    ///
+    /// * `key`: The key to find
+    pub fn get_txt_value(&self, key: &str) -> Option<&str> {
+        self.txt.iter().find_map(|entry| {
+            let (entry_key, value) = entry.split_once('=').unwrap_or((entry.as_str(), ""));
+            entry_key.eq_ignore_ascii_case(key).then_some(value)
+        })
+    }
+
+    /// Gets the full name of the service type (like `_http._tcp.local`)
+    pub fn get_type_name(&self) -> String {
+        make_local_name(&self.service_type)
+    }
+
+    /// Gets the full name of the service instance (like `My Switch._http._tcp.local`)
+    pub fn get_instance_name(&self) -> String {
+        format!("{}.{}", self.instance_name, self.get_type_name())
+    }
+}
+
+/// Answers mDNS queries about the console and the services it announces (see the [module-level documentation](self))
+///
+/// The services are announced when they are added, and withdrawn (with "goodbye" announcements) when they are removed or the responder is dropped
+pub struct Responder {
+    socket: UdpSocket,
+    host_name: String,
+    address: Ipv4Addr,
+    services: Vec<Service>,
+}
+
+impl Responder {
+    /// Creates a new [`Responder`] for the console's current IP address (see [`crate::net::get_current_ip_address`])
+    ///
+    /// # Arguments
+    ///
+    /// * `host_name`: The host name, without the `.local` domain
+    #[inline]
+    pub fn new(host_name: &str) -> Result<Self> {
+        Self::new_with_address(host_name, crate::net::get_current_ip_address()?)
+    }
+
+    /// Creates a new [`Responder`] for the given IP address
+    ///
+    /// # Arguments
+    ///
+    /// * `host_name`: The host name, without the `.local` domain
+    /// * `address`: The console's IP address in the network to announce on
+    pub fn new_with_address(host_name: &str, address: Ipv4Addr) -> Result<Self> {
+        let socket = UdpSocket::bind((Ipv4Addr::UNSPECIFIED, MDNS_PORT))?;
+        socket.join_multicast_group(MDNS_ADDRESS, address)?;
+        socket.set_multicast_ttl(255)?;
+
+        let responder = Self {
+            socket,
+            host_name: make_local_name(host_name),
+            address,
+            services: Vec::new(),
+        };
+        responder.send_response(
+            responder.get_host_records(HOST_RECORD_TTL),
+            Vec::new(),
+            MDNS_SOCKET_ADDRESS.into(),
+        )?;
+        Ok(responder)
+    }
+
+    /// Gets the full host name (like `my-switch.local`)
+    #[inline]
+    pub fn get_host_name(&self) -> &str {
+        &self.host_name
+    }
+
+    /// Gets the announced IP address
+    #[inline]
+    pub fn get_address(&self) -> Ipv4Addr {
+        self.address
+    }
+
+    /// Gets the announced services
+    #[inline]
+    pub fn get_services(&self) -> &[Service] {
+        &self.services
+    }
+
+    /// Adds a service and announces it, replacing any previous service with the same instance name and type
+    ///
+    /// # Arguments
+    ///
+    /// * `service`: The service to add
+    pub fn add_service(&mut self, service: Service) -> Result<()> {
+        let instance_name = service.get_instance_name();
+        self.services
+            .retain(|added| !dns::names_equal(&added.get_instance_name(), &instance_name));
+
+        let mut records = self.get_service_records(&service, OTHER_RECORD_TTL, HOST_RECORD_TTL);
+        records.extend(self.get_host_records(HOST_RECORD_TTL));
+        self.services.push(service);
+        self.send_response(records, Vec::new(), MDNS_SOCKET_ADDRESS.into())
+    }
+
+    /// Removes a service, announcing that it's no longer available
+    ///
+    /// # Arguments
+    ///
+    /// * `instance_name`: The full instance name of the service (see [`Service::get_instance_name`])
+    pub fn remove_service(&mut self, instance_name: &str) -> Result<()> {
+        let Some(index) = self
+            .services
+            .iter()
+            .position(|service| dns::names_equal(&service.get_instance_name(), instance_name))
+        else {
+            return Ok(());
+        };
+
+        let service = self.services.remove(index);
+        // A zero TTL means the records are no longer valid
+        let records = self.get_service_records(&service, 0, 0);
+        self.send_response(records, Vec::new(), MDNS_SOCKET_ADDRESS.into())
+    }
+
+    /// Announces the console and all the services again
+    ///
+    /// This is automatically done when they are added, but RFC 6762 recommends announcing again a second later (in case the first announcement was lost)
+    pub fn announce(&self) -> Result<()> {
+        let mut records = Vec::new();
+        for service in &self.services {
+            records.extend(self.get_service_records(service, OTHER_RECORD_TTL, HOST_RECORD_TTL));
+        }
+        records.extend(self.get_host_records(HOST_RECORD_TTL));
+        self.send_response(records, Vec::new(), MDNS_SOCKET_ADDRESS.into())
+    }
+
+    /// Waits for queries and answers them
+    ///
+    /// # Arguments
+    ///
+    /// * `timeout`: The wait timeout
+    pub fn poll(&mut self, timeout: impl Into<Timeout>) -> Result<()> {
+        if !wait_readable(&self.socket, timeout)? {
+            return Ok(());
+        }
+
+        for (message, origin) in receive_messages(&self.socket)? {
+            if !message.is_response() {
+                self.answer_query(&message, origin)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Answers queries (see [`Responder::poll`]) while the given callback returns `true`, checking it at least every second
+    ///
+    /// # Arguments
+    ///
+    /// * `should_continue`: Whether to keep answering
+    pub fn run(&mut self, mut should_continue: impl FnMut() -> bool) -> Result<()> {
+        while should_continue() {
+            self.poll(Duration::from_secs(1))?;
+        }
+        Ok(())
+    }
+
+    fn get_host_records(&self, ttl: u32) -> Vec<Record> {
+        let mut record = Record::new(self.host_name.clone(), ttl, RecordData::A(self.address));
+        record.cache_flush = true;
+        vec![record]
+    }
+
+    fn get_service_records(&self, service: &Service, ttl: u32, host_ttl: u32) -> Vec<Record> {
+        let instance_name = service.get_instance_name();
+        let mut srv_record = Record::new(
+            instance_name.clone(),
+            host_ttl,
+            RecordData::Srv {
+                priority: 0,
+                weight: 0,
+                port: service.port,
+                target: self.host_name.clone(),
+            },
+        );
+        srv_record.cache_flush = true;
+        let mut txt_record = Record::new(
+            instance_name.clone(),
+            ttl,
+            RecordData::Txt(service.txt.clone()),
+        );
+        txt_record.cache_flush = true;
+
+        vec![
+            Record::new(service.get_type_name(), ttl, RecordData::Ptr(instance_name)),
+            srv_record,
+            txt_record,
+        ]
+    }
+
+    /// Gets the records answering a question, and the additional records the querier will likely need next
+    fn get_answers(&self, question: &Question) -> (Vec<Record>, Vec<Record>) {
+        let mut answers = Vec::new();
+        let mut additionals = Vec::new();
+
+        if dns::names_equal(&question.name, &self.host_name)
+            && question.record_type.matches(RecordType::A)
+        {
+            answers.extend(self.get_host_records(HOST_RECORD_TTL));
+        }
+
+        if dns::names_equal(&question.name, SERVICE_TYPE_ENUMERATION_NAME)
+            && question.record_type.matches(RecordType::Ptr)
+        {
+            for service in &self.services {
+                let record = Record::new(
+                    SERVICE_TYPE_ENUMERATION_NAME,
+                    OTHER_RECORD_TTL,
+                    RecordData::Ptr(service.get_type_name()),
+                );
+                if !answers
+                    .iter()
+                    .any(|answer: &Record| answer.is_same(&record))
+                {
+                    answers.push(record);
+                }
+            }
+        }
+
+        for service in &self.services {
+            let records = self.get_service_records(service, OTHER_RECORD_TTL, HOST_RECORD_TTL);
+            let [ptr_record, srv_record, txt_record] = <[Record; 3]>::try_from(records).unwrap();
+
+            if dns::names_equal(&question.name, &ptr_record.name)
+                && question.record_type.matches(RecordType::Ptr)
+            {
+                answers.push(ptr_record);
+                additionals.push(srv_record);
+                additionals.push(txt_record);
+                additionals.extend(self.get_host_records(HOST_RECORD_TTL));
+            } else if dns::names_equal(&question.name, &srv_record.name) {
+                let asks_srv = question.record_type.matches(RecordType::Srv);
+                if asks_srv {
+                    answers.push(srv_record);
+                    additionals.extend(self.get_host_records(HOST_RECORD_TTL));
+                }
+                if question.record_type.matches(RecordType::Txt) {
+                    answers.push(txt_record);
+                }
+            }
+        }
+
+        (answers, additionals)
+    }
+
+    fn answer_query(&self, query: &Message, origin: SocketAddr) -> Result<()> {
+        let mut answers: Vec<Record> = Vec::new();
+        let mut additionals: Vec<Record> = Vec::new();
+        let mut unicast_response = false;
+        for question in &query.questions {
+            let (question_answers, question_additionals) = self.get_answers(question);
+            if !question_answers.is_empty() {
+                unicast_response |= question.unicast_response;
+            }
+            for answer in question_answers {
+                // Known-answer suppression: the querier already has this record (with at least half of its TTL left)
+                let is_known = query
+                    .answers
+                    .iter()
+                    .any(|known| known.is_same(&answer) && (known.ttl >= answer.ttl / 2));
+                if !is_known && !answers.iter().any(|added| added.is_same(&answer)) {
+                    answers.push(answer);
+                }
+            }
+            additionals.extend(question_additionals);
+        }
+        if answers.is_empty() {
+            return Ok(());
+        }
+        additionals.retain(|additional| !answers.iter().any(|answer| answer.is_same(additional)));
+        additionals.dedup_by(|a, b| a.is_same(b));
+
+        // Queries not sent from the mDNS port come from simple (legacy) resolvers, which expect regular DNS responses
+        if origin.port() != MDNS_PORT {
+            let mut response = Message::new_response(query.id);
+            response.questions = query.questions.clone();
+            for record in answers.iter_mut().chain(additionals.iter_mut()) {
+                record.ttl = record.ttl.min(LEGACY_RECORD_TTL);
+                record.cache_flush = false;
+            }
+            response.answers = answers;
+            response.additionals = additionals;
+            return self.send_message(&response, origin);
+        }
+
+        let destination = match unicast_response {
+            true => origin,
+            false => MDNS_SOCKET_ADDRESS.into(),
+        };
+        self.send_response(answers, additionals, destination)
+    }
+
+    fn send_response(
+        &self,
+        answers: Vec<Record>,
+        additionals: Vec<Record>,
+        destination: SocketAddr,
+    ) -> Result<()> {
+        let mut response = Message::new_response(0);
+        response.answers = answers;
+        response.additionals = additionals;
+        self.send_message(&response, destination)
+    }
+
+    fn send_message(&self, message: &Message, destination: SocketAddr) -> Result<()> {
+        self.socket.send_to(&message.serialize()?, destination)
+    }
+}
+
+impl Drop for Responder {
+    fn drop(&mut self) {
+        let mut records = Vec::new();
+        for service in &self.services {
+            records.extend(self.get_service_records(service, 0, 0));
+        }
+        let _ = self.send_response(records, Vec::new(), MDNS_SOCKET_ADDRESS.into());
+    }
+}
+
+/// Sends queries from a (non-mDNS port) socket, which responders answer directly to it
+struct Querier {
+    socket: UdpSocket,
+    records: Vec<Record>,
+}
+
+impl Querier {
+    fn new() -> Result<Self> {
+        Ok(Self {
+            socket: UdpSocket::bind((Ipv4Addr::UNSPECIFIED, 0))?,
+            records: Vec::new(),
+        })
+    }
+
+    fn send_query(&self, questions: Vec<Question>) -> Result<()> {
+        let mut query = Message::new_query(0);
+        query.questions = questions;
+        // Known answers avoid getting the same records again
+        query.answers = self
+            .records
+            .iter()
+            .filter(|record| record.data.get_record_type() == RecordType::Ptr)
+            .cloned()
+            .collect();
+        self.socket
+            .send_to(&query.serialize()?, MDNS_SOCKET_ADDRESS)
+    }
+
+    /// Receives responses until the deadline (or until no more are received within the timeout), adding their records
+    fn receive_records(&mut self, timeout: impl Into<Timeout>) -> Result<()> {
+        if wait_readable(&self.socket, timeout)? {
+            for (message, _) in receive_messages(&self.socket)? {
+                if message.is_response() {
+                    for record in message.records() {
+                        self.add_record(record);
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn add_record(&mut self, record: &Record) {
+        match self.records.iter_mut().find(|added| added.is_same(record)) {
+            Some(added) => added.ttl = record.ttl,
+            None => self.records.push(record.clone()),
+        }
+    }
+
+    fn find_records<'a>(
+        &'a self,
+        name: &'a str,
+        record_type: RecordType,
+    ) -> impl Iterator<Item = &'a RecordData> {
+        self.records
+            .iter()
+            .filter(move |record| {
+                // Zero-TTL records are goodbyes of no longer available ones
+                (record.ttl != 0)
+                    && (record.data.get_record_type() == record_type)
+                    && dns::names_equal(&record.name, name)
+            })
+            .map(|record| &record.data)
+    }
+
+    fn find_addresses(&self, host_name: &str) -> Vec<Ipv4Addr> {
+        self.find_records(host_name, RecordType::A)
+            .filter_map(|data| match data {
+                RecordData::A(address) => Some(*address),
+                _ => None,
+            })
+            .collect()
+    }
+}
+
+/// Represents a service found by [`browse`]
+#[derive(Clone, PartialEq, Eq, Debug, Default)]
+pub struct DiscoveredService {
+    pub service: Service,
+    /// The full host name of the device announcing the service (like `my-pc.local`)
+    pub host_name: String,
+    /// The IP addresses of the host (which might be empty if they couldn't be found)
+    pub addresses: Vec<Ipv4Addr>,
+}
+
+impl DiscoveredService {
+    /// Gets the socket address to connect to the service, if any address of the host was found
+    pub fn get_socket_addr(&self) -> Option<SocketAddrV4> {
+        self.addresses
+            .first()
+            .map(|address| SocketAddrV4::new(*address, self.service.port))
+    }
+}
+
+/// Finds the services of the given type announced on the local network, waiting for responses until the timeout expires
+///
+/// The service records (and host addresses) are queried as needed, and services whose records weren't found are not returned
+///
+/// Since there's no way to know whether all the services have answered, responses are collected until the timeout expires: infinite timeouts fail with [`rc::ResultInvalidTimeout`]
+///
+/// # Arguments
+///
+/// * `service_type`: The service type, as its protocol and transport (like `_http._tcp`)
+/// * `timeout`: The time to wait for responses
+pub fn browse(service_type: &str, timeout: impl Into<Timeout>) -> Result<Vec<DiscoveredService>> {
+    let type_name = make_local_name(service_type);
+    let deadline = timeout
+        .into()
+        .get_deadline()
+        .ok_or(rc::ResultInvalidTimeout::make())?;
+
+    let mut querier = Querier::new()?;
+    let mut queried_names: Vec<String> = Vec::new();
+    let mut next_type_query = Instant::now();
+    loop {
+        let now = Instant::now();
+        if now >= deadline {
+            break;
+        }
+
+        let mut questions = Vec::new();
+        if now >= next_type_query {
+            questions.push(Question::new(type_name.clone(), RecordType::Ptr));
+            next_type_query = now + QUERY_INTERVAL;
+        }
+
+        // Query the records of found instances (and hosts) which weren't sent along with them
+        let instance_names: Vec<String> = querier
+            .find_records(&type_name, RecordType::Ptr)
+            .filter_map(|data| match data {
+                RecordData::Ptr(instance_name) => Some(instance_name.clone()),
+                _ => None,
+            })
+            .collect();
+        for instance_name in instance_names {
+            let srv_target = querier
+                .find_records(&instance_name, RecordType::Srv)
+                .find_map(|data| match data {
+                    RecordData::Srv { target, .. } => Some(target.clone()),
+                    _ => None,
+                });
+            let missing_name = match srv_target {
+                None => Some((instance_name, RecordType::Any)),
+                Some(target) if querier.find_addresses(&target).is_empty() => {
+                    Some((target, RecordType::A))
+                }
+                Some(_) => None,
+            };
+            if let Some((name, record_type)) = missing_name
+                && !queried_names
+                    .iter()
+                    .any(|queried| dns::names_equal(queried, &name))
+            {
+                questions.push(Question::new(name.clone(), record_type));
+                queried_names.push(name);
+            }
+        }
+
+        if !questions.is_empty() {
+            querier.send_query(questions)?;
+        }
+
+        querier.receive_records(deadline.min(next_type_query))?;
+    }
+
+    let mut services = Vec::new();
+    for data in querier.find_records(&type_name, RecordType::Ptr) {
+        let RecordData::Ptr(instance_name) = data else {
+            continue;
+        };
+        let Some((port, host_name)) = querier
+            .find_records(instance_name, RecordType::Srv)
+            .find_map(|data| match data {
+                RecordData::Srv { port, target, .. } => Some((*port, target.clone())),
+                _ => None,
+            })
+        else {
+            continue;
+        };
+        let txt = querier
+            .find_records(instance_name, RecordType::Txt)
+            .find_map(|data| match data {
+                RecordData::Txt(strings) => Some(strings.clone()),
+                _ => None,
+            })
+            .unwrap_or_default();
+
+        let instance_label = instance_name
+            .strip_suffix(type_name.as_str())
+            .map(|label| label.trim_end_matches('.'))
+            .unwrap_or(instance_name);
+        services.push(DiscoveredService {
+            service: Service {
+                instance_name: String::from(instance_label),
+                service_type: String::from(service_type),
+                port,
+                txt,
+            },
+            addresses: querier.find_addresses(&host_name),
+            host_name,
+        });
+    }
+    Ok(services)
+}
+
+/// Finds the IP address of a `.local` host name (like `my-pc.local`)
+///
+/// # Arguments
+///
+/// * `host_name`: The host name, with or without the `.local` domain
+/// * `timeout`: The maximum time to wait for a response
+pub fn resolve_host(host_name: &str, timeout: impl Into<Timeout>) -> Result<Ipv4Addr> {
+    let host_name = match host_name
+        .trim_end_matches('.')
+        .to_ascii_lowercase()
+        .ends_with(".local")
+    {
+        true => String::from(host_name),
+        false => make_local_name(host_name),
+    };
+    let deadline = timeout.into().get_deadline();
+
+    let mut querier = Querier::new()?;
+    loop {
+        let now = Instant::now();
+        result_return_if!(
+            deadline.is_some_and(|deadline| now >= deadline),
+            rc::ResultHostNotFound
+        );
+
+        querier.send_query(vec![Question::new(host_name.clone(), RecordType::A)])?;
+        let wait_deadline = match deadline {
+            Some(deadline) => deadline.min(now + QUERY_INTERVAL),
+            None => now + QUERY_INTERVAL,
+        };
+        querier.receive_records(wait_deadline)?;
+
+        if let Some(address) = querier.find_addresses(&host_name).first() {
+            return Ok(*address);
+        }
+    }
+}
@@ -0,0 +1,858 @@
+//! DNS message encoding and decoding (RFC 1035), with the mDNS (RFC 6762) class flags
+//!
+//! Nothing here performs any I/O: messages are parsed from (and serialized to) byte buffers
+//!
+//! Names are represented as dotted strings (like `_http._tcp.local`), thus labels can't contain dots themselves. Names are compressed when parsing but not when serializing, which is valid (just larger)
+
+use super::rc;
+use crate::result::*;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::net::Ipv4Addr;
+
+/// The `IN` (internet) class
+pub const CLASS_IN: u16 = 1;
+
+/// The mDNS class bit meaning "cache flush" in records and "unicast response" in questions
+pub const CLASS_MDNS_FLAG: u16 = 0x8000;
+
+/// The header flags of a query
+pub const FLAGS_QUERY: u16 = 0;
+
+/// The header flags of an (authoritative) response
+pub const FLAGS_RESPONSE: u16 = 0x8400;
+
+const FLAG_RESPONSE: u16 = 0x8000;
+
+const MAX_LABEL_LENGTH: usize = 63;
+
+const MAX_NAME_LENGTH: usize = 255;
+
+const POINTER_MASK: u8 = 0xC0;
+
+/// Represents a record type
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum RecordType {
+    A,
+    Ptr,
+    Txt,
+    Aaaa,
+    Srv,
+    Any,
+    Other(u16),
+}
+
+impl RecordType {
+    /// Gets the [`RecordType`] with the given value
+    ///
+    /// # Arguments
+    ///
+    /// * `value`: The type value
+    pub const fn from_value(value: u16) -> Self {
+        match value {
+            1 => Self::A,
+            12 => Self::Ptr,
+            16 => Self::Txt,
+            28 => Self::Aaaa,
+            33 => Self::Srv,
+            255 => Self::Any,
+            _ => Self::Other(value),
+        }
+    }
+
+    /// Gets the type value
+    pub const fn get_value(self) -> u16 {
+        match self {
+            Self::A => 1,
+            Self::Ptr => 12,
+            Self::Txt => 16,
+            Self::Aaaa => 28,
+            Self::Srv => 33,
+            Self::Any => 255,
+            Self::Other(value) => value,
+        }
+    }
+
+    /// Gets whether a question of this type asks for records of the given type
+    ///
+    /// # Arguments
+    ///
+    /// * `record_type`: The record type
+    #[inline]
+    pub fn matches(self, record_type: RecordType) -> bool {
+        (self == Self::Any) || (self == record_type)
+    }
+}
+
+/// Represents a question
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Question {
+    pub name: String,
+    pub record_type: RecordType,
+    /// Whether the answer is preferred to be sent by unicast (the mDNS "QU" bit)
+    pub unicast_response: bool,
+}
+
+impl Question {
+    /// Creates a new [`Question`], asking for a multicast answer
+    ///
+    /// # Arguments
+    ///
+    /// * `name`: The name to ask about
+    /// * `record_type`: The type of the records to ask for
+    pub fn new(name: impl Into<String>, record_type: RecordType) -> Self {
+        Self {
+            name: name.into(),
+            record_type,
+            unicast_response: false,
+        }
+    }
+}
+
+/// Represents the data of a record
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum RecordData {
+    A(Ipv4Addr),
+    Ptr(String),
+    /// The strings (usually `key=value` pairs) of the record
+    Txt(Vec<String>),
+    Srv {
+        priority: u16,
+        weight: u16,
+        port: u16,
+        target: String,
+    },
+    Other {
+        record_type: u16,
+        data: Vec<u8>,
+    },
+}
+
+impl RecordData {
+    /// Gets the type of the record containing this data
+    pub fn get_record_type(&self) -> RecordType {
+        match self {
+            Self::A(_) => RecordType::A,
+            Self::Ptr(_) => RecordType::Ptr,
+            Self::Txt(_) => RecordType::Txt,
+            Self::Srv { .. } => RecordType::Srv,
+            Self::Other { record_type, .. } => RecordType::from_value(*record_type),
+        }
+    }
+}
+
+/// Represents a resource record
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Record {
+    pub name: String,
+    /// Whether this record replaces the cached ones with the same name and type (the mDNS "cache flush" bit)
+    pub cache_flush: bool,
+    /// The time (in seconds) the record can be cached for, where `0` means the record is no longer valid
+    pub ttl: u32,
+    pub data: RecordData,
+}
+
+impl Record {
+    /// Creates a new [`Record`] without the cache flush bit
+    ///
+    /// # Arguments
+    ///
+    /// * `name`: The record name
+    /// * `ttl`: The record TTL, in seconds
+    /// * `data`: The record data
+    pub fn new(name: impl Into<String>, ttl: u32, data: RecordData) -> Self {
+        Self {
+            name: name.into(),
+            cache_flush: false,
+            ttl,
+            data,
+        }
+    }
+
+    /// Gets whether this record has the same name and data as another one (ignoring the TTL and flags)
+    ///
+    /// # Arguments
+    ///
+    /// * `other`: The other record
+    pub fn is_same(&self, other: &Record) -> bool {
+        names_equal(&self.name, &other.name)
+            && match (&self.data, &other.data) {
+                (RecordData::Ptr(a), RecordData::Ptr(b)) => names_equal(a, b),
+                (
+                    RecordData::Srv {
+                        priority,
+                        weight,
+                        port,
+                        target,
+                    },
+                    RecordData::Srv {
+                        priority: other_priority,
+                        weight: other_weight,
+                        port: other_port,
+                        target: other_target,
+                    },
+                ) => {
+                    (priority, weight, port) == (other_priority, other_weight, other_port)
+                        && names_equal(target, other_target)
+                }
+                (data, other_data) => data == other_data,
+            }
+    }
+}
+
+/// Represents a DNS message
+#[derive(Clone, PartialEq, Eq, Debug, Default)]
+pub struct Message {
+    pub id: u16,
+    pub flags: u16,
+    pub questions: Vec<Question>,
+    pub answers: Vec<Record>,
+    pub authorities: Vec<Record>,
+    pub additionals: Vec<Record>,
+}
+
+impl Message {
+    /// Creates an empty query
+    ///
+    /// # Arguments
+    ///
+    /// * `id`: The message ID (which should be `0` for multicast queries)
+    pub const fn new_query(id: u16) -> Self {
+        Self {
+            id,
+            flags: FLAGS_QUERY,
+            questions: Vec::new(),
+            answers: Vec::new(),
+            authorities: Vec::new(),
+            additionals: Vec::new(),
+        }
+    }
+
+    /// Creates an empty (authoritative) response
+    ///
+    /// # Arguments
+    ///
+    /// * `id`: The message ID (which should be `0` for multicast responses)
+    pub const fn new_response(id: u16) -> Self {
+        Self {
+            id,
+            flags: FLAGS_RESPONSE,
+            questions: Vec::new(),
+            answers: Vec::new(),
+            authorities: Vec::new(),
+            additionals: Vec::new(),
+        }
+    }
+
+    /// Gets whether this message is a response
+    #[inline]
+    pub const fn is_response(&self) -> bool {
+        (self.flags & FLAG_RESPONSE) != 0
+    }
+
+    /// Gets all the records of this message (answers, authorities and additionals)
+    pub fn records(&self) -> impl Iterator<Item = &Record> {
+        self.answers
+            .iter()
+            .chain(self.authorities.iter())
+            .chain(self.additionals.iter())
+    }
+
+    /// Serializes this message
+    pub fn serialize(&self) -> Result<Vec<u8>> {
+        let mut data = Vec::new();
+        data.extend_from_slice(&self.id.to_be_bytes());
+        data.extend_from_slice(&self.flags.to_be_bytes());
+        for count in [
+            self.questions.len(),
+            self.answers.len(),
+            self.authorities.len(),
+            self.additionals.len(),
+        ] {
+            let count = u16::try_from(count).map_err(|_| rc::ResultInvalidMessage::make())?;
+            data.extend_from_slice(&count.to_be_bytes());
+        }
+
+        for question in &self.questions {
+            serialize_name(&question.name, &mut data)?;
+            data.extend_from_slice(&question.record_type.get_value().to_be_bytes());
+            let class = match question.unicast_response {
+                true => CLASS_IN | CLASS_MDNS_FLAG,
+                false => CLASS_IN,
+            };
+            data.extend_from_slice(&class.to_be_bytes());
+        }
+        for record in self.records() {
+            serialize_record(record, &mut data)?;
+        }
+        Ok(data)
+    }
+
+    /// Parses a message
+    ///
+    /// # Arguments
+    ///
+    /// * `data`: The message data
+    pub fn parse(data: &[u8]) -> Result<Self> {
+        let mut reader = Reader::new(data);
+        let id = reader.read_u16()?;
+        let flags = reader.read_u16()?;
+        let question_count = reader.read_u16()?;
+        let answer_count = reader.read_u16()?;
+        let authority_count = reader.read_u16()?;
+        let additional_count = reader.read_u16()?;
+
+        let mut questions = Vec::new();
+        for _ in 0..question_count {
+            let name = reader.read_name()?;
+            let record_type = RecordType::from_value(reader.read_u16()?);
+            let class = reader.read_u16()?;
+            questions.push(Question {
+                name,
+                record_type,
+                unicast_response: (class & CLASS_MDNS_FLAG) != 0,
+            });
+        }
+
+        let mut read_records = |count: u16| -> Result<Vec<Record>> {
+            let mut records = Vec::new();
+            for _ in 0..count {
+                records.push(reader.read_record()?);
+            }
+            Ok(records)
+        };
+        let answers = read_records(answer_count)?;
+        let authorities = read_records(authority_count)?;
+        let additionals = read_records(additional_count)?;
+
+        Ok(Self {
+            id,
+            flags,
+            questions,
+            answers,
+            authorities,
+            additionals,
+        })
+    }
+}
+
+/// Compares two names (case-insensitively, ignoring any trailing dot)
+///
+/// # Arguments
+///
+/// * `a`: The first name
+/// * `b`: The second name
+pub fn names_equal(a: &str, b: &str) -> bool {
+    a.trim_end_matches('.')
+        .eq_ignore_ascii_case(b.trim_end_matches('.'))
+}
+
+fn serialize_name(name: &str, out_data: &mut Vec<u8>) -> Result<()> {
+    let name = name.trim_end_matches('.');
+    result_return_if!(name.len() >= MAX_NAME_LENGTH, rc::ResultInvalidName);
+    if !name.is_empty() {
+        for label in name.split('.') {
+            result_return_if!(
+                label.is_empty() || (label.len() > MAX_LABEL_LENGTH),
+                rc::ResultInvalidName
+            );
+            out_data.push(label.len() as u8);
+            out_data.extend_from_slice(label.as_bytes());
+        }
+    }
+    out_data.push(0);
+    Ok(())
+}
+
+fn serialize_record(record: &Record, out_data: &mut Vec<u8>) -> Result<()> {
+    serialize_name(&record.name, out_data)?;
+    out_data.extend_from_slice(&record.data.get_record_type().get_value().to_be_bytes());
+    let class = match record.cache_flush {
+        true => CLASS_IN | CLASS_MDNS_FLAG,
+        false => CLASS_IN,
+    };
+    out_data.extend_from_slice(&class.to_be_bytes());
+    out_data.extend_from_slice(&record.ttl.to_be_bytes());
+
+    // The data length is written once the data is serialized
+    let length_offset = out_data.len();
+    out_data.extend_from_slice(&[0u8; 2]);
+    match &record.data {
+        RecordData::A(address) => out_data.extend_from_slice(&address.octets()),
+        RecordData::Ptr(name) => serialize_name(name, out_data)?,
+        RecordData::Txt(strings) => {
+            for string in strings {
+                let len =
+                    u8::try_from(string.len()).map_err(|_| rc::ResultInvalidMessage::make())?;
+                out_data.push(len);
+                out_data.extend_from_slice(string.as_bytes());
+            }
+            // A TXT record must contain at least a (empty) string
+            if strings.is_empty() {
+                out_data.push(0);
+            }
+        }
+        RecordData::Srv {
+            priority,
+            weight,
+            port,
+            target,
+        } => {
+            out_data.extend_from_slice(&priority.to_be_bytes());
+            out_data.extend_from_slice(&weight.to_be_bytes());
+            out_data.extend_from_slice(&port.to_be_bytes());
+            serialize_name(target, out_data)?;
+        }
+        RecordData::Other { data, .. } => out_data.extend_from_slice(data),
+    }
+
+    let data_len = u16::try_from(out_data.len() - length_offset - 2)
+        .map_err(|_| rc::ResultInvalidMessage::make())?;
+    out_data[length_offset..length_offset + 2].copy_from_slice(&data_len.to_be_bytes());
+    Ok(())
+}
+
+struct Reader<'a> {
+    data: &'a [u8],
+    offset: usize,
+}
+
+impl<'a> Reader<'a> {
+    const fn new(data: &'a [u8]) -> Self {
+        Self { data, offset: 0 }
+    }
+
+    fn read_bytes(&mut self, size: usize) -> Result<&'a [u8]> {
+        let bytes = self
+            .data
+            .get(self.offset..self.offset.saturating_add(size))
+            .ok_or(rc::ResultInvalidMessage::make())?;
+        self.offset += size;
+        Ok(bytes)
+    }
+
+    fn read_u8(&mut self) -> Result<u8> {
+        Ok(self.read_bytes(1)?[0])
+    }
+
+    fn read_u16(&mut self) -> Result<u16> {
+        let bytes = self.read_bytes(2)?;
+        Ok(u16::from_be_bytes([bytes[0], bytes[1]]))
+    }
+
+    fn read_u32(&mut self) -> Result<u32> {
+        let bytes = self.read_bytes(4)?;
+        Ok(u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+    }
+
+    fn read_name(&mut self) -> Result<String> {
+        let mut name = String::new();
+        // Where to continue once a compression pointer is followed
+        let mut resume_offset = None;
+        let mut jump_count = 0;
+        loop {
+            let len = self.read_u8()?;
+            if (len & POINTER_MASK) == POINTER_MASK {
+                let pointer = (((len & !POINTER_MASK) as usize) << 8) | self.read_u8()? as usize;
+                // Pointers must go backwards, which also prevents loops
+                result_return_unless!(
+                    (pointer < self.offset - 2) && (jump_count < MAX_NAME_LENGTH),
+                    rc::ResultInvalidMessage
+                );
+                resume_offset.get_or_insert(self.offset);
+                self.offset = pointer;
+                jump_count += 1;
+                continue;
+            }
+            result_return_if!((len & POINTER_MASK) != 0, rc::ResultInvalidMessage);
+
+            if len == 0 {
+                break;
+            }
+            let label = self.read_bytes(len as usize)?;
+            if !name.is_empty() {
+                name.push('.');
+            }
+            name.push_str(&String::from_utf8_lossy(label));
+            result_return_if!(name.len() >= MAX_NAME_LENGTH, rc::ResultInvalidMessage);
+        }
+
+        if let Some(resume_offset) = resume_offset {
+            self.offset = resume_offset;
+        }
+        Ok(name)
+    }
+
+    fn read_record(&mut self) -> Result<Record> {
+        let name = self.read_name()?;
+        let record_type = self.read_u16()?;
+        let class = self.read_u16()?;
+        let ttl = self.read_u32()?;
+        let data_len = self.read_u16()? as usize;
+
+        let data_offset = self.offset;
+        let data_end = data_offset.saturating_add(data_len);
+        result_return_if!(data_end > self.data.len(), rc::ResultInvalidMessage);
+
+        let data = match RecordType::from_value(record_type) {
+            RecordType::A if data_len == 4 => {
+                let bytes = self.read_bytes(4)?;
+                RecordData::A(Ipv4Addr::new(bytes[0], bytes[1], bytes[2], bytes[3]))
+            }
+            RecordType::Ptr => RecordData::Ptr(self.read_name()?),
+            RecordType::Txt => {
+                let mut strings = Vec::new();
+                while self.offset < data_end {
+                    let len = self.read_u8()? as usize;
+                    let string = self.read_bytes(len)?;
+                    if !string.is_empty() {
+                        strings.push(String::from_utf8_lossy(string).into_owned());
+                    }
+                }
+                RecordData::Txt(strings)
+            }
+            RecordType::Srv => RecordData::Srv {
+                priority: self.read_u16()?,
+                weight: self.read_u16()?,
+                port: self.read_u16()?,
+                target: self.read_name()?,
+            },
+            _ => RecordData::Other {
+                record_type,
+                data: self.read_bytes(data_len)?.to_vec(),
+            },
+        };
+        // Names inside the data must not go past it
+        result_return_if!(self.offset > data_end, rc::ResultInvalidMessage);
+        self.offset = data_end;
+
+        Ok(Record {
+            name,
+            cache_flush: (class & CLASS_MDNS_FLAG) != 0,
+            ttl,
+            data,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::string::ToString;
+    use alloc::vec;
+
+    fn response() -> Message {
+        let mut message = Message::new_response(0);
+        message.questions.push(Question {
+            unicast_response: true,
+            ..Question::new("_http._tcp.local", RecordType::Ptr)
+        });
+        message.answers.push(Record::new(
+            "_http._tcp.local",
+            4500,
+            RecordData::Ptr("My Device._http._tcp.local".to_string()),
+        ));
+        message.answers.push(Record {
+            cache_flush: true,
+            ..Record::new(
+                "My Device._http._tcp.local",
+                120,
+                RecordData::Srv {
+                    priority: 1,
+                    weight: 2,
+                    port: 8080,
+                    target: "switch.local".to_string(),
+                },
+            )
+        });
+        message.authorities.push(Record::new(
+            "My Device._http._tcp.local",
+            4500,
+            RecordData::Txt(vec!["path=/".to_string(), "v=1".to_string()]),
+        ));
+        message.additionals.push(Record::new(
+            "switch.local",
+            120,
+            RecordData::A(Ipv4Addr::new(192, 168, 1, 20)),
+        ));
+        message.additionals.push(Record::new(
+            "switch.local",
+            0,
+            RecordData::Other {
+                record_type: 28,
+                data: vec![0xFE, 0x80, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1],
+            },
+        ));
+        message
+    }
+
+    /// Builds a message with a header announcing the given counts, followed by the given data
+    fn raw_message(counts: [u16; 4], body: &[u8]) -> Vec<u8> {
+        let mut data = vec![0, 0, 0x84, 0];
+        for count in counts {
+            data.extend_from_slice(&count.to_be_bytes());
+        }
+        data.extend_from_slice(body);
+        data
+    }
+
+    #[test]
+    fn record_types() {
+        for record_type in [
+            RecordType::A,
+            RecordType::Ptr,
+            RecordType::Txt,
+            RecordType::Aaaa,
+            RecordType::Srv,
+            RecordType::Any,
+            RecordType::Other(47),
+        ] {
+            assert_eq!(RecordType::from_value(record_type.get_value()), record_type);
+        }
+        assert!(RecordType::Any.matches(RecordType::Srv));
+        assert!(RecordType::Srv.matches(RecordType::Srv));
+        assert!(!RecordType::Srv.matches(RecordType::Any));
+        assert_eq!(
+            RecordData::Other {
+                record_type: 28,
+                data: Vec::new()
+            }
+            .get_record_type(),
+            RecordType::Aaaa
+        );
+    }
+
+    #[test]
+    fn names() {
+        assert!(names_equal("Switch.LOCAL.", "switch.local"));
+        assert!(!names_equal("switch.local", "switch2.local"));
+
+        let record = Record::new("a.local", 120, RecordData::Ptr("B.local.".to_string()));
+        assert!(record.is_same(&Record {
+            cache_flush: true,
+            ..Record::new("A.LOCAL", 0, RecordData::Ptr("b.local".to_string()))
+        }));
+        assert!(!record.is_same(&Record::new(
+            "a.local",
+            120,
+            RecordData::Ptr("c.local".to_string())
+        )));
+        assert!(!record.is_same(&Record::new("a.local", 120, RecordData::Txt(Vec::new()))));
+    }
+
+    #[test]
+    fn round_trip() {
+        let message = response();
+        assert!(message.is_response());
+        assert_eq!(message.records().count(), 5);
+
+        let data = message.serialize().unwrap();
+        assert_eq!(&data[..12], [0, 0, 0x84, 0, 0, 1, 0, 2, 0, 1, 0, 2]);
+        assert_eq!(Message::parse(&data).unwrap(), message);
+
+        let mut query = Message::new_query(0x1234);
+        query
+            .questions
+            .push(Question::new("switch.local.", RecordType::A));
+        query.questions.push(Question::new("", RecordType::Any));
+        let data = query.serialize().unwrap();
+        assert_eq!(
+            data,
+            b"\x12\x34\0\0\0\x02\0\0\0\0\0\0\x06switch\x05local\0\0\x01\0\x01\0\0\xFF\0\x01"
+        );
+        let parsed = Message::parse(&data).unwrap();
+        assert!(!parsed.is_response());
+        assert_eq!(parsed.questions[0].name, "switch.local");
+        assert_eq!(parsed.questions[1], Question::new("", RecordType::Any));
+
+        // Empty TXT records are sent with an empty string, which is not kept
+        let mut message = Message::new_response(0);
+        message
+            .answers
+            .push(Record::new("a.local", 1, RecordData::Txt(Vec::new())));
+        let data = message.serialize().unwrap();
+        assert_eq!(&data[data.len() - 3..], [0, 1, 0]);
+        assert_eq!(Message::parse(&data).unwrap(), message);
+    }
+
+    #[test]
+    fn compressed_names() {
+        // "switch.local" at 12, then "_http._tcp" + pointer to "local" (at 19), then a pointer to the whole first name
+        let body = b"\x06switch\x05local\0\0\x01\0\x01\x05_http\x04_tcp\xC0\x13\0\x0C\0\x01\xC0\x0C\0\x01\x80\x01\0\0\0\x78\0\x04\xC0\xA8\x01\x14";
+        let message = Message::parse(&raw_message([2, 1, 0, 0], body)).unwrap();
+        assert_eq!(message.questions[0].name, "switch.local");
+        assert_eq!(message.questions[1].name, "_http._tcp.local");
+        assert_eq!(message.questions[1].record_type, RecordType::Ptr);
+        assert_eq!(
+            message.answers,
+            [Record {
+                cache_flush: true,
+                ..Record::new(
+                    "switch.local",
+                    120,
+                    RecordData::A(Ipv4Addr::new(192, 168, 1, 20))
+                )
+            }]
+        );
+
+        // Names inside record data can be compressed too
+        let body = b"\x05local\0\0\x01\0\x01\x01a\xC0\x0C\0\x0C\0\x01\0\0\0\x01\0\x04\x01b\xC0\x0C";
+        let message = Message::parse(&raw_message([1, 1, 0, 0], body)).unwrap();
+        assert_eq!(
+            message.answers[0].data,
+            RecordData::Ptr("b.local".to_string())
+        );
+    }
+
+    #[test]
+    fn compression_pointer_loops() {
+        for body in [
+            // Pointing to itself
+            &b"\xC0\x0C\0\x01\0\x01"[..],
+            // Pointing forward
+            b"\xC0\x0E\x01a\0\0\x01\0\x01",
+            // Pointing outside the message
+            b"\xC0\xFF\0\x01\0\x01",
+            // Pointing to a label which leads back to the same pointer
+            b"\x01a\xC0\x0C\0\x01\0\x01",
+            // Two names pointing to each other
+            b"\x01a\xC0\x12\0\x01\0\x01\x01b\xC0\x0C\0\x01\0\x01",
+        ] {
+            let data = raw_message([(body.len() > 9) as u16 + 1, 0, 0, 0], body);
+            assert!(
+                rc::ResultInvalidMessage::matches(Message::parse(&data).unwrap_err()),
+                "{:?}",
+                body
+            );
+        }
+    }
+
+    #[test]
+    fn malformed_messages() {
+        // Reserved label types
+        for len in [0x40, 0x80] {
+            let data = raw_message([1, 0, 0, 0], &[len, b'a', 0, 0, 1, 0, 1]);
+            assert!(rc::ResultInvalidMessage::matches(
+                Message::parse(&data).unwrap_err()
+            ));
+        }
+
+        // Names which are too long
+        let mut body = Vec::new();
+        for _ in 0..5 {
+            body.push(MAX_LABEL_LENGTH as u8);
+            body.extend_from_slice(&[b'a'; MAX_LABEL_LENGTH]);
+        }
+        body.extend_from_slice(&[0, 0, 1, 0, 1]);
+        let data = raw_message([1, 0, 0, 0], &body);
+        assert!(rc::ResultInvalidMessage::matches(
+            Message::parse(&data).unwrap_err()
+        ));
+
+        // More entries than present
+        let data = raw_message([1, 0, 0, 0], b"");
+        assert!(Message::parse(&data).is_err());
+        let data = raw_message([0, 0, 0, 0xFFFF], b"\0\0\x01\0\x01\0\0\0\0\0\0");
+        assert!(Message::parse(&data).is_err());
+    }
+
+    #[test]
+    fn truncated_messages() {
+        let data = response().serialize().unwrap();
+        for size in 0..data.len() {
+            assert!(
+                rc::ResultInvalidMessage::matches(Message::parse(&data[..size]).unwrap_err()),
+                "{}",
+                size
+            );
+        }
+        // Data after the last record is ignored
+        let mut data = data;
+        data.push(0);
+        assert_eq!(Message::parse(&data).unwrap(), response());
+    }
+
+    #[test]
+    fn truncated_records() {
+        // The record data length goes past the message
+        let data = raw_message(
+            [0, 1, 0, 0],
+            b"\0\0\x01\0\x01\0\0\0\0\0\x05\x01\x02\x03\x04",
+        );
+        assert!(rc::ResultInvalidMessage::matches(
+            Message::parse(&data).unwrap_err()
+        ));
+
+        // The contents go past the record data, even though the message continues
+        for body in [
+            // A PTR name
+            &b"\0\0\x0C\0\x01\0\0\0\0\0\x02\x01a\0"[..],
+            // A TXT string
+            b"\0\0\x10\0\x01\0\0\0\0\0\x02\x03abc",
+            // A SRV target
+            b"\0\0\x21\0\x01\0\0\0\0\0\x06\0\0\0\0\0\x50\x01a\0",
+            // The SRV fields
+            b"\0\0\x21\0\x01\0\0\0\0\0\x02\0\0\0\0\0\x50\0",
+        ] {
+            let data = raw_message([0, 1, 0, 0], body);
+            assert!(
+                rc::ResultInvalidMessage::matches(Message::parse(&data).unwrap_err()),
+                "{:?}",
+                body
+            );
+        }
+
+        // A records with another length are kept as they are
+        let data = raw_message([0, 1, 0, 0], b"\0\0\x01\0\x01\0\0\0\0\0\x02\x01\x02");
+        assert_eq!(
+            Message::parse(&data).unwrap().answers[0].data,
+            RecordData::Other {
+                record_type: 1,
+                data: vec![1, 2]
+            }
+        );
+    }
+
+    #[test]
+    fn invalid_serialization() {
+        let long_label = "a".repeat(MAX_LABEL_LENGTH + 1);
+        let long_name = vec!["a".repeat(MAX_LABEL_LENGTH); 4].join(".");
+        for name in [
+            "a..local",
+            ".local",
+            long_label.as_str(),
+            long_name.as_str(),
+        ] {
+            let mut message = Message::new_query(0);
+            message.questions.push(Question::new(name, RecordType::A));
+            assert!(
+                rc::ResultInvalidName::matches(message.serialize().unwrap_err()),
+                "{}",
+                name
+            );
+        }
+
+        let mut message = Message::new_response(0);
+        message.answers.push(Record::new(
+            "a.local",
+            1,
+            RecordData::Txt(vec!["a".repeat(0x100)]),
+        ));
+        assert!(rc::ResultInvalidMessage::matches(
+            message.serialize().unwrap_err()
+        ));
+
+        let mut message = Message::new_response(0);
+        message.answers.push(Record::new(
+            "a.local",
+            1,
+            RecordData::Other {
+                record_type: 99,
+                data: vec![0; 0x10000],
+            },
+        ));
+        assert!(rc::ResultInvalidMessage::matches(
+            message.serialize().unwrap_err()
+        ));
+    }
+}
@@ -0,0 +1,13 @@
+//! mDNS-specific result definitions
+
+use crate::rc;
+
+/// Result Submodule ID for the parent module
+pub const RESULT_SUBMODULE: u32 = 1800;
+
+result_define_subgroup!(rc::RESULT_MODULE, RESULT_SUBMODULE => {
+    InvalidMessage: 1,
+    InvalidName: 2,
+    HostNotFound: 3,
+    InvalidTimeout: 4
+});
@@ -2,6 +2,7 @@
 
 use crate::result::ResultBase;
 use crate::svc;
+use crate::time::{Duration, Instant, Timeout};
 pub mod alloc;
 
 /// Blocks thread until the memory region specified has the permission passed
@@ -10,25 +11,27 @@ pub mod alloc;
 ///
 /// * `address`: The address to query for memory permissions
 /// * `permissions`: The memory permission to wait on
+/// * `timeout`: The wait timeout (see [`Timeout`])
 ///
 /// Note that if multiple permissions are specified (e.g. `MemoryPermission::Read | MemoryPermission::Write`), the function will return if *any* specified permission is present.
 #[inline(always)]
 pub fn wait_for_permission(
     address: svc::Address,
     permission: svc::MemoryPermission,
-    timeout: Option<usize>,
+    timeout: impl Into<Timeout>,
 ) -> crate::result::Result<()> {
-    let timeout = timeout.unwrap_or(usize::MAX);
-    let mut time_taken: usize = 0;
+    let deadline = timeout.into().get_deadline();
 
     while !svc::query_memory(address)?
         .0
         .permission
         .intersects(permission)
     {
-        result_return_if!(time_taken >= timeout, svc::rc::ResultTimedOut);
-        time_taken = time_taken.saturating_add(100_000);
-        let _ = crate::thread::sleep(100_000);
+        result_return_if!(
+            deadline.is_some_and(|deadline| Instant::now() >= deadline),
+            svc::rc::ResultTimedOut
+        );
+        let _ = crate::thread::sleep(Duration::from_micros(100));
     }
 
     Ok(())
@@ -115,7 +115,8 @@ pub unsafe trait AllocatorEx: Allocator {
 
 unsafe impl AllocatorEx for Global {}
 
-#[global_allocator]
+// Tests run on the host, with its own allocator
+#[cfg_attr(not(test), global_allocator)]
 static GLOBAL_ALLOCATOR: linked_list_allocator::LockedHeap =
     linked_list_allocator::LockedHeap::empty();
 
@@ -0,0 +1,257 @@
+//! Network connection status support
+//!
+//! Sockets (see the `socket` feature) don't know whether the console is connected to a network, and connecting without one just waits until the connection times out. Checking [`status`] (or [`is_connected`]) first allows failing early instead.
+//!
+//! Connections are managed by the system, but applications may also submit a [`NetworkRequest`] to ask for one to be established (and wait for it)
+
+use crate::ipc::sf;
+use crate::result::*;
+use crate::sync::{Mutex, MutexGuard};
+use crate::time::Timeout;
+use crate::version;
+use crate::wait::RemoteEvent;
+
+use core::net::Ipv4Addr;
+
+pub use crate::service::nifm::*;
+
+pub mod rc;
+
+static G_GENERAL_SRV: Mutex<Option<GeneralService>> = Mutex::new(None);
+
+/// Initializes the nifm general service object
+///
+/// This is automatically done by the functions in this module if needed
+pub fn initialize() -> Result<()> {
+    let mut general_srv = G_GENERAL_SRV.lock();
+    if general_srv.is_none() {
+        let static_srv = crate::service::new_service_object::<StaticService>()?;
+        *general_srv = Some(
+            if version::get_version() >= version::Version::new(3, 0, 0) {
+                static_srv.create_general_service(sf::ProcessId::new())?
+            } else {
+                static_srv.create_general_service_old()?
+            },
+        );
+    }
+    Ok(())
+}
+
+/// Gets access to the global [`IGeneralServiceClient`] shared object instance, initializing it if needed
+pub fn get_general_service<'a>() -> Result<MutexGuard<'a, Option<GeneralService>>> {
+    initialize()?;
+    Ok(G_GENERAL_SRV.lock())
+}
+
+pub(crate) fn finalize() {
+    *G_GENERAL_SRV.lock() = None;
+}
+
+#[inline]
+fn with_general_service<T>(f: impl FnOnce(&mut GeneralService) -> Result<T>) -> Result<T> {
+    let mut general_srv = get_general_service()?;
+    // The object is always present after a successful initialization
+    f(general_srv.as_mut().unwrap())
+}
+
+/// Represents the network connection status of the console
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum NetworkStatus {
+    /// There is no network connection
+    Offline,
+    /// A connection is being established
+    Connecting {
+        connection_type: InternetConnectionType,
+        wifi_strength: u8,
+    },
+    /// The console is connected to a network
+    Connected {
+        connection_type: InternetConnectionType,
+        wifi_strength: u8,
+        ip_address: Ipv4Addr,
+    },
+}
+
+impl NetworkStatus {
+    /// Gets whether the console is connected to a network
+    #[inline]
+    pub const fn is_connected(&self) -> bool {
+        matches!(self, Self::Connected { .. })
+    }
+
+    /// Gets the IP address of the console, if it's connected
+    #[inline]
+    pub const fn get_ip_address(&self) -> Option<Ipv4Addr> {
+        match self {
+            Self::Connected { ip_address, .. } => Some(*ip_address),
+            _ => None,
+        }
+    }
+}
+
+/// Gets the current network connection status
+///
+/// This never blocks waiting for a connection, thus it's suitable for checking whether network operations make sense at all
+pub fn status() -> Result<NetworkStatus> {
+    with_general_service(|general_srv| {
+        // The service fails the commands below when there is no connection
+        if version::get_version() < version::Version::new(3, 0, 0) {
+            // The connection status command doesn't exist here, so having an IP address is the best indicator
+            return Ok(match general_srv.get_current_ip_address() {
+                Ok(ip_address) => NetworkStatus::Connected {
+                    connection_type: InternetConnectionType::WiFi,
+                    wifi_strength: 0,
+                    ip_address: ip_address.into(),
+                },
+                Err(_) => NetworkStatus::Offline,
+            });
+        }
+
+        let conn_status = match general_srv.get_internet_connection_status() {
+            Ok(conn_status) => conn_status,
+            Err(_) => return Ok(NetworkStatus::Offline),
+        };
+        if conn_status.state != InternetConnectionState::Connected {
+            return Ok(NetworkStatus::Connecting {
+                connection_type: conn_status.connection_type,
+                wifi_strength: conn_status.wifi_strength,
+            });
+        }
+
+        Ok(match general_srv.get_current_ip_address() {
+            Ok(ip_address) => NetworkStatus::Connected {
+                connection_type: conn_status.connection_type,
+                wifi_strength: conn_status.wifi_strength,
+                ip_address: ip_address.into(),
+            },
+            // The connection may be dropped between both commands
+            Err(_) => NetworkStatus::Offline,
+        })
+    })
+}
+
+/// Gets whether the console is currently connected to a network
+#[inline]
+pub fn is_connected() -> Result<bool> {
+    Ok(status()?.is_connected())
+}
+
+/// Gets the current IP address of the console, failing with [`rc::ResultNotConnected`] if there is no connection
+pub fn get_current_ip_address() -> Result<Ipv4Addr> {
+    with_general_service(|general_srv| {
+        general_srv
+            .get_current_ip_address()
+            .map(Ipv4Addr::from)
+            .map_err(|_| rc::ResultNotConnected::make())
+    })
+}
+
+/// Gets the current IP address, subnet mask, gateway and DNS server configuration, failing with [`rc::ResultNotConnected`] if there is no connection
+pub fn get_current_ip_config_info() -> Result<IpConfigInfo> {
+    with_general_service(|general_srv| {
+        general_srv
+            .get_current_ip_config_info()
+            .map_err(|_| rc::ResultNotConnected::make())
+    })
+}
+
+/// Represents a network connection request
+///
+/// The connection is kept available (as far as the system is concerned) while the request is accepted and not dropped
+pub struct NetworkRequest {
+    request: Request,
+    state_event: RemoteEvent,
+    _other_event: RemoteEvent,
+}
+
+impl NetworkRequest {
+    /// Creates a new (not yet submitted) [`NetworkRequest`]
+    ///
+    /// # Arguments
+    ///
+    /// * `preset`: The requirement preset, [`RequirementPreset::InternetGeneric`] being the usual one for applications
+    pub fn new(preset: RequirementPreset) -> Result<Self> {
+        let mut request = with_general_service(|general_srv| general_srv.create_request(preset))?;
+        let (state_event_handle, other_event_handle) =
+            request.get_system_event_readable_handles()?;
+        Ok(Self {
+            request,
+            state_event: RemoteEvent::new(state_event_handle.handle),
+            _other_event: RemoteEvent::new(other_event_handle.handle),
+        })
+    }
+
+    /// Gets the underlying [`IRequestClient`] object, in order to set additional request options before submitting it
+    #[inline]
+    pub fn get_request(&mut self) -> &mut Request {
+        &mut self.request
+    }
+
+    /// Gets the current state of the request
+    #[inline]
+    pub fn get_state(&self) -> Result<RequestState> {
+        self.request.get_request_state()
+    }
+
+    /// Submits the request, which is then processed asynchronously (see [`NetworkRequest::wait`])
+    #[inline]
+    pub fn submit(&mut self) -> Result<()> {
+        self.request.submit()
+    }
+
+    /// Cancels the request
+    #[inline]
+    pub fn cancel(&mut self) -> Result<()> {
+        self.request.cancel()
+    }
+
+    /// Waits until the request is no longer being processed, returning its final state
+    ///
+    /// Fails with [`ResultTimedOut`][`crate::svc::rc::ResultTimedOut`] if the request is still being processed when the timeout expires
+    ///
+    /// # Arguments
+    ///
+    /// * `timeout`: The wait timeout (see [`Timeout`])
+    pub fn wait(&self, timeout: impl Into<Timeout>) -> Result<RequestState> {
+        let deadline = timeout.into().get_deadline();
+        loop {
+            let state = self.get_state()?;
+            if state != RequestState::OnHold {
+                return Ok(state);
+            }
+            self.state_event.wait(deadline)?;
+        }
+    }
+
+    /// Checks that the request was accepted, failing with the reason why it wasn't otherwise
+    pub fn get_result(&self) -> Result<()> {
+        if self.get_state()? == RequestState::Accepted {
+            return Ok(());
+        }
+        // This fails with the actual reason (no configured networks, user cancellation, etc.) if there is one
+        self.request.get_result()?;
+        rc::ResultRequestNotAccepted::make_err()
+    }
+}
+
+impl Drop for NetworkRequest {
+    /// Destroys the [`NetworkRequest`], cancelling it
+    fn drop(&mut self) {
+        let _ = self.request.cancel();
+    }
+}
+
+/// Submits a [`NetworkRequest`] for internet access and waits for it to be processed, returning the accepted request
+///
+/// The connection might be lost once the returned request is dropped
+///
+/// # Arguments
+///
+/// * `timeout`: The wait timeout (see [`Timeout`])
+pub fn request_connection(timeout: impl Into<Timeout>) -> Result<NetworkRequest> {
+    let mut request = NetworkRequest::new(RequirementPreset::InternetGeneric)?;
+    request.submit()?;
+    request.wait(timeout)?;
+    request.get_result()?;
+    Ok(request)
+}
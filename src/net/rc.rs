@@ -0,0 +1,11 @@
+//! Network-specific result definitions
+
+use crate::rc;
+
+/// Result Submodule ID for the parent module
+pub const RESULT_SUBMODULE: u32 = 1600;
+
+result_define_subgroup!(rc::RESULT_MODULE, RESULT_SUBMODULE => {
+    NotConnected: 1,
+    RequestNotAccepted: 2
+});
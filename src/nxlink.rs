@@ -0,0 +1,449 @@
+//! nxlink stdio redirection and NRO upload server support
+//!
+//! nxlink is the host tool (part of devkitPro's `switch-tools`) which uploads NROs to a console running a netloader server (like the one in hbmenu), which then launches them with the host address appended to their arguments (see [`get_host_address`])
+//!
+//! When launched with `-s`, the host also waits for the NRO to connect back and prints everything it sends, which is what [`initialize`] sets up:
+//!
+//! ```no_run
+//! use nx::nxlink;
+//! use core::fmt::Write;
+//!
+//! nxlink::initialize()?;
+//! writeln!(nxlink::Stdio, "Hello from the console!").unwrap();
+//! ```
+//!
+//! Logs can also be sent there with [`NxlinkLogger`][`crate::diag::log::nxlink::NxlinkLogger`], and a [`Server`] implements the netloader side of the protocol (receiving NROs into a filesystem)
+
+use crate::fs::{self, DirectoryEntryType, FileAccessor, FileAttribute, FileOpenOption};
+use crate::hbl;
+use crate::result::*;
+use crate::socket::PollFlags;
+use crate::socket::net::traits::{Pollable, SocketCommon};
+use crate::socket::net::{TcpListener, TcpStream, UdpSocket};
+use crate::sync::Mutex;
+use crate::time::Timeout;
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::net::{Ipv4Addr, SocketAddr};
+use core::time::Duration;
+use miniz_oxide::inflate::stream::{InflateState, inflate};
+use miniz_oxide::{DataFormat, MZError, MZFlush, MZStatus};
+
+pub mod rc;
+
+/// The port netloader servers listen on, both for discovery datagrams and uploads
+pub const NXLINK_SERVER_PORT: u16 = 28280;
+
+/// The port the host listens on, both for discovery replies and stdio connections
+pub const NXLINK_CLIENT_PORT: u16 = 28771;
+
+/// The suffix of the argument containing the host address, which is preceded by the address as 8 hex digits
+const HOST_ARG_SUFFIX: &str = "_NXLINK_";
+
+const DISCOVERY_REQUEST: &[u8] = b"nxboot";
+
+const DISCOVERY_REPLY: &[u8] = b"bootnx";
+
+/// The maximum size of the compressed chunks sent by the host
+const CHUNK_SIZE: usize = 0x4000;
+
+const MAX_NAME_LENGTH: usize = 0x300;
+
+const MAX_ARGS_LENGTH: usize = 0x800;
+
+/// The time to wait for the host during an upload before giving up
+const TRANSFER_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Parses the host address from an nxlink argument (like `0101a8c0_NXLINK_` for `192.168.1.1`)
+///
+/// # Arguments
+///
+/// * `arg`: The argument to parse
+pub fn parse_host_arg(arg: &str) -> Option<Ipv4Addr> {
+    let address = arg.strip_suffix(HOST_ARG_SUFFIX)?;
+    if address.len() != 8 {
+        return None;
+    }
+
+    // The address is the (network byte order) `in_addr` value, printed as a little-endian integer
+    let value = u32::from_str_radix(address, 16).ok()?;
+    Some(Ipv4Addr::from(value.to_le_bytes()))
+}
+
+/// Makes the nxlink argument containing the given host address (see [`parse_host_arg`])
+///
+/// # Arguments
+///
+/// * `address`: The host address
+pub fn make_host_arg(address: Ipv4Addr) -> String {
+    format!(
+        "{:08x}{}",
+        u32::from_le_bytes(address.octets()),
+        HOST_ARG_SUFFIX
+    )
+}
+
+/// Gets the address of the host which launched the current NRO, if it was launched through nxlink
+///
+/// The host address is the last argument (see [`hbl::get_args`]), thus this is only useful if the current code is running through HBL
+pub fn get_host_address() -> Option<Ipv4Addr> {
+    hbl::get_args().last().and_then(parse_host_arg)
+}
+
+/// Gets an iterator over the launch arguments (see [`hbl::get_args`]), skipping the nxlink host argument
+pub fn get_args() -> impl Iterator<Item = &'static str> {
+    hbl::get_args().filter(|arg| parse_host_arg(arg).is_none())
+}
+
+/// Connects to the stdio server of the host which launched the current NRO (see [`get_host_address`])
+///
+/// Note that the host only accepts a single connection, which is usually managed by [`initialize`]
+pub fn connect() -> Result<TcpStream> {
+    let host_address = get_host_address().ok_or(rc::ResultHostNotFound::make())?;
+    TcpStream::connect((host_address, NXLINK_CLIENT_PORT))
+}
+
+static G_STDIO_STREAM: Mutex<Option<TcpStream>> = Mutex::new(None);
+
+fn send_all(stream: &TcpStream, mut data: &[u8]) -> Result<()> {
+    while !data.is_empty() {
+        let sent_size = stream.send(data)? as usize;
+        data = &data[sent_size.min(data.len())..];
+    }
+    Ok(())
+}
+
+/// Connects to the host (see [`connect`]) for [`write`] to send data to it
+///
+/// This does nothing if the connection is already initialized
+pub fn initialize() -> Result<()> {
+    let mut stream = G_STDIO_STREAM.lock();
+    if stream.is_none() {
+        *stream = Some(connect()?);
+    }
+    Ok(())
+}
+
+/// Gets whether the host connection is initialized (see [`initialize`])
+pub fn is_initialized() -> bool {
+    G_STDIO_STREAM.lock().is_some()
+}
+
+/// Closes the host connection
+///
+/// This is automatically called on exit, before finalizing socket support
+pub fn finalize() {
+    *G_STDIO_STREAM.lock() = None;
+}
+
+/// Sends data to the host, which prints it
+///
+/// # Arguments
+///
+/// * `data`: The data to send
+pub fn write(data: &[u8]) -> Result<()> {
+    match G_STDIO_STREAM.lock().as_ref() {
+        Some(stream) => send_all(stream, data),
+        None => rc::ResultNotInitialized::make_err(),
+    }
+}
+
+/// Represents the host's output, as a [`Write`][`core::fmt::Write`] sink
+///
+/// Writes fail if the connection isn't initialized (see [`initialize`])
+#[derive(Copy, Clone, Debug, Default)]
+pub struct Stdio;
+
+impl core::fmt::Write for Stdio {
+    fn write_str(&mut self, s: &str) -> core::fmt::Result {
+        write(s.as_bytes()).map_err(|_| core::fmt::Error)
+    }
+}
+
+/// Represents an NRO received by a [`Server`]
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct ReceivedNro {
+    /// The path the NRO was saved to
+    pub path: String,
+    /// The arguments sent by the host, the first one being (usually) the NRO name
+    pub args: Vec<String>,
+    /// The address of the host which sent the NRO
+    pub host_address: Ipv4Addr,
+}
+
+impl ReceivedNro {
+    /// Gets the argv string to launch the NRO with, like hbmenu does
+    ///
+    /// The first argument is replaced by the saved path, and the host address argument is appended (see [`make_host_arg`])
+    pub fn get_argv(&self) -> String {
+        let host_arg = make_host_arg(self.host_address);
+        let args = core::iter::once(self.path.as_str())
+            .chain(self.args.iter().skip(1).map(String::as_str))
+            .chain(core::iter::once(host_arg.as_str()));
+
+        let mut argv = String::new();
+        for arg in args {
+            if !argv.is_empty() {
+                argv.push(' ');
+            }
+            match arg.contains(' ') {
+                true => argv.push_str(&format!("\"{}\"", arg)),
+                false => argv.push_str(arg),
+            }
+        }
+        argv
+    }
+
+    /// Sets the NRO as the next one HBL executes, once the current one exits (see [`hbl::set_next_load_entry`])
+    ///
+    /// Returns whether both the path and argv were set
+    pub fn set_next_load(&self) -> Result<bool> {
+        let (path_set, argv_set) = hbl::set_next_load_entry(&self.path, &self.get_argv())?;
+        Ok(path_set && argv_set)
+    }
+}
+
+struct PollTarget(i32);
+
+impl Pollable for PollTarget {
+    fn get_poll_fd(&self) -> i32 {
+        self.0
+    }
+}
+
+fn recv_exact(stream: &TcpStream, mut data: &mut [u8]) -> Result<()> {
+    while !data.is_empty() {
+        let read_size = stream.recv(data)?;
+        result_return_if!(read_size == 0, rc::ResultConnectionClosed);
+        data = &mut data[read_size..];
+    }
+    Ok(())
+}
+
+fn recv_i32(stream: &TcpStream) -> Result<i32> {
+    let mut value = [0u8; 4];
+    recv_exact(stream, &mut value)?;
+    Ok(i32::from_le_bytes(value))
+}
+
+fn recv_length(stream: &TcpStream, max_length: usize) -> Result<usize> {
+    let length = recv_i32(stream)?;
+    match usize::try_from(length) {
+        Ok(length) if length <= max_length => Ok(length),
+        _ => rc::ResultInvalidTransfer::make_err(),
+    }
+}
+
+fn send_i32(stream: &TcpStream, value: i32) -> Result<()> {
+    send_all(stream, &value.to_le_bytes())
+}
+
+/// Represents a netloader server, which receives NROs from nxlink hosts (like hbmenu does)
+///
+/// It also answers the discovery datagrams hosts broadcast when they're not given the console's address
+///
+/// ```no_run
+/// use nx::nxlink::Server;
+///
+/// let mut server = Server::new("sdmc:/switch")?;
+/// let nro = server.run()?;
+/// // Launch it once we exit
+/// nro.set_next_load()?;
+/// ```
+pub struct Server {
+    listener: TcpListener,
+    discovery_socket: UdpSocket,
+    directory: String,
+}
+
+impl Server {
+    /// Creates a new [`Server`], listening on [`NXLINK_SERVER_PORT`]
+    ///
+    /// # Arguments
+    ///
+    /// * `directory`: The directory to save the received NROs to (see [`fs::format_path`]), which the names sent by hosts are relative to
+    pub fn new(directory: &str) -> Result<Self> {
+        Ok(Self {
+            listener: TcpListener::bind((Ipv4Addr::UNSPECIFIED, NXLINK_SERVER_PORT))?,
+            discovery_socket: UdpSocket::bind((Ipv4Addr::UNSPECIFIED, NXLINK_SERVER_PORT))?,
+            directory: String::from(directory.trim_end_matches('/')),
+        })
+    }
+
+    /// Waits for discovery datagrams and uploads, answering the former and receiving the latter
+    ///
+    /// Returns the received NRO, if any upload was completed. Failed uploads return their error, but the server can still be used afterwards
+    ///
+    /// # Arguments
+    ///
+    /// * `timeout`: The wait timeout
+    pub fn poll(&mut self, timeout: impl Into<Timeout>) -> Result<Option<ReceivedNro>> {
+        let pollers = [
+            (
+                PollTarget(self.discovery_socket.as_raw_fd()),
+                PollFlags::PollIn(),
+            ),
+            (PollTarget(self.listener.get_poll_fd()), PollFlags::PollIn()),
+        ];
+        let mut discovery_ready = false;
+        let mut upload_ready = false;
+        for (index, _) in crate::socket::net::poll(&pollers, timeout)? {
+            match index {
+                0 => discovery_ready = true,
+                _ => upload_ready = true,
+            }
+        }
+
+        // Each source is handled regardless of the other one failing, otherwise a failed discovery answer would leave a pending upload waiting
+        let discovery_result = match discovery_ready {
+            true => self.answer_discovery(),
+            false => Ok(()),
+        };
+        if upload_ready {
+            let (stream, origin) = self.listener.accept()?;
+            let SocketAddr::V4(origin) = origin else {
+                return discovery_result.map(|_| None);
+            };
+            return self.receive(&stream, *origin.ip()).map(Some);
+        }
+        discovery_result.map(|_| None)
+    }
+
+    /// Waits until an NRO is received (see [`Server::poll`]), ignoring failed uploads
+    pub fn run(&mut self) -> Result<ReceivedNro> {
+        loop {
+            if let Ok(Some(nro)) = self.poll(Timeout::Infinite) {
+                return Ok(nro);
+            }
+        }
+    }
+
+    fn answer_discovery(&self) -> Result<()> {
+        let mut buffer = [0u8; 0x40];
+        while let Some((size, origin)) =
+            self.discovery_socket.recv_from_non_blocking(&mut buffer)?
+        {
+            if buffer[..size].starts_with(DISCOVERY_REQUEST)
+                && let SocketAddr::V4(origin) = origin
+            {
+                self.discovery_socket
+                    .send_to(DISCOVERY_REPLY, (*origin.ip(), NXLINK_CLIENT_PORT))?;
+            }
+        }
+        Ok(())
+    }
+
+    fn make_path(&self, name: &str) -> Result<String> {
+        let name = name.trim_start_matches('/');
+        let is_valid = !name.is_empty()
+            && name.split('/').all(|component| {
+                !component.is_empty() && (component != ".") && (component != "..")
+            });
+        result_return_unless!(is_valid, rc::ResultInvalidPath);
+
+        Ok(format!("{}/{}", self.directory, name))
+    }
+
+    fn create_file(path: &str) -> Result<FileAccessor> {
+        // Create the missing parent directories
+        for (index, _) in path.match_indices('/').skip(1) {
+            let parent = &path[..index];
+            if fs::get_entry_type(parent).is_err() {
+                fs::create_directory(parent)?;
+            }
+        }
+
+        match fs::get_entry_type(path) {
+            Ok(DirectoryEntryType::File) => fs::remove_file(path)?,
+            Ok(DirectoryEntryType::Directory) => return rc::ResultInvalidPath::make_err(),
+            Err(_) => {}
+        }
+        fs::create_file(path, 0, FileAttribute::None())?;
+        fs::open_file(path, FileOpenOption::Write() | FileOpenOption::Append())
+    }
+
+    fn receive(&self, stream: &TcpStream, host_address: Ipv4Addr) -> Result<ReceivedNro> {
+        stream.set_read_timeout(Some(TRANSFER_TIMEOUT))?;
+
+        let name_length = recv_length(stream, MAX_NAME_LENGTH)?;
+        let mut name = vec![0u8; name_length];
+        recv_exact(stream, &mut name)?;
+        let file_size = recv_length(stream, i32::MAX as usize)?;
+
+        let file = core::str::from_utf8(&name)
+            .map_err(|_| rc::ResultInvalidPath::make())
+            .and_then(|name| self.make_path(name))
+            .and_then(|path| Ok((Self::create_file(&path)?, path)));
+        // The host waits for this response before sending the file
+        let (mut file, path) = match file {
+            Ok(file) => {
+                send_i32(stream, 0)?;
+                file
+            }
+            Err(rc) => {
+                let _ = send_i32(stream, -1);
+                return Err(rc);
+            }
+        };
+
+        let received_size = Self::receive_file_data(stream, &mut file, file_size)?;
+        result_return_unless!(received_size == file_size, rc::ResultInvalidTransfer);
+        send_i32(stream, 0)?;
+
+        let args_length = recv_length(stream, MAX_ARGS_LENGTH)?;
+        let mut args = vec![0u8; args_length];
+        recv_exact(stream, &mut args)?;
+        let args = args
+            .split(|&byte| byte == 0)
+            .filter(|arg| !arg.is_empty())
+            .map(|arg| String::from_utf8_lossy(arg).into_owned())
+            .collect();
+
+        Ok(ReceivedNro {
+            path,
+            args,
+            host_address,
+        })
+    }
+
+    /// Receives the (zlib-compressed) file data in chunks, returning the decompressed size (failing as soon as it exceeds the announced file size)
+    fn receive_file_data(
+        stream: &TcpStream,
+        file: &mut FileAccessor,
+        file_size: usize,
+    ) -> Result<usize> {
+        let mut inflater = InflateState::new_boxed(DataFormat::Zlib);
+        let mut chunk = vec![0u8; CHUNK_SIZE];
+        let mut out_data = vec![0u8; CHUNK_SIZE];
+        let mut total_size = 0;
+        loop {
+            let chunk_size = recv_length(stream, CHUNK_SIZE)?;
+            recv_exact(stream, &mut chunk[..chunk_size])?;
+
+            let mut in_data = &chunk[..chunk_size];
+            loop {
+                let result = inflate(&mut inflater, in_data, &mut out_data, MZFlush::None);
+                in_data = &in_data[result.bytes_consumed..];
+                if result.bytes_written > 0 {
+                    total_size += result.bytes_written;
+                    // Don't keep writing data which decompresses beyond the announced size
+                    result_return_if!(total_size > file_size, rc::ResultInvalidTransfer);
+                    file.write_array(&out_data[..result.bytes_written])?;
+                }
+
+                match result.status {
+                    Ok(MZStatus::StreamEnd) => return Ok(total_size),
+                    Ok(_) => {}
+                    // No progress can be made until more data is received
+                    Err(MZError::Buf) => break,
+                    Err(_) => return rc::ResultInvalidTransfer::make_err(),
+                }
+                // The output buffer being filled means there might be more pending output
+                if in_data.is_empty() && (result.bytes_written < out_data.len()) {
+                    break;
+                }
+            }
+        }
+    }
+}
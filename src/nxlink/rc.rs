@@ -0,0 +1,14 @@
+//! nxlink-specific result definitions
+
+use crate::rc;
+
+/// Result Submodule ID for the parent module
+pub const RESULT_SUBMODULE: u32 = 1900;
+
+result_define_subgroup!(rc::RESULT_MODULE, RESULT_SUBMODULE => {
+    HostNotFound: 1,
+    NotInitialized: 2,
+    ConnectionClosed: 3,
+    InvalidPath: 4,
+    InvalidTransfer: 5
+});
@@ -22,6 +22,12 @@
 //! * `1100`: gpu/binder
 //! * `1200`: gpu/parcel
 //! * `1300`: ipc/server
+//! * `1400`: time
+//! * `1500`: audio
+//! * `1600`: net
+//! * `1700`: http
+//! * `1800`: mdns
+//! * `1900`: nxlink
 
 pub const RESULT_MODULE: u32 = 430;
 /// Result submodule for the base `rc` module.
@@ -52,5 +58,7 @@ result_define_subgroup!(RESULT_MODULE, RESULT_SUBMODULE => {
 1100: gpu/binder
 1200: gpu/parcel
 1300: ipc/server
+1400: time
+1500: audio
 
 */
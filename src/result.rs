@@ -5,6 +5,10 @@ use core::result;
 
 use nx_derive::{Request, Response};
 
+mod names;
+
+pub mod context;
+
 const MODULE_BITS: u32 = 9;
 const DESCRIPTION_BITS: u32 = 13;
 const DEFAULT_VALUE: u32 = 0;
@@ -31,7 +35,7 @@ pub(crate) const fn unpack_description(value: u32) -> u32 {
 ///
 /// Results are often displayed/shown, for example, like `2168-0002`, which corresponds to `<2000 + module>-<description>`
 ///
-/// [`Debug`][`fmt::Debug`] formatting formats the results as a hex-value (`0x4A8`), while [`Display`][`fmt::Display`] formatting formats the result in the format described above (`2168-0002`)
+/// [`Debug`][`fmt::Debug`] formatting formats the results as a hex-value (`0x4A8`), while [`Display`][`fmt::Display`] formatting formats the result in the format described above, followed by its name if it's known (`2002-0001 (fs::ResultPathNotFound)`, see [`get_name`][`ResultCode::get_name`])
 #[derive(Request, Response, Copy, Clone, PartialEq, Eq, Default)]
 #[repr(C)]
 pub struct ResultCode {
@@ -92,6 +96,34 @@ impl ResultCode {
     pub const fn get_description(&self) -> u32 {
         unpack_description(self.value)
     }
+
+    /// Gets the name of the [`ResultCode`], if it's known
+    ///
+    /// Results defined with [`result_define!`][`crate::result_define`] and the like (in this or any other crate) are known by their definition name, and well-known system results are known too
+    ///
+    /// System results are named after their system module (like `fs::ResultPathNotFound`), while any other result is named after the module path of its definition, without the trailing `rc` module (like `nx::fs::ResultDeviceNotFound`)
+    pub fn get_name(&self) -> Option<ResultCodeName> {
+        let module_name = names::find_module_name(self.get_module());
+        let registered_name = get_registered_names()
+            .iter()
+            .find(|name| name.value == self.value);
+
+        match (registered_name, module_name) {
+            (Some(registered_name), Some(module_name)) => Some(ResultCodeName {
+                module_name,
+                name: registered_name.name,
+            }),
+            (Some(registered_name), None) => Some(ResultCodeName {
+                module_name: registered_name.get_module_name(),
+                name: registered_name.name,
+            }),
+            (None, Some(module_name)) => {
+                names::find_result_name(self.get_module(), self.get_description())
+                    .map(|name| ResultCodeName { module_name, name })
+            }
+            (None, None) => None,
+        }
+    }
 }
 
 impl fmt::Debug for ResultCode {
@@ -107,10 +139,82 @@ impl fmt::Display for ResultCode {
             "{:0>4}-{:0>4}",
             2000 + self.get_module(),
             self.get_description()
-        )
+        )?;
+        match self.get_name() {
+            Some(name) => write!(fmt, " ({})", name),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Represents the name of a [`ResultCode`] (see [`get_name`][`ResultCode::get_name`])
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct ResultCodeName {
+    /// The module name (like `fs`)
+    pub module_name: &'static str,
+    /// The result name (like `ResultPathNotFound`)
+    pub name: &'static str,
+}
+
+impl fmt::Display for ResultCodeName {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> result::Result<(), fmt::Error> {
+        write!(fmt, "{}::{}", self.module_name, self.name)
     }
 }
 
+/// Represents the name of a result definition, which [`result_define!`][`crate::result_define`] registers in a static table (the `nx_result_names` link section)
+///
+/// This is only meant to be used by the result definition macros
+#[doc(hidden)]
+#[repr(C)]
+pub struct ResultName {
+    value: u32,
+    module_path: &'static str,
+    name: &'static str,
+}
+
+impl ResultName {
+    /// Creates a new [`ResultName`]
+    ///
+    /// # Arguments
+    ///
+    /// * `module`: The result module
+    /// * `description`: The result description
+    /// * `module_path`: The module path of the definition
+    /// * `name`: The definition name
+    #[inline]
+    pub const fn new(
+        module: u32,
+        description: u32,
+        module_path: &'static str,
+        name: &'static str,
+    ) -> Self {
+        Self {
+            value: pack_value(module, description),
+            module_path,
+            name,
+        }
+    }
+
+    fn get_module_name(&self) -> &'static str {
+        self.module_path
+            .strip_suffix("::rc")
+            .unwrap_or(self.module_path)
+    }
+}
+
+unsafe extern "C" {
+    // Both are provided by the linker for the `nx_result_names` link section
+    static __start_nx_result_names: u8;
+    static __stop_nx_result_names: u8;
+}
+
+fn get_registered_names() -> &'static [ResultName] {
+    let start = &raw const __start_nx_result_names as *const ResultName;
+    let end = &raw const __stop_nx_result_names as *const ResultName;
+    unsafe { core::slice::from_raw_parts(start, end.offset_from(start) as usize) }
+}
+
 /// Represents a result holding a certain value or a  [`ResultCode`] as an indication of failure
 pub type Result<T> = result::Result<T, ResultCode>;
 
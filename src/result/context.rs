@@ -0,0 +1,189 @@
+//! Error context support
+//!
+//! A [`ContextError`] wraps a [`ResultCode`] along with a chain of context messages (and the source locations they were added at), describing what was being done when the error happened:
+//!
+//! ```no_run
+//! use nx::fs;
+//! use nx::result::context::{ContextResult, ResultContext};
+//!
+//! fn load_save() -> ContextResult<usize> {
+//!     let mut file = fs::open_file("save:/data.bin", fs::FileOpenOption::Read()).context("opening save")?;
+//!     let size = file.get_size().context("getting save size")?;
+//!     Ok(size)
+//! }
+//!
+//! fn load() -> ContextResult<()> {
+//!     let _size = load_save().context("loading game")?;
+//!     Ok(())
+//! }
+//! ```
+//!
+//! Formatting a [`ContextError`] shows the result followed by the context chain, from the innermost context to the outermost one
+//!
+//! Converting it back to a bare [`ResultCode`] (for instance, for IPC replies) just discards the context
+
+use super::*;
+use crate::diag::abort::{self, AbortLevel};
+use alloc::borrow::Cow;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::panic::Location;
+
+/// Represents a context message of a [`ContextError`]
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct ErrorContext {
+    /// The message
+    pub message: Cow<'static, str>,
+    /// The source location where the context was added
+    pub location: &'static Location<'static>,
+}
+
+impl fmt::Display for ErrorContext {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> result::Result<(), fmt::Error> {
+        write!(
+            fmt,
+            "{} (at {}:{})",
+            self.message,
+            self.location.file(),
+            self.location.line()
+        )
+    }
+}
+
+/// Represents a [`ResultCode`] along with a chain of context messages
+#[derive(Clone, PartialEq, Eq)]
+pub struct ContextError {
+    rc: ResultCode,
+    contexts: Vec<ErrorContext>,
+}
+
+impl ContextError {
+    /// Creates a new [`ContextError`] without any context
+    ///
+    /// # Arguments
+    ///
+    /// * `rc`: The [`ResultCode`]
+    #[inline]
+    pub const fn new(rc: ResultCode) -> Self {
+        Self {
+            rc,
+            contexts: Vec::new(),
+        }
+    }
+
+    /// Gets the [`ResultCode`]
+    #[inline]
+    pub const fn get_code(&self) -> ResultCode {
+        self.rc
+    }
+
+    /// Gets the context chain, from the innermost context to the outermost one
+    #[inline]
+    pub fn get_contexts(&self) -> &[ErrorContext] {
+        &self.contexts
+    }
+
+    /// Adds a context message, recording the caller's location
+    ///
+    /// # Arguments
+    ///
+    /// * `message`: The context message
+    #[track_caller]
+    pub fn push_context(&mut self, message: impl Into<Cow<'static, str>>) {
+        self.push_context_at(message.into(), Location::caller());
+    }
+
+    fn push_context_at(
+        &mut self,
+        message: Cow<'static, str>,
+        location: &'static Location<'static>,
+    ) {
+        self.contexts.push(ErrorContext { message, location });
+    }
+
+    /// Aborts with this error (see [`abort::abort_with_message`]), thus the context chain is shown when aborting via panic
+    ///
+    /// # Arguments
+    ///
+    /// * `desired_level`: Desired [`AbortLevel`]
+    pub fn abort(&self, desired_level: AbortLevel) -> ! {
+        abort::abort_with_message(desired_level, self.rc, self)
+    }
+}
+
+impl From<ResultCode> for ContextError {
+    #[inline]
+    fn from(rc: ResultCode) -> Self {
+        Self::new(rc)
+    }
+}
+
+impl From<ContextError> for ResultCode {
+    #[inline]
+    fn from(error: ContextError) -> Self {
+        error.rc
+    }
+}
+
+impl fmt::Display for ContextError {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> result::Result<(), fmt::Error> {
+        write!(fmt, "{}", self.rc)?;
+        for context in self.contexts.iter() {
+            write!(fmt, "\n  while {}", context)?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Debug for ContextError {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> result::Result<(), fmt::Error> {
+        write!(fmt, "{:?}", self.rc)?;
+        for context in self.contexts.iter() {
+            write!(fmt, "\n  while {}", context)?;
+        }
+        Ok(())
+    }
+}
+
+/// Represents a result holding a certain value or a [`ContextError`] as an indication of failure
+pub type ContextResult<T> = result::Result<T, ContextError>;
+
+/// Extension trait to add context to [`Result`]s and [`ContextResult`]s
+pub trait ResultContext<T> {
+    /// Adds a context message to the error (if any), recording the caller's location
+    ///
+    /// # Arguments
+    ///
+    /// * `message`: The context message
+    fn context(self, message: impl Into<Cow<'static, str>>) -> ContextResult<T>;
+
+    /// Same as [`context`][`ResultContext::context`] but only creating the message in case of error, for messages which are expensive to create (like formatted ones)
+    ///
+    /// # Arguments
+    ///
+    /// * `message_fn`: The function creating the context message
+    fn with_context<F: FnOnce() -> String>(self, message_fn: F) -> ContextResult<T>;
+}
+
+impl<T, E: Into<ContextError>> ResultContext<T> for result::Result<T, E> {
+    #[track_caller]
+    fn context(self, message: impl Into<Cow<'static, str>>) -> ContextResult<T> {
+        // The location must be got here, closures don't track the caller
+        let location = Location::caller();
+        self.map_err(|error| {
+            let mut error: ContextError = error.into();
+            error.push_context_at(message.into(), location);
+            error
+        })
+    }
+
+    #[track_caller]
+    fn with_context<F: FnOnce() -> String>(self, message_fn: F) -> ContextResult<T> {
+        let location = Location::caller();
+        self.map_err(|error| {
+            let mut error: ContextError = error.into();
+            error.push_context_at(Cow::Owned(message_fn()), location);
+            error
+        })
+    }
+}
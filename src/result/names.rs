@@ -0,0 +1,189 @@
+//! Built-in names of system modules and their well-known results
+//!
+//! These cover results which aren't defined (thus registered) by this library, and give the system modules' results their usual module names (like `fs` instead of the module path of the library's definitions)
+
+/// Known system module names
+const MODULE_NAMES: &[(u32, &str)] = &[
+    (1, "kernel"),
+    (2, "fs"),
+    (3, "os"),
+    (5, "ncm"),
+    (8, "lr"),
+    (9, "ldr"),
+    (10, "sf"),
+    (11, "hipc"),
+    (15, "pm"),
+    (16, "ns"),
+    (17, "socket"),
+    (21, "sm"),
+    (22, "ro"),
+    (26, "spl"),
+    (105, "settings"),
+    (110, "nifm"),
+    (114, "vi"),
+    (115, "nfp"),
+    (116, "time"),
+    (123, "ssl"),
+    (124, "account"),
+    (128, "am"),
+    (140, "usb"),
+    (153, "audio"),
+    (161, "nfc"),
+    (162, "userland_assert"),
+    (163, "fatal"),
+    (168, "userland_crash"),
+    (202, "hid"),
+    (345, "libnx"),
+    (346, "homebrew_abi"),
+    (347, "homebrew_loader"),
+    (348, "libnx_nvidia"),
+    (349, "libnx_binder"),
+];
+
+/// Well-known system results, as `(module, description, name)`
+const RESULT_NAMES: &[(u32, u32, &str)] = &[
+    // kernel
+    (1, 7, "ResultOutOfSessions"),
+    (1, 14, "ResultInvalidArgument"),
+    (1, 33, "ResultNotImplemented"),
+    (1, 54, "ResultStopProcessingException"),
+    (1, 57, "ResultNoSynchronizationObject"),
+    (1, 59, "ResultTerminationRequested"),
+    (1, 70, "ResultNoEvent"),
+    (1, 101, "ResultInvalidSize"),
+    (1, 102, "ResultInvalidAddress"),
+    (1, 103, "ResultOutOfResource"),
+    (1, 104, "ResultOutOfMemory"),
+    (1, 105, "ResultOutOfHandles"),
+    (1, 106, "ResultInvalidCurrentMemory"),
+    (1, 108, "ResultInvalidNewMemoryPermission"),
+    (1, 110, "ResultInvalidMemoryRegion"),
+    (1, 112, "ResultInvalidPriority"),
+    (1, 113, "ResultInvalidCoreId"),
+    (1, 114, "ResultInvalidHandle"),
+    (1, 115, "ResultInvalidPointer"),
+    (1, 116, "ResultInvalidCombination"),
+    (1, 117, "ResultTimedOut"),
+    (1, 118, "ResultCancelled"),
+    (1, 119, "ResultOutOfRange"),
+    (1, 120, "ResultInvalidEnumValue"),
+    (1, 121, "ResultNotFound"),
+    (1, 122, "ResultBusy"),
+    (1, 123, "ResultSessionClosed"),
+    (1, 124, "ResultNotHandled"),
+    (1, 125, "ResultInvalidState"),
+    (1, 126, "ResultReservedUsed"),
+    (1, 127, "ResultNotSupported"),
+    (1, 128, "ResultDebug"),
+    (1, 129, "ResultNoThread"),
+    (1, 130, "ResultUnknownThread"),
+    (1, 131, "ResultPortClosed"),
+    (1, 132, "ResultLimitReached"),
+    (1, 133, "ResultInvalidMemoryPool"),
+    (1, 258, "ResultReceiveListBroken"),
+    (1, 259, "ResultOutOfAddressSpace"),
+    (1, 260, "ResultMessageTooLarge"),
+    (1, 517, "ResultInvalidProcessId"),
+    (1, 518, "ResultInvalidThreadId"),
+    (1, 519, "ResultInvalidId"),
+    (1, 520, "ResultProcessTerminated"),
+    // fs
+    (2, 1, "ResultPathNotFound"),
+    (2, 2, "ResultPathAlreadyExists"),
+    (2, 7, "ResultTargetLocked"),
+    (2, 8, "ResultDirectoryNotEmpty"),
+    (2, 30, "ResultUsableSpaceNotEnough"),
+    (2, 1001, "ResultPartitionNotFound"),
+    (2, 1002, "ResultTargetNotFound"),
+    (2, 2001, "ResultSdCardNotPresent"),
+    (2, 3001, "ResultNotImplemented"),
+    (2, 3005, "ResultOutOfRange"),
+    (2, 6001, "ResultInvalidArgument"),
+    (2, 6002, "ResultInvalidPath"),
+    (2, 6003, "ResultTooLongPath"),
+    (2, 6004, "ResultInvalidCharacter"),
+    (2, 6005, "ResultInvalidPathFormat"),
+    (2, 6006, "ResultDirectoryUnobtainable"),
+    (2, 6007, "ResultNotNormalized"),
+    (2, 6061, "ResultInvalidOffset"),
+    (2, 6062, "ResultInvalidSize"),
+    (2, 6063, "ResultNullptrArgument"),
+    (2, 6072, "ResultInvalidOpenMode"),
+    (2, 6201, "ResultFileExtensionWithoutOpenModeAllowAppend"),
+    (2, 6202, "ResultReadNotPermitted"),
+    (2, 6203, "ResultWriteNotPermitted"),
+    (2, 6300, "ResultUnsupportedOperation"),
+    (2, 6400, "ResultPermissionDenied"),
+    // sf
+    (10, 1, "ResultNotSupported"),
+    (10, 202, "ResultInvalidHeaderSize"),
+    (10, 211, "ResultInvalidInHeader"),
+    (10, 212, "ResultInvalidOutHeader"),
+    (10, 221, "ResultUnknownCommandId"),
+    (10, 232, "ResultInvalidOutRawSize"),
+    (10, 235, "ResultInvalidNumInObjects"),
+    (10, 236, "ResultInvalidNumOutObjects"),
+    (10, 239, "ResultInvalidInObject"),
+    (10, 261, "ResultTargetNotFound"),
+    (10, 301, "ResultOutOfDomainEntries"),
+    // hipc
+    (11, 102, "ResultOutOfSessionMemory"),
+    (11, 131, "ResultOutOfSessions"),
+    (11, 141, "ResultPointerBufferTooSmall"),
+    (11, 200, "ResultOutOfDomains"),
+    (11, 301, "ResultSessionClosed"),
+    (11, 402, "ResultInvalidRequestSize"),
+    (11, 403, "ResultUnknownCommandType"),
+    (11, 420, "ResultInvalidCmifRequest"),
+    (11, 491, "ResultTargetNotDomain"),
+    (11, 492, "ResultDomainObjectNotFound"),
+    // sm
+    (21, 1, "ResultOutOfProcesses"),
+    (21, 2, "ResultInvalidClient"),
+    (21, 3, "ResultOutOfSessions"),
+    (21, 4, "ResultAlreadyRegistered"),
+    (21, 5, "ResultOutOfServices"),
+    (21, 6, "ResultInvalidServiceName"),
+    (21, 7, "ResultNotRegistered"),
+    (21, 8, "ResultNotAllowed"),
+    (21, 9, "ResultTooLargeAccessControl"),
+    // libnx
+    (345, 1, "ResultBadReloc"),
+    (345, 2, "ResultOutOfMemory"),
+    (345, 3, "ResultAlreadyMapped"),
+    (345, 4, "ResultBadGetInfoStack"),
+    (345, 5, "ResultBadGetInfoHeap"),
+    (345, 6, "ResultBadQueryMemory"),
+    (345, 7, "ResultAlreadyInitialized"),
+    (345, 8, "ResultNotInitialized"),
+    (345, 9, "ResultNotFound"),
+    (345, 10, "ResultIoError"),
+    (345, 11, "ResultBadInput"),
+];
+
+/// Finds the name of a system module
+///
+/// # Arguments
+///
+/// * `module`: The module
+pub(crate) fn find_module_name(module: u32) -> Option<&'static str> {
+    MODULE_NAMES
+        .iter()
+        .find(|(known_module, _)| *known_module == module)
+        .map(|(_, name)| *name)
+}
+
+/// Finds the name of a well-known system result
+///
+/// # Arguments
+///
+/// * `module`: The result module
+/// * `description`: The result description
+pub(crate) fn find_result_name(module: u32, description: u32) -> Option<&'static str> {
+    RESULT_NAMES
+        .iter()
+        .find(|(known_module, known_description, _)| {
+            *known_module == module && *known_description == description
+        })
+        .map(|(_, _, name)| *name)
+}
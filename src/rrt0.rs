@@ -45,6 +45,7 @@ use crate::vmem;
 #[cfg(feature = "services")]
 use crate::{ipc::sf, service, service::set};
 
+#[cfg(all(target_arch = "aarch64", target_os = "horizon"))]
 use core::arch::asm;
 use core::mem;
 use core::ptr;
@@ -140,6 +141,7 @@ pub fn get_module_name() -> ModulePath {
 static G_EXIT_FN: sync::Mutex<Option<ExitFn>> = sync::Mutex::new(None);
 static G_MAIN_THREAD: sync::Mutex<Option<thread::Thread>> = sync::Mutex::new(None);
 pub(crate) static TEXT_BASE_ADDRESS: AtomicUsize = AtomicUsize::new(0);
+pub(crate) static MODULE_BASE_ADDRESS: AtomicUsize = AtomicUsize::new(0);
 static EH_FRAME_HDR_SECTION: elf::EhFrameHdrPtr = elf::EhFrameHdrPtr::new();
 
 /// Exits the current process
@@ -238,7 +240,12 @@ unsafe fn normal_entry(loader_mode: LoaderMode, exit_config: Option<ExitFn>) ->
                             // todo!("OverrideService");
                         }
                         hbl::AbiConfigEntryKey::Argv => {
-                            // todo!("Argv");
+                            let argv_ptr = (*abi_entry).value[1] as *const core::ffi::c_char;
+                            if !argv_ptr.is_null()
+                                && let Ok(argv) = core::ffi::CStr::from_ptr(argv_ptr).to_str()
+                            {
+                                hbl::set_argv(argv);
+                            }
                         }
                         hbl::AbiConfigEntryKey::SyscallAvailableHint => {
                             // todo!("SyscallAvailableHint");
@@ -327,11 +334,26 @@ unsafe fn normal_entry(loader_mode: LoaderMode, exit_config: Option<ExitFn>) ->
         crate::rand::finalize();
     }
 
+    #[cfg(feature = "nxlink")]
+    {
+        crate::nxlink::finalize();
+    }
+
     #[cfg(feature = "socket")]
     {
         crate::socket::finalize();
     }
 
+    #[cfg(feature = "time")]
+    {
+        crate::time::finalize();
+    }
+
+    #[cfg(feature = "net")]
+    {
+        crate::net::finalize();
+    }
+
     // Successful exit by default
     exit(ResultSuccess::make());
 }
@@ -347,10 +369,16 @@ unsafe extern "C" fn __nx_rrt0_entry(arg0: usize, arg1: usize) -> ! {
     // Since we're using the `b` instruction instead of `bl` in `rrt0.s`, the `lr` register will still have the passed in value.
     // This will be null for NSOs that are directly executed, but has the loader's return pointer for hbl/ovll loaded NROs.
     let lr_raw: usize;
+    #[cfg(all(target_arch = "aarch64", target_os = "horizon"))]
     asm!(
         "mov {}, lr",
         out(reg) lr_raw
     );
+    // This entrypoint is only used on the console, there's no loader to return to anywhere else
+    #[cfg(not(all(target_arch = "aarch64", target_os = "horizon")))]
+    {
+        lr_raw = 0;
+    }
 
     let lr_exit_fn: Option<ExitFn> = match lr_raw {
         0 => None,
@@ -362,10 +390,15 @@ unsafe extern "C" fn __nx_rrt0_entry(arg0: usize, arg1: usize) -> ! {
     // Since this function is in `.text` anyway, use QueryMemory SVC to find the actual start
     // This is also a contender to let the compiler decide how to do this by just getting a function pointer (e.g. just get `__nx_rrt0_entry`` as a `fn() - !` type)
     let self_base_address: *mut u8;
+    #[cfg(all(target_arch = "aarch64", target_os = "horizon"))]
     asm!(
         "adr {}, __nx_rrt0_entry",
         out(reg) self_base_address
     );
+    #[cfg(not(all(target_arch = "aarch64", target_os = "horizon")))]
+    {
+        self_base_address = __nx_rrt0_entry as *mut u8;
+    }
     let (info, _) = svc::query_memory(self_base_address).unwrap();
     // Use the strict provenance API to convert the usize to a *mut u8 with copied pointer metadata.
     let aslr_base_address = self_base_address.with_addr(info.base_address);
@@ -382,6 +415,10 @@ unsafe extern "C" fn __nx_rrt0_entry(arg0: usize, arg1: usize) -> ! {
         self_base_address.expose_provenance(),
         core::sync::atomic::Ordering::Relaxed,
     );
+    MODULE_BASE_ADDRESS.store(
+        aslr_base_address.expose_provenance(),
+        core::sync::atomic::Ordering::Relaxed,
+    );
     EH_FRAME_HDR_SECTION.set(eh_hdr_ptr_start);
     let _ = unwinding::custom_eh_frame_finder::set_custom_eh_frame_finder(&EH_FRAME_HDR_SECTION);
 
@@ -392,20 +429,11 @@ unsafe extern "C" fn __nx_rrt0_entry(arg0: usize, arg1: usize) -> ! {
     Possible entry arguments:
     - NSO/KIP: x0 = 0, x1 = <main-thread-handle>
     - NRO (hbl): x0 = <abi-config-entries-ptr>, x1 = usize::MAX
-    - Exception: x0 = <exception-type>, x1 = <stack-top>
+    - Exception: x0 = <exception-type>, x1 = <exception-info> (already dispatched in `rrt0.s`, never reaching this point)
     */
-    let loader_mode = match (arg0, arg1) {
-        (0, main_thread_handle) => LoaderMode::Nso(main_thread_handle as u32),
-        (config_pointer, usize::MAX) => {
-            LoaderMode::Nro(aslr_base_address.with_addr(config_pointer) as _)
-        }
-        (exception_type, exception_stack_top) => {
-            let exception_type: svc::ExceptionType = mem::transmute(exception_type as u32);
-            crate::exception::__nx_exception_dispatch(
-                exception_type,
-                core::ptr::with_exposed_provenance_mut(exception_stack_top),
-            );
-        }
+    let loader_mode = match arg0 {
+        0 => LoaderMode::Nso(arg1 as u32),
+        config_pointer => LoaderMode::Nro(aslr_base_address.with_addr(config_pointer) as _),
     };
 
     normal_entry(loader_mode, lr_exit_fn);
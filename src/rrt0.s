@@ -3,6 +3,19 @@
 
 .global _start
 _start:
-	b __nx_rrt0_entry
+	b __nx_rrt0_start
 	.word __module_header - _start
 	.ascii "HOMEBREW"
+
+.section .text.__nx_rrt0_start, "ax", %progbits
+.align 2
+
+__nx_rrt0_start:
+	// Exceptions (x0 = <exception-type>, x1 = <exception-info>) must be dispatched before any register gets clobbered
+	cbz x0, 1f
+	cmn x1, #1
+	b.eq 1f
+	b __nx_exception_entry
+1:
+	// Keep branching (instead of calling) so that `lr` still holds the loader's return pointer
+	b __nx_rrt0_entry
@@ -126,3 +126,24 @@ pub mod lr;
 
 /// "bsd" socket service definitions
 pub mod bsd;
+
+/// "time:u" service definitions.
+pub mod time;
+
+/// "audout:u" service definitions.
+pub mod audout;
+
+/// "audren:u" service definitions.
+pub mod audren;
+
+/// "audin:u" service definitions.
+pub mod audin;
+
+/// "nifm:u" service definitions.
+pub mod nifm;
+
+/// "sfdnsres" service definitions.
+pub mod sfdnsres;
+
+/// "ssl" service definitions.
+pub mod ssl;
@@ -0,0 +1,22 @@
+use crate::ipc::sf::sm;
+use crate::result::*;
+use crate::service;
+
+pub use crate::ipc::sf::audren::*;
+
+ipc_client_define_client_default!(AudioRendererManagerService);
+impl IAudioRendererManagerClient for AudioRendererManagerService {}
+
+impl service::IService for AudioRendererManagerService {
+    fn get_name() -> sm::ServiceName {
+        sm::ServiceName::new("audren:u")
+    }
+
+    fn as_domain() -> bool {
+        false
+    }
+
+    fn post_initialize(&mut self) -> Result<()> {
+        Ok(())
+    }
+}
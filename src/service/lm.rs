@@ -21,3 +21,21 @@ impl service::IService for LoggingService {
         Ok(())
     }
 }
+
+ipc_client_define_client_default!(LogGetterService);
+
+impl ILogGetterClient for LogGetterService {}
+
+impl service::IService for LogGetterService {
+    fn get_name() -> sm::ServiceName {
+        sm::ServiceName::new("lm:get")
+    }
+
+    fn as_domain() -> bool {
+        false
+    }
+
+    fn post_initialize(&mut self) -> Result<()> {
+        Ok(())
+    }
+}
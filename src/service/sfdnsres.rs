@@ -0,0 +1,22 @@
+use crate::ipc::sf::sm;
+use crate::result::*;
+use crate::service;
+
+pub use crate::ipc::sf::sfdnsres::*;
+
+ipc_client_define_client_default!(ResolverService);
+impl IResolverClient for ResolverService {}
+
+impl service::IService for ResolverService {
+    fn get_name() -> sm::ServiceName {
+        sm::ServiceName::new("sfdnsres")
+    }
+
+    fn as_domain() -> bool {
+        false
+    }
+
+    fn post_initialize(&mut self) -> Result<()> {
+        Ok(())
+    }
+}
@@ -0,0 +1,38 @@
+use crate::ipc::sf::sm;
+use crate::result::*;
+use crate::service;
+use crate::version;
+
+pub use crate::ipc::sf::ssl::*;
+
+ipc_client_define_client_default!(SslService);
+impl ISslClient for SslService {}
+
+impl service::IService for SslService {
+    fn get_name() -> sm::ServiceName {
+        sm::ServiceName::new("ssl")
+    }
+
+    fn as_domain() -> bool {
+        false
+    }
+
+    fn post_initialize(&mut self) -> Result<()> {
+        // Contexts can't be created before setting the interface version on 5.0.0+
+        let version = version::get_version();
+        if version >= version::Version::new(5, 0, 0) {
+            let interface_version = if version >= version::Version::new(11, 0, 0) {
+                4
+            } else if version >= version::Version::new(8, 0, 0) {
+                3
+            } else if version >= version::Version::new(6, 0, 0) {
+                2
+            } else {
+                1
+            };
+            self.set_interface_version(interface_version)?;
+        }
+
+        Ok(())
+    }
+}
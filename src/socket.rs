@@ -15,10 +15,21 @@ use crate::result::Result;
 
 pub use crate::service::bsd::*;
 
+pub mod addrinfo;
+
+pub mod msghdr;
+
+pub mod resolver;
+
+pub mod reactor;
+
+pub mod tls;
+
 use crate::service::new_service_object;
 use crate::svc::Handle;
 use crate::svc::MemoryPermission;
 use crate::sync::{ReadGuard, RwLock, WriteGuard};
+use crate::time::Timeout;
 
 /// Holder type for the intialized bsd service
 pub struct BsdSocketService {
@@ -102,7 +113,11 @@ impl Drop for BsdSocketService {
         self._monitor_service.close_session();
         self.service.close_session();
         let _ = crate::svc::close_handle(self.tmem_handle);
-        let _ = wait_for_permission(self._tmem_buffer.ptr as _, MemoryPermission::Write(), None);
+        let _ = wait_for_permission(
+            self._tmem_buffer.ptr as _,
+            MemoryPermission::Write(),
+            Timeout::Infinite,
+        );
     }
 }
 
@@ -139,8 +154,11 @@ pub fn write_socket_service<'a>() -> WriteGuard<'a, Option<BsdSocketService>> {
 
 /// Implementation of the Rust stdlib TCP/UDP API
 pub mod net {
+    use core::mem::offset_of;
+    use core::net::{Ipv4Addr, SocketAddr, SocketAddrV4};
     use core::time::Duration;
-    use core::{mem::offset_of, net::Ipv4Addr};
+
+    pub use super::resolver::{ToSocketAddrs, lookup_host};
 
     use alloc::vec::Vec;
 
@@ -178,11 +196,6 @@ pub mod net {
             /// gets the raw file descriptor for the type
             fn as_raw_fd(&self) -> i32;
 
-            /// Opens a connection to a remote host.
-            fn connect<A: Into<Ipv4Addr>>(destination: A, port: u16) -> Result<Self>
-            where
-                Self: Sized;
-
             /// Creates a new independently owned handle to the underlying socket.
             ///
             /// The returned object is a references the same stream that this
@@ -190,6 +203,8 @@ pub mod net {
             /// data, and options set on one stream will be propagated to the other
             /// stream.
             ///
+            /// The handle is a duplicated descriptor, thus the socket stays open until all the handles are dropped.
+            ///
             /// This function is also why objects implementing this trait _should not_ contain any methods requiring mutable references.
             /// Consumers should expect that calls to these functions are synchronized by the implementation.
             fn try_clone(&self) -> Result<Self>
@@ -250,7 +265,7 @@ pub mod net {
             ///
             /// Returns the length of the data written from the buffer
             ///
-            /// `Self::connect`` will connect this socket to a remote address. This method will fail if the socket is not connected.
+            /// `UdpSocket::connect` will connect this socket to a remote address. This method will fail if the socket is not connected.
             fn send(&self, data: &[u8]) -> Result<u32> {
                 let socket_server_handle = BSD_SERVICE.read();
                 let socket_server = socket_server_handle.as_ref().unwrap();
@@ -273,7 +288,7 @@ pub mod net {
             ///
             /// Returns the length of the data written from the buffer
             ///
-            /// `Self::connect`` will connect this socket to a remote address. This method will fail if the socket is not connected.
+            /// `UdpSocket::connect` will connect this socket to a remote address. This method will fail if the socket is not connected.
             fn send_non_blocking(&self, data: &[u8]) -> Result<()> {
                 let socket_server_handle = BSD_SERVICE.read();
                 let socket_server = socket_server_handle.as_ref().unwrap();
@@ -296,7 +311,7 @@ pub mod net {
             ///
             ///The function must be called with valid byte array buf of sufficient size to hold the message bytes. If a message is too long to fit in the supplied buffer, excess bytes may be discarded.
             ///
-            /// `UdpSocket::connect`` will connect this socket to a remote address. This method will fail if the socket is not connected.
+            /// `UdpSocket::connect` will connect this socket to a remote address. This method will fail if the socket is not connected.
             fn recv_non_blocking(&self, buffer: &mut [u8]) -> Result<Option<usize>> {
                 let socket_server_handle = BSD_SERVICE.read();
                 let socket_server = socket_server_handle.as_ref().unwrap();
@@ -318,32 +333,13 @@ pub mod net {
             }
 
             /// Returns the local address of this socket
-            fn local_addr(&self) -> Result<SocketAddrRepr> {
-                let socket_server_handle = BSD_SERVICE.read();
-
-                let socket_server = socket_server_handle.as_ref().unwrap();
-
-                let mut out_ip: SocketAddrRepr = Default::default();
-                match socket_server
-                    .service
-                    .get_socket_name(self.as_raw_fd(), Buffer::from_mut_var(&mut out_ip))?
-                {
-                    BsdResult::Ok(_, written_sockaddr_size) => {
-                        debug_assert!(
-                            written_sockaddr_size as usize >= offset_of!(SocketAddrRepr, _zero),
-                            "Invalid write length for returned socket addr"
-                        );
-                        Ok(out_ip)
-                    }
-                    BsdResult::Err(errno) => ResultCode::new_err(nx::result::pack_value(
-                        rc::RESULT_MODULE,
-                        1000 + errno.cast_unsigned(),
-                    )),
-                }
+            #[inline(always)]
+            fn local_addr(&self) -> Result<SocketAddr> {
+                get_local_addr(self.as_raw_fd())
             }
 
             /// Returns the remote address of this socket (errors for unconnected UDP sockets).
-            fn peer_addr(&self) -> Result<SocketAddrRepr> {
+            fn peer_addr(&self) -> Result<SocketAddr> {
                 let socket_server_handle = BSD_SERVICE.read();
                 let socket_server = socket_server_handle.as_ref().unwrap();
 
@@ -357,7 +353,7 @@ pub mod net {
                             written_sockaddr_size as usize >= offset_of!(SocketAddrRepr, _zero),
                             "Invalid write length for returned socket addr"
                         );
-                        Ok(out_ip)
+                        Ok(out_ip.into())
                     }
                     BsdResult::Err(errno) => ResultCode::new_err(nx::result::pack_value(
                         rc::RESULT_MODULE,
@@ -370,47 +366,18 @@ pub mod net {
             ///
             /// This value sets the time-to-live field that is used in every packet sent
             /// from this socket.
+            #[inline(always)]
             fn set_ttl(&self, ttl: u32) -> Result<()> {
-                let socket_server_handle = BSD_SERVICE.read();
-                let socket_server = socket_server_handle.as_ref().unwrap();
-
-                if let BsdResult::Err(errno) = socket_server.service.set_sock_opt(
-                    self.as_raw_fd(),
-                    IpProto::IP as _,
-                    IpOptions::TimeToLive as _,
-                    Buffer::from_other_var(&ttl),
-                )? {
-                    return ResultCode::new_err(nx::result::pack_value(
-                        rc::RESULT_MODULE,
-                        1000 + errno.cast_unsigned(),
-                    ));
-                }
-
-                Ok(())
+                set_ttl(self.as_raw_fd(), ttl)
             }
 
             /// Gets the value of the `IP_TTL` option for this socket
+            #[inline(always)]
             fn ttl(&self) -> Result<u32> {
-                let socket_server_handle = BSD_SERVICE.read();
-                let socket_server = socket_server_handle.as_ref().unwrap();
-
-                let mut ttl: u32 = 0;
-                if let BsdResult::Err(errno) = socket_server.service.get_sock_opt(
-                    self.as_raw_fd(),
-                    IpProto::IP as _,
-                    IpOptions::TimeToLive as _,
-                    Buffer::from_other_mut_var(&mut ttl),
-                )? {
-                    return ResultCode::new_err(nx::result::pack_value(
-                        rc::RESULT_MODULE,
-                        1000 + errno.cast_unsigned(),
-                    ));
-                }
-
-                Ok(ttl)
+                get_ttl(self.as_raw_fd())
             }
 
-            /// Moves this TCP stream into or out of nonblocking mode.
+            /// Moves this socket into or out of nonblocking mode.
             ///
             ///  This will result in `read`, `write`, `recv` and `send` system operations
             ///  becoming nonblocking, i.e., immediately returning from their calls.
@@ -418,49 +385,15 @@ pub mod net {
             /// action is required. If the IO operation could not be completed and needs
             /// to be retried, an error with the value set to `EAGAIN` is
             /// returned.
+            #[inline(always)]
             fn set_nonblocking(&self, nonblocking: bool) -> Result<()> {
-                const O_NONBLOCK: i32 = 0x4000;
-
-                let socket_server_handle = BSD_SERVICE.read();
-                let socket_server = socket_server_handle.as_ref().unwrap();
-
-                let current_flags = match socket_server.service.fnctl(
-                    self.as_raw_fd(),
-                    super::FnCtlCmd::GetFl,
-                    0,
-                )? {
-                    BsdResult::Ok(flags, ()) => flags,
-                    BsdResult::Err(errno) => {
-                        return ResultCode::new_err(nx::result::pack_value(
-                            rc::RESULT_MODULE,
-                            1000 + errno.cast_unsigned(),
-                        ));
-                    }
-                };
-
-                let flags = if nonblocking {
-                    current_flags | O_NONBLOCK
-                } else {
-                    current_flags & !O_NONBLOCK
-                };
-
-                if let BsdResult::Err(errno) =
-                    socket_server
-                        .service
-                        .fnctl(self.as_raw_fd(), super::FnCtlCmd::SetFl, flags)?
-                {
-                    return ResultCode::new_err(nx::result::pack_value(
-                        rc::RESULT_MODULE,
-                        1000 + errno.cast_unsigned(),
-                    ));
-                }
-
-                Ok(())
+                set_nonblocking(self.as_raw_fd(), nonblocking)
             }
 
-            /// Returns the read timeout of this socket.
+            /// Returns the read timeout of this socket (the `SO_RCVTIMEO` option).
+            ///
             /// If the timeout is [`None`], then [`SocketCommon::recv`] calls will block indefinitely.
-            fn recv_timeout(&self) -> Result<Option<Duration>> {
+            fn read_timeout(&self) -> Result<Option<Duration>> {
                 let socket_server_handle = BSD_SERVICE.read();
                 let socket_server = socket_server_handle.as_ref().unwrap();
 
@@ -516,10 +449,10 @@ pub mod net {
                 Ok(())
             }
 
-            /// Returns the write timeout of this socket.
+            /// Returns the write timeout of this socket (the `SO_SNDTIMEO` option).
             ///
-            ///  If the timeout is [`None`], then [`SocketCommon::send`] calls will block indefinitely.
-            fn send_timeout(&self) -> Result<Option<Duration>> {
+            /// If the timeout is [`None`], then [`SocketCommon::send`] calls will block indefinitely.
+            fn write_timeout(&self) -> Result<Option<Duration>> {
                 let socket_server_handle = BSD_SERVICE.read();
                 let socket_server = socket_server_handle.as_ref().unwrap();
 
@@ -550,7 +483,7 @@ pub mod net {
 
             /// Sets the write timeout to the timeout specified.
             ///
-            ///  If the value specified is [`None`], then [`write`] calls will block
+            /// If the value specified is [`None`], then [`SocketCommon::send`] calls will block
             /// indefinitely. An [`Err`] is returned if the zero [`Duration`] is
             /// passed to this method.
             fn set_write_timeout(&self, timeout: Option<Duration>) -> Result<()> {
@@ -580,39 +513,21 @@ pub mod net {
             /// This will retrieve the stored error in the underlying socket, clearing
             /// the field in the process. This can be useful for checking errors between
             /// calls.
-            fn take_error(&self) -> Result<Option<i32>> {
-                let socket_server_handle = BSD_SERVICE.read();
-
-                let socket_server = socket_server_handle.as_ref().unwrap();
-
-                let mut ret_errno: i32 = 0;
-                if let BsdResult::Err(errno) = socket_server.service.get_sock_opt(
-                    self.as_raw_fd(),
-                    SOL_SOCKET,
-                    SocketOptions::Error as i32,
-                    OutAutoSelectBuffer::from_other_mut_var(&mut ret_errno),
-                )? {
-                    return ResultCode::new_err(nx::result::pack_value(
-                        rc::RESULT_MODULE,
-                        1000 + errno.cast_unsigned(),
-                    ));
-                }
-
-                Ok(if ret_errno != 0 {
-                    Some(ret_errno)
-                } else {
-                    None
-                })
+            #[inline(always)]
+            fn take_error(&self) -> Result<Option<ResultCode>> {
+                take_error(self.as_raw_fd())
             }
         }
     }
 
     use traits::{Pollable, SocketCommon};
     /// Takes a slice of pollable values and requested events returns an iterator over the matched index in the input list and the returned events.
+    ///
+    /// The timeout (see [`Timeout`]) has millisecond precision, rounding up.
     #[inline(always)]
     pub fn poll<P: traits::Pollable>(
         pollers: &[(P, PollFlags)],
-        timeout: Option<i32>,
+        timeout: impl Into<Timeout>,
     ) -> Result<impl Iterator<Item = (usize, PollFlags)>> {
         poll_impl(
             pollers
@@ -623,7 +538,7 @@ pub mod net {
                     revents: Default::default(),
                 })
                 .collect(),
-            timeout.unwrap_or(-1),
+            timeout.into().get_remaining_millis(),
         )
     }
 
@@ -657,31 +572,253 @@ pub mod net {
         }))
     }
 
+    /// Duplicates a socket, returning the new descriptor (which must be closed separately)
+    fn duplicate_socket(socket: i32) -> Result<i32> {
+        let socket_server_handle = BSD_SERVICE.read();
+
+        let socket_server = socket_server_handle
+            .as_ref()
+            .ok_or(rc::ResultNotInitialized::make())?;
+
+        match socket_server.service.dup_fd(socket, 0)? {
+            BsdResult::Ok(new_socket, ()) => Ok(new_socket),
+            BsdResult::Err(errno) => ResultCode::new_err(nx::result::pack_value(
+                rc::RESULT_MODULE,
+                1000 + errno.cast_unsigned(),
+            )),
+        }
+    }
+
+    /// Closes a socket when its owning type is dropped, ignoring any errors (the service might have been finalized first)
+    fn close_socket(socket: i32) {
+        let socket_server_handle = BSD_SERVICE.read();
+
+        if let Some(socket_server) = socket_server_handle.as_ref() {
+            let _ = socket_server.service.close(socket);
+        }
+    }
+
+    /// Creates a new IPv4 socket of the given type, returning its descriptor
+    fn create_socket(socket_type: SocketType, protocol: IpProto) -> Result<i32> {
+        let socket_server_handle = BSD_SERVICE.read();
+
+        let socket_server = socket_server_handle
+            .as_ref()
+            .ok_or(rc::ResultNotInitialized::make())?;
+
+        match socket_server
+            .service
+            .socket(super::SocketDomain::INet, socket_type, protocol)?
+        {
+            BsdResult::Ok(socket, ()) => Ok(socket),
+            BsdResult::Err(errno) => ResultCode::new_err(nx::result::pack_value(
+                rc::RESULT_MODULE,
+                1000 + errno.cast_unsigned(),
+            )),
+        }
+    }
+
+    /// Tries each of the given addresses in order with the given function, returning the result of the first successful attempt or the error of the last one
+    fn try_each_address<A: ToSocketAddrs, T>(
+        addresses: A,
+        mut f: impl FnMut(SocketAddrRepr) -> Result<T>,
+    ) -> Result<T> {
+        let mut last_result = rc::ResultHostNotFound::make_err();
+        for address in addresses.to_socket_addrs()? {
+            last_result = f(address.into());
+            if last_result.is_ok() {
+                break;
+            }
+        }
+        last_result
+    }
+
+    fn get_local_addr(socket: i32) -> Result<SocketAddr> {
+        let socket_server_handle = BSD_SERVICE.read();
+
+        let socket_server = socket_server_handle.as_ref().unwrap();
+
+        let mut out_ip: SocketAddrRepr = Default::default();
+        match socket_server
+            .service
+            .get_socket_name(socket, Buffer::from_mut_var(&mut out_ip))?
+        {
+            BsdResult::Ok(_, written_sockaddr_size) => {
+                debug_assert!(
+                    written_sockaddr_size as usize >= offset_of!(SocketAddrRepr, _zero),
+                    "Invalid write length for returned socket addr"
+                );
+                Ok(out_ip.into())
+            }
+            BsdResult::Err(errno) => ResultCode::new_err(nx::result::pack_value(
+                rc::RESULT_MODULE,
+                1000 + errno.cast_unsigned(),
+            )),
+        }
+    }
+
+    fn set_ttl(socket: i32, ttl: u32) -> Result<()> {
+        let socket_server_handle = BSD_SERVICE.read();
+        let socket_server = socket_server_handle.as_ref().unwrap();
+
+        if let BsdResult::Err(errno) = socket_server.service.set_sock_opt(
+            socket,
+            IpProto::IP as _,
+            IpOptions::TimeToLive as _,
+            Buffer::from_other_var(&ttl),
+        )? {
+            return ResultCode::new_err(nx::result::pack_value(
+                rc::RESULT_MODULE,
+                1000 + errno.cast_unsigned(),
+            ));
+        }
+
+        Ok(())
+    }
+
+    fn get_ttl(socket: i32) -> Result<u32> {
+        let socket_server_handle = BSD_SERVICE.read();
+        let socket_server = socket_server_handle.as_ref().unwrap();
+
+        let mut ttl: u32 = 0;
+        if let BsdResult::Err(errno) = socket_server.service.get_sock_opt(
+            socket,
+            IpProto::IP as _,
+            IpOptions::TimeToLive as _,
+            Buffer::from_other_mut_var(&mut ttl),
+        )? {
+            return ResultCode::new_err(nx::result::pack_value(
+                rc::RESULT_MODULE,
+                1000 + errno.cast_unsigned(),
+            ));
+        }
+
+        Ok(ttl)
+    }
+
+    fn set_nonblocking(socket: i32, nonblocking: bool) -> Result<()> {
+        const O_NONBLOCK: i32 = 0x4000;
+
+        let socket_server_handle = BSD_SERVICE.read();
+        let socket_server = socket_server_handle.as_ref().unwrap();
+
+        let current_flags = match socket_server
+            .service
+            .fnctl(socket, super::FnCtlCmd::GetFl, 0)?
+        {
+            BsdResult::Ok(flags, ()) => flags,
+            BsdResult::Err(errno) => {
+                return ResultCode::new_err(nx::result::pack_value(
+                    rc::RESULT_MODULE,
+                    1000 + errno.cast_unsigned(),
+                ));
+            }
+        };
+
+        let flags = if nonblocking {
+            current_flags | O_NONBLOCK
+        } else {
+            current_flags & !O_NONBLOCK
+        };
+
+        if let BsdResult::Err(errno) =
+            socket_server
+                .service
+                .fnctl(socket, super::FnCtlCmd::SetFl, flags)?
+        {
+            return ResultCode::new_err(nx::result::pack_value(
+                rc::RESULT_MODULE,
+                1000 + errno.cast_unsigned(),
+            ));
+        }
+
+        Ok(())
+    }
+
+    fn take_error(socket: i32) -> Result<Option<ResultCode>> {
+        let socket_server_handle = BSD_SERVICE.read();
+
+        let socket_server = socket_server_handle.as_ref().unwrap();
+
+        let mut ret_errno: i32 = 0;
+        if let BsdResult::Err(errno) = socket_server.service.get_sock_opt(
+            socket,
+            SOL_SOCKET,
+            SocketOptions::Error as i32,
+            OutAutoSelectBuffer::from_other_mut_var(&mut ret_errno),
+        )? {
+            return ResultCode::new_err(nx::result::pack_value(
+                rc::RESULT_MODULE,
+                1000 + errno.cast_unsigned(),
+            ));
+        }
+
+        Ok(if ret_errno != 0 {
+            Some(ResultCode::new(nx::result::pack_value(
+                rc::RESULT_MODULE,
+                1000 + ret_errno.cast_unsigned(),
+            )))
+        } else {
+            None
+        })
+    }
+
+    /// A TCP socket server, listening for connections.
+    ///
+    /// After creating a `TcpListener` by [`bind`]ing it to a socket address, it listens for
+    /// incoming TCP connections. These can be accepted by calling [`accept`] or by iterating
+    /// over the [`Incoming`] iterator returned by [`incoming`].
+    ///
+    /// The socket will be closed when the value is dropped.
+    ///
+    /// [`accept`]: TcpListener::accept
+    /// [`bind`]: TcpListener::bind
+    /// [`incoming`]: TcpListener::incoming
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use nx::socket::net::{TcpListener, TcpStream};
+    ///
+    /// fn handle_client(stream: TcpStream) {
+    ///     // ...
+    /// }
+    ///
+    /// let listener = TcpListener::bind("0.0.0.0:80")?;
+    ///
+    /// // accept connections and process them serially
+    /// for stream in listener.incoming() {
+    ///     handle_client(stream?);
+    /// }
+    /// ```
     pub struct TcpListener(i32);
 
+    impl Drop for TcpListener {
+        fn drop(&mut self) {
+            close_socket(self.0);
+        }
+    }
+
     impl TcpListener {
-        pub fn bind(ip: Ipv4Addr, port: u16) -> Result<Self> {
+        /// Creates a new `TcpListener` which will be bound to the specified address.
+        ///
+        /// Binding with a port number of 0 will request that the OS assigns a port to this listener.
+        /// The port allocated can be queried via [`TcpListener::local_addr`].
+        ///
+        /// Host names are resolved (see [`ToSocketAddrs`]), and each resulting address is tried in order until one succeeds, returning the error of the last attempt otherwise.
+        pub fn bind<A: ToSocketAddrs>(addresses: A) -> Result<Self> {
+            try_each_address(addresses, Self::bind_impl)
+        }
+
+        fn bind_impl(address: SocketAddrRepr) -> Result<Self> {
+            let listenfd = create_socket(super::SocketType::Stream, super::IpProto::IP)?;
+
             let socket_server_handle = BSD_SERVICE.read();
 
             let socket_server = socket_server_handle
                 .as_ref()
                 .ok_or(rc::ResultNotInitialized::make())?;
 
-            let ipaddr = SocketAddrRepr::from((ip, port));
-            let listenfd = match socket_server.service.socket(
-                super::SocketDomain::INet,
-                super::SocketType::Stream,
-                super::IpProto::IP,
-            )? {
-                BsdResult::Ok(ret, ()) => ret,
-                BsdResult::Err(errno) => {
-                    return ResultCode::new_err(nx::result::pack_value(
-                        rc::RESULT_MODULE,
-                        1000 + errno.cast_unsigned(),
-                    ));
-                }
-            };
-
             let yes = 1i32;
             if let BsdResult::Err(errno) = socket_server.service.set_sock_opt(
                 listenfd,
@@ -689,6 +826,7 @@ pub mod net {
                 SocketOptions::ReuseAddr as i32,
                 Buffer::from_other_var(&yes),
             )? {
+                let _ = socket_server.service.close(listenfd);
                 return ResultCode::new_err(nx::result::pack_value(
                     rc::RESULT_MODULE,
                     1000 + errno.cast_unsigned(),
@@ -697,8 +835,9 @@ pub mod net {
 
             if let BsdResult::Err(errno) = socket_server
                 .service
-                .bind(listenfd, Buffer::from_var(&ipaddr))?
+                .bind(listenfd, Buffer::from_var(&address))?
             {
+                let _ = socket_server.service.close(listenfd);
                 return ResultCode::new_err(nx::result::pack_value(
                     rc::RESULT_MODULE,
                     1000 + errno.cast_unsigned(),
@@ -706,6 +845,7 @@ pub mod net {
             };
 
             if let BsdResult::Err(errno) = socket_server.service.listen(listenfd, 5)? {
+                let _ = socket_server.service.close(listenfd);
                 return ResultCode::new_err(nx::result::pack_value(
                     rc::RESULT_MODULE,
                     1000 + errno.cast_unsigned(),
@@ -715,7 +855,12 @@ pub mod net {
             Ok(Self(listenfd))
         }
 
-        pub fn accept(&self) -> Result<(TcpStream, SocketAddrRepr)> {
+        /// Accepts a new incoming connection from this listener.
+        ///
+        /// This function will block the calling thread until a new TCP connection
+        /// is established. When established, the corresponding [`TcpStream`] and the
+        /// remote peer's address will be returned.
+        pub fn accept(&self) -> Result<(TcpStream, SocketAddr)> {
             let socket_server_handle = BSD_SERVICE.read();
 
             let socket_server = socket_server_handle.as_ref().unwrap();
@@ -724,14 +869,14 @@ pub mod net {
 
             match socket_server
                 .service
-                .accept(self.get_poll_fd(), Buffer::from_mut_var(&mut out_ip))?
+                .accept(self.0, Buffer::from_mut_var(&mut out_ip))?
             {
                 BsdResult::Ok(new_sock, written_sockaddr_size) => {
                     debug_assert!(
                         written_sockaddr_size as usize >= offset_of!(SocketAddrRepr, _zero),
                         "Invalid write length for returned socket addr"
                     );
-                    Ok((TcpStream(new_sock), out_ip))
+                    Ok((TcpStream(new_sock), out_ip.into()))
                 }
                 BsdResult::Err(errno) => ResultCode::new_err(nx::result::pack_value(
                     rc::RESULT_MODULE,
@@ -740,28 +885,67 @@ pub mod net {
             }
         }
 
-        pub fn local_addr(&self) -> Result<SocketAddrRepr> {
-            let socket_server_handle = BSD_SERVICE.read();
+        /// Returns an iterator over the connections being received on this listener.
+        ///
+        /// The returned iterator will never return [`None`] and will also not yield the
+        /// peer's [`SocketAddr`] structure. Iterating over it is equivalent to calling
+        /// [`TcpListener::accept`] in a loop.
+        #[inline]
+        pub fn incoming(&self) -> Incoming<'_> {
+            Incoming { listener: self }
+        }
 
-            let socket_server = socket_server_handle.as_ref().unwrap();
+        /// Returns the local address of this listener
+        #[inline(always)]
+        pub fn local_addr(&self) -> Result<SocketAddr> {
+            get_local_addr(self.0)
+        }
 
-            let mut out_ip: SocketAddrRepr = Default::default();
-            match socket_server
-                .service
-                .get_socket_name(self.0, Buffer::from_mut_var(&mut out_ip))?
-            {
-                BsdResult::Ok(_, written_sockaddr_size) => {
-                    debug_assert!(
-                        written_sockaddr_size as usize >= offset_of!(SocketAddrRepr, _zero),
-                        "Invalid write length for returned socket addr"
-                    );
-                    Ok(out_ip)
-                }
-                BsdResult::Err(errno) => ResultCode::new_err(nx::result::pack_value(
-                    rc::RESULT_MODULE,
-                    1000 + errno.cast_unsigned(),
-                )),
-            }
+        /// Creates a new independently owned handle to the underlying socket.
+        ///
+        /// The returned [`TcpListener`] is a reference to the same socket that this
+        /// object references. Both handles can be used to accept incoming
+        /// connections and options set on one listener will affect the other.
+        ///
+        /// The handle is a duplicated descriptor, thus the socket stays open until all the handles are dropped.
+        #[inline(always)]
+        pub fn try_clone(&self) -> Result<Self> {
+            Ok(Self(duplicate_socket(self.0)?))
+        }
+
+        /// Sets the value for the `IP_TTL` option on this socket.
+        ///
+        /// This value sets the time-to-live field that is used in every packet sent
+        /// from this socket.
+        #[inline(always)]
+        pub fn set_ttl(&self, ttl: u32) -> Result<()> {
+            set_ttl(self.0, ttl)
+        }
+
+        /// Gets the value of the `IP_TTL` option for this socket
+        #[inline(always)]
+        pub fn ttl(&self) -> Result<u32> {
+            get_ttl(self.0)
+        }
+
+        /// Moves this listener into or out of nonblocking mode.
+        ///
+        /// This will result in the `accept` operation becoming nonblocking,
+        /// i.e., immediately returning from its call. If there is no pending
+        /// connection, an error with the value set to `EAGAIN` is returned.
+        #[inline(always)]
+        pub fn set_nonblocking(&self, nonblocking: bool) -> Result<()> {
+            set_nonblocking(self.0, nonblocking)
+        }
+
+        /// Gets the value of the `SO_ERROR` option on this socket.
+        ///
+        /// This will retrieve the stored error in the underlying socket, clearing
+        /// the field in the process. This can be useful for checking errors between
+        /// calls.
+        #[inline(always)]
+        pub fn take_error(&self) -> Result<Option<ResultCode>> {
+            take_error(self.0)
         }
     }
 
@@ -771,34 +955,57 @@ pub mod net {
         }
     }
 
+    /// An iterator that infinitely [`accept`]s connections on a [`TcpListener`].
+    ///
+    /// This `struct` is created by the [`TcpListener::incoming`] method.
+    ///
+    /// [`accept`]: TcpListener::accept
+    pub struct Incoming<'a> {
+        listener: &'a TcpListener,
+    }
+
+    impl Iterator for Incoming<'_> {
+        type Item = Result<TcpStream>;
+
+        fn next(&mut self) -> Option<Self::Item> {
+            Some(self.listener.accept().map(|(stream, _)| stream))
+        }
+    }
+
+    /// A TCP stream between a local and a remote socket.
+    ///
+    /// The connection will be closed when the value is dropped (once all the handles created with [`try_clone`][`traits::SocketCommon::try_clone`] are dropped too).
     pub struct TcpStream(i32);
 
+    impl Drop for TcpStream {
+        fn drop(&mut self) {
+            close_socket(self.0);
+        }
+    }
+
     impl TcpStream {
+        /// Opens a TCP connection to a remote host.
+        ///
+        /// Host names are resolved (see [`ToSocketAddrs`]), and each resulting address is tried in order until a connection succeeds, returning the error of the last attempt otherwise.
+        pub fn connect<A: ToSocketAddrs>(addresses: A) -> Result<Self> {
+            try_each_address(addresses, Self::connect_impl)
+        }
+
         fn connect_impl(destination: SocketAddrRepr) -> Result<Self> {
+            let socket = create_socket(super::SocketType::Stream, super::IpProto::IP)?;
+
             let socket_server_handle = BSD_SERVICE.read();
 
             let socket_server = socket_server_handle
                 .as_ref()
                 .ok_or(rc::ResultNotInitialized::make())?;
 
-            let socket = match socket_server.service.socket(
-                super::SocketDomain::INet,
-                super::SocketType::Stream,
-                super::IpProto::IP,
-            )? {
-                BsdResult::Ok(ret, ()) => ret,
-                BsdResult::Err(errno) => {
-                    return ResultCode::new_err(nx::result::pack_value(
-                        rc::RESULT_MODULE,
-                        1000 + errno.cast_unsigned(),
-                    ));
-                }
-            };
-
             if let BsdResult::Err(errno) = socket_server
                 .service
                 .connect(socket, Buffer::from_var(&destination))?
             {
+                // Otherwise the socket would be leaked, since it's not returned
+                let _ = socket_server.service.close(socket);
                 return ResultCode::new_err(nx::result::pack_value(
                     rc::RESULT_MODULE,
                     1000 + errno.cast_unsigned(),
@@ -808,6 +1015,77 @@ pub mod net {
             Ok(Self(socket))
         }
 
+        /// Opens a TCP connection to a remote host with a timeout.
+        ///
+        /// Unlike [`TcpStream::connect`], this takes a single address since a timeout must be applied to individual connection attempts.
+        /// If the connection isn't established within the timeout, the `ETIMEDOUT` error is returned.
+        ///
+        /// An [`Err`] is returned if the zero [`Duration`] is passed to this method.
+        pub fn connect_timeout(address: &SocketAddr, timeout: Duration) -> Result<Self> {
+            const EINPROGRESS: i32 = 115;
+            const ETIMEDOUT: i32 = 110;
+
+            result_return_if!(timeout == Duration::ZERO, rc::ResultInvalidTimeout);
+
+            let destination: SocketAddrRepr = address
+                .to_socket_addrs()?
+                .next()
+                .ok_or(rc::ResultInvalidSockAddr::make())?
+                .into();
+
+            // The socket is closed on drop if anything fails from now on
+            let stream = Self(create_socket(
+                super::SocketType::Stream,
+                super::IpProto::IP,
+            )?);
+            stream.set_nonblocking(true)?;
+
+            let connect_result = {
+                let socket_server_handle = BSD_SERVICE.read();
+
+                let socket_server = socket_server_handle
+                    .as_ref()
+                    .ok_or(rc::ResultNotInitialized::make())?;
+
+                socket_server
+                    .service
+                    .connect(stream.0, Buffer::from_var(&destination))?
+            };
+
+            match connect_result {
+                BsdResult::Ok(_, ()) => {}
+                BsdResult::Err(EINPROGRESS) => {
+                    let poll_fd = PollFd {
+                        fd: stream.0,
+                        events: PollFlags::PollOut() | PollFlags::PollError(),
+                        revents: Default::default(),
+                    };
+                    if poll_impl(vec![poll_fd], Timeout::from(timeout).get_remaining_millis())?
+                        .next()
+                        .is_none()
+                    {
+                        return ResultCode::new_err(nx::result::pack_value(
+                            rc::RESULT_MODULE,
+                            1000 + ETIMEDOUT.cast_unsigned(),
+                        ));
+                    }
+
+                    if let Some(error) = stream.take_error()? {
+                        return Err(error);
+                    }
+                }
+                BsdResult::Err(errno) => {
+                    return ResultCode::new_err(nx::result::pack_value(
+                        rc::RESULT_MODULE,
+                        1000 + errno.cast_unsigned(),
+                    ));
+                }
+            }
+
+            stream.set_nonblocking(false)?;
+            Ok(stream)
+        }
+
         pub fn linger(&self) -> Result<Option<Duration>> {
             let socket_server_handle = BSD_SERVICE.read();
 
@@ -839,7 +1117,7 @@ pub mod net {
             let mut delay: i32 = 0;
             match socket_server.service.get_sock_opt(
                 self.0,
-                IpProto::IP as _,
+                IpProto::TCP as _,
                 TcpOptions::NoDelay as _,
                 Buffer::from_other_mut_var(&mut delay),
             )? {
@@ -867,8 +1145,8 @@ pub mod net {
 
             if let BsdResult::Err(errno) = socket_server.service.set_sock_opt(
                 self.0,
-                SOL_SOCKET,
-                SocketOptions::Broadcast as _,
+                IpProto::TCP as _,
+                TcpOptions::NoDelay as _,
                 Buffer::from_other_var(&(value as i32)),
             )? {
                 return ResultCode::new_err(nx::result::pack_value(
@@ -891,7 +1169,7 @@ pub mod net {
         /// ```no_run
         /// use nx::socket::net::{Shutdown, TcpStream};
         ///
-        /// let stream = TcpStream::connect(Ipv4Addr::LOCALHOST, Some(8080))
+        /// let stream = TcpStream::connect((Ipv4Addr::LOCALHOST, 8080))
         ///                        .expect("Couldn't connect to the server...");
         /// stream.shutdown(Shutdown::Both).expect("shutdown call failed");
         /// ```
@@ -916,15 +1194,9 @@ pub mod net {
             self.0
         }
 
-        #[inline(always)]
-        fn connect<A: Into<Ipv4Addr>>(destination: A, port: u16) -> Result<Self> {
-            let destination = (destination.into(), port).into();
-            Self::connect_impl(destination)
-        }
-
         #[inline(always)]
         fn try_clone(&self) -> Result<Self> {
-            Ok(Self(self.0))
+            Ok(Self(duplicate_socket(self.0)?))
         }
     }
 
@@ -943,7 +1215,7 @@ pub mod net {
     /// primitives.
     ///
     /// [`bind`]: UdpSocket::bind
-    /// [`connect`]: SocketCommon::connect
+    /// [`connect`]: UdpSocket::connect
     /// [IETF RFC 768]: https://tools.ietf.org/html/rfc768
     /// [`recv`]: SocketCommon::recv
     /// [received from]: UdpSocket::recv_from
@@ -952,47 +1224,55 @@ pub mod net {
     /// [`TcpListener`]: TcpListener
     /// [`TcpStream`]: TcpStream
     ///
+    /// The socket will be closed when the value is dropped.
+    ///
     /// # Examples
     ///
     /// ```no_run
+    /// use nx::socket::net::UdpSocket;
     ///
-    /// let socket = UdpSocket::bind(Ipv4Addr::LOCALHOST, Some(34254))?;
+    /// let socket = UdpSocket::bind("127.0.0.1:34254")?;
     ///
     /// // Receives a single datagram message on the socket. If `buf` is too small to hold
     /// // the message, it will be cut off.
     /// let mut buf = [0; 10];
-    /// let (amt, src_ip, src_port) = socket.recv_from(&mut buf)?;
+    /// let (amt, src) = socket.recv_from(&mut buf)?;
     ///
     /// // Redeclare `buf` as slice of the received data and send reverse data back to origin.
     /// let buf = &mut buf[..amt];
     /// buf.reverse();
-    /// socket.send_to(buf, (src_ip, src_port))?;
-    ///
+    /// socket.send_to(buf, src)?;
     /// ```
     pub struct UdpSocket(i32);
 
+    impl Drop for UdpSocket {
+        fn drop(&mut self) {
+            close_socket(self.0);
+        }
+    }
+
     impl UdpSocket {
         /// Creates a UDP socket from the given address.
         ///
-        /// The address type can be any implementor of [`Into<Ipv4Addr>`].
+        /// Host names are resolved (see [`ToSocketAddrs`]), and each resulting address is tried in order until one succeeds, returning the error of the last attempt otherwise.
         ///
         /// # Examples
         ///
         /// Creates a UDP socket bound to `127.0.0.1:3400`:
         ///
         /// ```no_run
-        /// use std::net::UdpSocket;
+        /// use nx::socket::net::UdpSocket;
         ///
-        /// let socket = UdpSocket::bind(Ipv4Addr::LOCALHOST,Some(3400)).expect("couldn't bind to address");
+        /// let socket = UdpSocket::bind("127.0.0.1:3400").expect("couldn't bind to address");
         /// ```
         ///
         /// Creates a UDP socket bound to a port assigned by the operating system
         /// at `127.0.0.1`.
         ///
         /// ```no_run
-        /// use std::net::UdpSocket;
+        /// use nx::socket::net::UdpSocket;
         ///
-        /// let socket = UdpSocket::bind(Ipv4Addr::LOCALHOST, None).unwrap();
+        /// let socket = UdpSocket::bind((Ipv4Addr::LOCALHOST, 0)).unwrap();
         /// ```
         ///
         /// Note that `bind` declares the scope of your network connection.
@@ -1004,64 +1284,54 @@ pub mod net {
         ///
         /// In order to limit your view of the network the least, `bind` to
         /// [`Ipv4Addr::UNSPECIFIED`].
-        pub fn bind<A: Into<Ipv4Addr>>(addr: A, port: Option<u16>) -> Result<Self> {
+        pub fn bind<A: ToSocketAddrs>(addresses: A) -> Result<Self> {
+            try_each_address(addresses, Self::bind_impl)
+        }
+
+        fn bind_impl(address: SocketAddrRepr) -> Result<Self> {
+            let socket = create_socket(super::SocketType::DataGram, super::IpProto::UDP)?;
+
             let socket_server_handle = BSD_SERVICE.read();
 
             let socket_server = socket_server_handle
                 .as_ref()
                 .ok_or(rc::ResultNotInitialized::make())?;
-            let socket = match socket_server.service.socket(
-                super::SocketDomain::INet,
-                super::SocketType::DataGram,
-                super::IpProto::UDP,
-            )? {
-                BsdResult::Ok(ret, ()) => ret,
-                BsdResult::Err(errno) => {
-                    return ResultCode::new_err(nx::result::pack_value(
-                        rc::RESULT_MODULE,
-                        1000 + errno.cast_unsigned(),
-                    ));
-                }
-            };
 
-            let ipaddr: Ipv4Addr = addr.into();
-            let socketaddr = SocketAddrRepr::from((ipaddr, port.unwrap_or(0)));
             if let BsdResult::Err(errno) = socket_server
                 .service
-                .bind(socket, Buffer::from_var(&socketaddr))?
+                .bind(socket, Buffer::from_var(&address))?
             {
+                // Otherwise the socket would be leaked, since it's not returned
+                let _ = socket_server.service.close(socket);
                 return ResultCode::new_err(nx::result::pack_value(
                     rc::RESULT_MODULE,
                     1000 + errno.cast_unsigned(),
                 ));
             };
-            Err(rc::ResultNotInitialized::make())
+
+            Ok(Self(socket))
         }
 
-        fn connect_impl(destination: SocketAddrRepr) -> Result<Self> {
-            let socket_server_handle = BSD_SERVICE.read();
+        /// Connects this UDP socket to a remote address, allowing the [`send`] and
+        /// [`recv`] methods to be used to send data and also applies filters to only
+        /// receive data from the specified address.
+        ///
+        /// Host names are resolved (see [`ToSocketAddrs`]), and each resulting address is tried in order until one succeeds, returning the error of the last attempt otherwise.
+        ///
+        /// [`recv`]: SocketCommon::recv
+        /// [`send`]: SocketCommon::send
+        pub fn connect<A: ToSocketAddrs>(&self, addresses: A) -> Result<()> {
+            try_each_address(addresses, |destination| self.connect_impl(destination))
+        }
 
-            let socket_server = socket_server_handle
-                .as_ref()
-                .ok_or(rc::ResultNotInitialized::make())?;
+        fn connect_impl(&self, destination: SocketAddrRepr) -> Result<()> {
+            let socket_server_handle = BSD_SERVICE.read();
 
-            let socket = match socket_server.service.socket(
-                super::SocketDomain::INet,
-                super::SocketType::DataGram,
-                super::IpProto::UDP,
-            )? {
-                BsdResult::Ok(ret, ()) => ret,
-                BsdResult::Err(errno) => {
-                    return ResultCode::new_err(nx::result::pack_value(
-                        rc::RESULT_MODULE,
-                        1000 + errno.cast_unsigned(),
-                    ));
-                }
-            };
+            let socket_server = socket_server_handle.as_ref().unwrap();
 
             if let BsdResult::Err(errno) = socket_server
                 .service
-                .connect(socket, Buffer::from_var(&destination))?
+                .connect(self.0, Buffer::from_var(&destination))?
             {
                 return ResultCode::new_err(nx::result::pack_value(
                     rc::RESULT_MODULE,
@@ -1069,14 +1339,14 @@ pub mod net {
                 ));
             };
 
-            Ok(Self(socket))
+            Ok(())
         }
 
         /// Receives data on the socket from the remote address to which it is connected.
         /// On success, returns the number of bytes read and the origin.
         ///
         ///The function must be called with valid byte array buf of sufficient size to hold the message bytes. If a message is too long to fit in the supplied buffer, excess bytes may be discarded.
-        pub fn recv_from(&self, buffer: &mut [u8]) -> Result<(usize, Ipv4Addr, u16)> {
+        pub fn recv_from(&self, buffer: &mut [u8]) -> Result<(usize, SocketAddr)> {
             let socket_server_handle = BSD_SERVICE.read();
 
             let socket_server = socket_server_handle.as_ref().unwrap();
@@ -1088,11 +1358,7 @@ pub mod net {
                 Buffer::from_mut_array(buffer),
                 Buffer::from_mut_var(&mut out_addr),
             )? {
-                BsdResult::Ok(ret, ()) => Ok((
-                    ret as usize,
-                    Ipv4Addr::from_bits(u32::from_be_bytes(out_addr.addr)),
-                    u16::from_be(out_addr.port),
-                )),
+                BsdResult::Ok(ret, ()) => Ok((ret as usize, out_addr.into())),
                 BsdResult::Err(errno) => ResultCode::new_err(nx::result::pack_value(
                     rc::RESULT_MODULE,
                     1000 + errno.cast_unsigned(),
@@ -1108,7 +1374,7 @@ pub mod net {
         /// Successive calls return the same data. This is accomplished by passing `MSG_PEEK` as a flag to the underlying `recvfrom` system call.
         ///
         /// Do not use this function to implement busy waiting, instead use [`poll`][`nx::socket::net::poll`] to synchronize IO events on one or more sockets.
-        pub fn peek_from(&self, buffer: &mut [u8]) -> Result<(usize, Ipv4Addr, u16)> {
+        pub fn peek_from(&self, buffer: &mut [u8]) -> Result<(usize, SocketAddr)> {
             let socket_server_handle = BSD_SERVICE.read();
 
             let socket_server = socket_server_handle.as_ref().unwrap();
@@ -1120,11 +1386,7 @@ pub mod net {
                 Buffer::from_mut_array(buffer),
                 Buffer::from_mut_var(&mut out_addr),
             )? {
-                BsdResult::Ok(ret, ()) => Ok((
-                    ret as usize,
-                    Ipv4Addr::from_bits(u32::from_be_bytes(out_addr.addr)),
-                    u16::from_be(out_addr.port),
-                )),
+                BsdResult::Ok(ret, ()) => Ok((ret as usize, out_addr.into())),
                 BsdResult::Err(errno) => ResultCode::new_err(nx::result::pack_value(
                     rc::RESULT_MODULE,
                     1000 + errno.cast_unsigned(),
@@ -1139,7 +1401,7 @@ pub mod net {
         pub fn recv_from_non_blocking(
             &self,
             buffer: &mut [u8],
-        ) -> Result<Option<(usize, Ipv4Addr, u16)>> {
+        ) -> Result<Option<(usize, SocketAddr)>> {
             let socket_server_handle = BSD_SERVICE.read();
 
             let socket_server = socket_server_handle.as_ref().unwrap();
@@ -1147,7 +1409,7 @@ pub mod net {
             let mut out_addr: SocketAddrRepr = Default::default();
             match socket_server.service.recv_from(self.0, ReadFlags::DontWait(), Buffer::from_mut_array(buffer), Buffer::from_mut_var(&mut out_addr))? {
                 BsdResult::Ok(ret, ()) => {
-                    Ok(Some((ret as usize, Ipv4Addr::from_bits(u32::from_be_bytes(out_addr.addr)), u16::from_be(out_addr.port))))
+                    Ok(Some((ret as usize, out_addr.into())))
                 },
                 BsdResult::Err(11) /* EAGAIN */ => {
                     Ok(None)
@@ -1164,8 +1426,13 @@ pub mod net {
         /// Sends data on the socket to the remote address provided (no call to `UdpSocket::connect` is necessary).
         /// Unlike `std::net::UdpSocket`, this method does not return length of the written data.
         /// All data is sent or an error is returned.
-        #[inline(always)]
-        pub fn send_to<A: Into<SocketAddrRepr>>(&self, data: &[u8], destination: A) -> Result<()> {
+        ///
+        /// Host names are resolved (see [`ToSocketAddrs`]), and the data is sent to the first resulting address.
+        pub fn send_to<A: ToSocketAddrs>(&self, data: &[u8], destination: A) -> Result<()> {
+            let destination = destination
+                .to_socket_addrs()?
+                .next()
+                .ok_or(rc::ResultHostNotFound::make())?;
             self.send_to_impl(data, destination.into())
         }
 
@@ -1186,6 +1453,85 @@ pub mod net {
                 )),
             }
         }
+        /// Receives multiple datagrams on the socket in a single request, returning the size and origin of each received one.
+        ///
+        /// Each datagram is received into the buffer at the same index, and excess bytes may be discarded like in [`UdpSocket::recv_from`].
+        /// This waits for the first datagram, then receives the ones already queued without waiting any further, thus fewer datagrams than buffers may be received.
+        ///
+        /// This is only available on 7.0.0+.
+        pub fn recv_many(&self, buffers: &mut [&mut [u8]]) -> Result<Vec<(usize, SocketAddr)>> {
+            let mut data = Vec::new();
+            for buffer in buffers.iter() {
+                super::msghdr::serialize_receive_message_header(buffer.len(), &mut data);
+            }
+
+            let received_count = {
+                let socket_server_handle = BSD_SERVICE.read();
+
+                let socket_server = socket_server_handle.as_ref().unwrap();
+
+                match socket_server.service.recv_mmesg(
+                    self.0,
+                    Buffer::from_mut_array(data.as_mut_slice()),
+                    buffers.len() as i32,
+                    ReadFlags::WaitForOne(),
+                    Duration::ZERO,
+                )? {
+                    BsdResult::Ok(ret, ()) => ret as usize,
+                    BsdResult::Err(errno) => {
+                        return ResultCode::new_err(nx::result::pack_value(
+                            rc::RESULT_MODULE,
+                            1000 + errno.cast_unsigned(),
+                        ));
+                    }
+                }
+            };
+
+            let headers = super::msghdr::deserialize_message_headers(&data, received_count)?;
+            Ok(headers
+                .iter()
+                .zip(buffers.iter_mut())
+                .map(|(header, buffer)| {
+                    let size = header.length.min(header.data.len()).min(buffer.len());
+                    buffer[..size].copy_from_slice(&header.data[..size]);
+                    let address = header
+                        .address
+                        .unwrap_or(SocketAddrV4::new(Ipv4Addr::UNSPECIFIED, 0));
+                    (size, SocketAddr::V4(address))
+                })
+                .collect())
+        }
+
+        /// Sends multiple datagrams on the socket in a single request, each one to its own destination, returning the number of sent ones.
+        ///
+        /// This is only available on 7.0.0+.
+        pub fn send_many(&self, messages: &[(&[u8], SocketAddr)]) -> Result<usize> {
+            let mut data = Vec::new();
+            for (message, destination) in messages {
+                let SocketAddr::V4(destination) = destination else {
+                    return rc::ResultInvalidSockAddr::make_err();
+                };
+                super::msghdr::serialize_send_message_header(*destination, message, &mut data);
+            }
+
+            let socket_server_handle = BSD_SERVICE.read();
+
+            let socket_server = socket_server_handle.as_ref().unwrap();
+
+            match socket_server.service.send_mmesg(
+                self.0,
+                Buffer::from_mut_array(data.as_mut_slice()),
+                messages.len() as i32,
+                SendFlags::None(),
+            )? {
+                BsdResult::Ok(ret, ()) => Ok(ret as usize),
+                BsdResult::Err(errno) => ResultCode::new_err(nx::result::pack_value(
+                    rc::RESULT_MODULE,
+                    1000 + errno.cast_unsigned(),
+                )),
+            }
+        }
+
         /// Gets the value of the SO_BROADCAST option for this socket.
         ///
         /// For more information about this option, see `UdpSocket::set_broadcast``.
@@ -1393,15 +1739,9 @@ pub mod net {
             self.0
         }
 
-        #[inline(always)]
-        fn connect<A: Into<Ipv4Addr>>(destination: A, port: u16) -> Result<Self> {
-            let destination = (destination.into(), port).into();
-            Self::connect_impl(destination)
-        }
-
         #[inline(always)]
         fn try_clone(&self) -> Result<Self> {
-            Ok(Self(self.0))
+            Ok(Self(duplicate_socket(self.0)?))
         }
     }
 
@@ -1424,4 +1764,33 @@ pub mod net {
             }
         }
     }
+
+    #[cfg(test)]
+    mod tests {
+        use super::traits::SocketCommon;
+        use super::*;
+
+        #[test]
+        fn drop_without_service() {
+            // The service might have never been initialized (or already finalized) when sockets are dropped
+            drop(TcpListener(3));
+            drop(TcpStream(4));
+            drop(UdpSocket(5));
+        }
+
+        #[test]
+        fn try_clone_without_service() {
+            let stream = TcpStream(4);
+            assert_eq!(
+                stream.try_clone().err(),
+                Some(rc::ResultNotInitialized::make())
+            );
+
+            let socket = UdpSocket(5);
+            assert_eq!(
+                socket.try_clone().err(),
+                Some(rc::ResultNotInitialized::make())
+            );
+        }
+    }
 }
@@ -0,0 +1,444 @@
+//! Serialization of the `addrinfo`/`hostent` resolver structures
+//!
+//! The `sfdnsres` service doesn't use the C layouts of these structures, but a serialized format where all integers are big-endian:
+//!
+//! - An `addrinfo` list is a sequence of entries (each one starting with [`ADDR_INFO_MAGIC`]) terminated by a zero `u32`
+//!
+//! - A `hostent` is the name, the alias count and aliases, the address type and length, and the address count and addresses
+//!
+//! Only IPv4 addresses are supported, like in the rest of the socket implementation
+
+use super::rc;
+use crate::result::*;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::net::{Ipv4Addr, SocketAddrV4};
+
+/// The magic value starting each serialized `addrinfo` entry
+pub const ADDR_INFO_MAGIC: u32 = 0xBEEFCAFE;
+
+/// The `AF_INET` address family value
+pub const AF_INET: i32 = 2;
+
+const SOCKADDR_IN_SIZE: usize = 0x10;
+
+/// Represents an `addrinfo` entry
+#[derive(Clone, PartialEq, Eq, Debug, Default)]
+pub struct AddrInfo {
+    pub flags: i32,
+    pub family: i32,
+    pub socket_type: i32,
+    pub protocol: i32,
+    /// The entry address (`None` if it's not present or it's not an IPv4 one)
+    pub address: Option<SocketAddrV4>,
+    pub canonical_name: Option<String>,
+}
+
+/// Represents a `hostent` structure
+#[derive(Clone, PartialEq, Eq, Debug, Default)]
+pub struct HostEnt {
+    pub name: String,
+    pub aliases: Vec<String>,
+    pub address_type: u16,
+    /// The host addresses (empty if they are not IPv4 ones)
+    pub addresses: Vec<Ipv4Addr>,
+}
+
+struct Reader<'a> {
+    data: &'a [u8],
+    offset: usize,
+}
+
+impl<'a> Reader<'a> {
+    const fn new(data: &'a [u8]) -> Self {
+        Self { data, offset: 0 }
+    }
+
+    fn read_bytes(&mut self, size: usize) -> Result<&'a [u8]> {
+        let bytes = self
+            .data
+            .get(self.offset..self.offset + size)
+            .ok_or(rc::ResultInvalidAddrInfo::make())?;
+        self.offset += size;
+        Ok(bytes)
+    }
+
+    fn read_u16(&mut self) -> Result<u16> {
+        let bytes = self.read_bytes(2)?;
+        Ok(u16::from_be_bytes([bytes[0], bytes[1]]))
+    }
+
+    fn read_u32(&mut self) -> Result<u32> {
+        let bytes = self.read_bytes(4)?;
+        Ok(u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+    }
+
+    fn read_string(&mut self) -> Result<String> {
+        let remaining = self.data.get(self.offset..).unwrap_or(&[]);
+        let len = remaining
+            .iter()
+            .position(|&byte| byte == 0)
+            .ok_or(rc::ResultInvalidAddrInfo::make())?;
+        self.offset += len + 1;
+        Ok(String::from_utf8_lossy(&remaining[..len]).into_owned())
+    }
+}
+
+/// Serializes an `addrinfo` entry
+///
+/// # Arguments
+///
+/// * `info`: The entry to serialize
+/// * `out_data`: The buffer to append the serialized entry to
+pub fn serialize_addr_info(info: &AddrInfo, out_data: &mut Vec<u8>) {
+    out_data.extend_from_slice(&ADDR_INFO_MAGIC.to_be_bytes());
+    out_data.extend_from_slice(&info.flags.to_be_bytes());
+    out_data.extend_from_slice(&info.family.to_be_bytes());
+    out_data.extend_from_slice(&info.socket_type.to_be_bytes());
+    out_data.extend_from_slice(&info.protocol.to_be_bytes());
+    match info.address {
+        Some(address) => {
+            out_data.extend_from_slice(&(SOCKADDR_IN_SIZE as u32).to_be_bytes());
+            // The address family is also byte-swapped as a 16-bit value, unlike in regular sockaddrs
+            out_data.extend_from_slice(&(AF_INET as u16).to_be_bytes());
+            out_data.extend_from_slice(&address.port().to_be_bytes());
+            out_data.extend_from_slice(&address.ip().octets());
+            out_data.extend_from_slice(&[0u8; 8]);
+        }
+        None => {
+            // An empty address is still followed by 4 zero bytes
+            out_data.extend_from_slice(&0u32.to_be_bytes());
+            out_data.extend_from_slice(&0u32.to_be_bytes());
+        }
+    }
+    if let Some(canonical_name) = &info.canonical_name {
+        out_data.extend_from_slice(canonical_name.as_bytes());
+    }
+    out_data.push(0);
+}
+
+/// Serializes an `addrinfo` list (like the hints sent to the resolver)
+///
+/// # Arguments
+///
+/// * `infos`: The entries to serialize
+pub fn serialize_addr_info_list(infos: &[AddrInfo]) -> Vec<u8> {
+    let mut data = Vec::new();
+    for info in infos {
+        serialize_addr_info(info, &mut data);
+    }
+    data.extend_from_slice(&0u32.to_be_bytes());
+    data
+}
+
+/// Deserializes an `addrinfo` list (like the results sent by the resolver)
+///
+/// The list ends at the zero terminator or at the end of the data, whatever comes first
+///
+/// # Arguments
+///
+/// * `data`: The serialized data
+pub fn deserialize_addr_info_list(data: &[u8]) -> Result<Vec<AddrInfo>> {
+    let mut reader = Reader::new(data);
+    let mut infos = Vec::new();
+    while (reader.offset + 4 <= data.len()) && (reader.read_u32()? == ADDR_INFO_MAGIC) {
+        let flags = reader.read_u32()? as i32;
+        let family = reader.read_u32()? as i32;
+        let socket_type = reader.read_u32()? as i32;
+        let protocol = reader.read_u32()? as i32;
+        let address_len = reader.read_u32()? as usize;
+
+        let address = match address_len {
+            0 => {
+                reader.read_bytes(4)?;
+                None
+            }
+            _ => {
+                let address_data = reader.read_bytes(address_len)?;
+                // The port and address are at the same offsets regardless of how the family is encoded
+                match (family, address_data.len() >= 8) {
+                    (AF_INET, true) => Some(SocketAddrV4::new(
+                        Ipv4Addr::new(
+                            address_data[4],
+                            address_data[5],
+                            address_data[6],
+                            address_data[7],
+                        ),
+                        u16::from_be_bytes([address_data[2], address_data[3]]),
+                    )),
+                    _ => None,
+                }
+            }
+        };
+
+        let canonical_name = reader.read_string()?;
+        infos.push(AddrInfo {
+            flags,
+            family,
+            socket_type,
+            protocol,
+            address,
+            canonical_name: match canonical_name.is_empty() {
+                true => None,
+                false => Some(canonical_name),
+            },
+        });
+    }
+    Ok(infos)
+}
+
+/// Serializes a `hostent` structure
+///
+/// # Arguments
+///
+/// * `host_ent`: The structure to serialize
+pub fn serialize_host_ent(host_ent: &HostEnt) -> Vec<u8> {
+    let mut data = Vec::new();
+    data.extend_from_slice(host_ent.name.as_bytes());
+    data.push(0);
+    data.extend_from_slice(&(host_ent.aliases.len() as u32).to_be_bytes());
+    for alias in &host_ent.aliases {
+        data.extend_from_slice(alias.as_bytes());
+        data.push(0);
+    }
+    data.extend_from_slice(&host_ent.address_type.to_be_bytes());
+    data.extend_from_slice(&4u16.to_be_bytes());
+    data.extend_from_slice(&(host_ent.addresses.len() as u32).to_be_bytes());
+    for address in &host_ent.addresses {
+        data.extend_from_slice(&address.octets());
+    }
+    data
+}
+
+/// Deserializes a `hostent` structure
+///
+/// # Arguments
+///
+/// * `data`: The serialized data
+pub fn deserialize_host_ent(data: &[u8]) -> Result<HostEnt> {
+    let mut reader = Reader::new(data);
+    let name = reader.read_string()?;
+    let alias_count = reader.read_u32()? as usize;
+    let mut aliases = Vec::new();
+    for _ in 0..alias_count {
+        aliases.push(reader.read_string()?);
+    }
+
+    let address_type = reader.read_u16()?;
+    let address_len = reader.read_u16()? as usize;
+    let address_count = reader.read_u32()? as usize;
+    let mut addresses = Vec::new();
+    for _ in 0..address_count {
+        let address = reader.read_bytes(address_len)?;
+        if (address_type as i32 == AF_INET) && (address_len == 4) {
+            addresses.push(Ipv4Addr::new(
+                address[0], address[1], address[2], address[3],
+            ));
+        }
+    }
+
+    Ok(HostEnt {
+        name,
+        aliases,
+        address_type,
+        addresses,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::string::ToString;
+
+    fn addr_infos() -> Vec<AddrInfo> {
+        vec![
+            AddrInfo {
+                flags: 2,
+                family: AF_INET,
+                socket_type: 1,
+                protocol: 6,
+                address: Some(SocketAddrV4::new(Ipv4Addr::new(192, 168, 1, 20), 8080)),
+                canonical_name: Some("switch.local".to_string()),
+            },
+            AddrInfo {
+                family: AF_INET,
+                socket_type: 2,
+                protocol: 17,
+                address: Some(SocketAddrV4::new(Ipv4Addr::LOCALHOST, 53)),
+                ..Default::default()
+            },
+            AddrInfo::default(),
+        ]
+    }
+
+    fn host_ent() -> HostEnt {
+        HostEnt {
+            name: "example.com".to_string(),
+            aliases: vec!["www.example.com".to_string(), "example.net".to_string()],
+            address_type: AF_INET as u16,
+            addresses: vec![Ipv4Addr::new(93, 184, 216, 34), Ipv4Addr::new(10, 0, 0, 1)],
+        }
+    }
+
+    #[test]
+    fn addr_info_layout() {
+        let mut data = Vec::new();
+        serialize_addr_info(&addr_infos()[0], &mut data);
+        assert_eq!(
+            data,
+            [
+                &[0xBE, 0xEF, 0xCA, 0xFE][..],
+                &[0, 0, 0, 2, 0, 0, 0, 2, 0, 0, 0, 1, 0, 0, 0, 6],
+                &[0, 0, 0, 0x10, 0, 2, 0x1F, 0x90, 192, 168, 1, 20],
+                &[0; 8],
+                b"switch.local\0",
+            ]
+            .concat()
+        );
+
+        data.clear();
+        serialize_addr_info(&AddrInfo::default(), &mut data);
+        assert_eq!(data.len(), 0x1D);
+        assert!(data[0x14..].iter().all(|&byte| byte == 0));
+    }
+
+    #[test]
+    fn addr_info_round_trip() {
+        let infos = addr_infos();
+        let data = serialize_addr_info_list(&infos);
+        assert_eq!(&data[data.len() - 4..], &[0; 4]);
+        assert_eq!(deserialize_addr_info_list(&data).unwrap(), infos);
+
+        // Anything after the terminator is ignored
+        let mut trailing_data = data.clone();
+        serialize_addr_info(&infos[0], &mut trailing_data);
+        assert_eq!(deserialize_addr_info_list(&trailing_data).unwrap(), infos);
+
+        assert_eq!(deserialize_addr_info_list(&[]).unwrap(), []);
+        assert_eq!(
+            deserialize_addr_info_list(&serialize_addr_info_list(&[])).unwrap(),
+            []
+        );
+    }
+
+    #[test]
+    fn addr_info_other_families() {
+        // An IPv6 entry, whose address is skipped
+        let mut data = Vec::new();
+        data.extend_from_slice(&ADDR_INFO_MAGIC.to_be_bytes());
+        for value in [0u32, 10, 1, 6, 28] {
+            data.extend_from_slice(&value.to_be_bytes());
+        }
+        data.extend_from_slice(&[0xAA; 28]);
+        data.extend_from_slice(b"v6.local\0");
+
+        let infos = deserialize_addr_info_list(&data).unwrap();
+        assert_eq!(
+            infos,
+            [AddrInfo {
+                family: 10,
+                socket_type: 1,
+                protocol: 6,
+                address: None,
+                canonical_name: Some("v6.local".to_string()),
+                ..Default::default()
+            }]
+        );
+
+        // Addresses too short to hold the port and address are skipped too
+        let mut data = serialize_addr_info_list(&addr_infos()[..1]);
+        data[0x17] = 4;
+        data.drain(0x1C..0x28);
+        assert_eq!(deserialize_addr_info_list(&data).unwrap()[0].address, None);
+    }
+
+    #[test]
+    fn addr_info_truncation() {
+        let infos = addr_infos();
+        let mut entry_ends = Vec::new();
+        let mut data = Vec::new();
+        for info in &infos {
+            serialize_addr_info(info, &mut data);
+            entry_ends.push(data.len());
+        }
+
+        for size in 0..data.len() {
+            // Data ending between entries is a shorter list, otherwise entries are incomplete
+            let complete_count = entry_ends.iter().filter(|&&end| end <= size).count();
+            let next_start = match complete_count {
+                0 => 0,
+                count => entry_ends[count - 1],
+            };
+            match deserialize_addr_info_list(&data[..size]) {
+                Ok(truncated_infos) => {
+                    assert!(size < next_start + 4);
+                    assert_eq!(truncated_infos, infos[..complete_count]);
+                }
+                Err(err) => {
+                    assert!(size >= next_start + 4);
+                    assert!(rc::ResultInvalidAddrInfo::matches(err));
+                }
+            }
+        }
+
+        // Huge address lengths
+        let mut data = serialize_addr_info_list(&infos[..1]);
+        data[0x14..0x18].copy_from_slice(&u32::MAX.to_be_bytes());
+        assert!(deserialize_addr_info_list(&data).is_err());
+    }
+
+    #[test]
+    fn host_ent_round_trip() {
+        let host_ent = host_ent();
+        let data = serialize_host_ent(&host_ent);
+        assert_eq!(&data[..12], b"example.com\0");
+        assert_eq!(&data[12..16], &[0, 0, 0, 2]);
+        assert_eq!(
+            &data[data.len() - 16..data.len() - 8],
+            &[0, 2, 0, 4, 0, 0, 0, 2]
+        );
+        assert_eq!(deserialize_host_ent(&data).unwrap(), host_ent);
+
+        let empty = HostEnt::default();
+        assert_eq!(
+            deserialize_host_ent(&serialize_host_ent(&empty)).unwrap(),
+            empty
+        );
+    }
+
+    #[test]
+    fn host_ent_other_families() {
+        // IPv6 addresses are skipped
+        let mut data = b"v6.local\0".to_vec();
+        data.extend_from_slice(&0u32.to_be_bytes());
+        data.extend_from_slice(&10u16.to_be_bytes());
+        data.extend_from_slice(&16u16.to_be_bytes());
+        data.extend_from_slice(&1u32.to_be_bytes());
+        data.extend_from_slice(&[0xAA; 16]);
+
+        let host_ent = deserialize_host_ent(&data).unwrap();
+        assert_eq!(host_ent.name, "v6.local");
+        assert_eq!(host_ent.address_type, 10);
+        assert!(host_ent.addresses.is_empty());
+    }
+
+    #[test]
+    fn host_ent_truncation() {
+        let data = serialize_host_ent(&host_ent());
+        for size in 0..data.len() {
+            assert!(
+                deserialize_host_ent(&data[..size]).is_err_and(rc::ResultInvalidAddrInfo::matches)
+            );
+        }
+
+        // Counts bigger than the data
+        let mut data = serialize_host_ent(&host_ent());
+        let address_count_offset = data.len() - 12;
+        data[address_count_offset..address_count_offset + 4]
+            .copy_from_slice(&u32::MAX.to_be_bytes());
+        assert!(deserialize_host_ent(&data).is_err());
+        let mut data = serialize_host_ent(&host_ent());
+        data[12..16].copy_from_slice(&u32::MAX.to_be_bytes());
+        assert!(deserialize_host_ent(&data).is_err());
+    }
+}
@@ -0,0 +1,158 @@
+//! Serialization of the `msghdr` structures used by batched datagram transfers (`recv_mmesg`/`send_mmesg`)
+//!
+//! The bsd service doesn't use the C layouts of these structures (which contain pointers), but a serialized format where all integers are little-endian. Each message header is, in order:
+//!
+//! - The name (address) size as a `u32`, followed by the name itself (a regular `sockaddr`)
+//!
+//! - The I/O vector count as a `u32`, followed by the size (as a `u64`) and data of each vector
+//!
+//! - The control data size as a `u32`, followed by the control data itself
+//!
+//! - The message flags and the transferred length, both as `u32`s
+//!
+//! Message headers are concatenated, and the service writes them back in the same format once the transfer is done (with the received names, data and lengths)
+//!
+//! Only IPv4 addresses and a single I/O vector per message are supported, like in the rest of the socket implementation
+
+use super::addrinfo::AF_INET;
+use super::rc;
+use crate::result::*;
+use alloc::vec::Vec;
+use core::net::{Ipv4Addr, SocketAddrV4};
+
+const SOCKADDR_IN_SIZE: usize = 0x10;
+
+/// Represents a (deserialized) message header
+#[derive(Clone, PartialEq, Eq, Debug, Default)]
+pub struct MessageHeader<'a> {
+    /// The message address (`None` if it's not present or it's not an IPv4 one)
+    pub address: Option<SocketAddrV4>,
+    /// The data of the first I/O vector (the only one that gets serialized)
+    pub data: &'a [u8],
+    pub flags: u32,
+    /// The transferred size, which may be larger than the data for truncated datagrams
+    pub length: usize,
+}
+
+struct Reader<'a> {
+    data: &'a [u8],
+    offset: usize,
+}
+
+impl<'a> Reader<'a> {
+    const fn new(data: &'a [u8]) -> Self {
+        Self { data, offset: 0 }
+    }
+
+    fn read_bytes(&mut self, size: usize) -> Result<&'a [u8]> {
+        let bytes = self
+            .data
+            .get(self.offset..self.offset.saturating_add(size))
+            .ok_or(rc::ResultInvalidMessageHeader::make())?;
+        self.offset += size;
+        Ok(bytes)
+    }
+
+    fn read_u32(&mut self) -> Result<u32> {
+        let bytes = self.read_bytes(4)?;
+        Ok(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+    }
+
+    fn read_u64(&mut self) -> Result<u64> {
+        let bytes = self.read_bytes(8)?;
+        let mut value = [0u8; 8];
+        value.copy_from_slice(bytes);
+        Ok(u64::from_le_bytes(value))
+    }
+}
+
+fn serialize_message_header(
+    name: &[u8; SOCKADDR_IN_SIZE],
+    data_size: usize,
+    data: Option<&[u8]>,
+    out_data: &mut Vec<u8>,
+) {
+    out_data.extend_from_slice(&(SOCKADDR_IN_SIZE as u32).to_le_bytes());
+    out_data.extend_from_slice(name);
+    out_data.extend_from_slice(&1u32.to_le_bytes());
+    out_data.extend_from_slice(&(data_size as u64).to_le_bytes());
+    match data {
+        Some(data) => out_data.extend_from_slice(data),
+        None => out_data.resize(out_data.len() + data_size, 0),
+    }
+    // No control data, flags or length
+    out_data.extend_from_slice(&0u32.to_le_bytes());
+    out_data.extend_from_slice(&0u32.to_le_bytes());
+    out_data.extend_from_slice(&0u32.to_le_bytes());
+}
+
+/// Serializes the header of a message to send
+///
+/// # Arguments
+///
+/// * `address`: The destination address
+/// * `data`: The data to send
+/// * `out_data`: The buffer to append the serialized header to
+pub fn serialize_send_message_header(address: SocketAddrV4, data: &[u8], out_data: &mut Vec<u8>) {
+    let mut name = [0u8; SOCKADDR_IN_SIZE];
+    name[0] = SOCKADDR_IN_SIZE as u8;
+    name[1] = AF_INET as u8;
+    name[2..4].copy_from_slice(&address.port().to_be_bytes());
+    name[4..8].copy_from_slice(&address.ip().octets());
+    serialize_message_header(&name, data.len(), Some(data), out_data);
+}
+
+/// Serializes the header of a message to receive, reserving (zeroed) space for its address and data
+///
+/// # Arguments
+///
+/// * `data_size`: The maximum size of the data to receive
+/// * `out_data`: The buffer to append the serialized header to
+pub fn serialize_receive_message_header(data_size: usize, out_data: &mut Vec<u8>) {
+    serialize_message_header(&[0u8; SOCKADDR_IN_SIZE], data_size, None, out_data);
+}
+
+/// Deserializes consecutive message headers (like the ones written back by the service)
+///
+/// # Arguments
+///
+/// * `data`: The serialized data
+/// * `count`: The number of message headers to deserialize
+pub fn deserialize_message_headers(data: &[u8], count: usize) -> Result<Vec<MessageHeader<'_>>> {
+    let mut reader = Reader::new(data);
+    let mut headers = Vec::with_capacity(count);
+    for _ in 0..count {
+        let name_len = reader.read_u32()? as usize;
+        let name = reader.read_bytes(name_len)?;
+        let address = match (name.len() >= 8) && (name[1] as i32 == AF_INET) {
+            true => Some(SocketAddrV4::new(
+                Ipv4Addr::new(name[4], name[5], name[6], name[7]),
+                u16::from_be_bytes([name[2], name[3]]),
+            )),
+            false => None,
+        };
+
+        let vector_count = reader.read_u32()? as usize;
+        let mut message_data: &[u8] = &[];
+        for i in 0..vector_count {
+            let vector_len = reader.read_u64()? as usize;
+            let vector_data = reader.read_bytes(vector_len)?;
+            if i == 0 {
+                message_data = vector_data;
+            }
+        }
+
+        let control_len = reader.read_u32()? as usize;
+        reader.read_bytes(control_len)?;
+
+        let flags = reader.read_u32()?;
+        let length = reader.read_u32()? as usize;
+        headers.push(MessageHeader {
+            address,
+            data: message_data,
+            flags,
+            length,
+        });
+    }
+    Ok(headers)
+}
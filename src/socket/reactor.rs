@@ -0,0 +1,388 @@
+//! Readiness-based event loop for non-blocking sockets
+//!
+//! A [`Reactor`] tracks registered sockets by [`Token`], each one with the [`Interest`] events it waits for. Every [`Reactor::turn`] waits for all of them with a single [`poll`][`super::net::poll`] call, and dispatches the readiness events to their handlers, which can either be:
+//!
+//! - Callbacks, which own their socket and are called with the reactor (so that they can register or deregister other sockets)
+//!
+//! - [`Waker`]s, which are woken when a task waiting on the socket (see [`Reactor::poll_readiness`]) can make progress, in order to drive `async` code
+//!
+//! Readiness is level-triggered: sockets are reported again on the next turn until they are no longer ready, thus handlers should read/write until they get `EAGAIN`
+//!
+//! ```no_run
+//! use nx::socket::net::traits::SocketCommon;
+//! use nx::socket::net::{TcpListener, TcpStream};
+//! use nx::socket::reactor::{Interest, Reactor};
+//!
+//! let listener = TcpListener::bind("0.0.0.0:8080")?;
+//! listener.set_nonblocking(true)?;
+//!
+//! let mut reactor = Reactor::new();
+//! reactor.register(listener, Interest::Read(), |listener: &mut TcpListener, reactor, _, _| {
+//!     while let Ok((stream, _)) = listener.accept() {
+//!         let _ = stream.set_nonblocking(true);
+//!         // Echo everything back
+//!         reactor.register(stream, Interest::Read(), |stream: &mut TcpStream, reactor, token, _| {
+//!             let mut buffer = [0u8; 0x400];
+//!             match stream.recv(&mut buffer) {
+//!                 Ok(0) | Err(_) => {
+//!                     let _ = reactor.deregister(token);
+//!                 }
+//!                 Ok(size) => {
+//!                     let _ = stream.send(&buffer[..size]);
+//!                 }
+//!             }
+//!         });
+//!     }
+//! });
+//! reactor.run()?;
+//! ```
+
+use super::PollFlags;
+use super::net::traits::Pollable;
+use super::rc;
+use crate::result::*;
+use crate::time::Timeout;
+use alloc::boxed::Box;
+use alloc::vec::Vec;
+use core::task::{Context, Poll, Waker};
+
+define_bit_set! {
+    /// Represents the events a registration is interested in, which are also the readiness events reported to it
+    Interest (u16) {
+        /// The socket can be read from (or accepted from, for listeners) without blocking
+        Read = PollFlags::PollIn().get(),
+        /// The socket can be written to without blocking
+        Write = PollFlags::PollOut().get(),
+        /// The remote side hung up (otherwise, hangups are reported as the other interested events, so that the next read/write reports it)
+        Hangup = PollFlags::PollHangup().get(),
+        /// An error occurred on the socket (always reported, regardless of the interest)
+        Error = PollFlags::PollError().get()
+    }
+}
+
+/// Identifies a registration in a [`Reactor`]
+///
+/// Tokens of deregistered sockets might be reused for later registrations
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+pub struct Token(pub usize);
+
+/// The callback of a registration, which owns the registered socket
+type Callback = dyn FnMut(&mut Reactor, Token, Interest);
+
+enum Handler {
+    Callback(Box<Callback>),
+    Waker {
+        waker: Option<Waker>,
+        readiness: Interest,
+    },
+}
+
+struct Registration {
+    fd: i32,
+    interest: Interest,
+    /// Only `None` while its callback is being called
+    handler: Option<Handler>,
+}
+
+impl Registration {
+    fn is_waiting(&self) -> bool {
+        if self.interest == Interest::default() {
+            return false;
+        }
+
+        match &self.handler {
+            Some(Handler::Callback(_)) => true,
+            // Nobody would be woken otherwise, and the socket would be reported every turn
+            Some(Handler::Waker { waker, .. }) => waker.is_some(),
+            None => false,
+        }
+    }
+
+    fn get_poll_flags(&self) -> PollFlags {
+        // Hangups and errors are always requested, since they might be the only events reported for a socket
+        PollFlags::from(self.interest.get())
+            | PollFlags::PollHangup()
+            | PollFlags::PollError()
+            | PollFlags::PollInvalid()
+    }
+
+    fn get_readiness(&self, events: PollFlags) -> Interest {
+        let mut readiness = Interest::default();
+        let hung_up = events.intersects(PollFlags::PollHangup());
+        let hangup_reported = hung_up && self.interest.contains(Interest::Hangup());
+        if hangup_reported {
+            readiness |= Interest::Hangup();
+        }
+
+        let read_events = PollFlags::PollIn() | PollFlags::PollPri() | PollFlags::PollRDNorm();
+        if self.interest.contains(Interest::Read())
+            && (events.intersects(read_events) || (hung_up && !hangup_reported))
+        {
+            readiness |= Interest::Read();
+        }
+        if self.interest.contains(Interest::Write())
+            && (events.intersects(PollFlags::PollOut()) || (hung_up && !hangup_reported))
+        {
+            readiness |= Interest::Write();
+        }
+        if events.intersects(PollFlags::PollError() | PollFlags::PollInvalid()) {
+            readiness |= Interest::Error();
+        }
+        readiness
+    }
+}
+
+struct PollTarget(i32);
+
+impl Pollable for PollTarget {
+    fn get_poll_fd(&self) -> i32 {
+        self.0
+    }
+}
+
+/// Represents an event loop dispatching socket readiness to handlers (see the [module-level documentation](self))
+#[derive(Default)]
+pub struct Reactor {
+    registrations: Vec<Option<Registration>>,
+}
+
+impl Reactor {
+    /// Creates a new, empty [`Reactor`]
+    #[inline]
+    pub const fn new() -> Self {
+        Self {
+            registrations: Vec::new(),
+        }
+    }
+
+    fn add_registration(&mut self, registration: Registration) -> Token {
+        match self.registrations.iter().position(Option::is_none) {
+            Some(index) => {
+                self.registrations[index] = Some(registration);
+                Token(index)
+            }
+            None => {
+                self.registrations.push(Some(registration));
+                Token(self.registrations.len() - 1)
+            }
+        }
+    }
+
+    fn get_registration_mut(&mut self, token: Token) -> Result<&mut Registration> {
+        self.registrations
+            .get_mut(token.0)
+            .and_then(Option::as_mut)
+            .ok_or(rc::ResultInvalidToken::make())
+    }
+
+    /// Registers a socket, which is owned by the reactor until it's deregistered, returning its token
+    ///
+    /// The socket should be non-blocking, since the callback is called from the reactor thread
+    ///
+    /// # Arguments
+    ///
+    /// * `source`: The socket to register
+    /// * `interest`: The events to wait for
+    /// * `callback`: The callback to call with the socket, the reactor, the socket's token and the ready events
+    pub fn register<P: Pollable + 'static>(
+        &mut self,
+        mut source: P,
+        interest: Interest,
+        mut callback: impl FnMut(&mut P, &mut Reactor, Token, Interest) + 'static,
+    ) -> Token {
+        let fd = source.get_poll_fd();
+        self.add_registration(Registration {
+            fd,
+            interest,
+            handler: Some(Handler::Callback(Box::new(
+                move |reactor, token, readiness| callback(&mut source, reactor, token, readiness),
+            ))),
+        })
+    }
+
+    /// Registers a socket whose readiness is awaited with [`Reactor::poll_readiness`], returning its token
+    ///
+    /// The socket isn't owned by the reactor, thus it must be deregistered before it's dropped
+    ///
+    /// # Arguments
+    ///
+    /// * `source`: The socket to register
+    /// * `interest`: The events to wait for
+    pub fn register_waker<P: Pollable>(&mut self, source: &P, interest: Interest) -> Token {
+        self.add_registration(Registration {
+            fd: source.get_poll_fd(),
+            interest,
+            handler: Some(Handler::Waker {
+                waker: None,
+                readiness: Interest::default(),
+            }),
+        })
+    }
+
+    /// Changes the events a registered socket is waiting for
+    ///
+    /// # Arguments
+    ///
+    /// * `token`: The socket token
+    /// * `interest`: The events to wait for (no events pauses the registration)
+    pub fn reregister(&mut self, token: Token, interest: Interest) -> Result<()> {
+        self.get_registration_mut(token)?.interest = interest;
+        Ok(())
+    }
+
+    /// Deregisters a socket, dropping it (along with its callback) if it's owned by the reactor
+    ///
+    /// This can be called from the socket's own callback
+    ///
+    /// # Arguments
+    ///
+    /// * `token`: The socket token
+    pub fn deregister(&mut self, token: Token) -> Result<()> {
+        let registration = self
+            .registrations
+            .get_mut(token.0)
+            .ok_or(rc::ResultInvalidToken::make())?;
+        let registration = registration.take().ok_or(rc::ResultInvalidToken::make())?;
+
+        if let Some(Handler::Waker {
+            waker: Some(waker), ..
+        }) = registration.handler
+        {
+            // The waiting task must find out that the socket is gone
+            waker.wake();
+        }
+
+        while let Some(None) = self.registrations.last() {
+            self.registrations.pop();
+        }
+        Ok(())
+    }
+
+    /// Gets the number of registered sockets
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.registrations.iter().flatten().count()
+    }
+
+    /// Gets whether there are no registered sockets
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Polls the readiness of a socket registered with [`Reactor::register_waker`], like a [`Future`][`core::future::Future`] would
+    ///
+    /// If no events were reported since the last call, the task's waker is stored and woken once any of them happen
+    ///
+    /// # Arguments
+    ///
+    /// * `token`: The socket token
+    /// * `cx`: The task context
+    pub fn poll_readiness(&mut self, token: Token, cx: &mut Context<'_>) -> Poll<Result<Interest>> {
+        let registration = match self.get_registration_mut(token) {
+            Ok(registration) => registration,
+            Err(rc) => return Poll::Ready(Err(rc)),
+        };
+
+        match &mut registration.handler {
+            Some(Handler::Waker { waker, readiness }) => {
+                if *readiness == Interest::default() {
+                    *waker = Some(cx.waker().clone());
+                    Poll::Pending
+                } else {
+                    *waker = None;
+                    Poll::Ready(Ok(core::mem::take(readiness)))
+                }
+            }
+            _ => Poll::Ready(rc::ResultInvalidToken::make_err()),
+        }
+    }
+
+    /// Waits for events on the registered sockets with a single [`poll`][`super::net::poll`] call, and dispatches them to their handlers, returning the number of sockets which got events
+    ///
+    /// This returns immediately if no sockets are waiting (registrations with no interest, or with no task waiting on them)
+    ///
+    /// The timeout (see [`Timeout`]) has millisecond precision, rounding up
+    ///
+    /// # Arguments
+    ///
+    /// * `timeout`: The wait timeout
+    pub fn turn(&mut self, timeout: impl Into<Timeout>) -> Result<usize> {
+        let mut tokens: Vec<Token> = Vec::new();
+        let mut pollers: Vec<(PollTarget, PollFlags)> = Vec::new();
+        for (index, registration) in self.registrations.iter().enumerate() {
+            if let Some(registration) = registration
+                && registration.is_waiting()
+            {
+                tokens.push(Token(index));
+                pollers.push((PollTarget(registration.fd), registration.get_poll_flags()));
+            }
+        }
+
+        if pollers.is_empty() {
+            return Ok(0);
+        }
+
+        let ready_events: Vec<(usize, PollFlags)> = super::net::poll(&pollers, timeout)?.collect();
+        let mut dispatched_count = 0;
+        for (index, events) in ready_events {
+            let token = tokens[index];
+            // Registrations might have changed in previous callbacks
+            let Ok(registration) = self.get_registration_mut(token) else {
+                continue;
+            };
+            if registration.fd != pollers[index].0.0 {
+                continue;
+            }
+
+            let readiness = registration.get_readiness(events);
+            if readiness == Interest::default() {
+                continue;
+            }
+            dispatched_count += 1;
+
+            match registration.handler.take() {
+                Some(Handler::Callback(mut callback)) => {
+                    callback(self, token, readiness);
+
+                    // Put it back, unless it was deregistered (and maybe replaced) from the callback
+                    if let Some(Some(registration)) = self.registrations.get_mut(token.0)
+                        && registration.handler.is_none()
+                    {
+                        registration.handler = Some(Handler::Callback(callback));
+                    }
+                }
+                Some(Handler::Waker {
+                    waker,
+                    readiness: prev_readiness,
+                }) => {
+                    if let Some(waker) = waker {
+                        waker.wake();
+                    }
+                    registration.handler = Some(Handler::Waker {
+                        waker: None,
+                        readiness: prev_readiness | readiness,
+                    });
+                }
+                None => {}
+            }
+        }
+        Ok(dispatched_count)
+    }
+
+    /// Gets whether any registered socket is waiting for events (see [`Reactor::turn`])
+    pub fn is_waiting(&self) -> bool {
+        self.registrations
+            .iter()
+            .flatten()
+            .any(Registration::is_waiting)
+    }
+
+    /// Keeps dispatching events (see [`Reactor::turn`]) while any registered socket is waiting for them
+    pub fn run(&mut self) -> Result<()> {
+        while self.is_waiting() {
+            self.turn(Timeout::Infinite)?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,236 @@
+//! Host name resolution (DNS) support, via the `sfdnsres` service
+
+use super::addrinfo::{self, AddrInfo, HostEnt};
+use super::rc;
+use crate::ipc::sf;
+use crate::result::*;
+use crate::service;
+use crate::service::sfdnsres::{IResolverClient, ResolverService};
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::net::{Ipv4Addr, SocketAddr, SocketAddrV4};
+use core::str::FromStr;
+
+use super::SocketAddrRepr;
+
+/// The size of the buffer where the resolver writes the serialized results
+pub const RESPONSE_BUFFER_SIZE: usize = 0x1000;
+
+#[inline]
+fn make_c_string(s: &str) -> Vec<u8> {
+    let mut c_str = Vec::with_capacity(s.len() + 1);
+    c_str.extend_from_slice(s.as_bytes());
+    c_str.push(0);
+    c_str
+}
+
+#[inline]
+fn make_errno_result<T>(errno: i32) -> Result<T> {
+    // Same as the bsd errors
+    ResultCode::new_err(crate::result::pack_value(
+        rc::RESULT_MODULE,
+        1000 + errno.cast_unsigned(),
+    ))
+}
+
+/// Gets the `addrinfo` entries for a node (host name or address) and/or service (name or port)
+///
+/// # Arguments
+///
+/// * `node`: The node to resolve
+/// * `service`: The service to resolve
+/// * `hints`: The hints to filter results by (`family`, `socket_type`, `protocol` and `flags` are used)
+pub fn get_addr_info(
+    node: Option<&str>,
+    service: Option<&str>,
+    hints: Option<&AddrInfo>,
+) -> Result<Vec<AddrInfo>> {
+    let node = node.map(make_c_string).unwrap_or_default();
+    let service_name = service.map(make_c_string).unwrap_or_default();
+    let hints = match hints {
+        Some(hints) => addrinfo::serialize_addr_info_list(core::slice::from_ref(hints)),
+        None => Vec::new(),
+    };
+
+    let resolver = service::new_service_object::<ResolverService>()?;
+    let mut response = vec![0u8; RESPONSE_BUFFER_SIZE];
+    let (errno, ret, response_size) = resolver.get_addr_info_request(
+        true,
+        0,
+        sf::ProcessId::new(),
+        sf::Buffer::from_array(&node),
+        sf::Buffer::from_array(&service_name),
+        sf::Buffer::from_array(&hints),
+        sf::Buffer::from_mut_array(&mut response),
+    )?;
+    if ret != 0 {
+        return match errno {
+            0 => rc::ResultHostNotFound::make_err(),
+            errno => make_errno_result(errno),
+        };
+    }
+
+    let response_size = (response_size as usize).min(response.len());
+    addrinfo::deserialize_addr_info_list(&response[..response_size])
+}
+
+/// Gets the `hostent` information for a host name
+///
+/// # Arguments
+///
+/// * `name`: The host name
+pub fn get_host_by_name(name: &str) -> Result<HostEnt> {
+    let name = make_c_string(name);
+
+    let resolver = service::new_service_object::<ResolverService>()?;
+    let mut response = vec![0u8; RESPONSE_BUFFER_SIZE];
+    let (host_errno, errno, response_size) = resolver.get_host_by_name_request(
+        true,
+        0,
+        sf::ProcessId::new(),
+        sf::Buffer::from_array(&name),
+        sf::Buffer::from_mut_array(&mut response),
+    )?;
+    if host_errno != 0 {
+        return match errno {
+            0 => rc::ResultHostNotFound::make_err(),
+            errno => make_errno_result(errno),
+        };
+    }
+
+    let response_size = (response_size as usize).min(response.len());
+    addrinfo::deserialize_host_ent(&response[..response_size])
+}
+
+/// Resolves the IPv4 addresses of a host, returning them with the given port
+///
+/// Fails with [`rc::ResultHostNotFound`] if the host has no IPv4 addresses
+///
+/// # Arguments
+///
+/// * `host`: The host name or address
+/// * `port`: The port to use for the resulting addresses
+pub fn lookup_host(host: &str, port: u16) -> Result<Vec<SocketAddrV4>> {
+    if let Ok(address) = Ipv4Addr::from_str(host) {
+        return Ok(vec![SocketAddrV4::new(address, port)]);
+    }
+
+    let hints = AddrInfo {
+        family: addrinfo::AF_INET,
+        ..Default::default()
+    };
+    let mut addresses: Vec<SocketAddrV4> = Vec::new();
+    for info in get_addr_info(Some(host), None, Some(&hints))? {
+        // The same address is listed once per socket type
+        if let Some(address) = info.address
+            && !addresses.iter().any(|added| added.ip() == address.ip())
+        {
+            addresses.push(SocketAddrV4::new(*address.ip(), port));
+        }
+    }
+    result_return_if!(addresses.is_empty(), rc::ResultHostNotFound);
+    Ok(addresses)
+}
+
+/// Represents types which can be converted (and resolved) to one or more socket addresses, like [`std::net::ToSocketAddrs`](https://doc.rust-lang.org/std/net/trait.ToSocketAddrs.html)
+///
+/// Host names (in `"host:port"` strings or `(host, port)` tuples) are resolved with [`lookup_host`], while addresses are used as they are
+pub trait ToSocketAddrs {
+    /// The iterator over the resulting addresses
+    type Iter: Iterator<Item = SocketAddrV4>;
+
+    /// Converts this value to the addresses it represents, resolving host names if needed
+    fn to_socket_addrs(&self) -> Result<Self::Iter>;
+}
+
+impl ToSocketAddrs for SocketAddrV4 {
+    type Iter = core::option::IntoIter<SocketAddrV4>;
+
+    fn to_socket_addrs(&self) -> Result<Self::Iter> {
+        Ok(Some(*self).into_iter())
+    }
+}
+
+impl ToSocketAddrs for SocketAddr {
+    type Iter = core::option::IntoIter<SocketAddrV4>;
+
+    fn to_socket_addrs(&self) -> Result<Self::Iter> {
+        match self {
+            SocketAddr::V4(address) => address.to_socket_addrs(),
+            // IPv6 is not supported by the bsd service
+            SocketAddr::V6(_) => rc::ResultInvalidSockAddr::make_err(),
+        }
+    }
+}
+
+impl ToSocketAddrs for SocketAddrRepr {
+    type Iter = core::option::IntoIter<SocketAddrV4>;
+
+    fn to_socket_addrs(&self) -> Result<Self::Iter> {
+        Ok(Some(SocketAddrV4::from(*self)).into_iter())
+    }
+}
+
+impl ToSocketAddrs for (Ipv4Addr, u16) {
+    type Iter = core::option::IntoIter<SocketAddrV4>;
+
+    fn to_socket_addrs(&self) -> Result<Self::Iter> {
+        Ok(Some(SocketAddrV4::new(self.0, self.1)).into_iter())
+    }
+}
+
+impl ToSocketAddrs for (&str, u16) {
+    type Iter = alloc::vec::IntoIter<SocketAddrV4>;
+
+    fn to_socket_addrs(&self) -> Result<Self::Iter> {
+        Ok(lookup_host(self.0, self.1)?.into_iter())
+    }
+}
+
+impl ToSocketAddrs for (String, u16) {
+    type Iter = alloc::vec::IntoIter<SocketAddrV4>;
+
+    fn to_socket_addrs(&self) -> Result<Self::Iter> {
+        (self.0.as_str(), self.1).to_socket_addrs()
+    }
+}
+
+impl ToSocketAddrs for str {
+    type Iter = alloc::vec::IntoIter<SocketAddrV4>;
+
+    fn to_socket_addrs(&self) -> Result<Self::Iter> {
+        if let Ok(address) = SocketAddrV4::from_str(self) {
+            return Ok(vec![address].into_iter());
+        }
+
+        let (host, port) = self
+            .rsplit_once(':')
+            .ok_or(rc::ResultInvalidSocketString::make())?;
+        let port = u16::from_str(port).map_err(|_| rc::ResultInvalidSocketString::make())?;
+        (host, port).to_socket_addrs()
+    }
+}
+
+impl ToSocketAddrs for String {
+    type Iter = alloc::vec::IntoIter<SocketAddrV4>;
+
+    fn to_socket_addrs(&self) -> Result<Self::Iter> {
+        self.as_str().to_socket_addrs()
+    }
+}
+
+impl<'a> ToSocketAddrs for &'a [SocketAddrV4] {
+    type Iter = core::iter::Copied<core::slice::Iter<'a, SocketAddrV4>>;
+
+    fn to_socket_addrs(&self) -> Result<Self::Iter> {
+        Ok(self.iter().copied())
+    }
+}
+
+impl<T: ToSocketAddrs + ?Sized> ToSocketAddrs for &T {
+    type Iter = T::Iter;
+
+    fn to_socket_addrs(&self) -> Result<Self::Iter> {
+        (**self).to_socket_addrs()
+    }
+}
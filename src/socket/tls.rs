@@ -0,0 +1,230 @@
+//! TLS client support, via the `ssl` service
+//!
+//! The TLS implementation runs in the system: a [`TlsContext`] holds the trusted certificates and settings, and each [`TlsStream`] is a connection over an already connected [`TcpStream`]
+//!
+//! ```no_run
+//! use nx::socket::net::TcpStream;
+//! use nx::socket::tls::TlsStream;
+//!
+//! let tcp_stream = TcpStream::connect("example.com:443")?;
+//! let tls_stream = TlsStream::connect_default(tcp_stream, "example.com")?;
+//! tls_stream.send(b"GET / HTTP/1.1\r\nHost: example.com\r\nConnection: close\r\n\r\n")?;
+//! ```
+
+use super::net::TcpStream;
+use super::net::traits::SocketCommon;
+use crate::ipc::sf;
+use crate::result::*;
+use crate::service;
+use crate::time::Timeout;
+use alloc::sync::Arc;
+
+pub use crate::service::ssl::*;
+
+/// Represents a TLS context, which holds the certificates and settings shared by its connections
+pub struct TlsContext {
+    context: SslContext,
+}
+
+impl TlsContext {
+    /// Creates a new [`TlsContext`]
+    ///
+    /// Only the system trusted CAs are used to verify servers, unless more are imported
+    ///
+    /// # Arguments
+    ///
+    /// * `version`: The TLS versions to use
+    pub fn new(version: SslVersion) -> Result<Self> {
+        let ssl_srv = service::new_service_object::<SslService>()?;
+        let context = ssl_srv.create_context(version, sf::ProcessId::new())?;
+        Ok(Self { context })
+    }
+
+    /// Imports a trusted CA certificate (to verify servers with), returning its ID
+    ///
+    /// # Arguments
+    ///
+    /// * `cert`: The certificate data
+    /// * `format`: The certificate format
+    pub fn import_server_pki(&mut self, cert: &[u8], format: CertificateFormat) -> Result<u64> {
+        self.context
+            .import_server_pki(format, sf::Buffer::from_array(cert))
+    }
+
+    /// Imports a client certificate and key (to authenticate with servers), returning its ID
+    ///
+    /// # Arguments
+    ///
+    /// * `pkcs12`: The PKCS#12 data containing the certificate and key
+    /// * `password`: The password of the PKCS#12 data
+    pub fn import_client_pki(&mut self, pkcs12: &[u8], password: &str) -> Result<u64> {
+        self.context.import_client_pki(
+            sf::Buffer::from_array(pkcs12),
+            sf::Buffer::from_array(password.as_bytes()),
+        )
+    }
+
+    /// Registers a system client certificate, returning its ID
+    ///
+    /// # Arguments
+    ///
+    /// * `pki`: The certificate to register
+    pub fn register_internal_pki(&mut self, pki: InternalPki) -> Result<u64> {
+        self.context.register_internal_pki(pki)
+    }
+
+    /// Gets the underlying [`ISslContextClient`] object
+    #[inline]
+    pub fn get_context(&mut self) -> &mut SslContext {
+        &mut self.context
+    }
+}
+
+/// Represents a TLS connection over a [`TcpStream`], which provides the same data transfer API
+pub struct TlsStream {
+    connection: SslConnection,
+    _context: Arc<TlsContext>,
+    stream: TcpStream,
+}
+
+impl TlsStream {
+    /// Performs a TLS handshake over the given stream, verifying the server with the default checks (see [`VerifyOption::default_checks`])
+    ///
+    /// # Arguments
+    ///
+    /// * `context`: The context to create the connection from
+    /// * `stream`: The connected stream, which is owned by the resulting [`TlsStream`]
+    /// * `host_name`: The server host name, used for SNI and to verify its certificate
+    #[inline]
+    pub fn connect(context: Arc<TlsContext>, stream: TcpStream, host_name: &str) -> Result<Self> {
+        Self::connect_with_verify_option(context, stream, host_name, VerifyOption::default_checks())
+    }
+
+    /// Same as [`TlsStream::connect`], but with a new context (see [`TlsContext::new`]) negotiating the best TLS version
+    ///
+    /// # Arguments
+    ///
+    /// * `stream`: The connected stream, which is owned by the resulting [`TlsStream`]
+    /// * `host_name`: The server host name, used for SNI and to verify its certificate
+    #[inline]
+    pub fn connect_default(stream: TcpStream, host_name: &str) -> Result<Self> {
+        Self::connect(
+            Arc::new(TlsContext::new(SslVersion::Auto())?),
+            stream,
+            host_name,
+        )
+    }
+
+    /// Performs a TLS handshake over the given stream, verifying the server with the given checks
+    ///
+    /// # Arguments
+    ///
+    /// * `context`: The context to create the connection from
+    /// * `stream`: The connected stream, which is owned by the resulting [`TlsStream`]
+    /// * `host_name`: The server host name, used for SNI and to verify its certificate
+    /// * `verify_option`: The checks to verify the server with
+    pub fn connect_with_verify_option(
+        context: Arc<TlsContext>,
+        stream: TcpStream,
+        host_name: &str,
+        verify_option: VerifyOption,
+    ) -> Result<Self> {
+        let mut connection = context.context.create_connection()?;
+        // The stream keeps owning the socket, so that it's closed as usual
+        connection.set_option(true, ConnectionOption::DoNotCloseSocket)?;
+        // Otherwise, the connection would have taken ownership of the socket (indicated by returning -1)
+        let socket_fd = connection.set_socket_descriptor(stream.as_raw_fd())?;
+        result_return_if!(socket_fd == -1, super::rc::ResultSocketOwnershipMismatch);
+        connection.set_host_name(sf::Buffer::from_array(host_name.as_bytes()))?;
+        connection.set_verify_option(verify_option)?;
+        connection.set_io_mode(IoMode::Blocking)?;
+        connection.do_handshake()?;
+
+        Ok(Self {
+            connection,
+            _context: context,
+            stream,
+        })
+    }
+
+    /// Gets the underlying [`TcpStream`]
+    ///
+    /// Reading or writing it directly would corrupt the TLS connection
+    #[inline]
+    pub fn get_ref(&self) -> &TcpStream {
+        &self.stream
+    }
+
+    /// Gets the underlying [`ISslConnectionClient`] object
+    #[inline]
+    pub fn get_connection(&mut self) -> &mut SslConnection {
+        &mut self.connection
+    }
+
+    /// Reads (decrypted) data from the remote side into the provided buffer, returning the read size (`0` if the connection was closed)
+    ///
+    /// # Arguments
+    ///
+    /// * `data`: The buffer to read into
+    pub fn recv(&self, data: &mut [u8]) -> Result<usize> {
+        Ok(self.connection.read(sf::Buffer::from_mut_array(data))? as usize)
+    }
+
+    /// Same as [`TlsStream::recv`], but without removing the data from the connection
+    ///
+    /// # Arguments
+    ///
+    /// * `data`: The buffer to read into
+    pub fn peek(&self, data: &mut [u8]) -> Result<usize> {
+        Ok(self.connection.peek(sf::Buffer::from_mut_array(data))? as usize)
+    }
+
+    /// Sends data to the remote side, returning the sent size
+    ///
+    /// # Arguments
+    ///
+    /// * `data`: The data to send
+    pub fn send(&self, data: &[u8]) -> Result<u32> {
+        self.connection.write(sf::Buffer::from_array(data))
+    }
+
+    /// Sends all the given data to the remote side
+    ///
+    /// # Arguments
+    ///
+    /// * `data`: The data to send
+    pub fn send_all(&self, mut data: &[u8]) -> Result<()> {
+        while !data.is_empty() {
+            let sent_size = self.send(data)? as usize;
+            data = &data[sent_size.min(data.len())..];
+        }
+        Ok(())
+    }
+
+    /// Gets the size of the (decrypted) data which can be read without blocking
+    pub fn pending(&self) -> Result<usize> {
+        Ok(self.connection.pending()?.max(0) as usize)
+    }
+
+    /// Waits for any of the given events to be ready on the connection, returning the ready ones
+    ///
+    /// The timeout (see [`Timeout`]) has millisecond precision, rounding up
+    ///
+    /// # Arguments
+    ///
+    /// * `events`: The events to wait for
+    /// * `timeout`: The wait timeout
+    pub fn poll(&self, events: PollEvent, timeout: impl Into<Timeout>) -> Result<PollEvent> {
+        let timeout_ms = match timeout.into().get_remaining_millis() {
+            -1 => u32::MAX,
+            timeout_ms => timeout_ms as u32,
+        };
+        self.connection.poll(events, timeout_ms)
+    }
+}
+
+impl core::fmt::Write for TlsStream {
+    fn write_str(&mut self, s: &str) -> core::fmt::Result {
+        self.send_all(s.as_bytes()).map_err(|_| core::fmt::Error)
+    }
+}
@@ -1,6 +1,4 @@
-use core::arch::naked_asm as nasm;
-
-use crate::macros::util::maybe_cfi;
+use crate::macros::util::naked_asm as nasm;
 use crate::result::ResultCode;
 use crate::svc::{CreateProcessInfo, DebugThreadParam, SystemInfoParam};
 use crate::{arm, svc::PhysicalMemoryInfo};
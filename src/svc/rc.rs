@@ -3,12 +3,14 @@
 pub const RESULT_MODULE: u32 = 1;
 
 result_define_group!(RESULT_MODULE => {
+    StopProcessingException: 54,
     InvalidSize: 101,
     InvalidAddress: 102,
     InvalidCurrentMemory: 106,
     InvalidHandle: 114,
     TimedOut: 117,
     Cancelled: 118,
+    OutOfRange: 119,
     SessionClosed: 123,
     NotHandled: 124,
     Debug: 128
@@ -81,6 +81,11 @@ impl<T: ?Sized> Mutex<T> {
         MutexGuard { lock: self }
     }
 
+    /// Gets whether the Mutex is locked by the current thread, in which case locking it again would deadlock
+    pub fn is_locked_by_current_thread(&self) -> bool {
+        self.raw_lock.is_locked_by_current_thread()
+    }
+
     pub fn try_lock(&self) -> Option<MutexGuard<'_, T>> {
         if self.raw_lock.try_lock() {
             Some(MutexGuard { lock: self })
@@ -27,6 +27,12 @@ impl Mutex {
         (self.owner_thread_handle.load(Relaxed) & !WAIT_MASK) != svc::INVALID_HANDLE
     }
 
+    /// Gets whether the mutex is locked by the current thread (thus locking it again would deadlock)
+    #[inline]
+    pub fn is_locked_by_current_thread(&self) -> bool {
+        (self.owner_thread_handle.load(Relaxed) & !WAIT_MASK) == get_current_thread_handle()
+    }
+
     #[inline]
     pub fn try_lock(&self) -> bool {
         self.owner_thread_handle
@@ -10,6 +10,7 @@ use crate::diag::abort;
 use crate::diag::abort::AbortLevel;
 use crate::result::*;
 use crate::svc;
+use crate::time::{Duration, Instant, Timeout};
 use crate::util;
 use core::cell::UnsafeCell;
 use core::fmt;
@@ -815,7 +816,7 @@ impl<T> JoinInner<'_, T> {
             .unwrap()
     }
 
-    fn wait_exit(&self, timeout: Option<i64>) -> crate::result::Result<()> {
+    fn wait_exit(&self, timeout: Timeout) -> crate::result::Result<()> {
         self.native.join_timeout(timeout)
     }
 }
@@ -946,7 +947,7 @@ impl<T, const BLOCK_ON_DROP: bool> JoinHandle<T, BLOCK_ON_DROP> {
         self.0.join()
     }
 
-    /// Waits for the associated thread to finish, with a timeout (in nanoseconds)
+    /// Waits for the associated thread to finish, with a timeout (see [`Timeout`])
     ///
     /// This function will return immediately if the associated thread has already finished.
     ///
@@ -979,8 +980,8 @@ impl<T, const BLOCK_ON_DROP: bool> JoinHandle<T, BLOCK_ON_DROP> {
     /// }).unwrap();
     /// join_handle.join().expect("Couldn't join on the associated thread");
     /// ```
-    pub fn wait_exit(&self, timeout: Option<i64>) -> crate::result::Result<()> {
-        self.0.wait_exit(timeout)
+    pub fn wait_exit(&self, timeout: impl Into<Timeout>) -> crate::result::Result<()> {
+        self.0.wait_exit(timeout.into())
     }
 
     /// Checks if the associated thread has finished running its main function.
@@ -1001,7 +1002,7 @@ impl<T, const BLOCK_ON_DROP: bool> JoinHandle<T, BLOCK_ON_DROP> {
 impl<T, const BLOCK_ON_DROP: bool> Drop for JoinHandle<T, BLOCK_ON_DROP> {
     fn drop(&mut self) {
         if BLOCK_ON_DROP {
-            let _ = self.wait_exit(None);
+            let _ = self.wait_exit(Timeout::Infinite);
         }
     }
 }
@@ -1117,7 +1118,9 @@ pub mod imp {
         ThreadArgs, ThreadId, ThreadName, ThreadPriority, ThreadStartCore, ThreadState,
         thread_wrapper,
     };
-    use crate::{mem::alloc::PAGE_ALIGNMENT, svc, util::ArrayString, wait::wait_handles};
+    use crate::{
+        mem::alloc::PAGE_ALIGNMENT, svc, time::Timeout, util::ArrayString, wait::wait_handles,
+    };
 
     pub type Thread = StratosphereThreadType;
 
@@ -1187,11 +1190,11 @@ pub mod imp {
         pub(crate) const MAGIC: u16 = 0xF5A5;
 
         pub(crate) fn join(&self) -> crate::result::Result<()> {
-            wait_handles(&[self.__nx_thread.handle], -1).map(|_| ())
+            wait_handles(&[self.__nx_thread.handle], Timeout::Infinite).map(|_| ())
         }
 
-        pub(crate) fn join_timeout(&self, timeout: Option<i64>) -> crate::result::Result<()> {
-            wait_handles(&[self.__nx_thread.handle], timeout.unwrap_or(-1)).map(|_| ())
+        pub(crate) fn join_timeout(&self, timeout: Timeout) -> crate::result::Result<()> {
+            wait_handles(&[self.__nx_thread.handle], timeout).map(|_| ())
         }
 
         pub fn name(&self) -> ThreadName {
@@ -1411,7 +1414,7 @@ const_assert!(core::mem::align_of::<ThreadLocalRegion>() >= 4); // for some assu
 pub fn get_thread_local_region() -> *mut ThreadLocalRegion {
     #[unsafe(naked)]
     unsafe extern "C" fn __nx_thread_get_thread_local_region() -> *mut ThreadLocalRegion {
-        core::arch::naked_asm!(
+        crate::macros::util::naked_asm!(
             maybe_cfi!(".cfi_startproc"),
             "mrs x0, tpidrro_el0",
             "ret",
@@ -1468,16 +1471,31 @@ pub fn get_current_thread_name() -> String {
     }
 }
 
-/// Sleeps for the given timeout
+/// Sleeps for (at least) the given duration
 ///
-/// Essentially a wrapper for [`svc::sleep_thread`]
+/// Essentially a wrapper for [`svc::sleep_thread`], note that a zero duration yields without core migration (see [`yield`][`r#yield`] for other yielding types)
 ///
 /// # Arguments
 ///
-/// * `timeout`: Sleep timeout in nanoseconds, where `0` can be used for yielding without core migration, `-1` for yielding with core migration and `-2` for yielding to any other thread
+/// * `duration`: Sleep duration
 #[inline]
-pub fn sleep(timeout: i64) -> crate::result::Result<()> {
-    svc::sleep_thread(timeout)
+pub fn sleep(duration: Duration) -> crate::result::Result<()> {
+    svc::sleep_thread(duration.as_nanos().min(i64::MAX as u128) as i64)
+}
+
+/// Sleeps until (at least) the given deadline
+///
+/// This returns immediately if the deadline has already passed
+///
+/// # Arguments
+///
+/// * `deadline`: Sleep deadline
+pub fn sleep_until(deadline: Instant) -> crate::result::Result<()> {
+    let now = Instant::now();
+    if deadline > now {
+        sleep(deadline.duration_since(now))?;
+    }
+    Ok(())
 }
 
 /// Represents the thread yielding types for cooperative multitasking
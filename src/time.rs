@@ -0,0 +1,19 @@
+//! Time support (monotonic instants, clocks, time zones and calendar conversion)
+
+pub use core::time::Duration;
+
+pub use crate::ipc::sf::time::{
+    CalendarAdditionalInfo, CalendarTime, LocalTime, LocationName, PosixTime,
+};
+
+pub mod rc;
+
+mod instant;
+pub use instant::*;
+
+pub mod tz;
+
+#[cfg(feature = "time")]
+mod clock;
+#[cfg(feature = "time")]
+pub use clock::*;
@@ -0,0 +1,131 @@
+//! System clock and time zone service support
+
+use crate::ipc::sf::Buffer;
+use crate::result::*;
+use crate::service;
+use crate::sync::{Mutex, MutexGuard};
+use alloc::boxed::Box;
+
+pub use crate::service::time::*;
+
+static G_STATIC_SRV: Mutex<Option<StaticService>> = Mutex::new(None);
+
+/// Represents the system clocks which can be accessed
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum ClockType {
+    /// The standard user system clock, as set by the user in settings
+    User,
+    /// The standard network system clock, synchronized via the network
+    Network,
+    /// The standard local system clock
+    Local,
+}
+
+/// Initializes the time service object
+pub fn initialize() -> Result<()> {
+    let mut guard = G_STATIC_SRV.lock();
+    if guard.is_none() {
+        *guard = Some(service::new_service_object::<StaticService>()?);
+    }
+    Ok(())
+}
+
+/// Gets access to the global [`IStaticClient`] shared object instance
+pub fn get_static_service<'a>() -> MutexGuard<'a, Option<StaticService>> {
+    G_STATIC_SRV.lock()
+}
+
+pub(crate) fn finalize() {
+    *G_STATIC_SRV.lock() = None;
+}
+
+fn with_static_service<T>(f: impl FnOnce(&StaticService) -> Result<T>) -> Result<T> {
+    let guard = G_STATIC_SRV.lock();
+    let static_service = guard
+        .as_ref()
+        .ok_or(crate::rc::ResultNotInitialized::make())?;
+    f(static_service)
+}
+
+/// Opens a [`SystemClock`] of the given type
+///
+/// # Arguments
+///
+/// * `clock_type`: The clock type
+pub fn open_system_clock(clock_type: ClockType) -> Result<SystemClock> {
+    with_static_service(|static_service| match clock_type {
+        ClockType::User => static_service.get_standard_user_system_clock(),
+        ClockType::Network => static_service.get_standard_network_system_clock(),
+        ClockType::Local => static_service.get_standard_local_system_clock(),
+    })
+}
+
+/// Opens the standard [`SteadyClock`]
+pub fn open_steady_clock() -> Result<SteadyClock> {
+    with_static_service(|static_service| static_service.get_standard_steady_clock())
+}
+
+/// Opens a [`TimeZoneService`]
+pub fn open_time_zone_service() -> Result<TimeZoneService> {
+    with_static_service(|static_service| static_service.get_time_zone_service())
+}
+
+/// Gets the current POSIX time of the given clock
+///
+/// # Arguments
+///
+/// * `clock_type`: The clock type
+pub fn get_current_time(clock_type: ClockType) -> Result<PosixTime> {
+    open_system_clock(clock_type)?.get_current_time()
+}
+
+/// Gets the current time point of the standard steady clock
+pub fn get_current_time_point() -> Result<SteadyClockTimePoint> {
+    open_steady_clock()?.get_current_time_point()
+}
+
+/// Gets the device time zone location name (like `Europe/Madrid`)
+pub fn get_device_location_name() -> Result<LocationName> {
+    open_time_zone_service()?.get_device_location_name()
+}
+
+/// Converts a POSIX time to local calendar time, using the device time zone
+///
+/// # Arguments
+///
+/// * `time`: The POSIX time
+pub fn to_local_time(time: PosixTime) -> Result<LocalTime> {
+    let (calendar_time, additional_info) =
+        open_time_zone_service()?.to_calendar_time_with_my_rule(time)?;
+    Ok(LocalTime::new(calendar_time, additional_info))
+}
+
+/// Converts a POSIX time to local calendar time, using the system's rule of the given time zone
+///
+/// # Arguments
+///
+/// * `time`: The POSIX time
+/// * `location_name`: The time zone location name (like `Europe/Madrid`)
+pub fn to_local_time_with_location(
+    time: PosixTime,
+    location_name: LocationName,
+) -> Result<LocalTime> {
+    let time_zone_service = open_time_zone_service()?;
+
+    // The rule is too big to be placed in the stack (and all-zeros is a valid empty rule)
+    let mut rule = unsafe { Box::<TimeZoneRule>::new_zeroed().assume_init() };
+    time_zone_service.load_time_zone_rule(location_name, Buffer::from_mut_var(rule.as_mut()))?;
+
+    let (calendar_time, additional_info) =
+        time_zone_service.to_calendar_time(time, Buffer::from_var(rule.as_ref()))?;
+    Ok(LocalTime::new(calendar_time, additional_info))
+}
+
+/// Gets the current local calendar time of the given clock, using the device time zone
+///
+/// # Arguments
+///
+/// * `clock_type`: The clock type
+pub fn get_current_local_time(clock_type: ClockType) -> Result<LocalTime> {
+    to_local_time(get_current_time(clock_type)?)
+}
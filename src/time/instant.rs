@@ -0,0 +1,243 @@
+use crate::arm;
+use core::ops::{Add, AddAssign, Sub, SubAssign};
+use core::time::Duration;
+
+/// Converts a [`Duration`] to system ticks, saturating on overflow
+///
+/// # Arguments
+///
+/// * `duration`: The duration to convert
+#[inline]
+pub const fn duration_to_ticks(duration: Duration) -> u64 {
+    let ticks = (duration.as_nanos() * 12) / 625;
+    if ticks > u64::MAX as u128 {
+        u64::MAX
+    } else {
+        ticks as u64
+    }
+}
+
+/// Converts system ticks to a [`Duration`]
+///
+/// # Arguments
+///
+/// * `ticks`: The ticks to convert
+#[inline]
+pub const fn ticks_to_duration(ticks: u64) -> Duration {
+    Duration::from_nanos(arm::ticks_to_nanoseconds(ticks))
+}
+
+/// Represents a measurement of the monotonic system tick (like `std::time::Instant`)
+///
+/// Instants are opaque and only useful to compare them or to measure durations between them
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+pub struct Instant {
+    tick: u64,
+}
+
+impl Instant {
+    /// Gets the [`Instant`] corresponding to "now"
+    #[inline]
+    pub fn now() -> Self {
+        Self {
+            tick: arm::get_system_tick(),
+        }
+    }
+
+    /// Creates an [`Instant`] from a raw system tick value
+    ///
+    /// # Arguments
+    ///
+    /// * `tick`: The system tick value
+    #[inline]
+    pub const fn from_ticks(tick: u64) -> Self {
+        Self { tick }
+    }
+
+    /// Gets the raw system tick value of this [`Instant`]
+    #[inline]
+    pub const fn get_ticks(&self) -> u64 {
+        self.tick
+    }
+
+    /// Gets the amount of time elapsed from another [`Instant`] to this one, or [`None`] if that one is later than this one
+    ///
+    /// # Arguments
+    ///
+    /// * `earlier`: The earlier instant
+    #[inline]
+    pub const fn checked_duration_since(&self, earlier: Instant) -> Option<Duration> {
+        match self.tick.checked_sub(earlier.tick) {
+            Some(ticks) => Some(ticks_to_duration(ticks)),
+            None => None,
+        }
+    }
+
+    /// Gets the amount of time elapsed from another [`Instant`] to this one, or zero if that one is later than this one
+    ///
+    /// # Arguments
+    ///
+    /// * `earlier`: The earlier instant
+    #[inline]
+    pub const fn saturating_duration_since(&self, earlier: Instant) -> Duration {
+        ticks_to_duration(self.tick.saturating_sub(earlier.tick))
+    }
+
+    /// Gets the amount of time elapsed from another [`Instant`] to this one, or zero if that one is later than this one
+    ///
+    /// # Arguments
+    ///
+    /// * `earlier`: The earlier instant
+    #[inline]
+    pub const fn duration_since(&self, earlier: Instant) -> Duration {
+        self.saturating_duration_since(earlier)
+    }
+
+    /// Gets the amount of time elapsed since this [`Instant`]
+    #[inline]
+    pub fn elapsed(&self) -> Duration {
+        Self::now().saturating_duration_since(*self)
+    }
+
+    /// Returns `self + duration` if it can be represented, [`None`] otherwise
+    ///
+    /// # Arguments
+    ///
+    /// * `duration`: The duration to add
+    #[inline]
+    pub const fn checked_add(&self, duration: Duration) -> Option<Instant> {
+        match self.tick.checked_add(duration_to_ticks(duration)) {
+            Some(tick) => Some(Self { tick }),
+            None => None,
+        }
+    }
+
+    /// Returns `self - duration` if it can be represented, [`None`] otherwise
+    ///
+    /// # Arguments
+    ///
+    /// * `duration`: The duration to subtract
+    #[inline]
+    pub const fn checked_sub(&self, duration: Duration) -> Option<Instant> {
+        match self.tick.checked_sub(duration_to_ticks(duration)) {
+            Some(tick) => Some(Self { tick }),
+            None => None,
+        }
+    }
+}
+
+impl Add<Duration> for Instant {
+    type Output = Instant;
+
+    /// # Panics
+    ///
+    /// This panics if the resulting instant overflows, see [`Instant::checked_add`] for a non-panicking version
+    fn add(self, duration: Duration) -> Instant {
+        self.checked_add(duration)
+            .expect("Overflow when adding a duration to an instant")
+    }
+}
+
+impl AddAssign<Duration> for Instant {
+    fn add_assign(&mut self, duration: Duration) {
+        *self = *self + duration;
+    }
+}
+
+impl Sub<Duration> for Instant {
+    type Output = Instant;
+
+    /// # Panics
+    ///
+    /// This panics if the resulting instant underflows, see [`Instant::checked_sub`] for a non-panicking version
+    fn sub(self, duration: Duration) -> Instant {
+        self.checked_sub(duration)
+            .expect("Overflow when subtracting a duration from an instant")
+    }
+}
+
+impl SubAssign<Duration> for Instant {
+    fn sub_assign(&mut self, duration: Duration) {
+        *self = *self - duration;
+    }
+}
+
+impl Sub<Instant> for Instant {
+    type Output = Duration;
+
+    /// Same as [`Instant::duration_since`]
+    fn sub(self, earlier: Instant) -> Duration {
+        self.duration_since(earlier)
+    }
+}
+
+/// Represents a wait timeout, either relative to when the wait starts or as an absolute deadline
+///
+/// Waiting APIs accept anything convertible into a [`Timeout`], thus any of these can be used:
+///
+/// * A [`Duration`], to wait for (at most) that amount of time
+/// * An [`Instant`], to wait until (at most) that deadline
+/// * An [`Option`] of any of those, where [`None`] waits indefinitely
+/// * [`Timeout::Infinite`], to wait indefinitely
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum Timeout {
+    /// Waits indefinitely
+    Infinite,
+    /// Waits for (at most) the given amount of time
+    After(Duration),
+    /// Waits until (at most) the given deadline
+    Deadline(Instant),
+}
+
+impl Timeout {
+    /// Gets the deadline of this [`Timeout`] (if it's not infinite), taking "now" as the start for relative timeouts
+    #[inline]
+    pub fn get_deadline(&self) -> Option<Instant> {
+        match *self {
+            Self::Infinite => None,
+            // Overflowing deadlines are effectively infinite
+            Self::After(duration) => Instant::now().checked_add(duration),
+            Self::Deadline(deadline) => Some(deadline),
+        }
+    }
+
+    /// Gets the remaining timeout in nanoseconds as expected by [`svc`][`crate::svc`]s, where `-1` is used for infinite timeouts
+    ///
+    /// Relative timeouts are taken as they are, and expired deadlines result in `0`
+    pub fn get_remaining_nanos(&self) -> i64 {
+        let remaining = match *self {
+            Self::Infinite => return -1,
+            Self::After(duration) => duration,
+            Self::Deadline(deadline) => deadline.saturating_duration_since(Instant::now()),
+        };
+        remaining.as_nanos().min(i64::MAX as u128) as i64
+    }
+
+    /// Gets the remaining timeout in milliseconds (rounded up), where `-1` is used for infinite timeouts
+    ///
+    /// This is the format expected by some system APIs like sockets
+    pub fn get_remaining_millis(&self) -> i32 {
+        match self.get_remaining_nanos() {
+            -1 => -1,
+            nanos => (nanos as u64).div_ceil(1_000_000).min(i32::MAX as u64) as i32,
+        }
+    }
+}
+
+impl From<Duration> for Timeout {
+    fn from(duration: Duration) -> Self {
+        Self::After(duration)
+    }
+}
+
+impl From<Instant> for Timeout {
+    fn from(deadline: Instant) -> Self {
+        Self::Deadline(deadline)
+    }
+}
+
+impl<T: Into<Timeout>> From<Option<T>> for Timeout {
+    fn from(timeout: Option<T>) -> Self {
+        timeout.map(Into::into).unwrap_or(Self::Infinite)
+    }
+}
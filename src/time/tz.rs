@@ -0,0 +1,1090 @@
+//! Pure-Rust time zone support, based on TZif binaries and POSIX TZ rules
+//!
+//! This module doesn't depend on any system service, so zone files can be bundled (or loaded from the SD card) and converted on any host
+
+use super::rc;
+use crate::ipc::sf::time::{CalendarAdditionalInfo, CalendarTime, LocalTime, PosixTime};
+use crate::result::*;
+use crate::util;
+use alloc::string::String;
+use alloc::vec::Vec;
+
+const SECONDS_PER_MINUTE: i64 = 60;
+const SECONDS_PER_HOUR: i64 = 60 * SECONDS_PER_MINUTE;
+const SECONDS_PER_DAY: i64 = 24 * SECONDS_PER_HOUR;
+
+const TZIF_MAGIC: &[u8; 4] = b"TZif";
+const TZIF_HEADER_SIZE: usize = 0x2C;
+
+/// Gets whether a year is a leap year
+///
+/// # Arguments
+///
+/// * `year`: The year
+pub const fn is_leap_year(year: i64) -> bool {
+    (year % 4 == 0) && ((year % 100 != 0) || (year % 400 == 0))
+}
+
+/// Gets the number of days of a month
+///
+/// # Arguments
+///
+/// * `year`: The year
+/// * `month`: The month (`1`-`12`)
+pub const fn get_days_in_month(year: i64, month: u32) -> u32 {
+    match month {
+        2 => {
+            if is_leap_year(year) {
+                29
+            } else {
+                28
+            }
+        }
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+/// Gets the number of days since the UNIX epoch of a given date
+///
+/// # Arguments
+///
+/// * `year`: The year
+/// * `month`: The month (`1`-`12`)
+/// * `day`: The day of the month (`1`-`31`)
+pub const fn days_from_civil(year: i64, month: u32, day: u32) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let month_index = (month as i64 + 9) % 12;
+    let day_of_year = (153 * month_index + 2) / 5 + day as i64 - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146097 + day_of_era - 719468
+}
+
+/// Gets the date (year, month and day of the month) of a given number of days since the UNIX epoch
+///
+/// # Arguments
+///
+/// * `days`: The days since the UNIX epoch
+pub const fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let days = days + 719468;
+    let era = days.div_euclid(146097);
+    let day_of_era = days - era * 146097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = (day_of_year - (153 * month_index + 2) / 5 + 1) as u32;
+    let month = if month_index < 10 {
+        month_index + 3
+    } else {
+        month_index - 9
+    } as u32;
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+    (year, month, day)
+}
+
+/// Converts a POSIX time to calendar time, applying the given local time type
+///
+/// # Arguments
+///
+/// * `time`: The POSIX time
+/// * `time_type`: The local time type to apply
+pub fn make_local_time(time: PosixTime, time_type: &LocalTimeType) -> Result<LocalTime> {
+    let local_time = time
+        .checked_add(time_type.utc_offset as i64)
+        .ok_or(rc::ResultTimeOutOfRange::make())?;
+    let days = local_time.div_euclid(SECONDS_PER_DAY);
+    let day_seconds = local_time.rem_euclid(SECONDS_PER_DAY);
+
+    let (year, month, day) = civil_from_days(days);
+    let year: i16 = year
+        .try_into()
+        .map_err(|_| rc::ResultTimeOutOfRange::make())?;
+
+    let calendar_time = CalendarTime::new(
+        year,
+        month as u8,
+        day as u8,
+        (day_seconds / SECONDS_PER_HOUR) as u8,
+        ((day_seconds % SECONDS_PER_HOUR) / SECONDS_PER_MINUTE) as u8,
+        (day_seconds % SECONDS_PER_MINUTE) as u8,
+    );
+    let additional_info = CalendarAdditionalInfo {
+        // 1970-01-01 was a Thursday
+        day_of_week: (days + 4).rem_euclid(7) as u32,
+        day_of_year: (days - days_from_civil(year as i64, 1, 1)) as u32,
+        time_zone_name: util::ArrayString::from_str(&time_type.abbreviation),
+        is_dst: time_type.is_dst,
+        pad: [0; 3],
+        utc_offset: time_type.utc_offset,
+    };
+    Ok(LocalTime::new(calendar_time, additional_info))
+}
+
+/// Represents a local time type (an offset from UTC along with its designation)
+#[derive(Clone, PartialEq, Eq, Debug, Default)]
+pub struct LocalTimeType {
+    /// The offset from UTC, in seconds
+    pub utc_offset: i32,
+    /// Whether this is a daylight saving time type
+    pub is_dst: bool,
+    /// The designation of this type (like `CEST`)
+    pub abbreviation: String,
+}
+
+impl LocalTimeType {
+    /// Creates a new [`LocalTimeType`]
+    ///
+    /// # Arguments
+    ///
+    /// * `utc_offset`: The offset from UTC, in seconds
+    /// * `is_dst`: Whether this is a daylight saving time type
+    /// * `abbreviation`: The designation of this type
+    pub fn new(utc_offset: i32, is_dst: bool, abbreviation: &str) -> Self {
+        Self {
+            utc_offset,
+            is_dst,
+            abbreviation: String::from(abbreviation),
+        }
+    }
+
+    /// Gets the UTC local time type
+    pub fn utc() -> Self {
+        Self::new(0, false, "UTC")
+    }
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+enum RuleDate {
+    /// `Jn`: Julian day (`1`-`365`), where February 29 is never counted
+    Julian(u16),
+    /// `n`: Zero-based day of the year (`0`-`365`), counting February 29
+    DayOfYear(u16),
+    /// `Mm.w.d`: Day `d` (`0` being Sunday) of week `w` (`1`-`5`, `5` being the last one) of month `m`
+    MonthWeekDay { month: u8, week: u8, day: u8 },
+}
+
+impl RuleDate {
+    /// Gets the days since the UNIX epoch of this date in the given year
+    fn get_days(&self, year: i64) -> i64 {
+        let year_days = days_from_civil(year, 1, 1);
+        match *self {
+            Self::Julian(day) => {
+                let mut day_of_year = day as i64 - 1;
+                if is_leap_year(year) && day >= 60 {
+                    day_of_year += 1;
+                }
+                year_days + day_of_year
+            }
+            Self::DayOfYear(day) => year_days + day as i64,
+            Self::MonthWeekDay { month, week, day } => {
+                let month_days = days_from_civil(year, month as u32, 1);
+                let first_week_day = (month_days + 4).rem_euclid(7);
+                let mut month_day =
+                    (day as i64 - first_week_day).rem_euclid(7) + (week as i64 - 1) * 7;
+                let days_in_month = get_days_in_month(year, month as u32) as i64;
+                while month_day >= days_in_month {
+                    month_day -= 7;
+                }
+                month_days + month_day
+            }
+        }
+    }
+}
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+struct DstRule {
+    time_type: LocalTimeType,
+    start: RuleDate,
+    start_time: i64,
+    end: RuleDate,
+    end_time: i64,
+}
+
+/// Represents a POSIX TZ rule (like `CET-1CEST,M3.5.0,M10.5.0/3`)
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct PosixRule {
+    std: LocalTimeType,
+    dst: Option<DstRule>,
+}
+
+struct RuleParser<'a> {
+    data: &'a [u8],
+    offset: usize,
+}
+
+impl<'a> RuleParser<'a> {
+    fn peek(&self) -> Option<u8> {
+        self.data.get(self.offset).copied()
+    }
+
+    fn is_at_end(&self) -> bool {
+        self.offset >= self.data.len()
+    }
+
+    fn expect(&mut self, byte: u8) -> Result<()> {
+        result_return_unless!(self.peek() == Some(byte), rc::ResultInvalidPosixRule);
+        self.offset += 1;
+        Ok(())
+    }
+
+    fn parse_name(&mut self) -> Result<&'a str> {
+        let (start, end) = if self.peek() == Some(b'<') {
+            self.offset += 1;
+            let start = self.offset;
+            while self.peek().is_some_and(|byte| byte != b'>') {
+                self.offset += 1;
+            }
+            let end = self.offset;
+            self.expect(b'>')?;
+            (start, end)
+        } else {
+            let start = self.offset;
+            while self.peek().is_some_and(|byte| byte.is_ascii_alphabetic()) {
+                self.offset += 1;
+            }
+            (start, self.offset)
+        };
+
+        result_return_if!(end - start < 3, rc::ResultInvalidPosixRule);
+        core::str::from_utf8(&self.data[start..end]).map_err(|_| rc::ResultInvalidPosixRule::make())
+    }
+
+    fn parse_number(&mut self, max: i64) -> Result<i64> {
+        let start = self.offset;
+        let mut value: i64 = 0;
+        while let Some(digit) = self.peek().filter(u8::is_ascii_digit) {
+            value = value * 10 + (digit - b'0') as i64;
+            result_return_if!(value > max, rc::ResultInvalidPosixRule);
+            self.offset += 1;
+        }
+        result_return_if!(self.offset == start, rc::ResultInvalidPosixRule);
+        Ok(value)
+    }
+
+    fn parse_sign(&mut self) -> i64 {
+        match self.peek() {
+            Some(b'-') => {
+                self.offset += 1;
+                -1
+            }
+            Some(b'+') => {
+                self.offset += 1;
+                1
+            }
+            _ => 1,
+        }
+    }
+
+    /// Parses `[+-]hh[:mm[:ss]]`, returning the amount of seconds
+    fn parse_time(&mut self, max_hours: i64) -> Result<i64> {
+        let sign = self.parse_sign();
+        let mut seconds = self.parse_number(max_hours)? * SECONDS_PER_HOUR;
+        if self.peek() == Some(b':') {
+            self.offset += 1;
+            seconds += self.parse_number(59)? * SECONDS_PER_MINUTE;
+            if self.peek() == Some(b':') {
+                self.offset += 1;
+                seconds += self.parse_number(59)?;
+            }
+        }
+        Ok(sign * seconds)
+    }
+
+    fn parse_offset(&mut self) -> Result<i32> {
+        // POSIX offsets are the ones to add to local time to get UTC, thus the opposite of ours
+        Ok(-self.parse_time(24)? as i32)
+    }
+
+    fn parse_date(&mut self) -> Result<RuleDate> {
+        match self.peek() {
+            Some(b'J') => {
+                self.offset += 1;
+                let day = self.parse_number(365)?;
+                result_return_if!(day < 1, rc::ResultInvalidPosixRule);
+                Ok(RuleDate::Julian(day as u16))
+            }
+            Some(b'M') => {
+                self.offset += 1;
+                let month = self.parse_number(12)?;
+                self.expect(b'.')?;
+                let week = self.parse_number(5)?;
+                self.expect(b'.')?;
+                let day = self.parse_number(6)?;
+                result_return_if!(month < 1 || week < 1, rc::ResultInvalidPosixRule);
+                Ok(RuleDate::MonthWeekDay {
+                    month: month as u8,
+                    week: week as u8,
+                    day: day as u8,
+                })
+            }
+            _ => Ok(RuleDate::DayOfYear(self.parse_number(365)? as u16)),
+        }
+    }
+
+    fn parse_date_time(&mut self) -> Result<(RuleDate, i64)> {
+        let date = self.parse_date()?;
+        let time = match self.peek() {
+            Some(b'/') => {
+                self.offset += 1;
+                // TZif v3 extension: transition times may range from -167 to 167 hours
+                self.parse_time(167)?
+            }
+            _ => 2 * SECONDS_PER_HOUR,
+        };
+        Ok((date, time))
+    }
+}
+
+impl PosixRule {
+    /// Parses a POSIX TZ rule
+    ///
+    /// When a DST type is specified without transition rules, the US rules (`M3.2.0,M11.1.0`) are assumed
+    ///
+    /// # Arguments
+    ///
+    /// * `rule`: The rule string
+    pub fn parse(rule: &str) -> Result<Self> {
+        let mut parser = RuleParser {
+            data: rule.as_bytes(),
+            offset: 0,
+        };
+
+        let std_name = parser.parse_name()?;
+        let std_offset = parser.parse_offset()?;
+        let std = LocalTimeType::new(std_offset, false, std_name);
+        if parser.is_at_end() {
+            return Ok(Self { std, dst: None });
+        }
+
+        let dst_name = parser.parse_name()?;
+        let dst_offset = match parser.peek() {
+            Some(b',') | None => std_offset + SECONDS_PER_HOUR as i32,
+            _ => parser.parse_offset()?,
+        };
+        let dst_type = LocalTimeType::new(dst_offset, true, dst_name);
+
+        let ((start, start_time), (end, end_time)) = if parser.is_at_end() {
+            (
+                (
+                    RuleDate::MonthWeekDay {
+                        month: 3,
+                        week: 2,
+                        day: 0,
+                    },
+                    2 * SECONDS_PER_HOUR,
+                ),
+                (
+                    RuleDate::MonthWeekDay {
+                        month: 11,
+                        week: 1,
+                        day: 0,
+                    },
+                    2 * SECONDS_PER_HOUR,
+                ),
+            )
+        } else {
+            parser.expect(b',')?;
+            let start = parser.parse_date_time()?;
+            parser.expect(b',')?;
+            let end = parser.parse_date_time()?;
+            (start, end)
+        };
+        result_return_unless!(parser.is_at_end(), rc::ResultInvalidPosixRule);
+
+        Ok(Self {
+            std,
+            dst: Some(DstRule {
+                time_type: dst_type,
+                start,
+                start_time,
+                end,
+                end_time,
+            }),
+        })
+    }
+
+    /// Gets the standard local time type of this rule
+    pub fn get_standard_type(&self) -> &LocalTimeType {
+        &self.std
+    }
+
+    /// Gets the DST local time type of this rule, if any
+    pub fn get_dst_type(&self) -> Option<&LocalTimeType> {
+        self.dst.as_ref().map(|dst| &dst.time_type)
+    }
+
+    /// Finds the local time type in effect at a given POSIX time
+    ///
+    /// # Arguments
+    ///
+    /// * `time`: The POSIX time
+    pub fn find_local_time_type(&self, time: PosixTime) -> &LocalTimeType {
+        let Some(dst) = self.dst.as_ref() else {
+            return &self.std;
+        };
+
+        let std_local_time = time.saturating_add(self.std.utc_offset as i64);
+        let (year, _, _) = civil_from_days(std_local_time.div_euclid(SECONDS_PER_DAY));
+
+        // Transition times are expressed in the local time in effect before them
+        let dst_start = dst.start.get_days(year) * SECONDS_PER_DAY + dst.start_time
+            - self.std.utc_offset as i64;
+        let dst_end = dst.end.get_days(year) * SECONDS_PER_DAY + dst.end_time
+            - dst.time_type.utc_offset as i64;
+
+        let is_dst = if dst_start < dst_end {
+            (dst_start..dst_end).contains(&time)
+        } else {
+            // Southern hemisphere, DST spans the change of year
+            !(dst_end..dst_start).contains(&time)
+        };
+
+        if is_dst { &dst.time_type } else { &self.std }
+    }
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+struct Transition {
+    time: PosixTime,
+    type_index: usize,
+}
+
+struct TzifReader<'a> {
+    data: &'a [u8],
+    offset: usize,
+}
+
+impl<'a> TzifReader<'a> {
+    fn read_bytes(&mut self, size: usize) -> Result<&'a [u8]> {
+        let end = self
+            .offset
+            .checked_add(size)
+            .ok_or(rc::ResultInvalidTimeZoneBinary::make())?;
+        let bytes = self
+            .data
+            .get(self.offset..end)
+            .ok_or(rc::ResultInvalidTimeZoneBinary::make())?;
+        self.offset = end;
+        Ok(bytes)
+    }
+
+    fn read_u8(&mut self) -> Result<u8> {
+        Ok(self.read_bytes(1)?[0])
+    }
+
+    fn read_u32(&mut self) -> Result<u32> {
+        let bytes = self.read_bytes(4)?;
+        Ok(u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+    }
+
+    fn read_i32(&mut self) -> Result<i32> {
+        Ok(self.read_u32()? as i32)
+    }
+
+    fn read_i64(&mut self) -> Result<i64> {
+        let bytes = self.read_bytes(8)?;
+        let mut raw = [0u8; 8];
+        raw.copy_from_slice(bytes);
+        Ok(i64::from_be_bytes(raw))
+    }
+
+    fn skip(&mut self, size: usize) -> Result<()> {
+        self.read_bytes(size).map(|_| ())
+    }
+
+    fn get_remaining_size(&self) -> usize {
+        self.data.len() - self.offset
+    }
+}
+
+#[derive(Copy, Clone, Debug)]
+struct TzifHeader {
+    version: u8,
+    is_ut_count: usize,
+    is_std_count: usize,
+    leap_count: usize,
+    time_count: usize,
+    type_count: usize,
+    char_count: usize,
+}
+
+impl TzifHeader {
+    fn read(reader: &mut TzifReader) -> Result<Self> {
+        let magic = reader.read_bytes(TZIF_MAGIC.len())?;
+        result_return_unless!(magic == TZIF_MAGIC, rc::ResultInvalidTimeZoneBinary);
+
+        let version = match reader.read_u8()? {
+            0 => 1,
+            b'2' => 2,
+            b'3' => 3,
+            b'4' => 4,
+            _ => return rc::ResultUnsupportedTimeZoneBinaryVersion::make_err(),
+        };
+        reader.skip(15)?;
+
+        Ok(Self {
+            version,
+            is_ut_count: reader.read_u32()? as usize,
+            is_std_count: reader.read_u32()? as usize,
+            leap_count: reader.read_u32()? as usize,
+            time_count: reader.read_u32()? as usize,
+            type_count: reader.read_u32()? as usize,
+            char_count: reader.read_u32()? as usize,
+        })
+    }
+
+    fn get_data_size(&self, time_size: usize) -> Result<usize> {
+        // The counts come straight from the binary, thus they could overflow on 32-bit targets
+        let sizes = [
+            self.time_count.checked_mul(time_size + 1),
+            self.type_count.checked_mul(6),
+            Some(self.char_count),
+            self.leap_count.checked_mul(time_size + 4),
+            Some(self.is_std_count),
+            Some(self.is_ut_count),
+        ];
+        sizes
+            .into_iter()
+            .try_fold(0usize, |total, size| total.checked_add(size?))
+            .ok_or(rc::ResultInvalidTimeZoneBinary::make())
+    }
+}
+
+/// Represents a time zone, as loaded from a TZif binary (like the ones in `/usr/share/zoneinfo`) or a POSIX TZ rule
+///
+/// Leap second records are ignored, since POSIX times don't account for them
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct TimeZone {
+    transitions: Vec<Transition>,
+    types: Vec<LocalTimeType>,
+    rule: Option<PosixRule>,
+}
+
+impl TimeZone {
+    /// Creates the UTC [`TimeZone`]
+    pub fn utc() -> Self {
+        Self {
+            transitions: Vec::new(),
+            types: vec![LocalTimeType::utc()],
+            rule: None,
+        }
+    }
+
+    /// Creates a [`TimeZone`] from a POSIX TZ rule
+    ///
+    /// # Arguments
+    ///
+    /// * `rule`: The rule string (like `CET-1CEST,M3.5.0,M10.5.0/3`)
+    pub fn from_posix_rule(rule: &str) -> Result<Self> {
+        let rule = PosixRule::parse(rule)?;
+        Ok(Self {
+            transitions: Vec::new(),
+            types: vec![rule.get_standard_type().clone()],
+            rule: Some(rule),
+        })
+    }
+
+    /// Parses a [`TimeZone`] from a TZif binary (see RFC 8536)
+    ///
+    /// # Arguments
+    ///
+    /// * `data`: The TZif binary data
+    pub fn from_tzif(data: &[u8]) -> Result<Self> {
+        result_return_if!(
+            data.len() < TZIF_HEADER_SIZE,
+            rc::ResultInvalidTimeZoneBinary
+        );
+        let mut reader = TzifReader { data, offset: 0 };
+
+        let mut header = TzifHeader::read(&mut reader)?;
+        let time_size = if header.version >= 2 {
+            // Skip the legacy 32-bit data block, the 64-bit one follows it
+            reader.skip(header.get_data_size(4)?)?;
+            header = TzifHeader::read(&mut reader)?;
+            8
+        } else {
+            4
+        };
+
+        result_return_if!(header.type_count == 0, rc::ResultInvalidTimeZoneBinary);
+        result_return_if!(header.char_count == 0, rc::ResultInvalidTimeZoneBinary);
+        // Check the counts against the actual data before allocating anything with them
+        result_return_if!(
+            header.get_data_size(time_size)? > reader.get_remaining_size(),
+            rc::ResultInvalidTimeZoneBinary
+        );
+
+        let mut transition_times = Vec::with_capacity(header.time_count);
+        for _ in 0..header.time_count {
+            let time = match time_size {
+                8 => reader.read_i64()?,
+                _ => reader.read_i32()? as i64,
+            };
+            transition_times.push(time);
+        }
+
+        let mut transitions = Vec::with_capacity(header.time_count);
+        for time in transition_times {
+            let type_index = reader.read_u8()? as usize;
+            result_return_unless!(
+                type_index < header.type_count,
+                rc::ResultInvalidTimeZoneBinary
+            );
+            transitions.push(Transition { time, type_index });
+        }
+        result_return_unless!(
+            transitions
+                .windows(2)
+                .all(|pair| pair[0].time < pair[1].time),
+            rc::ResultInvalidTimeZoneBinary
+        );
+
+        let mut raw_types = Vec::with_capacity(header.type_count);
+        for _ in 0..header.type_count {
+            let utc_offset = reader.read_i32()?;
+            let is_dst = reader.read_u8()? != 0;
+            let abbreviation_index = reader.read_u8()? as usize;
+            result_return_unless!(
+                abbreviation_index < header.char_count,
+                rc::ResultInvalidTimeZoneBinary
+            );
+            raw_types.push((utc_offset, is_dst, abbreviation_index));
+        }
+
+        let abbreviations = reader.read_bytes(header.char_count)?;
+        let types = raw_types
+            .into_iter()
+            .map(|(utc_offset, is_dst, abbreviation_index)| {
+                let abbreviation = &abbreviations[abbreviation_index..];
+                let abbreviation_len = abbreviation
+                    .iter()
+                    .position(|byte| *byte == 0)
+                    .unwrap_or(abbreviation.len());
+                let abbreviation = core::str::from_utf8(&abbreviation[..abbreviation_len])
+                    .map_err(|_| rc::ResultInvalidTimeZoneBinary::make())?;
+                Ok(LocalTimeType::new(utc_offset, is_dst, abbreviation))
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        // Leap second records and the standard/wall and UT/local indicators aren't needed for conversions
+        reader
+            .skip(header.leap_count * (time_size + 4) + header.is_std_count + header.is_ut_count)?;
+
+        let rule = if header.version >= 2 {
+            let footer = &data[reader.offset..];
+            result_return_unless!(
+                footer.first() == Some(&b'\n'),
+                rc::ResultInvalidTimeZoneBinary
+            );
+            let footer_len = footer[1..]
+                .iter()
+                .position(|byte| *byte == b'\n')
+                .ok_or(rc::ResultInvalidTimeZoneBinary::make())?;
+            let footer = core::str::from_utf8(&footer[1..1 + footer_len])
+                .map_err(|_| rc::ResultInvalidTimeZoneBinary::make())?;
+            match footer.is_empty() {
+                true => None,
+                false => Some(PosixRule::parse(footer)?),
+            }
+        } else {
+            None
+        };
+
+        Ok(Self {
+            transitions,
+            types,
+            rule,
+        })
+    }
+
+    /// Gets the local time types of this [`TimeZone`]
+    pub fn get_local_time_types(&self) -> &[LocalTimeType] {
+        &self.types
+    }
+
+    /// Gets the POSIX TZ rule used for times after the last transition, if any
+    pub fn get_posix_rule(&self) -> Option<&PosixRule> {
+        self.rule.as_ref()
+    }
+
+    /// Finds the local time type in effect at a given POSIX time
+    ///
+    /// # Arguments
+    ///
+    /// * `time`: The POSIX time
+    pub fn find_local_time_type(&self, time: PosixTime) -> &LocalTimeType {
+        match (self.transitions.last(), self.rule.as_ref()) {
+            (None, Some(rule)) => return rule.find_local_time_type(time),
+            (Some(last), Some(rule)) if time >= last.time => {
+                return rule.find_local_time_type(time);
+            }
+            _ => {}
+        }
+
+        let next_index = self
+            .transitions
+            .partition_point(|transition| transition.time <= time);
+        match next_index {
+            // Times before the first transition use the first type
+            0 => &self.types[0],
+            _ => &self.types[self.transitions[next_index - 1].type_index],
+        }
+    }
+
+    /// Converts a POSIX time to local calendar time in this [`TimeZone`]
+    ///
+    /// # Arguments
+    ///
+    /// * `time`: The POSIX time
+    pub fn to_local_time(&self, time: PosixTime) -> Result<LocalTime> {
+        make_local_time(time, self.find_local_time_type(time))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    macro_rules! tz_fixture {
+        ($name:literal) => {
+            include_bytes!(concat!(
+                env!("CARGO_MANIFEST_DIR"),
+                "/tests/fixtures/tz/",
+                $name
+            ))
+        };
+    }
+
+    const MADRID: &[u8] = tz_fixture!("Europe_Madrid.tzif");
+    const MADRID_V1: &[u8] = tz_fixture!("Europe_Madrid_v1.tzif");
+    const SYDNEY: &[u8] = tz_fixture!("Australia_Sydney.tzif");
+    const SAO_PAULO: &[u8] = tz_fixture!("America_Sao_Paulo.tzif");
+
+    fn posix_time(
+        year: i64,
+        month: u32,
+        day: u32,
+        hour: i64,
+        minute: i64,
+        second: i64,
+    ) -> PosixTime {
+        days_from_civil(year, month, day) * SECONDS_PER_DAY
+            + hour * SECONDS_PER_HOUR
+            + minute * SECONDS_PER_MINUTE
+            + second
+    }
+
+    fn assert_type(
+        tz: &TimeZone,
+        time: PosixTime,
+        utc_offset: i32,
+        is_dst: bool,
+        abbreviation: &str,
+    ) {
+        assert_eq!(
+            tz.find_local_time_type(time),
+            &LocalTimeType::new(utc_offset, is_dst, abbreviation),
+            "time {}",
+            time
+        );
+    }
+
+    #[test]
+    fn civil_conversion_round_trip() {
+        assert_eq!(days_from_civil(1970, 1, 1), 0);
+        assert_eq!(days_from_civil(2000, 3, 1), 11017);
+        assert_eq!(days_from_civil(1969, 12, 31), -1);
+        for days in (-800_000..800_000).step_by(97) {
+            let (year, month, day) = civil_from_days(days);
+            assert_eq!(days_from_civil(year, month, day), days);
+        }
+    }
+
+    #[test]
+    fn rule_dates() {
+        // J counts never include February 29, while zero-based days do
+        assert_eq!(
+            RuleDate::Julian(60).get_days(2024),
+            days_from_civil(2024, 3, 1)
+        );
+        assert_eq!(
+            RuleDate::Julian(60).get_days(2023),
+            days_from_civil(2023, 3, 1)
+        );
+        assert_eq!(
+            RuleDate::Julian(365).get_days(2024),
+            days_from_civil(2024, 12, 31)
+        );
+        assert_eq!(
+            RuleDate::DayOfYear(59).get_days(2024),
+            days_from_civil(2024, 2, 29)
+        );
+        assert_eq!(
+            RuleDate::DayOfYear(59).get_days(2023),
+            days_from_civil(2023, 3, 1)
+        );
+        assert_eq!(
+            RuleDate::DayOfYear(365).get_days(2024),
+            days_from_civil(2024, 12, 31)
+        );
+
+        // Last Sunday of March/October, and the first and second Sundays
+        let month_week_day = |month, week, day| RuleDate::MonthWeekDay { month, week, day };
+        assert_eq!(
+            month_week_day(3, 5, 0).get_days(2021),
+            days_from_civil(2021, 3, 28)
+        );
+        assert_eq!(
+            month_week_day(10, 5, 0).get_days(2021),
+            days_from_civil(2021, 10, 31)
+        );
+        assert_eq!(
+            month_week_day(10, 1, 0).get_days(2021),
+            days_from_civil(2021, 10, 3)
+        );
+        assert_eq!(
+            month_week_day(3, 2, 0).get_days(2021),
+            days_from_civil(2021, 3, 14)
+        );
+        // February 2015 has exactly four Sundays
+        assert_eq!(
+            month_week_day(2, 5, 0).get_days(2015),
+            days_from_civil(2015, 2, 22)
+        );
+    }
+
+    #[test]
+    fn posix_rule_northern_hemisphere() {
+        let tz = TimeZone::from_posix_rule("CET-1CEST,M3.5.0,M10.5.0/3").unwrap();
+        assert_type(&tz, posix_time(2021, 1, 15, 12, 0, 0), 3600, false, "CET");
+        assert_type(&tz, posix_time(2021, 3, 28, 0, 59, 59), 3600, false, "CET");
+        assert_type(&tz, posix_time(2021, 3, 28, 1, 0, 0), 7200, true, "CEST");
+        assert_type(&tz, posix_time(2021, 10, 31, 0, 59, 59), 7200, true, "CEST");
+        assert_type(&tz, posix_time(2021, 10, 31, 1, 0, 0), 3600, false, "CET");
+    }
+
+    #[test]
+    fn posix_rule_southern_hemisphere() {
+        let tz = TimeZone::from_posix_rule("AEST-10AEDT,M10.1.0,M4.1.0/3").unwrap();
+        assert_type(&tz, posix_time(2021, 1, 15, 0, 0, 0), 39600, true, "AEDT");
+        assert_type(&tz, posix_time(2021, 4, 3, 15, 59, 59), 39600, true, "AEDT");
+        assert_type(&tz, posix_time(2021, 4, 3, 16, 0, 0), 36000, false, "AEST");
+        assert_type(&tz, posix_time(2021, 7, 1, 0, 0, 0), 36000, false, "AEST");
+        assert_type(
+            &tz,
+            posix_time(2021, 10, 2, 15, 59, 59),
+            36000,
+            false,
+            "AEST",
+        );
+        assert_type(&tz, posix_time(2021, 10, 2, 16, 0, 0), 39600, true, "AEDT");
+        assert_type(&tz, posix_time(2021, 12, 31, 23, 0, 0), 39600, true, "AEDT");
+    }
+
+    #[test]
+    fn posix_rule_julian_and_day_of_year() {
+        // DST from March 1 (J60) at 00:00 to the zero-based day 300 at 02:00
+        let tz = TimeZone::from_posix_rule("EST5EDT,J60/0,300").unwrap();
+        assert_type(&tz, posix_time(2024, 3, 1, 4, 59, 59), -18000, false, "EST");
+        assert_type(&tz, posix_time(2024, 3, 1, 5, 0, 0), -14400, true, "EDT");
+        // Day 300 is October 27 in leap years and October 28 otherwise
+        assert_type(
+            &tz,
+            posix_time(2024, 10, 27, 5, 59, 59),
+            -14400,
+            true,
+            "EDT",
+        );
+        assert_type(&tz, posix_time(2024, 10, 27, 6, 0, 0), -18000, false, "EST");
+        assert_type(
+            &tz,
+            posix_time(2023, 10, 28, 5, 59, 59),
+            -14400,
+            true,
+            "EDT",
+        );
+        assert_type(&tz, posix_time(2023, 10, 28, 6, 0, 0), -18000, false, "EST");
+    }
+
+    #[test]
+    fn posix_rule_defaults_and_names() {
+        // Without transition rules, the US ones apply (second Sunday of March to the first Sunday of November)
+        let tz = TimeZone::from_posix_rule("EST5EDT").unwrap();
+        assert_type(
+            &tz,
+            posix_time(2021, 3, 14, 6, 59, 59),
+            -18000,
+            false,
+            "EST",
+        );
+        assert_type(&tz, posix_time(2021, 3, 14, 7, 0, 0), -14400, true, "EDT");
+        assert_type(&tz, posix_time(2021, 11, 7, 5, 59, 59), -14400, true, "EDT");
+        assert_type(&tz, posix_time(2021, 11, 7, 6, 0, 0), -18000, false, "EST");
+
+        let tz = TimeZone::from_posix_rule("<+0330>-3:30").unwrap();
+        assert_type(&tz, 0, 12600, false, "+0330");
+        let tz = TimeZone::from_posix_rule("<-03>3").unwrap();
+        assert_type(&tz, 0, -10800, false, "-03");
+        let tz = TimeZone::from_posix_rule("JST-9").unwrap();
+        assert_type(&tz, 0, 32400, false, "JST");
+
+        // Transition times beyond 24 hours (TZif v3 extension)
+        let tz = TimeZone::from_posix_rule("<-02>2<-01>,M3.5.0/-1,M10.5.0/25").unwrap();
+        assert_type(&tz, posix_time(2021, 3, 28, 0, 59, 59), -7200, false, "-02");
+        assert_type(&tz, posix_time(2021, 3, 28, 1, 0, 0), -3600, true, "-01");
+    }
+
+    #[test]
+    fn posix_rule_invalid() {
+        for rule in [
+            "",
+            "AB",
+            "CET",
+            "CET-1CEST,M3.5.0",
+            "CET-1CEST,M13.5.0,M10.5.0/3",
+            "CET-1CEST,M3.6.0,M10.5.0/3",
+            "CET-1CEST,M3.5.7,M10.5.0/3",
+            "CET-1CEST,J0,J300",
+            "CET-1CEST,366,300",
+            "CET-25",
+            "<CET-1",
+            "CET-1CEST,M3.5.0,M10.5.0/3,",
+        ] {
+            assert!(PosixRule::parse(rule).is_err(), "rule {:?}", rule);
+        }
+    }
+
+    #[test]
+    fn tzif_v2_with_footer() {
+        let tz = TimeZone::from_tzif(MADRID).unwrap();
+        assert_eq!(
+            tz.get_posix_rule(),
+            Some(&PosixRule::parse("CET-1CEST,M3.5.0,M10.5.0/3").unwrap())
+        );
+        assert_type(&tz, posix_time(1900, 1, 1, 0, 0, 0), -884, false, "LMT");
+        assert_type(&tz, posix_time(2021, 1, 15, 12, 0, 0), 3600, false, "CET");
+        assert_type(&tz, posix_time(2021, 7, 1, 12, 0, 0), 7200, true, "CEST");
+        assert_type(&tz, posix_time(2021, 3, 28, 0, 59, 59), 3600, false, "CET");
+        assert_type(&tz, posix_time(2021, 3, 28, 1, 0, 0), 7200, true, "CEST");
+        assert_type(&tz, posix_time(2021, 10, 31, 0, 59, 59), 7200, true, "CEST");
+        assert_type(&tz, posix_time(2021, 10, 31, 1, 0, 0), 3600, false, "CET");
+        // Past the last transition, the footer rule applies
+        assert_type(&tz, posix_time(2100, 1, 1, 0, 0, 0), 3600, false, "CET");
+        assert_type(&tz, posix_time(2100, 7, 1, 0, 0, 0), 7200, true, "CEST");
+    }
+
+    #[test]
+    fn tzif_v2_southern_hemisphere() {
+        let tz = TimeZone::from_tzif(SYDNEY).unwrap();
+        assert_type(&tz, posix_time(2021, 1, 15, 0, 0, 0), 39600, true, "AEDT");
+        assert_type(&tz, posix_time(2021, 7, 1, 0, 0, 0), 36000, false, "AEST");
+        assert_type(&tz, posix_time(2021, 4, 3, 15, 59, 59), 39600, true, "AEDT");
+        assert_type(&tz, posix_time(2021, 4, 3, 16, 0, 0), 36000, false, "AEST");
+        assert_type(
+            &tz,
+            posix_time(2021, 10, 2, 15, 59, 59),
+            36000,
+            false,
+            "AEST",
+        );
+        assert_type(&tz, posix_time(2021, 10, 2, 16, 0, 0), 39600, true, "AEDT");
+        assert_type(&tz, posix_time(2100, 1, 1, 0, 0, 0), 39600, true, "AEDT");
+        assert_type(&tz, posix_time(2100, 7, 1, 0, 0, 0), 36000, false, "AEST");
+    }
+
+    #[test]
+    fn tzif_v2_without_dst_footer() {
+        // DST was abolished in 2019, thus the footer has no DST rule
+        let tz = TimeZone::from_tzif(SAO_PAULO).unwrap();
+        assert_eq!(tz.get_posix_rule().and_then(PosixRule::get_dst_type), None);
+        assert_type(&tz, posix_time(2018, 1, 15, 0, 0, 0), -7200, true, "-02");
+        assert_type(&tz, posix_time(2019, 1, 15, 0, 0, 0), -7200, true, "-02");
+        assert_type(&tz, posix_time(2100, 1, 1, 0, 0, 0), -10800, false, "-03");
+    }
+
+    #[test]
+    fn tzif_v1() {
+        let tz = TimeZone::from_tzif(MADRID_V1).unwrap();
+        assert_eq!(tz.get_posix_rule(), None);
+        assert_type(&tz, posix_time(2021, 1, 15, 12, 0, 0), 3600, false, "CET");
+        assert_type(&tz, posix_time(2021, 3, 28, 1, 0, 0), 7200, true, "CEST");
+        assert_type(&tz, posix_time(2021, 10, 31, 1, 0, 0), 3600, false, "CET");
+
+        // Both data blocks must describe the same zone
+        let v2 = TimeZone::from_tzif(MADRID).unwrap();
+        for year in 1950..2037 {
+            let time = posix_time(year, 7, 1, 0, 0, 0);
+            assert_eq!(tz.find_local_time_type(time), v2.find_local_time_type(time));
+        }
+    }
+
+    #[test]
+    fn tzif_invalid() {
+        // Truncated anywhere: header, data blocks or footer
+        for len in [0, 4, 0x2B, 0x2C, 0x100, MADRID_V1.len(), MADRID.len() - 1] {
+            assert!(
+                TimeZone::from_tzif(&MADRID[..len]).is_err(),
+                "length {}",
+                len
+            );
+        }
+        assert!(TimeZone::from_tzif(&MADRID_V1[..MADRID_V1.len() - 1]).is_err());
+
+        let mut bad_magic = MADRID.to_vec();
+        bad_magic[0] = b'X';
+        assert!(TimeZone::from_tzif(&bad_magic).is_err());
+
+        let mut bad_version = MADRID.to_vec();
+        bad_version[4] = b'9';
+        assert!(TimeZone::from_tzif(&bad_version).is_err());
+
+        let mut bad_footer = MADRID.to_vec();
+        let footer_start = bad_footer.len() - "CET-1CEST,M3.5.0,M10.5.0/3\n".len();
+        bad_footer[footer_start] = b'?';
+        assert!(TimeZone::from_tzif(&bad_footer).is_err());
+
+        // An out-of-range type index in the first transition of the v1 block
+        let mut bad_type_index = MADRID_V1.to_vec();
+        let time_count = u32::from_be_bytes([
+            bad_type_index[32],
+            bad_type_index[33],
+            bad_type_index[34],
+            bad_type_index[35],
+        ]) as usize;
+        bad_type_index[TZIF_HEADER_SIZE + time_count * 4] = 0xFF;
+        assert!(TimeZone::from_tzif(&bad_type_index).is_err());
+
+        // Counts way larger than the data itself (which must be rejected before allocating anything)
+        for count_offset in [32, 36] {
+            let mut bad_count = MADRID_V1.to_vec();
+            bad_count[count_offset..count_offset + 4].copy_from_slice(&u32::MAX.to_be_bytes());
+            assert!(TimeZone::from_tzif(&bad_count).is_err());
+        }
+    }
+
+    #[test]
+    fn local_time() {
+        let tz = TimeZone::from_tzif(MADRID).unwrap();
+        let local_time = tz
+            .to_local_time(posix_time(2021, 7, 1, 12, 30, 15))
+            .unwrap();
+        assert_eq!(local_time.time, CalendarTime::new(2021, 7, 1, 14, 30, 15));
+        // Thursday, and the zero-based day of the year
+        assert_eq!(local_time.info.day_of_week, 4);
+        assert_eq!(local_time.info.day_of_year, 181);
+        assert_eq!(local_time.info.time_zone_name.get_str().unwrap(), "CEST");
+        assert!(local_time.info.is_dst);
+        assert_eq!(local_time.info.utc_offset, 7200);
+
+        // Crossing the change of year backwards
+        let tz = TimeZone::from_posix_rule("<-03>3").unwrap();
+        let local_time = tz.to_local_time(posix_time(2021, 1, 1, 2, 0, 0)).unwrap();
+        assert_eq!(local_time.time, CalendarTime::new(2020, 12, 31, 23, 0, 0));
+        assert_eq!(local_time.info.day_of_year, 365);
+
+        assert!(TimeZone::utc().to_local_time(i64::MAX).is_err());
+    }
+}
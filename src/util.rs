@@ -1,6 +1,7 @@
 //! Common library utilities
 
 use crate::diag::abort;
+use crate::diag::backtrace;
 use crate::diag::log;
 use crate::diag::log::Logger;
 use crate::result::*;
@@ -623,7 +624,7 @@ pub unsafe fn str_ptr_len(str_ptr: *const u8) -> usize {
 /// Simplified panic handler using a provided [`Logger`] type, available as a helpful default panic handler
 ///
 /// This handler does the following:
-/// * Logs the panic information via [`diag_log!`] macro and the provided [`Logger`] type
+/// * Logs the panic information via [`diag_log!`] macro and the provided [`Logger`] type, along with a backtrace (see [`backtrace::capture`]) if heap allocations are available
 /// * Aborts with [`ResultPanicked`][`super::rc::ResultPanicked`] and the specified desired [`AbortLevel`][`abort::AbortLevel`]
 ///
 /// # Arguments
@@ -638,7 +639,15 @@ pub fn simple_panic_handler<L: Logger>(
         Some(Ok(name)) => name,
         _ => "<unknown>",
     };
-    diag_log!(L { log::LogSeverity::Fatal, true } => "Panic! at thread '{}' -> {}\n", thread_name, info);
+    match crate::mem::alloc::is_enabled() {
+        true => {
+            let backtrace = backtrace::capture();
+            diag_log!(L { log::LogSeverity::Fatal, true } => "Panic! at thread '{}' -> {}\nBacktrace:\n{}", thread_name, info, backtrace);
+        }
+        false => {
+            diag_log!(L { log::LogSeverity::Fatal, true } => "Panic! at thread '{}' -> {}\n", thread_name, info);
+        }
+    }
 
     abort::abort(desired_level, super::rc::ResultPanicked::make())
 }
@@ -1,8 +1,9 @@
 //! Sync/waiting utilities and wrappers
 
-use crate::arm;
 use crate::result::*;
 use crate::svc;
+use crate::time::{Instant, Timeout};
+use arrayvec::ArrayVec;
 
 /// Represents an event via a remote handle
 pub struct RemoteEvent {
@@ -31,9 +32,9 @@ impl RemoteEvent {
     ///
     /// # Arguments
     ///
-    /// * `timeout` - Wait timeout in nanoseconds, `-1` can be used to wait indefinitely
+    /// * `timeout` - Wait timeout (see [`Timeout`])
     #[inline]
-    pub fn wait(&self, timeout: i64) -> Result<()> {
+    pub fn wait(&self, timeout: impl Into<Timeout>) -> Result<()> {
         wait_handles(&[self.handle], timeout)?;
         self.reset()
     }
@@ -56,10 +57,6 @@ pub struct SystemEvent {
 
 impl SystemEvent {
     /// Creates a new [`SystemEvent`] via the client/server handles obtained from [`svc::create_event`]
-    ///
-    /// # Arguments
-    ///
-    /// * `timeout` - Wait timeout in nanoseconds, `-1` can be used to wait indefinitely
     pub fn new() -> Result<Self> {
         let (server_handle, client_handle) = svc::create_event()?;
         Ok(Self {
@@ -95,7 +92,6 @@ pub enum WaiterType {
 pub const MAX_OBJECT_COUNT: u32 = 0x40;
 
 /// Represents a waiting object for a handle
-#[allow(dead_code)]
 pub struct Waiter {
     handle: svc::Handle,
     wait_type: WaiterType,
@@ -140,30 +136,35 @@ fn handles_wait_fn(handles: &[svc::Handle], timeout: i64) -> Result<usize> {
     unsafe { svc::wait_synchronization(handles, timeout).map(|idx| idx as usize) }
 }
 
-fn waiters_wait_fn(_waiters: &[Waiter], _timeout: i64) -> Result<usize> {
-    todo!();
-}
+fn waiters_wait_fn(waiters: &[Waiter], timeout: i64) -> Result<usize> {
+    result_return_if!(
+        waiters.len() > MAX_OBJECT_COUNT as usize,
+        svc::rc::ResultOutOfRange
+    );
 
-fn wait_impl<W>(wait_objects: &[W], timeout: i64, wait_fn: WaitFn<W>) -> Result<usize> {
-    let has_timeout = timeout != -1;
-    let mut deadline: u64 = 0;
-    if has_timeout {
-        deadline = arm::get_system_tick().saturating_add(arm::nanoseconds_to_ticks(timeout as u64));
+    let mut handles: ArrayVec<svc::Handle, { MAX_OBJECT_COUNT as usize }> = ArrayVec::new();
+    handles.extend(waiters.iter().map(|waiter| waiter.handle));
+
+    let index = handles_wait_fn(handles.as_slice(), timeout)?;
+    if let WaiterType::HandleWithClear = waiters[index].wait_type {
+        svc::reset_signal(waiters[index].handle)?;
     }
+    Ok(index)
+}
+
+fn wait_impl<W>(wait_objects: &[W], timeout: Timeout, wait_fn: WaitFn<W>) -> Result<usize> {
+    let deadline = timeout.get_deadline();
 
     loop {
-        let this_timeout = match has_timeout {
-            true => {
-                let remaining = deadline.saturating_sub(arm::get_system_tick());
-                arm::ticks_to_nanoseconds(remaining) as i64
-            }
-            false => -1,
+        let this_timeout = match deadline {
+            Some(deadline) => Timeout::Deadline(deadline).get_remaining_nanos(),
+            None => -1,
         };
         match (wait_fn)(wait_objects, this_timeout) {
             Ok(index) => return Ok(index),
             Err(rc) => {
                 if svc::rc::ResultTimedOut::matches(rc) {
-                    if has_timeout {
+                    if deadline.is_some_and(|deadline| Instant::now() >= deadline) {
                         return Err(rc);
                     }
                 } else if !svc::rc::ResultCancelled::matches(rc) {
@@ -179,10 +180,10 @@ fn wait_impl<W>(wait_objects: &[W], timeout: i64, wait_fn: WaitFn<W>) -> Result<
 /// # Arguments
 ///
 /// * `waiters` - [`Waiter`]s to wait for
-/// * `timeout` - Wait timeout in nanoseconds, `-1` can be used to wait indefinitely
+/// * `timeout` - Wait timeout (see [`Timeout`])
 #[inline]
-pub fn wait(waiters: &[Waiter], timeout: i64) -> Result<usize> {
-    wait_impl(waiters, timeout, waiters_wait_fn)
+pub fn wait(waiters: &[Waiter], timeout: impl Into<Timeout>) -> Result<usize> {
+    wait_impl(waiters, timeout.into(), waiters_wait_fn)
 }
 
 /// Waits for several handles for a specified timeout, returning the index of the handle which signals first
@@ -190,8 +191,8 @@ pub fn wait(waiters: &[Waiter], timeout: i64) -> Result<usize> {
 /// # Arguments
 ///
 /// * `handles` - Handles to wait for
-/// * `timeout` - Wait timeout in nanoseconds, `-1` can be used to wait indefinitely
+/// * `timeout` - Wait timeout (see [`Timeout`])
 #[inline]
-pub fn wait_handles(handles: &[svc::Handle], timeout: i64) -> Result<usize> {
-    wait_impl(handles, timeout, handles_wait_fn)
+pub fn wait_handles(handles: &[svc::Handle], timeout: impl Into<Timeout>) -> Result<usize> {
+    wait_impl(handles, timeout.into(), handles_wait_fn)
 }